@@ -0,0 +1,21 @@
+package java.io;
+
+public final class FileDescriptor {
+    public static final FileDescriptor in = new FileDescriptor(0);
+    public static final FileDescriptor out = new FileDescriptor(1);
+    public static final FileDescriptor err = new FileDescriptor(2);
+
+    private final int fd;
+
+    public FileDescriptor() {
+        this(-1);
+    }
+
+    private FileDescriptor(int fd) {
+        this.fd = fd;
+    }
+
+    public boolean valid() {
+        return fd >= 0;
+    }
+}
@@ -0,0 +1,26 @@
+package java.io;
+
+public class FileOutputStream extends OutputStream {
+    private final FileDescriptor fd;
+
+    public FileOutputStream(FileDescriptor fdObj) {
+        this.fd = fdObj;
+    }
+
+    public void write(int b) {
+        writeBytes(new byte[] {(byte) b}, 0, 1);
+    }
+
+    public void write(byte[] b, int off, int len) {
+        if (off < 0 || len < 0 || off > b.length - len) {
+            throw new IndexOutOfBoundsException();
+        }
+        writeBytes(b, off, len);
+    }
+
+    public final FileDescriptor getFD() {
+        return fd;
+    }
+
+    private native void writeBytes(byte[] b, int off, int len);
+}
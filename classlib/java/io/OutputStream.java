@@ -0,0 +1,24 @@
+package java.io;
+
+public abstract class OutputStream {
+    public OutputStream() {}
+
+    public abstract void write(int b);
+
+    public void write(byte[] b) {
+        write(b, 0, b.length);
+    }
+
+    public void write(byte[] b, int off, int len) {
+        if (off < 0 || len < 0 || off > b.length - len) {
+            throw new IndexOutOfBoundsException();
+        }
+        for (int i = 0; i < len; i++) {
+            write(b[off + i]);
+        }
+    }
+
+    public void flush() {}
+
+    public void close() {}
+}
@@ -0,0 +1,111 @@
+package java.io;
+
+public class PrintStream extends OutputStream {
+    private final OutputStream out;
+
+    public PrintStream(OutputStream out) {
+        this.out = out;
+    }
+
+    public void write(int b) {
+        out.write(b);
+    }
+
+    public void write(byte[] b, int off, int len) {
+        out.write(b, off, len);
+    }
+
+    public void flush() {
+        out.flush();
+    }
+
+    public void close() {
+        out.close();
+    }
+
+    public void print(String s) {
+        byte[] bytes = String.valueOf((Object) s).getBytes();
+        out.write(bytes, 0, bytes.length);
+    }
+
+    public void print(Object obj) {
+        print(String.valueOf(obj));
+    }
+
+    public void print(char[] s) {
+        print(new String(s));
+    }
+
+    public void print(boolean b) {
+        print(String.valueOf(b));
+    }
+
+    public void print(char c) {
+        print(String.valueOf(c));
+    }
+
+    public void print(int i) {
+        print(String.valueOf(i));
+    }
+
+    public void print(long l) {
+        print(String.valueOf(l));
+    }
+
+    public void print(float f) {
+        print(String.valueOf(f));
+    }
+
+    public void print(double d) {
+        print(String.valueOf(d));
+    }
+
+    public void println() {
+        out.write('\n');
+    }
+
+    public void println(String x) {
+        print(x);
+        println();
+    }
+
+    public void println(Object x) {
+        print(x);
+        println();
+    }
+
+    public void println(char[] x) {
+        print(x);
+        println();
+    }
+
+    public void println(boolean x) {
+        print(x);
+        println();
+    }
+
+    public void println(char x) {
+        print(x);
+        println();
+    }
+
+    public void println(int x) {
+        print(x);
+        println();
+    }
+
+    public void println(long x) {
+        print(x);
+        println();
+    }
+
+    public void println(float x) {
+        print(x);
+        println();
+    }
+
+    public void println(double x) {
+        print(x);
+        println();
+    }
+}
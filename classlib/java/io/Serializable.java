@@ -0,0 +1,4 @@
+package java.io;
+
+// implemented by the arrays, there is no serialization
+public interface Serializable {}
@@ -0,0 +1,22 @@
+package java.lang;
+
+public final class Class<T> {
+    // the instances are only created by the virtual machine
+    private Class() {}
+
+    // the binary name of the class with dots, like java.lang.String
+    public native String getName();
+
+    public native boolean isInterface();
+
+    public native boolean isArray();
+
+    public native boolean isPrimitive();
+
+    public String toString() {
+        if (isPrimitive()) {
+            return getName();
+        }
+        return (isInterface() ? "interface " : "class ").concat(getName());
+    }
+}
@@ -0,0 +1,4 @@
+package java.lang;
+
+// implemented by the arrays, Object.clone isn't supported yet
+public interface Cloneable {}
@@ -0,0 +1,76 @@
+package java.lang;
+
+public final class Integer extends Number {
+    public static final int MIN_VALUE = 0x80000000;
+    public static final int MAX_VALUE = 0x7fffffff;
+
+    // valueOf returns the same instance for small values, like the JDK
+    private static final int CACHE_LOW = -128;
+    private static final int CACHE_HIGH = 127;
+    private static final Integer[] CACHE = new Integer[CACHE_HIGH - CACHE_LOW + 1];
+
+    static {
+        for (int i = 0; i < CACHE.length; i++) {
+            CACHE[i] = new Integer(i + CACHE_LOW);
+        }
+    }
+
+    private final int value;
+
+    public Integer(int value) {
+        this.value = value;
+    }
+
+    public static Integer valueOf(int i) {
+        if (i >= CACHE_LOW && i <= CACHE_HIGH) {
+            return CACHE[i - CACHE_LOW];
+        }
+        return new Integer(i);
+    }
+
+    public static Integer valueOf(String s) {
+        return valueOf(parseInt(s));
+    }
+
+    public static native int parseInt(String s);
+
+    public static native String toString(int i);
+
+    public static native String toHexString(int i);
+
+    public static int compare(int x, int y) {
+        return x < y ? -1 : (x == y ? 0 : 1);
+    }
+
+    public int intValue() {
+        return value;
+    }
+
+    public long longValue() {
+        return value;
+    }
+
+    public float floatValue() {
+        return value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public int compareTo(Integer anotherInteger) {
+        return compare(value, anotherInteger.value);
+    }
+
+    public boolean equals(Object obj) {
+        return obj instanceof Integer && ((Integer) obj).value == value;
+    }
+
+    public int hashCode() {
+        return value;
+    }
+
+    public String toString() {
+        return toString(value);
+    }
+}
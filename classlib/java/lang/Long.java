@@ -0,0 +1,77 @@
+package java.lang;
+
+public final class Long extends Number {
+    public static final long MIN_VALUE = 0x8000000000000000L;
+    public static final long MAX_VALUE = 0x7fffffffffffffffL;
+
+    // valueOf returns the same instance for small values, like the JDK
+    private static final int CACHE_LOW = -128;
+    private static final int CACHE_HIGH = 127;
+    private static final Long[] CACHE = new Long[CACHE_HIGH - CACHE_LOW + 1];
+
+    static {
+        for (int i = 0; i < CACHE.length; i++) {
+            CACHE[i] = new Long(i + CACHE_LOW);
+        }
+    }
+
+    private final long value;
+
+    public Long(long value) {
+        this.value = value;
+    }
+
+    public static Long valueOf(long l) {
+        int i = (int) l;
+        if (i == l && i >= CACHE_LOW && i <= CACHE_HIGH) {
+            return CACHE[i - CACHE_LOW];
+        }
+        return new Long(l);
+    }
+
+    public static Long valueOf(String s) {
+        return valueOf(parseLong(s));
+    }
+
+    public static native long parseLong(String s);
+
+    public static native String toString(long l);
+
+    public static native String toHexString(long l);
+
+    public static native int compare(long x, long y);
+
+    public static native int hashCode(long value);
+
+    public int intValue() {
+        return (int) value;
+    }
+
+    public long longValue() {
+        return value;
+    }
+
+    public float floatValue() {
+        return value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public int compareTo(Long anotherLong) {
+        return compare(value, anotherLong.value);
+    }
+
+    public boolean equals(Object obj) {
+        return obj instanceof Long && ((Long) obj).value == value;
+    }
+
+    public int hashCode() {
+        return hashCode(value);
+    }
+
+    public String toString() {
+        return toString(value);
+    }
+}
@@ -0,0 +1,30 @@
+package java.lang;
+
+public class Object {
+    public Object() {}
+
+    public final native Class<?> getClass();
+
+    public native int hashCode();
+
+    public boolean equals(Object obj) {
+        return this == obj;
+    }
+
+    // a shallow copy, the class has to implement Cloneable unless it is an array class
+    protected native Object clone() throws CloneNotSupportedException;
+
+    // the class name and the hash code in hexadecimal, like the JDK
+    public native String toString();
+
+    public final native void notify();
+
+    public final native void notifyAll();
+
+    public final void wait() throws InterruptedException {
+        wait(0);
+    }
+
+    // waits until notified when timeoutMillis is 0
+    public final native void wait(long timeoutMillis) throws InterruptedException;
+}
@@ -0,0 +1,24 @@
+package java.lang;
+
+public class Runtime {
+    private static final Runtime currentRuntime = new Runtime();
+
+    private Runtime() {}
+
+    public static Runtime getRuntime() {
+        return currentRuntime;
+    }
+
+    public void gc() {
+        System.gc();
+    }
+
+    // the heap doesn't grow, all of it is available from the start
+    public native long maxMemory();
+
+    public long totalMemory() {
+        return maxMemory();
+    }
+
+    public native long freeMemory();
+}
@@ -0,0 +1,111 @@
+package java.lang;
+
+public final class String {
+    private final char[] value;
+
+    public String() {
+        this.value = new char[0];
+    }
+
+    public String(char[] value) {
+        this(value, 0, value.length);
+    }
+
+    public String(char[] value, int offset, int count) {
+        if (offset < 0 || count < 0 || offset > value.length - count) {
+            throw new StringIndexOutOfBoundsException();
+        }
+        this.value = new char[count];
+        System.arraycopy(value, offset, this.value, 0, count);
+    }
+
+    public int length() {
+        return value.length;
+    }
+
+    public boolean isEmpty() {
+        return value.length == 0;
+    }
+
+    public char charAt(int index) {
+        if (index < 0 || index >= value.length) {
+            throw new StringIndexOutOfBoundsException(index);
+        }
+        return value[index];
+    }
+
+    public char[] toCharArray() {
+        char[] copy = new char[value.length];
+        System.arraycopy(value, 0, copy, 0, value.length);
+        return copy;
+    }
+
+    // encoded in UTF-8
+    public native byte[] getBytes();
+
+    public boolean equals(Object anObject) {
+        if (this == anObject) {
+            return true;
+        }
+        if (!(anObject instanceof String)) {
+            return false;
+        }
+        char[] other = ((String) anObject).value;
+        if (other.length != value.length) {
+            return false;
+        }
+        for (int i = 0; i < value.length; i++) {
+            if (other[i] != value[i]) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    public native int hashCode();
+
+    public String concat(String str) {
+        if (str.value.length == 0) {
+            return this;
+        }
+        char[] chars = new char[value.length + str.value.length];
+        System.arraycopy(value, 0, chars, 0, value.length);
+        System.arraycopy(str.value, 0, chars, value.length, str.value.length);
+        return new String(chars);
+    }
+
+    public String toString() {
+        return this;
+    }
+
+    // the string with the same characters in the table of the literals
+    public native String intern();
+
+    public static String valueOf(Object obj) {
+        return obj == null ? "null" : obj.toString();
+    }
+
+    public static String valueOf(char[] data) {
+        return new String(data);
+    }
+
+    public static String valueOf(boolean b) {
+        return b ? "true" : "false";
+    }
+
+    public static String valueOf(char c) {
+        return new String(new char[] {c});
+    }
+
+    public static String valueOf(int i) {
+        return Integer.toString(i);
+    }
+
+    public static String valueOf(long l) {
+        return Long.toString(l);
+    }
+
+    public static native String valueOf(float f);
+
+    public static native String valueOf(double d);
+}
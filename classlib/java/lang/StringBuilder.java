@@ -0,0 +1,85 @@
+package java.lang;
+
+public final class StringBuilder {
+    private char[] value;
+    private int count;
+
+    public StringBuilder() {
+        this(16);
+    }
+
+    public StringBuilder(int capacity) {
+        value = new char[capacity];
+    }
+
+    public StringBuilder(String str) {
+        this(str.length() + 16);
+        append(str);
+    }
+
+    private void ensureCapacity(int minimumCapacity) {
+        if (minimumCapacity > value.length) {
+            int capacity = value.length * 2 + 2;
+            if (capacity < minimumCapacity) {
+                capacity = minimumCapacity;
+            }
+            char[] grown = new char[capacity];
+            System.arraycopy(value, 0, grown, 0, count);
+            value = grown;
+        }
+    }
+
+    public int length() {
+        return count;
+    }
+
+    public char charAt(int index) {
+        if (index < 0 || index >= count) {
+            throw new StringIndexOutOfBoundsException(index);
+        }
+        return value[index];
+    }
+
+    public StringBuilder append(String str) {
+        char[] chars = String.valueOf((Object) str).toCharArray();
+        ensureCapacity(count + chars.length);
+        System.arraycopy(chars, 0, value, count, chars.length);
+        count += chars.length;
+        return this;
+    }
+
+    public StringBuilder append(Object obj) {
+        return append(String.valueOf(obj));
+    }
+
+    public StringBuilder append(char c) {
+        ensureCapacity(count + 1);
+        value[count] = c;
+        count++;
+        return this;
+    }
+
+    public StringBuilder append(boolean b) {
+        return append(String.valueOf(b));
+    }
+
+    public StringBuilder append(int i) {
+        return append(String.valueOf(i));
+    }
+
+    public StringBuilder append(long l) {
+        return append(String.valueOf(l));
+    }
+
+    public StringBuilder append(float f) {
+        return append(String.valueOf(f));
+    }
+
+    public StringBuilder append(double d) {
+        return append(String.valueOf(d));
+    }
+
+    public String toString() {
+        return new String(value, 0, count);
+    }
+}
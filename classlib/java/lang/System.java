@@ -0,0 +1,33 @@
+package java.lang;
+
+import java.io.FileDescriptor;
+import java.io.FileOutputStream;
+import java.io.PrintStream;
+
+public final class System {
+    public static final PrintStream out;
+    public static final PrintStream err;
+
+    static {
+        out = new PrintStream(new FileOutputStream(FileDescriptor.out));
+        err = new PrintStream(new FileOutputStream(FileDescriptor.err));
+    }
+
+    private System() {}
+
+    public static native void arraycopy(Object src, int srcPos, Object dest, int destPos, int length);
+
+    public static native long currentTimeMillis();
+
+    public static native long nanoTime();
+
+    public static native int identityHashCode(Object x);
+
+    // set with -Dkey=value on the command line
+    public static native String getProperty(String key);
+
+    public static native void exit(int status);
+
+    // collect the cycles of unreachable objects, the other ones are already freed
+    public static native void gc();
+}
@@ -0,0 +1,133 @@
+package java.lang;
+
+// the threads are run by host threads, the virtual machine keeps their state
+public class Thread implements Runnable {
+    private volatile String name;
+    private boolean daemon;
+    private final Runnable target;
+    // a thread can only be started once
+    private boolean started;
+    private volatile UncaughtExceptionHandler uncaughtExceptionHandler;
+
+    private static volatile UncaughtExceptionHandler defaultUncaughtExceptionHandler;
+
+    public interface UncaughtExceptionHandler {
+        void uncaughtException(Thread t, Throwable e);
+    }
+
+    public Thread() {
+        this(null, null);
+    }
+
+    public Thread(Runnable target) {
+        this(target, null);
+    }
+
+    public Thread(String name) {
+        this(null, name);
+    }
+
+    public Thread(Runnable target, String name) {
+        this.target = target;
+        this.name = name != null ? name : "Thread-".concat(String.valueOf(nextThreadNumber()));
+        this.daemon = currentThread().isDaemon();
+    }
+
+    public static native Thread currentThread();
+
+    private static native int nextThreadNumber();
+
+    public static native void sleep(long millis) throws InterruptedException;
+
+    public static native void yield();
+
+    // synchronized, so two threads can't both see it was not started
+    public synchronized void start() {
+        if (started) {
+            throw new IllegalThreadStateException();
+        }
+        started = true;
+        start0();
+    }
+
+    private native void start0();
+
+    public void run() {
+        if (target != null) {
+            target.run();
+        }
+    }
+
+    public final native boolean isAlive();
+
+    public final void join() throws InterruptedException {
+        join(0);
+    }
+
+    // waits forever when millis is 0
+    public final native void join(long millis) throws InterruptedException;
+
+    public native void interrupt();
+
+    public native boolean isInterrupted();
+
+    // the interrupt status of the current thread is cleared
+    public static native boolean interrupted();
+
+    public final String getName() {
+        return name;
+    }
+
+    public final void setName(String name) {
+        if (name == null) {
+            throw new NullPointerException("name cannot be null");
+        }
+        this.name = name;
+    }
+
+    public final boolean isDaemon() {
+        return daemon;
+    }
+
+    public final void setDaemon(boolean on) {
+        if (started) {
+            throw new IllegalThreadStateException();
+        }
+        daemon = on;
+    }
+
+    public UncaughtExceptionHandler getUncaughtExceptionHandler() {
+        return uncaughtExceptionHandler;
+    }
+
+    public void setUncaughtExceptionHandler(UncaughtExceptionHandler handler) {
+        uncaughtExceptionHandler = handler;
+    }
+
+    public static UncaughtExceptionHandler getDefaultUncaughtExceptionHandler() {
+        return defaultUncaughtExceptionHandler;
+    }
+
+    public static void setDefaultUncaughtExceptionHandler(UncaughtExceptionHandler handler) {
+        defaultUncaughtExceptionHandler = handler;
+    }
+
+    // there are no thread groups or priorities, they are the ones of the JDK by default
+    public String toString() {
+        return "Thread[".concat(name).concat(",5,main]");
+    }
+
+    // called by the virtual machine when run throws an exception
+    private void dispatchUncaughtException(Throwable e) {
+        UncaughtExceptionHandler handler = uncaughtExceptionHandler;
+        if (handler == null) {
+            handler = defaultUncaughtExceptionHandler;
+        }
+        if (handler != null) {
+            handler.uncaughtException(this, e);
+            return;
+        }
+        System.err.print("Exception in thread \"".concat(name).concat("\" "));
+        e.printStackTrace();
+    }
+}
@@ -0,0 +1,83 @@
+package java.lang;
+
+public class Throwable {
+    private String detailMessage;
+    private Throwable cause;
+    // the frames recorded by fillInStackTrace, the innermost first
+    private String[] stackTrace;
+
+    public Throwable() {
+        fillInStackTrace();
+    }
+
+    public Throwable(String message) {
+        fillInStackTrace();
+        this.detailMessage = message;
+    }
+
+    public Throwable(String message, Throwable cause) {
+        fillInStackTrace();
+        this.detailMessage = message;
+        this.cause = cause;
+    }
+
+    public Throwable(Throwable cause) {
+        fillInStackTrace();
+        this.detailMessage = cause == null ? null : cause.toString();
+        this.cause = cause;
+    }
+
+    // the frames are named by their class and method, without the line numbers
+    public native Throwable fillInStackTrace();
+
+    public String getMessage() {
+        return detailMessage;
+    }
+
+    public String getLocalizedMessage() {
+        return getMessage();
+    }
+
+    public Throwable getCause() {
+        return cause;
+    }
+
+    public Throwable initCause(Throwable cause) {
+        if (cause == this) {
+            throw new IllegalArgumentException();
+        }
+        this.cause = cause;
+        return this;
+    }
+
+    // the binary name of the class with dots, like Class.getName
+    private native String getClassName();
+
+    public String toString() {
+        String message = getLocalizedMessage();
+        if (message == null) {
+            return getClassName();
+        }
+        return new StringBuilder(getClassName()).append(':').append(' ').append(message).toString();
+    }
+
+    public void printStackTrace() {
+        System.err.println(this);
+        printFrames();
+        for (Throwable cause = this.cause; cause != null; cause = cause.cause) {
+            System.err.print("Caused by: ");
+            System.err.println(cause);
+            cause.printFrames();
+        }
+    }
+
+    private void printFrames() {
+        if (stackTrace == null) {
+            return;
+        }
+        for (String frame : stackTrace) {
+            System.err.print("\tat ");
+            System.err.println(frame);
+        }
+    }
+}
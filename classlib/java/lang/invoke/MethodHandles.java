@@ -0,0 +1,22 @@
+package java.lang.invoke;
+
+public class MethodHandles {
+    private MethodHandles() {}
+
+    // given to the bootstrap methods of the dynamic constants, only created by the virtual machine
+    public static final class Lookup {
+        private final Class<?> lookupClass;
+
+        private Lookup(Class<?> lookupClass) {
+            this.lookupClass = lookupClass;
+        }
+
+        public Class<?> lookupClass() {
+            return lookupClass;
+        }
+
+        public String toString() {
+            return lookupClass.getName();
+        }
+    }
+}
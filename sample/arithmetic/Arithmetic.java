@@ -0,0 +1,52 @@
+// comparisons of long, float and double values, and int and long arithmetic overflowing
+public class Arithmetic {
+    // lcmp
+    static boolean longGreater(long a, long b) {
+        return a > b;
+    }
+
+    static boolean longLess(long a, long b) {
+        return a < b;
+    }
+
+    // dcmpl, NaN is not greater
+    static boolean doubleGreater(double a, double b) {
+        return a > b;
+    }
+
+    // dcmpg, NaN is not less
+    static boolean doubleLess(double a, double b) {
+        return a < b;
+    }
+
+    // fcmpl
+    static boolean floatGreater(float a, float b) {
+        return a > b;
+    }
+
+    // fcmpg
+    static boolean floatLess(float a, float b) {
+        return a < b;
+    }
+
+    static int increment(int value) {
+        value++;
+        return value;
+    }
+
+    static int add(int a, int b) {
+        return a + b;
+    }
+
+    static long multiply(long a, long b) {
+        return a * b;
+    }
+
+    static long subtract(long a, long b) {
+        return a - b;
+    }
+
+    static int negate(int value) {
+        return -value;
+    }
+}
@@ -0,0 +1,109 @@
+import java.io.Serializable;
+
+public class ArrayClasses {
+    interface Shape {}
+
+    static class Square implements Shape {}
+
+    static class Point implements Cloneable {
+        final int x;
+        final String name;
+
+        Point(int x, String name) {
+            this.x = x;
+            this.name = name;
+        }
+
+        // clone is protected in Object, the subclasses call it through super
+        Point copy() throws CloneNotSupportedException {
+            return (Point) super.clone();
+        }
+    }
+
+    static class Plain {
+        Object copy() throws CloneNotSupportedException {
+            return super.clone();
+        }
+    }
+
+    // every check that holds adds its bit
+    public static int instances() {
+        Object strings = new String[1];
+        Object ints = new int[1];
+        Object squares = new Square[1][1];
+        int bits = 0;
+        if (strings instanceof Object[]) bits |= 1;
+        if (ints instanceof Object[]) bits |= 2;
+        if (ints instanceof Cloneable) bits |= 4;
+        if (strings instanceof Serializable) bits |= 8;
+        if (squares instanceof Shape[][]) bits |= 16;
+        if (squares instanceof Object[][]) bits |= 32;
+        if (squares instanceof Cloneable[]) bits |= 64;
+        if (ints instanceof long[]) bits |= 128;
+        if (squares instanceof String[][]) bits |= 256;
+        return bits;
+    }
+
+    public static String cast() {
+        Object objects = new Object[1];
+        try {
+            String[] strings = (String[]) objects;
+            return String.valueOf(strings.length);
+        } catch (ClassCastException e) {
+            return e.getMessage();
+        }
+    }
+
+    // the arrays of arrays only accept the arrays of a subtype of their component
+    public static int covariance() {
+        Shape[][] shapes = new Square[2][];
+        Object[] objects = shapes;
+        objects[0] = new Square[3];
+        try {
+            objects[1] = new Shape[1];
+        } catch (ArrayStoreException e) {
+            return shapes[0].length;
+        }
+        return -1;
+    }
+
+    public static String names() {
+        Class<?> strings = new String[0].getClass();
+        Class<?> matrix = new int[1][1].getClass();
+        return strings.getName()
+                .concat(" ")
+                .concat(matrix.getName())
+                .concat(" ")
+                .concat(String.valueOf(matrix == int[][].class && matrix.isArray()))
+                .concat(" ")
+                .concat(String.valueOf(new Object().getClass().isArray()));
+    }
+
+    // the copies are shallow and have the class of the original, every check that holds adds
+    // its bit
+    public static int clones() throws CloneNotSupportedException {
+        int[] ints = {1, 2, 3};
+        int[] intsCopy = ints.clone();
+        intsCopy[0] = 10;
+        String[][] matrix = {{"a"}, {"b"}};
+        String[][] matrixCopy = matrix.clone();
+        Point point = new Point(7, "p");
+        Point pointCopy = point.copy();
+        int bits = 0;
+        if (ints[0] == 1 && intsCopy[0] == 10 && intsCopy[2] == 3) bits |= 1;
+        if (matrixCopy != matrix && matrixCopy[1] == matrix[1]) bits |= 2;
+        if (matrixCopy.getClass() == String[][].class) bits |= 4;
+        if (pointCopy != point && pointCopy.x == 7 && pointCopy.name == point.name) bits |= 8;
+        if (pointCopy.getClass() == Point.class) bits |= 16;
+        return bits;
+    }
+
+    public static String notCloneable() {
+        try {
+            new Plain().copy();
+            return "cloned";
+        } catch (CloneNotSupportedException e) {
+            return e.getMessage();
+        }
+    }
+}
@@ -0,0 +1,58 @@
+public class Arrays {
+    public static int matrix() {
+        int[][] matrix = new int[3][4];
+        matrix[2][3] = 7;
+        return matrix.length * 100 + matrix[2].length * 10 + matrix[2][3];
+    }
+
+    // only the first two dimensions are created
+    public static int partial() {
+        long[][][] cube = new long[2][3][];
+        return cube[1][2] == null ? cube[1].length : -1;
+    }
+
+    public static int jagged() {
+        int[][] jagged = new int[2][];
+        jagged[1] = new int[5];
+        return jagged[1].length + (jagged[0] == null ? 10 : 0);
+    }
+
+    public static String strings() {
+        String[][] strings = new String[2][2];
+        strings[1][0] = "x";
+        Object[] objects = new Object[1];
+        objects[0] = strings;
+        return strings[1][0].concat(String.valueOf(strings[0][1]));
+    }
+
+    public static int negative(int size) {
+        int[][] matrix = new int[2][size];
+        return matrix.length;
+    }
+
+    public static String arrayStore() {
+        Object[] objects = new String[1];
+        try {
+            objects[0] = Integer.valueOf(1);
+        } catch (ArrayStoreException e) {
+            return e.getMessage();
+        }
+        return null;
+    }
+
+    public static int bytes(int value) {
+        byte[] bytes = {(byte) value};
+        return bytes[0];
+    }
+
+    public static int chars(int value) {
+        char[] chars = {(char) value};
+        return chars[0];
+    }
+
+    public static int booleans(boolean value) {
+        boolean[] booleans = new boolean[2];
+        booleans[1] = value;
+        return (booleans[0] ? 10 : 0) + (booleans[1] ? 1 : 0);
+    }
+}
@@ -0,0 +1,5 @@
+public class Animal {
+    public int legs() {
+        return 4;
+    }
+}
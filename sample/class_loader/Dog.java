@@ -0,0 +1,5 @@
+public class Dog extends Animal implements Pet {
+    public String name() {
+        return "Rex";
+    }
+}
@@ -0,0 +1,3 @@
+public interface Pet {
+    String name();
+}
@@ -0,0 +1,6 @@
+package java.lang;
+
+// minimal stub, packed in the sample jars with the classes of the class loader tests
+public class Object {
+    public Object() {}
+}
@@ -0,0 +1,72 @@
+public class Concat {
+    static class Named {
+        final String name;
+
+        Named(String name) {
+            this.name = name;
+        }
+
+        public String toString() {
+            return name;
+        }
+    }
+
+    public static String literals(int value) {
+        return "a" + value + "b";
+    }
+
+    // every primitive is formatted like String.valueOf
+    public static String primitives(
+            byte b, short s, char c, int i, long l, float f, double d, boolean z) {
+        return b + "," + s + "," + c + "," + i + "," + l + "," + f + "," + d + "," + z;
+    }
+
+    public static String chars(char first, char second) {
+        return "" + first + second;
+    }
+
+    public static String floats() {
+        return 1.0f + " " + 1e10 + " " + 0.001 + " " + (0.0 / 0.0) + " " + (-1.0f / 0.0f);
+    }
+
+    public static String nulls() {
+        String string = null;
+        Object object = null;
+        return string + "/" + object;
+    }
+
+    public static String objects() {
+        return new Named("first") + " and " + new Named("second") + " " + new Named(null);
+    }
+
+    // the tag characters in a literal are passed as constants
+    public static String tags(int value) {
+        return "\u0001" + value + "\u0002";
+    }
+
+    public static String unicode(char c) {
+        return "caf\u00e9 " + c + " \ud83d\ude00";
+    }
+
+    // the lone surrogates of the recipe and of a constant are kept
+    public static String loneSurrogates(int value) {
+        return "a\ud83d" + value + "\u0001\ude00";
+    }
+
+    public static boolean array() {
+        String string = "" + new char[] {'a'};
+        return string.charAt(0) == '[' && string.charAt(1) == 'C' && string.charAt(2) == '@';
+    }
+
+    public static String loop(int count) {
+        String result = "";
+        for (int i = 0; i < count; i++) {
+            result += i;
+        }
+        return result;
+    }
+
+    public static String withoutConstants(String a, int b, char c) {
+        return ConcatWithoutConstants.concat(a, b, c);
+    }
+}
@@ -0,0 +1,6 @@
+// compiled with -XDstringConcat=indy, so javac uses makeConcat and passes the literals as arguments
+public class ConcatWithoutConstants {
+    public static String concat(String a, int b, char c) {
+        return a + "-" + b + "-" + c + "-" + true;
+    }
+}
@@ -0,0 +1,59 @@
+import java.lang.invoke.MethodHandles;
+
+// the bootstrap methods of the dynamic constants of Condy, see Condy.javap.txt
+public class Bootstraps {
+    private static int calls;
+
+    public static int calls() {
+        return calls;
+    }
+
+    public static String describe(
+            MethodHandles.Lookup lookup, String name, Class<?> type, String prefix) {
+        calls++;
+        return prefix + name + " in " + lookup.lookupClass().getName() + " as " + type.getName();
+    }
+
+    // the boxed result is unboxed to the long of the constant
+    public static Object boxedLong(
+            MethodHandles.Lookup lookup, String name, Class<?> type, long value) {
+        calls++;
+        return type.isPrimitive() ? Long.valueOf(value + 1) : null;
+    }
+
+    // the int argument is widened
+    public static double half(MethodHandles.Lookup lookup, String name, Class<?> type, double value) {
+        calls++;
+        return value / 2;
+    }
+
+    public static Object same(MethodHandles.Lookup lookup, String name, Class<?> type, Object value) {
+        calls++;
+        return value;
+    }
+
+    // the trailing static arguments are collected in an array, the ints are boxed
+    public static String join(
+            MethodHandles.Lookup lookup, String name, Class<?> type, String separator, Object... parts) {
+        calls++;
+        String joined = name;
+        for (Object part : parts) {
+            joined += separator + part;
+        }
+        return joined;
+    }
+
+    public static int sum(MethodHandles.Lookup lookup, String name, Class<?> type, int... values) {
+        calls++;
+        int sum = 0;
+        for (int value : values) {
+            sum += value;
+        }
+        return sum;
+    }
+
+    public static Object failing(MethodHandles.Lookup lookup, String name, Class<?> type) {
+        calls++;
+        throw new IllegalStateException(name);
+    }
+}
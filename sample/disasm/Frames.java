@@ -0,0 +1,54 @@
+public class Frames {
+    private final int value;
+
+    // `this` stays uninitialized across the branch
+    Frames(boolean flag) {
+        this(flag ? 1 : 2);
+    }
+
+    Frames(int value) {
+        this.value = value;
+    }
+
+    // a new object stays uninitialized on the stack across the branch
+    static Frames create(boolean flag) {
+        return new Frames(flag ? 3 : 4);
+    }
+
+    // long jumps give extended frames
+    static int far(int a) {
+        int b = a;
+        if (a > 0) {
+            b += a * 2 + a * 3 + a * 4 + a * 5 + a * 6 + a * 7 + a * 8 + a * 9 + a * 10 + a * 11;
+            b += a * 2 + a * 3 + a * 4 + a * 5 + a * 6 + a * 7 + a * 8 + a * 9 + a * 10 + a * 11;
+        }
+        return b;
+    }
+
+    static int farSame(int a) {
+        if (a > 0) {
+            a += a * 2 + a * 3 + a * 4 + a * 5 + a * 6 + a * 7 + a * 8 + a * 9 + a * 10 + a * 11;
+            a += a * 2 + a * 3 + a * 4 + a * 5 + a * 6 + a * 7 + a * 8 + a * 9 + a * 10 + a * 11;
+        }
+        return a;
+    }
+
+    static Object farStack(Object a, boolean flag) {
+        return flag ? a : (Object) String.valueOf(a.hashCode() * 2 + a.hashCode() * 3 + a.hashCode() * 4 + a.hashCode() * 5 + a.hashCode() * 6 + a.hashCode() * 7 + a.hashCode() * 8 + a.hashCode() * 9 + a.hashCode() * 10);
+    }
+
+    // leaving nested scopes chops several locals at once
+    static int chop(int a) {
+        int result = 0;
+        if (a > 0) {
+            int b = a;
+            int c = b;
+            for (int i = 0; i < c; i++) {
+                result += i;
+            }
+        }
+        Object o = null;
+        long l = 1;
+        return result + (o == null ? 0 : 1) + (int) l;
+    }
+}
@@ -0,0 +1,14 @@
+// interface with a constant, an abstract, a default and a static method
+public interface Greeter extends Runnable {
+    int TIMES = 2;
+
+    String name();
+
+    default String greet() {
+        return "Hi ".concat(name());
+    }
+
+    static Greeter none() {
+        return null;
+    }
+}
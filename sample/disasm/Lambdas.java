@@ -0,0 +1,23 @@
+import java.util.function.IntSupplier;
+import java.util.function.IntUnaryOperator;
+
+// a lambda, a capturing lambda and a method reference made with LambdaMetafactory,
+// and a string concatenation made with StringConcatFactory
+public class Lambdas {
+    static int twice(int value) {
+        IntUnaryOperator doubler = x -> x * 2;
+        return doubler.applyAsInt(value);
+    }
+
+    static IntSupplier sum(int a, int b) {
+        return () -> a + b;
+    }
+
+    static IntSupplier hash(Object object) {
+        return object::hashCode;
+    }
+
+    static String greet(String name) {
+        return "Hi " + name;
+    }
+}
@@ -0,0 +1,90 @@
+import java.io.Serializable;
+
+// covers most of what the disassembler renders, without generics, lambdas or inner classes
+public abstract class Shapes implements Serializable, Cloneable {
+    public static final int SIDES = 4;
+    public static final long BIG = 123456789012L;
+    public static final float RATIO = 1.5f;
+    public static final double TAU = 6.283185307179586;
+    public static final String NAME = "shape\t\"quoted\"\n";
+    private static final double HUGE = 1e300;
+    private static final float TINY = 1e-5f;
+    protected volatile int count;
+    transient long[][] grid = new long[3][4];
+
+    public abstract double area();
+
+    public int compareTo(Shapes other) {
+        return Double.compare(area(), other.area());
+    }
+
+    static int classify(int kind) {
+        switch (kind) {
+            case 0: return 10;
+            case 1: return 11;
+            case 2: return 12;
+            default: return -1;
+        }
+    }
+
+    static int sparse(int kind) {
+        switch (kind) {
+            case -100: return 1;
+            case 7: return 2;
+            case 100000: return 3;
+            default: return 0;
+        }
+    }
+
+    synchronized int safeDivide(int a, int b) {
+        try {
+            return a / b;
+        } catch (ArithmeticException e) {
+            return 0;
+        } finally {
+            count++;
+        }
+    }
+
+    static long manyLocals(long seed) {
+        int l0 = 0, l1 = 1, l2 = 2, l3 = 3, l4 = 4, l5 = 5, l6 = 6, l7 = 7, l8 = 8, l9 = 9;
+        int[] big = new int[300];
+        long acc = seed + 70000;
+        for (int i = 0; i < big.length; i++) {
+            big[i] = i * 3 + l9;
+            acc += big[i];
+        }
+        int w = (int) acc;
+        w += 1000;
+        Object o = big;
+        if (o instanceof int[]) {
+            acc -= ((int[]) o).length;
+        }
+        String[] names = new String[2];
+        return acc + names.length + w;
+    }
+
+    static native void nativeMethod(String... args);
+
+    protected final Object copy() throws CloneNotSupportedException, IllegalStateException {
+        return clone();
+    }
+
+    public static void main(String[] args) throws Exception {
+        char c = 'x';
+        short s = 300;
+        byte b = -5;
+        boolean flag = args == null;
+        float f = 2.5f * c;
+        double d = 1.0 / 3;
+        if (flag && s > b) {
+            throw new IllegalStateException(NAME);
+        }
+        Object o = new Object[] { c, f, d };
+        System.out.println(o);
+    }
+
+    static {
+        System.out.println(SIDES);
+    }
+}
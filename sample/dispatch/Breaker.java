@@ -0,0 +1,6 @@
+// compiled when Sealed.value was not final
+class Breaker extends Sealed {
+    int value() {
+        return 2;
+    }
+}
@@ -0,0 +1,9 @@
+class Calls {
+    static int middle(int unused) {
+        return new second.Middle().callId() * 10 + new second.Middle().callMiddleId();
+    }
+
+    static int leaf(int unused) {
+        return new first.Leaf().callId() * 10 + new first.Leaf().callMiddleId();
+    }
+}
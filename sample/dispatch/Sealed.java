@@ -0,0 +1,5 @@
+class Sealed {
+    final int value() {
+        return 1;
+    }
+}
@@ -0,0 +1,12 @@
+package first;
+
+public class Base {
+    // package private, so only the classes of first can override it
+    int id() {
+        return 1;
+    }
+
+    public int callId() {
+        return id();
+    }
+}
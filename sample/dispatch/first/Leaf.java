@@ -0,0 +1,8 @@
+package first;
+
+// overrides Base.id, but not Middle.id
+public class Leaf extends second.Middle {
+    int id() {
+        return 3;
+    }
+}
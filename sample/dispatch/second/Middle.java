@@ -0,0 +1,12 @@
+package second;
+
+// doesn't override Base.id, so it gets a new slot in the vtable
+public class Middle extends first.Base {
+    int id() {
+        return 2;
+    }
+
+    public int callMiddleId() {
+        return id();
+    }
+}
@@ -0,0 +1,81 @@
+public class Faults {
+    static int nullArray() {
+        int[] values = null;
+        return values.length;
+    }
+
+    static int outOfBounds(int index) {
+        int[] values = new int[3];
+        return values[index];
+    }
+
+    static int caughtOutOfBounds(int index) {
+        int[] values = new int[3];
+        try {
+            return values[index];
+        } catch (ArrayIndexOutOfBoundsException exception) {
+            return -1;
+        }
+    }
+
+    static int negativeSize(int size) {
+        int[] values = new int[size];
+        return values.length;
+    }
+
+    static int divide(int divisor) {
+        return 100 / divisor;
+    }
+
+    static int remainder(int divisor) {
+        return -100 % divisor;
+    }
+
+    static Object[] arrayStore() {
+        Object[] strings = new String[1];
+        try {
+            int[] values = null;
+            values[0] = 1;
+        } catch (NullPointerException exception) {
+            strings[0] = exception;
+        }
+        return strings;
+    }
+
+    static String classCast() {
+        Object object = null;
+        try {
+            int[] values = null;
+            values[0] = 1;
+        } catch (NullPointerException exception) {
+            object = exception;
+        }
+        return (String) object;
+    }
+
+    static void nested() {
+        fail();
+    }
+
+    static void fail() {
+        throw new IllegalStateException();
+    }
+
+    static int depth;
+
+    static void recurse() {
+        depth++;
+        recurse();
+    }
+
+    // the StackOverflowError thrown by the innermost call is caught here
+    static int stackOverflow() {
+        depth = 0;
+        try {
+            recurse();
+        } catch (StackOverflowError error) {
+            return depth;
+        }
+        return -1;
+    }
+}
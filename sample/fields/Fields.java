@@ -0,0 +1,21 @@
+public class Fields {
+    static boolean isDefault(Point3 point) {
+        return point.x == 0 && point.y == 0 && point.z == 0 && point.weight == 0 && point.name == null;
+    }
+
+    static long fill(Point3 point) {
+        point.x = 1;
+        point.y = 2;
+        point.z = 3000000000L;
+        point.weight = 0.5;
+        return point.x + point.y + point.z;
+    }
+
+    static double weight(Point3 point) {
+        return point.weight * 2;
+    }
+
+    static int nullX(Point point) {
+        return point.x;
+    }
+}
@@ -0,0 +1,4 @@
+public class Point {
+    int x;
+    int y;
+}
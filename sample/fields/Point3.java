@@ -0,0 +1,7 @@
+public class Point3 extends Point {
+    static int created;
+    long z;
+    double weight;
+    String name;
+    int[] values;
+}
@@ -0,0 +1,3 @@
+public abstract class Shape {
+    int sides;
+}
@@ -0,0 +1,46 @@
+public class Cycles {
+    static class Node {
+        Node previous;
+        Node next;
+        long[] payload = new long[16];
+    }
+
+    static Node kept;
+
+    // every pair of nodes references each other, and is dropped at the next iteration
+    public static int pairs(int count) {
+        int created = 0;
+        for (int i = 0; i < count; i++) {
+            Node first = new Node();
+            Node second = new Node();
+            first.next = second;
+            second.previous = first;
+            Object[] self = new Object[1];
+            self[0] = self;
+            created += 2;
+        }
+        return created;
+    }
+
+    // the nodes kept by a static field survive the collections
+    public static int keep() {
+        kept = new Node();
+        kept.next = new Node();
+        kept.next.previous = kept;
+        System.gc();
+        return kept.next.previous == kept ? kept.payload.length : -1;
+    }
+
+    public static String exhaust() {
+        long[][] blocks = new long[1024][];
+        try {
+            for (int i = 0; i < blocks.length; i++) {
+                blocks[i] = new long[64 * 1024];
+            }
+        } catch (OutOfMemoryError e) {
+            blocks = null;
+            return e.getMessage();
+        }
+        return null;
+    }
+}
@@ -0,0 +1,18 @@
+abstract class Animal {
+    int legs;
+
+    Animal(int legs) {
+        this.legs = legs;
+    }
+
+    int legs() {
+        return legs;
+    }
+
+    abstract int sound();
+
+    // dispatched on the class of the receiver
+    int describe() {
+        return legs() * 10 + sound();
+    }
+}
@@ -0,0 +1,14 @@
+class Bird extends Animal {
+    Bird() {
+        super(2);
+    }
+
+    // counts the wings too
+    int legs() {
+        return super.legs() + 2;
+    }
+
+    int sound() {
+        return 2;
+    }
+}
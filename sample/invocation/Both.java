@@ -0,0 +1,5 @@
+class Both implements Left, Right {
+    public int which() {
+        return Left.super.which() + Right.super.which() * 10;
+    }
+}
@@ -0,0 +1,51 @@
+class Calls {
+    // the order of the arguments matters, and longs and doubles take two slots
+    static long mix(int a, long b, double c, int d) {
+        return a * 1000L + b * 100 + (long) c * 10 + d;
+    }
+
+    static long callMix(int a) {
+        return mix(a, 2L, 3.0, 4);
+    }
+
+    static int factorial(int n) {
+        return n <= 1 ? 1 : n * factorial(n - 1);
+    }
+
+    static int describe(boolean dog) {
+        Animal animal = dog ? new Dog() : new Bird();
+        return animal.describe();
+    }
+
+    static int area(int side) {
+        Shape shape = new Square(side);
+        return shape.doubled() + Shape.unit();
+    }
+
+    static int both(int unused) {
+        Left left = new Both();
+        return left.which();
+    }
+
+    static int conflict(int unused) {
+        Left left = new Conflict();
+        return left.which();
+    }
+
+    static int nullReceiver(Animal animal) {
+        return animal.legs();
+    }
+
+    static int divide(int divisor) {
+        return 100 / divisor;
+    }
+
+    // the exception thrown by the callee is caught by the caller
+    static int safeDivide(int divisor) {
+        try {
+            return divide(divisor);
+        } catch (ArithmeticException e) {
+            return -1;
+        }
+    }
+}
@@ -0,0 +1,2 @@
+// compiled when Third had no default method, so which() is now ambiguous
+class Conflict implements Left, Third {}
@@ -0,0 +1,9 @@
+class Dog extends Animal {
+    Dog() {
+        super(4);
+    }
+
+    int sound() {
+        return 1;
+    }
+}
@@ -0,0 +1,5 @@
+interface Left {
+    default int which() {
+        return 1;
+    }
+}
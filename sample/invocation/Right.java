@@ -0,0 +1,5 @@
+interface Right {
+    default int which() {
+        return 2;
+    }
+}
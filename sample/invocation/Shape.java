@@ -0,0 +1,11 @@
+interface Shape {
+    int area();
+
+    default int doubled() {
+        return area() * 2;
+    }
+
+    static int unit() {
+        return 1;
+    }
+}
@@ -0,0 +1,11 @@
+class Square implements Shape {
+    int side;
+
+    Square(int side) {
+        this.side = side;
+    }
+
+    public int area() {
+        return side * side;
+    }
+}
@@ -0,0 +1,6 @@
+// Conflict was compiled before this default method was added
+interface Third {
+    default int which() {
+        return 3;
+    }
+}
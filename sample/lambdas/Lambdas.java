@@ -0,0 +1,135 @@
+import java.util.function.BiFunction;
+import java.util.function.Consumer;
+import java.util.function.Function;
+import java.util.function.IntBinaryOperator;
+import java.util.function.Predicate;
+import java.util.function.Supplier;
+
+public class Lambdas {
+    interface Marker {}
+
+    // the lambdas get a bridge for Function.apply(Object)
+    interface StringFunction extends Function<String, String> {
+        String apply(String s);
+    }
+
+    static class Counter {
+        int count;
+
+        Counter() {
+            count = 1;
+        }
+
+        Counter(int count) {
+            this.count = count;
+        }
+
+        // captures this
+        Runnable incrementer(int step) {
+            return () -> count += step;
+        }
+    }
+
+    // the array is captured, and updated by every run
+    public static int runnable() {
+        int[] box = new int[1];
+        Runnable runnable = () -> box[0] += 5;
+        runnable.run();
+        runnable.run();
+        return box[0];
+    }
+
+    public static int function(int value) {
+        Function<Integer, Integer> twice = x -> x * 2;
+        return twice.andThen(x -> x + 1).apply(value);
+    }
+
+    // the captured long and double take two slots
+    public static long captureWide(long a, int b, double c) {
+        Supplier<Long> supplier = () -> a + b + (long) c;
+        return supplier.get();
+    }
+
+    // a static method returning an int, boxed by the lambda
+    public static int staticReference(String text) {
+        Function<String, Integer> parse = Integer::parseInt;
+        return parse.apply(text);
+    }
+
+    // the receiver is captured
+    public static String boundReference(String prefix, String suffix) {
+        Function<String, String> concat = prefix::concat;
+        return concat.apply(suffix);
+    }
+
+    // the receiver is the first argument of the lambda
+    public static int unboundReference(String text) {
+        Function<String, Integer> length = String::length;
+        return length.apply(text);
+    }
+
+    public static int constructorReference() {
+        Supplier<Counter> create = Counter::new;
+        Function<Integer, Counter> createWith = Counter::new;
+        return create.get().count + createWith.apply(41).count;
+    }
+
+    // the ints are passed without boxing to a native method
+    public static int primitives(int a, int b) {
+        IntBinaryOperator max = Math::max;
+        return max.applyAsInt(a, b);
+    }
+
+    public static int capturedThis() {
+        Counter counter = new Counter(0);
+        Runnable increment = counter.incrementer(3);
+        increment.run();
+        increment.run();
+        return counter.count;
+    }
+
+    public static boolean predicates(int value) {
+        Predicate<Integer> positive = x -> x > 0;
+        Predicate<Integer> even = x -> x % 2 == 0;
+        return positive.and(even.negate()).test(value);
+    }
+
+    public static boolean identity(String text) {
+        return Function.<String>identity().apply(text) == text;
+    }
+
+    public static String biFunction(String a, String b) {
+        BiFunction<String, String, String> concat = String::concat;
+        return concat.andThen(s -> s.concat("!")).apply(a, b);
+    }
+
+    public static int consumers() {
+        int[] box = new int[1];
+        Consumer<Integer> add = x -> box[0] += x;
+        Consumer<Integer> twice = add.andThen(add);
+        twice.accept(21);
+        return box[0];
+    }
+
+    // made with altMetafactory
+    public static boolean marker() {
+        Runnable runnable = (Runnable & Marker) () -> {};
+        return runnable instanceof Marker;
+    }
+
+    public static String bridge(String text) {
+        StringFunction exclaim = s -> s.concat("!");
+        Function<String, String> function = exclaim;
+        return function.apply(text);
+    }
+
+    // each evaluation creates a new instance with its own captured value
+    public static int distinctCaptures() {
+        Supplier<Integer>[] suppliers = new Supplier[3];
+        for (int i = 0; i < suppliers.length; i++) {
+            int captured = i * 10;
+            suppliers[i] = () -> captured;
+        }
+        return suppliers[0].get() + suppliers[1].get() + suppliers[2].get();
+    }
+}
@@ -0,0 +1,8 @@
+public class Main {
+    public static void main(String[] args) {
+        int sum = 0;
+        for (int i = 0; i < args.length; i++) {
+            sum += i;
+        }
+    }
+}
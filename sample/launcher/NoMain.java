@@ -0,0 +1,4 @@
+public class NoMain {
+    // not public, so it can't be used as an entry point
+    static void main(String[] args) {}
+}
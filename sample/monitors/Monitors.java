@@ -0,0 +1,197 @@
+public class Monitors {
+    static int staticCount;
+
+    static synchronized void incrementStatic() {
+        staticCount++;
+    }
+
+    static class Counter implements Runnable {
+        final int[] shared;
+        final int times;
+
+        Counter(int[] shared, int times) {
+            this.shared = shared;
+            this.times = times;
+        }
+
+        public void run() {
+            for (int i = 0; i < times; i++) {
+                // an array is locked like any other object
+                synchronized (shared) {
+                    shared[0]++;
+                }
+                incrementStatic();
+            }
+        }
+    }
+
+    // every increment is made while holding the monitor, none of them is lost
+    public static int count(int threads, int times) throws InterruptedException {
+        int[] shared = new int[1];
+        staticCount = 0;
+        Thread[] started = new Thread[threads];
+        for (int i = 0; i < threads; i++) {
+            started[i] = new Thread(new Counter(shared, times));
+            started[i].start();
+        }
+        for (int i = 0; i < threads; i++) {
+            started[i].join();
+        }
+        return shared[0] == staticCount ? shared[0] : -1;
+    }
+
+    static class Reentrant {
+        int depth;
+
+        synchronized int enter(int times) {
+            if (times == 0) {
+                return depth;
+            }
+            depth++;
+            return enter(times - 1);
+        }
+
+        synchronized void fail() {
+            throw new IllegalStateException("failed");
+        }
+    }
+
+    static class Locker implements Runnable {
+        final Object lock;
+        boolean locked;
+
+        Locker(Object lock) {
+            this.lock = lock;
+        }
+
+        public void run() {
+            synchronized (lock) {
+                locked = true;
+            }
+        }
+    }
+
+    public static int reentrant() {
+        return new Reentrant().enter(10);
+    }
+
+    // the monitor is released when the synchronized method throws
+    public static boolean releasedOnThrow() throws InterruptedException {
+        Reentrant reentrant = new Reentrant();
+        try {
+            reentrant.fail();
+        } catch (IllegalStateException e) {
+        }
+        Locker locker = new Locker(reentrant);
+        Thread thread = new Thread(locker);
+        thread.start();
+        thread.join();
+        return locker.locked;
+    }
+
+    public static String notOwner() {
+        try {
+            new Object().notify();
+            return null;
+        } catch (IllegalMonitorStateException e) {
+            return e.getMessage();
+        }
+    }
+
+    static class Channel {
+        private int value;
+        private boolean full;
+
+        synchronized void put(int value) throws InterruptedException {
+            while (full) {
+                wait();
+            }
+            this.value = value;
+            full = true;
+            notifyAll();
+        }
+
+        synchronized int take() throws InterruptedException {
+            while (!full) {
+                wait();
+            }
+            full = false;
+            notifyAll();
+            return value;
+        }
+    }
+
+    static class Producer implements Runnable {
+        final Channel channel;
+        final int count;
+
+        Producer(Channel channel, int count) {
+            this.channel = channel;
+            this.count = count;
+        }
+
+        public void run() {
+            try {
+                for (int i = 1; i <= count; i++) {
+                    channel.put(i);
+                }
+            } catch (InterruptedException e) {
+            }
+        }
+    }
+
+    public static int produce(int count) throws InterruptedException {
+        Channel channel = new Channel();
+        Thread producer = new Thread(new Producer(channel, count));
+        producer.start();
+        int sum = 0;
+        for (int i = 0; i < count; i++) {
+            sum += channel.take();
+        }
+        producer.join();
+        return sum;
+    }
+
+    public static boolean timedWait() throws InterruptedException {
+        Object lock = new Object();
+        long start = System.nanoTime();
+        synchronized (lock) {
+            lock.wait(50);
+        }
+        int elapsedMillis = (int) ((System.nanoTime() - start) / 1000000);
+        return elapsedMillis >= 50;
+    }
+
+    static class Waiter extends Thread {
+        final Object lock = new Object();
+        boolean waiting;
+        String result;
+
+        public void run() {
+            synchronized (lock) {
+                waiting = true;
+                lock.notifyAll();
+                try {
+                    lock.wait();
+                    result = "notified";
+                } catch (InterruptedException e) {
+                    result = "interrupted";
+                }
+            }
+        }
+    }
+
+    public static String interruptedWait() throws InterruptedException {
+        Waiter waiter = new Waiter();
+        waiter.start();
+        synchronized (waiter.lock) {
+            while (!waiter.waiting) {
+                waiter.lock.wait();
+            }
+        }
+        // the waiter has released the lock, it is waiting
+        waiter.interrupt();
+        waiter.join();
+        return waiter.result;
+    }
+}
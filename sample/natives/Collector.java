@@ -0,0 +1,26 @@
+import java.io.OutputStream;
+
+// keeps the bytes written to it, to check what a PrintStream writes
+public class Collector extends OutputStream {
+    private byte[] bytes = new byte[4];
+    private int count;
+
+    public void write(int b) {
+        if (count == bytes.length) {
+            byte[] grown = new byte[count * 2];
+            System.arraycopy(bytes, 0, grown, 0, count);
+            bytes = grown;
+        }
+        bytes[count] = (byte) b;
+        count++;
+    }
+
+    // the bytes are ASCII
+    public String toString() {
+        char[] chars = new char[count];
+        for (int i = 0; i < count; i++) {
+            chars[i] = (char) bytes[i];
+        }
+        return new String(chars);
+    }
+}
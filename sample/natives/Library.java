@@ -0,0 +1,84 @@
+import java.io.PrintStream;
+
+public class Library {
+    static String build(int i, long l, double d, boolean b) {
+        return new StringBuilder().append(i).append(',').append(l).append(',').append(d)
+                .append(',').append(b).toString();
+    }
+
+    static String print(int i, char c, Object o) {
+        Collector collector = new Collector();
+        PrintStream stream = new PrintStream(collector);
+        stream.print(i);
+        stream.print(c);
+        stream.println(o);
+        return collector.toString();
+    }
+
+    static int boxing(int i) {
+        Integer boxed = i;
+        Integer other = Integer.valueOf(i);
+        return (boxed == other ? 1 : 0) + (boxed.equals(other) ? 2 : 0);
+    }
+
+    static long longBoxing(long l) {
+        Long boxed = l;
+        return boxed.longValue() + Long.valueOf(l).hashCode();
+    }
+
+    static double math(double x) {
+        return Math.sqrt(x) + Math.max(-0.0, 0.0) + Math.abs(-2) + Math.pow(2, 10);
+    }
+
+    static int copy() {
+        int[] values = {1, 2, 3, 4, 5};
+        System.arraycopy(values, 0, values, 1, 4);
+        return values[0] * 10000 + values[1] * 1000 + values[2] * 100 + values[3] * 10 + values[4];
+    }
+
+    static int copyOutOfBounds() {
+        System.arraycopy(new int[2], 0, new int[3], 2, 2);
+        return 0;
+    }
+
+    static int parse(String s) {
+        return Integer.parseInt(s);
+    }
+
+    static String parseError(String s) {
+        try {
+            return String.valueOf(Integer.parseInt(s));
+        } catch (NumberFormatException e) {
+            return e.toString();
+        }
+    }
+
+    static String describe(Object o) {
+        return String.valueOf(o);
+    }
+
+    static int stringHash(String s) {
+        return s.hashCode();
+    }
+
+    static boolean sameText(String s) {
+        return s.equals(new String(s.toCharArray())) && s != new String(s.toCharArray());
+    }
+
+    static String property(String key) {
+        return System.getProperty(key);
+    }
+
+    static Throwable chained(String message) {
+        return new IllegalStateException(message, new ArithmeticException());
+    }
+
+    static native int missing();
+
+    // registered by the tests
+    static native long twice(long l, int i);
+
+    static long callTwice(long l) {
+        return twice(l, 3) + 1;
+    }
+}
@@ -0,0 +1,35 @@
+// Gone.class is deleted after compiling, so every reference to Gone fails to resolve
+class Gone {
+    static int value;
+
+    static void run() {}
+}
+
+public class Resolution {
+    static String newInstance() {
+        try {
+            new Gone();
+        } catch (NoClassDefFoundError error) {
+            return error.getMessage();
+        }
+        return null;
+    }
+
+    static String staticField() {
+        try {
+            Gone.value = 1;
+        } catch (NoClassDefFoundError error) {
+            return error.getMessage();
+        }
+        return null;
+    }
+
+    static String staticMethod() {
+        try {
+            Gone.run();
+        } catch (NoClassDefFoundError error) {
+            return error.getMessage();
+        }
+        return null;
+    }
+}
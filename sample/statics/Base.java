@@ -0,0 +1,8 @@
+class Base {
+    // each initializer appends its digit, so the order can be checked
+    static int order;
+
+    static {
+        order = order * 10 + 1;
+    }
+}
@@ -0,0 +1,4 @@
+class Broken {
+    static int zero;
+    static int value = 1 / zero;
+}
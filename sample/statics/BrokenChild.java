@@ -0,0 +1,3 @@
+class BrokenChild extends Broken {
+    static int other = 3;
+}
@@ -0,0 +1,9 @@
+// the final fields are set from their ConstantValue attribute, there is no <clinit>
+class Constants {
+    static final int ANSWER = 42;
+    static final long BIG = 1L << 40;
+    static final float RATIO = 1.5f;
+    static final double HALF = 0.5;
+    static long counter;
+    static int after;
+}
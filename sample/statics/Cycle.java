@@ -0,0 +1,4 @@
+// Cycle is still being initialized when Other reads a, so it sees 0
+class Cycle {
+    static int a = Other.b + 1;
+}
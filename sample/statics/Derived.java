@@ -0,0 +1,8 @@
+class Derived extends Base {
+    static int seen;
+
+    static {
+        order = order * 10 + 2;
+        seen = order;
+    }
+}
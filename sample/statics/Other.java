@@ -0,0 +1,3 @@
+class Other {
+    static int b = Cycle.a + 10;
+}
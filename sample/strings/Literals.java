@@ -0,0 +1,7 @@
+public class Literals {
+    interface Named {}
+
+    public static String greeting() {
+        return "hello";
+    }
+}
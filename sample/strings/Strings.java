@@ -0,0 +1,54 @@
+public class Strings {
+    public static final String GREETING = "hello";
+
+    public static String literal() {
+        return "hello";
+    }
+
+    // the same literal in another class is the same object
+    public static boolean sameLiteral() {
+        return literal() == Literals.greeting();
+    }
+
+    public static boolean interned() {
+        String built = new String(new char[] {'h', 'e', 'l', 'l', 'o'});
+        return built != "hello" && built.intern() == "hello";
+    }
+
+    public static int nulCharacter() {
+        String text = "a\0b";
+        return text.length() * 100 + text.charAt(1);
+    }
+
+    // encoded as a surrogate pair in the class file
+    public static int supplementary() {
+        String text = "😀";
+        return text.length() * 100000 + text.charAt(1);
+    }
+
+    // a surrogate that isn't part of a pair, kept in the char[] of the string
+    public static String loneSurrogate() {
+        return "a\uD83D";
+    }
+
+    public static String accents() {
+        return "héllo €";
+    }
+
+    public static int numbers() {
+        float f = 2.5f;
+        return 123456 + (int) (f * 2);
+    }
+
+    public static Class<?> classLiteral() {
+        return Strings.class;
+    }
+
+    public static boolean sameClass() {
+        return Strings.class == classLiteral();
+    }
+
+    public static String className() {
+        return Literals.Named.class.toString().concat(String.class.getName());
+    }
+}
@@ -0,0 +1,48 @@
+interface Named {
+    String name();
+}
+
+interface Pet extends Named {}
+
+class Animal {}
+
+class Dog extends Animal implements Pet, Runnable {
+    public String name() {
+        return "Rex";
+    }
+
+    public void run() {}
+}
+
+class Puppy extends Dog {}
+
+public class Subtypes {
+    // every check that holds adds its bit
+    public static int instances() {
+        Object puppy = new Puppy();
+        Object animal = new Animal();
+        int bits = 0;
+        if (puppy instanceof Runnable) bits |= 1;
+        if (puppy instanceof Named) bits |= 2;
+        if (puppy instanceof Animal) bits |= 4;
+        if (animal instanceof Pet) bits |= 8;
+        if (new Pet[0] instanceof Named[]) bits |= 16;
+        if (new Dog[0] instanceof Runnable[]) bits |= 32;
+        if (new Named[0] instanceof Pet[]) bits |= 64;
+        return bits;
+    }
+
+    public static String name() {
+        Object puppy = new Puppy();
+        return ((Named) puppy).name();
+    }
+
+    public static String cast() {
+        Object animal = new Animal();
+        try {
+            return ((Pet) animal).name();
+        } catch (ClassCastException e) {
+            return e.getMessage();
+        }
+    }
+}
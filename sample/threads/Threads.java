@@ -0,0 +1,121 @@
+public class Threads {
+    static class Summer implements Runnable {
+        final long[] results;
+        final int index;
+        final int count;
+
+        Summer(long[] results, int index, int count) {
+            this.results = results;
+            this.index = index;
+            this.count = count;
+        }
+
+        public void run() {
+            long sum = 0;
+            for (int i = 1; i <= count; i++) {
+                sum += i;
+            }
+            results[index] = sum;
+        }
+    }
+
+    static class Sleeper extends Thread {
+        String message;
+
+        public void run() {
+            try {
+                Thread.sleep(60000);
+                message = "slept";
+            } catch (InterruptedException e) {
+                message = e.getMessage();
+            }
+        }
+    }
+
+    static class Thrower implements Runnable {
+        public void run() {
+            throw new IllegalStateException("boom");
+        }
+    }
+
+    static class Recorder implements Thread.UncaughtExceptionHandler {
+        String recorded;
+
+        public void uncaughtException(Thread t, Throwable e) {
+            recorded = t.getName().concat(": ").concat(e.getMessage());
+        }
+    }
+
+    // every thread writes its own slot, the results are read once they are joined
+    public static long sum(int threads, int count) throws InterruptedException {
+        long[] results = new long[threads];
+        Thread[] started = new Thread[threads];
+        for (int i = 0; i < threads; i++) {
+            started[i] = new Thread(new Summer(results, i, count));
+            started[i].start();
+        }
+        long total = 0;
+        for (int i = 0; i < threads; i++) {
+            started[i].join();
+            total += results[i];
+        }
+        return total;
+    }
+
+    public static String names() {
+        Thread named = new Thread("worker");
+        Thread unnamed = new Thread();
+        String prefix = new String(unnamed.getName().toCharArray(), 0, 7);
+        return Thread.currentThread().getName()
+                .concat(" ")
+                .concat(named.getName())
+                .concat(" ")
+                .concat(prefix)
+                .concat(" ")
+                .concat(named.toString());
+    }
+
+    public static String interruptSleep() throws InterruptedException {
+        Sleeper sleeper = new Sleeper();
+        sleeper.start();
+        sleeper.interrupt();
+        sleeper.join();
+        return sleeper.message;
+    }
+
+    public static String uncaught() throws InterruptedException {
+        Recorder recorder = new Recorder();
+        Thread thread = new Thread(new Thrower(), "thrower");
+        thread.setUncaughtExceptionHandler(recorder);
+        thread.start();
+        thread.join();
+        return recorder.recorded;
+    }
+
+    public static String lifecycle() throws InterruptedException {
+        Thread thread = new Thread(new Summer(new long[1], 0, 10));
+        boolean before = thread.isAlive();
+        thread.start();
+        thread.join();
+        boolean after = thread.isAlive();
+        try {
+            thread.start();
+            return "started twice";
+        } catch (IllegalThreadStateException e) {
+            return String.valueOf(before).concat(" ").concat(String.valueOf(after));
+        }
+    }
+
+    // the interrupt status is cleared by Thread.interrupted
+    public static String selfInterrupt() {
+        Thread.currentThread().interrupt();
+        boolean interrupted = Thread.currentThread().isInterrupted();
+        boolean first = Thread.interrupted();
+        boolean second = Thread.interrupted();
+        return String.valueOf(interrupted)
+                .concat(" ")
+                .concat(String.valueOf(first))
+                .concat(" ")
+                .concat(String.valueOf(second));
+    }
+}
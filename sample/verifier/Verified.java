@@ -0,0 +1,29 @@
+public class Verified {
+    private int value;
+
+    public Verified(int value) {
+        this.value = value;
+    }
+
+    static int add(int a, int b) {
+        return a + b;
+    }
+
+    static int twice(int a) {
+        int b = a * 2;
+        return b;
+    }
+
+    static int sum(int[] values) {
+        int total = 0;
+        for (int i = 0; i < values.length; i++) {
+            total += values[i];
+        }
+        return total;
+    }
+
+    static int create() {
+        Verified verified = new Verified(1);
+        return verified.value;
+    }
+}
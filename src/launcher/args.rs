@@ -0,0 +1,141 @@
+use std::{collections::HashMap, env, path::PathBuf};
+
+pub const USAGE: &str = "\
+Usage: custom_jvm [options] <main class> [args...]
+           (to execute a class)
+   or  custom_jvm [options] -jar <jar file> [args...]
+           (to execute a jar file)
+   or  custom_jvm disasm <class file>...
+           (to print the content of class files like javap -c -v -p)
+   or  custom_jvm rewrite <class file> <output file>
+           (to parse a class file and write it back)
+
+Options:
+    -cp, -classpath, --class-path <path:path...>
+                  directories and jar files to search for class files
+    -D<name>=<value>
+                  set a system property
+    -Xmx<size>    set the maximum size of the heap, in bytes or with a k, m or g suffix
+    -verbose:class
+                  print a message for each loaded class
+    -verbose:gc   print a message for each garbage collection";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MainTarget {
+    // binary name, with '/' separators
+    Class(String),
+    // the main class is read from the manifest
+    Jar(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchOptions {
+    pub classpath: Vec<PathBuf>,
+    pub main_target: MainTarget,
+    pub arguments: Vec<String>,
+    pub system_properties: HashMap<String, String>,
+    pub verbose_class: bool,
+    pub max_heap_size: Option<usize>,
+    pub verbose_gc: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgsError {
+    MissingMainClass,
+    MissingValue(String),
+    InvalidProperty(String),
+    InvalidHeapSize(String),
+    UnknownOption(String),
+}
+
+fn parse_property(
+    property: &str,
+    system_properties: &mut HashMap<String, String>,
+) -> Result<(), ArgsError> {
+    // -Dkey is valid and set the property to an empty string
+    let (key, value) = property.split_once('=').unwrap_or((property, ""));
+    if key.is_empty() {
+        return Err(ArgsError::InvalidProperty(property.to_string()));
+    }
+    system_properties.insert(key.to_string(), value.to_string());
+    Ok(())
+}
+
+// a size like 64m, the suffix is case insensitive like in HotSpot
+fn parse_size(size: &str) -> Option<usize> {
+    let (digits, unit) = match size.char_indices().last()? {
+        (index, 'k' | 'K') => (&size[..index], 1024),
+        (index, 'm' | 'M') => (&size[..index], 1024 * 1024),
+        (index, 'g' | 'G') => (&size[..index], 1024 * 1024 * 1024),
+        _ => (size, 1),
+    };
+    if digits.is_empty() || !digits.chars().all(|char| char.is_ascii_digit()) {
+        return None;
+    }
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|size| size.checked_mul(unit))
+        .filter(|&size| size > 0)
+}
+
+/// Parse the command line arguments, without the program name
+///
+/// Options are only parsed until the main class or the jar file,
+/// everything after is given to the java program.
+pub fn parse_args<I>(args: I) -> Result<LaunchOptions, ArgsError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter();
+    // the default classpath is the current directory
+    let mut classpath = vec![PathBuf::from(".")];
+    let mut system_properties = HashMap::new();
+    let mut verbose_class = false;
+    let mut max_heap_size = None;
+    let mut verbose_gc = false;
+
+    let main_target = loop {
+        let Some(arg) = args.next() else {
+            return Err(ArgsError::MissingMainClass);
+        };
+        match arg.as_str() {
+            "-cp" | "-classpath" | "--class-path" => {
+                let paths = args.next().ok_or(ArgsError::MissingValue(arg))?;
+                classpath = env::split_paths(&paths).collect();
+            }
+            "-jar" => {
+                let jar = args.next().ok_or(ArgsError::MissingValue(arg))?;
+                break MainTarget::Jar(PathBuf::from(jar));
+            }
+            "-D" => {
+                let property = args.next().ok_or(ArgsError::MissingValue(arg))?;
+                parse_property(&property, &mut system_properties)?;
+            }
+            "-verbose:class" => verbose_class = true,
+            "-verbose:gc" => verbose_gc = true,
+            _ => {
+                if let Some(property) = arg.strip_prefix("-D") {
+                    parse_property(property, &mut system_properties)?;
+                } else if let Some(size) = arg.strip_prefix("-Xmx") {
+                    let size = parse_size(size).ok_or(ArgsError::InvalidHeapSize(arg.clone()))?;
+                    max_heap_size = Some(size);
+                } else if arg.starts_with('-') {
+                    return Err(ArgsError::UnknownOption(arg));
+                } else {
+                    break MainTarget::Class(arg.replace('.', "/"));
+                }
+            }
+        }
+    };
+
+    Ok(LaunchOptions {
+        classpath,
+        main_target,
+        arguments: args.collect(),
+        system_properties,
+        verbose_class,
+        max_heap_size,
+        verbose_gc,
+    })
+}
@@ -0,0 +1,408 @@
+pub mod args;
+
+#[cfg(test)]
+mod test;
+
+use std::{
+    fs::{self, File},
+    io,
+    io::{BufReader, Read},
+    panic,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+};
+
+use crate::{
+    parser::{
+        classfile::classfile::{parse_class_file, write_class_file, ClassFile},
+        disassembler::disassemble,
+        jar::{
+            manifest::{parse_manifest, MANIFEST_PATH},
+            zip::ZipArchive,
+        },
+        utils::ParseError,
+    },
+    runtime::{
+        class_loader::{ClassLoader, ClassLoadingError, ClassPathEntry},
+        jvm::Jvm,
+        threads::MAIN_THREAD_NAME,
+    },
+    runtime_types::{
+        describe_exception, get_exception_cause, get_stack_trace, new_string, Array, Class,
+        Exception, InternalError, Method, Object, Stack, STRING_CLASS, THREAD_STACK_SIZE,
+    },
+};
+
+use args::{ArgsError, LaunchOptions, MainTarget, USAGE};
+
+pub const EXIT_SUCCESS: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+
+const MAIN_METHOD_NAME: &str = "main";
+const MAIN_METHOD_DESCRIPTOR: &str = "([Ljava/lang/String;)V";
+
+#[derive(Debug)]
+pub enum LaunchError {
+    InvalidArgs(ArgsError),
+    InvalidClassPathEntry {
+        path: PathBuf,
+        error: ParseError,
+    },
+    NoMainManifestAttribute(PathBuf),
+    MainClassNotFound {
+        name: String,
+        error: ClassLoadingError,
+    },
+    MainMethodNotFound(String),
+    UncaughtException {
+        thread: String,
+        exception: Exception,
+    },
+    MainThreadNotStarted(io::Error),
+    Internal(InternalError),
+}
+
+impl From<ArgsError> for LaunchError {
+    fn from(error: ArgsError) -> Self {
+        LaunchError::InvalidArgs(error)
+    }
+}
+
+impl From<InternalError> for LaunchError {
+    fn from(error: InternalError) -> Self {
+        LaunchError::Internal(error)
+    }
+}
+
+fn open_classpath(paths: Vec<PathBuf>) -> Result<Vec<ClassPathEntry>, LaunchError> {
+    paths
+        .into_iter()
+        .map(|path| {
+            ClassPathEntry::open(path.clone())
+                .map_err(|error| LaunchError::InvalidClassPathEntry { path, error })
+        })
+        .collect()
+}
+
+fn read_jar_main_class(archive: &ZipArchive, path: &Path) -> Result<String, LaunchError> {
+    let invalid_jar = |error| LaunchError::InvalidClassPathEntry {
+        path: path.to_path_buf(),
+        error,
+    };
+    let manifest = archive
+        .read(MANIFEST_PATH)
+        .map_err(invalid_jar)?
+        .ok_or_else(|| LaunchError::NoMainManifestAttribute(path.to_path_buf()))?;
+    let manifest = String::from_utf8_lossy(&manifest);
+    parse_manifest(&manifest)
+        .get_main_class()
+        .ok_or_else(|| LaunchError::NoMainManifestAttribute(path.to_path_buf()))
+}
+
+/// Return the classpath and the binary name of the main class
+fn resolve_main_target(
+    options: &LaunchOptions,
+) -> Result<(Vec<ClassPathEntry>, String), LaunchError> {
+    match &options.main_target {
+        MainTarget::Class(name) => {
+            let classpath = open_classpath(options.classpath.clone())?;
+            Ok((classpath, name.clone()))
+        }
+        // like java, the classpath is ignored when running a jar
+        MainTarget::Jar(path) => {
+            let entry = ClassPathEntry::open(path.clone()).map_err(|error| {
+                LaunchError::InvalidClassPathEntry {
+                    path: path.clone(),
+                    error,
+                }
+            })?;
+            let main_class = match &entry {
+                ClassPathEntry::Jar(_, archive) => read_jar_main_class(archive, path)?,
+                ClassPathEntry::Directory(_) | ClassPathEntry::ClassLibrary => {
+                    return Err(LaunchError::NoMainManifestAttribute(path.clone()))
+                }
+            };
+            Ok((vec![entry], main_class))
+        }
+    }
+}
+
+fn find_main_method(class: &Class) -> Option<&Arc<Method>> {
+    class
+        .find_method(MAIN_METHOD_NAME, MAIN_METHOD_DESCRIPTOR)
+        .filter(|method| method.is_static() && method.is_public())
+}
+
+fn build_arguments_array(jvm: &Jvm, arguments: &[String]) -> Result<Array, LaunchError> {
+    let string_class = jvm
+        .class_loader()
+        .load_class(STRING_CLASS)
+        .map_err(|_| InternalError::ClassLoadingFailed(STRING_CLASS.to_string()))?;
+    let array = Array::new_reference(string_class, arguments.len());
+    jvm.heap().track_array(&array)?;
+    for (index, argument) in arguments.iter().enumerate() {
+        let argument =
+            new_string(jvm, argument)?.map_err(|exception| LaunchError::UncaughtException {
+                thread: MAIN_THREAD_NAME.to_string(),
+                exception,
+            })?;
+        let stored = array.store_index(jvm, index as i32, Object::Reference(Some(argument)))?;
+        stored.map_err(|_| InternalError::WrongType)?;
+    }
+    Ok(array)
+}
+
+/// Load the main class and run its main method until it returns,
+/// then return the exit code of the program
+pub fn launch(options: LaunchOptions) -> Result<i32, LaunchError> {
+    let (classpath, main_class_name) = resolve_main_target(&options)?;
+
+    let mut class_loader = ClassLoader::new(classpath);
+    class_loader.set_verbose(options.verbose_class);
+    let mut jvm = Jvm::new(class_loader, options.system_properties);
+    if let Some(max_heap_size) = options.max_heap_size {
+        jvm.heap_mut().set_max_size(max_heap_size);
+    }
+    jvm.heap_mut().set_verbose(options.verbose_gc);
+    let jvm = jvm.into_shared();
+
+    let main_class = jvm
+        .class_loader()
+        .load_class(&main_class_name)
+        .map_err(|error| LaunchError::MainClassNotFound {
+            name: main_class_name.clone(),
+            error,
+        })?;
+    let main_method = find_main_method(&main_class)
+        .ok_or_else(|| LaunchError::MainMethodNotFound(main_class_name.clone()))?;
+
+    // like invoking main would, initialize the class first
+    if let Err(exception) = main_class.initialize(&jvm)? {
+        return Err(LaunchError::UncaughtException {
+            thread: MAIN_THREAD_NAME.to_string(),
+            exception,
+        });
+    }
+
+    let arguments = build_arguments_array(&jvm, &options.arguments)?;
+    let mut stack = Stack::new(1);
+    stack.push(Object::Array(Some(arguments)));
+
+    let exit_code = match main_method.execute(&jvm, &mut stack)? {
+        Ok(_) => EXIT_SUCCESS,
+        // reported when main ends, while the other threads may still be running
+        Err(exception) => {
+            print_launch_error(&LaunchError::UncaughtException {
+                thread: MAIN_THREAD_NAME.to_string(),
+                exception,
+            });
+            EXIT_FAILURE
+        }
+    };
+    // like java, the program ends when its last thread that is not a daemon ends
+    jvm.threads().wait_for_non_daemon_threads()?;
+    Ok(exit_code)
+}
+
+/// Launch the program in a host thread with the stack size of the other Java threads,
+/// as the stack of the process main thread is too small for MAX_FRAMES frames
+fn launch_in_main_thread(options: LaunchOptions) -> Result<i32, LaunchError> {
+    let launched = thread::Builder::new()
+        .name(MAIN_THREAD_NAME.to_string())
+        .stack_size(THREAD_STACK_SIZE)
+        .spawn(move || launch(options))
+        .map_err(LaunchError::MainThreadNotStarted)?;
+    launched
+        .join()
+        .unwrap_or_else(|payload| panic::resume_unwind(payload))
+}
+
+fn binary_name_to_java(name: &str) -> String {
+    name.replace('/', ".")
+}
+
+fn print_launch_error(error: &LaunchError) {
+    match error {
+        LaunchError::InvalidArgs(error) => {
+            match error {
+                ArgsError::MissingMainClass => {}
+                ArgsError::MissingValue(option) => {
+                    eprintln!("Error: {} requires an argument", option)
+                }
+                ArgsError::InvalidProperty(property) => {
+                    eprintln!("Error: invalid system property -D{}", property)
+                }
+                ArgsError::InvalidHeapSize(option) => {
+                    eprintln!("Invalid maximum heap size: {}", option)
+                }
+                ArgsError::UnknownOption(option) => {
+                    eprintln!("Error: unrecognized option: {}", option)
+                }
+            }
+            eprintln!("{}", USAGE);
+        }
+        LaunchError::InvalidClassPathEntry { path, error } => {
+            eprintln!("Error: could not open {}: {:?}", path.display(), error)
+        }
+        LaunchError::NoMainManifestAttribute(path) => {
+            eprintln!("no main manifest attribute, in {}", path.display())
+        }
+        LaunchError::MainClassNotFound {
+            name,
+            error: ClassLoadingError::VerifyError(error),
+        } => {
+            eprintln!(
+                "Error: Unable to initialize main class {}",
+                binary_name_to_java(name)
+            );
+            eprintln!("Caused by: java.lang.VerifyError: {}", error);
+        }
+        LaunchError::MainClassNotFound { name, error } => {
+            eprintln!(
+                "Error: Could not find or load main class {}",
+                binary_name_to_java(name)
+            );
+            eprintln!("Caused by: {:?}", error);
+        }
+        LaunchError::MainMethodNotFound(name) => {
+            eprintln!(
+                "Error: Main method not found in class {}, please define the main method as:",
+                binary_name_to_java(name)
+            );
+            eprintln!("   public static void main(String[] args)");
+        }
+        LaunchError::UncaughtException { thread, exception } => {
+            eprint!("Exception in thread \"{}\" ", thread);
+            if let Err(error) = print_stack_trace(exception) {
+                eprintln!("Internal error in the virtual machine: {:?}", error);
+            }
+        }
+        LaunchError::MainThreadNotStarted(error) => {
+            eprintln!("Error: unable to create the main thread: {}", error)
+        }
+        LaunchError::Internal(error) => {
+            eprintln!("Internal error in the virtual machine: {:?}", error)
+        }
+    }
+}
+
+// like Throwable.printStackTrace, the exception then its causes with their frames
+fn print_stack_trace(exception: &Exception) -> Result<(), InternalError> {
+    eprintln!("{}", describe_exception(exception)?);
+    let mut frames = get_stack_trace(exception)?;
+    let mut cause = get_exception_cause(exception)?;
+    loop {
+        for frame in frames {
+            eprintln!("\tat {}", frame);
+        }
+        let Some(exception) = cause else {
+            return Ok(());
+        };
+        eprintln!("Caused by: {}", describe_exception(&exception)?);
+        frames = get_stack_trace(&exception)?;
+        cause = get_exception_cause(&exception)?;
+    }
+}
+
+const DISASSEMBLE_COMMAND: &str = "disasm";
+const REWRITE_COMMAND: &str = "rewrite";
+
+fn read_class_file(path: &Path) -> Result<ClassFile, ParseError> {
+    let file = File::open(path).map_err(ParseError::IoError)?;
+    let mut bytes = BufReader::new(file).bytes();
+    parse_class_file(&mut bytes)
+}
+
+fn disassemble_file(path: &Path) -> Result<Vec<String>, ParseError> {
+    disassemble(&read_class_file(path)?)
+}
+
+/// Print the class files like javap, and return the exit code of the process
+fn run_disassembler<I>(paths: I) -> i32
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut exit_code = EXIT_SUCCESS;
+    for path in paths.into_iter().map(PathBuf::from) {
+        match disassemble_file(&path) {
+            Ok(lines) => {
+                let path = fs::canonicalize(&path).unwrap_or(path);
+                println!("Classfile {}", path.display());
+                for line in lines {
+                    println!("{}", line);
+                }
+            }
+            Err(error) => {
+                eprintln!(
+                    "Error: could not disassemble {}: {:?}",
+                    path.display(),
+                    error
+                );
+                exit_code = EXIT_FAILURE;
+            }
+        }
+    }
+    exit_code
+}
+
+fn rewrite_file(input: &Path, output: &Path) -> Result<(), ParseError> {
+    let bytes = write_class_file(&read_class_file(input)?)?;
+    fs::write(output, bytes).map_err(ParseError::IoError)
+}
+
+/// Parse a class file and write it back with the class file writer,
+/// and return the exit code of the process
+///
+/// The written file has the same bytes, it checks the class file round trips.
+fn run_rewriter<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = String>,
+{
+    let args: Vec<String> = args.into_iter().collect();
+    let [input, output] = args.as_slice() else {
+        eprintln!(
+            "Error: {} requires an input and an output file",
+            REWRITE_COMMAND
+        );
+        eprintln!("{}", USAGE);
+        return EXIT_FAILURE;
+    };
+    match rewrite_file(Path::new(input), Path::new(output)) {
+        Ok(()) => EXIT_SUCCESS,
+        Err(error) => {
+            eprintln!("Error: could not rewrite {}: {:?}", input, error);
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// Run the launcher with the command line arguments (without the program name)
+/// and return the exit code of the process
+pub fn run<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter().peekable();
+    match args.peek().map(String::as_str) {
+        Some(DISASSEMBLE_COMMAND) => {
+            args.next();
+            return run_disassembler(args);
+        }
+        Some(REWRITE_COMMAND) => {
+            args.next();
+            return run_rewriter(args);
+        }
+        _ => {}
+    }
+    let result = args::parse_args(args)
+        .map_err(LaunchError::from)
+        .and_then(launch_in_main_thread);
+    match result {
+        Ok(exit_code) => exit_code,
+        Err(error) => {
+            print_launch_error(&error);
+            EXIT_FAILURE
+        }
+    }
+}
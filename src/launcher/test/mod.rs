@@ -0,0 +1,152 @@
+use std::{collections::HashMap, env, fs, path::PathBuf};
+
+use crate::runtime::class_loader::ClassLoadingError;
+
+use super::{
+    args::{parse_args, ArgsError, LaunchOptions, MainTarget},
+    launch, run, LaunchError, EXIT_FAILURE, EXIT_SUCCESS,
+};
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn test_parse_args() {
+    let options = parse_args(args(&[
+        "-cp",
+        "a:b.jar",
+        "-Dfoo=bar",
+        "-D",
+        "empty",
+        "-verbose:class",
+        "-Xmx64m",
+        "-verbose:gc",
+        "com.example.Main",
+        "-cp",
+        "arg",
+    ]))
+    .unwrap();
+
+    let expected = LaunchOptions {
+        classpath: vec![PathBuf::from("a"), PathBuf::from("b.jar")],
+        main_target: MainTarget::Class("com/example/Main".to_string()),
+        // options after the main class belong to the program
+        arguments: args(&["-cp", "arg"]),
+        system_properties: HashMap::from([
+            ("foo".to_string(), "bar".to_string()),
+            ("empty".to_string(), "".to_string()),
+        ]),
+        verbose_class: true,
+        max_heap_size: Some(64 * 1024 * 1024),
+        verbose_gc: true,
+    };
+    assert_eq!(options, expected);
+
+    let options = parse_args(args(&["-jar", "app.jar", "arg"])).unwrap();
+    assert_eq!(options.classpath, vec![PathBuf::from(".")]);
+    assert_eq!(
+        options.main_target,
+        MainTarget::Jar(PathBuf::from("app.jar"))
+    );
+    assert_eq!(options.arguments, args(&["arg"]));
+
+    let max_heap_size =
+        |size: &str| parse_args(args(&[size, "Main"])).map(|options| options.max_heap_size);
+    assert_eq!(max_heap_size("-Xmx4096"), Ok(Some(4096)));
+    assert_eq!(max_heap_size("-Xmx512K"), Ok(Some(512 * 1024)));
+    assert_eq!(max_heap_size("-Xmx2g"), Ok(Some(2 * 1024 * 1024 * 1024)));
+}
+
+#[test]
+fn test_parse_args_errors() {
+    assert_eq!(parse_args(args(&[])), Err(ArgsError::MissingMainClass));
+    assert_eq!(
+        parse_args(args(&["Main", "-cp"])).map(|options| options.arguments),
+        Ok(args(&["-cp"]))
+    );
+    assert_eq!(
+        parse_args(args(&["-cp"])),
+        Err(ArgsError::MissingValue("-cp".to_string()))
+    );
+    assert_eq!(
+        parse_args(args(&["-D=value", "Main"])),
+        Err(ArgsError::InvalidProperty("=value".to_string()))
+    );
+    for size in ["-Xmx", "-Xmx0", "-Xmxm", "-Xmx12q", "-Xmx-1k"] {
+        assert_eq!(
+            parse_args(args(&[size, "Main"])),
+            Err(ArgsError::InvalidHeapSize(size.to_string()))
+        );
+    }
+    assert_eq!(
+        parse_args(args(&["-server", "Main"])),
+        Err(ArgsError::UnknownOption("-server".to_string()))
+    );
+}
+
+#[test]
+fn test_launch_main() {
+    let exit_code = run(args(&["-cp", "sample/launcher", "Main", "a", "b"]));
+    assert_eq!(exit_code, EXIT_SUCCESS);
+
+    let exit_code = run(args(&["-jar", "sample/launcher/launcher.jar"]));
+    assert_eq!(exit_code, EXIT_SUCCESS);
+}
+
+#[test]
+fn test_launch_errors() {
+    let options = parse_args(args(&["-cp", "sample/launcher", "Missing"])).unwrap();
+    assert!(matches!(
+        launch(options),
+        Err(LaunchError::MainClassNotFound {
+            error: ClassLoadingError::NotFound(_),
+            ..
+        })
+    ));
+
+    // NoMain.main is not public
+    let options = parse_args(args(&["-cp", "sample/launcher", "NoMain"])).unwrap();
+    assert!(matches!(
+        launch(options),
+        Err(LaunchError::MainMethodNotFound(name)) if name == "NoMain"
+    ));
+
+    // a jar without Main-Class in its manifest
+    let options = parse_args(args(&["-jar", "sample/jar/animals_stored.jar"])).unwrap();
+    assert!(matches!(
+        launch(options),
+        Err(LaunchError::NoMainManifestAttribute(_))
+    ));
+
+    // add uses fadd on two ints
+    let classpath = "sample/verifier/bad_operand_type:sample/class_loader";
+    let options = parse_args(args(&["-cp", classpath, "Verified"])).unwrap();
+    assert!(matches!(
+        launch(options),
+        Err(LaunchError::MainClassNotFound {
+            error: ClassLoadingError::VerifyError(_),
+            ..
+        })
+    ));
+
+    assert_eq!(run(args(&["-cp", "sample/launcher"])), EXIT_FAILURE);
+}
+
+#[test]
+fn test_rewrite_class_file() {
+    let output = env::temp_dir().join("custom_jvm_rewritten_HelloWorld.class");
+    let output_arg = output.to_str().unwrap();
+    let exit_code = run(args(&["rewrite", "sample/HelloWorld.class", output_arg]));
+    assert_eq!(exit_code, EXIT_SUCCESS);
+    let rewritten = fs::read(&output).unwrap();
+    fs::remove_file(&output).unwrap();
+    assert_eq!(rewritten, fs::read("sample/HelloWorld.class").unwrap());
+
+    assert_eq!(
+        run(args(&["rewrite", "sample/HelloWorld.class"])),
+        EXIT_FAILURE
+    );
+    let exit_code = run(args(&["rewrite", "sample/Missing.class", output_arg]));
+    assert_eq!(exit_code, EXIT_FAILURE);
+}
@@ -1,33 +1,42 @@
-use std::{collections::HashMap, ops::RangeInclusive};
+use std::{collections::HashMap, ops::Range};
 
-use crate::parser::utils::{pop_u2_as_index, pop_u4_as_index, skip_n, FileByte, ParseError};
+use crate::parser::utils::{
+    pop_n, pop_u16, pop_u2_as_index, pop_u4_as_index, push_u2_index, push_u4_index, FileByte,
+    ParseError,
+};
 
 use super::{
+    classfile::AccesFlag,
     constant_pool::{ConstantInfo, ConstantPool},
-    opcode::{parse_n_opcodes, update_jump, OpCode},
+    opcode::{parse_n_opcodes, update_jump, write_opcodes, OpCode},
+    stack_map_table::{
+        parse_stack_map_table_attribute, update_stack_map_table_jumps,
+        write_stack_map_table_attribute, StackMapTableAttribute,
+    },
 };
 
 #[derive(Debug, Clone)]
 pub enum Attribute {
     ConstantValue(ConstantValueAttribute),
     Code(CodeAttribute),
-    StackMapTable,       // TODO
-    BootStrapMethod,     // TODO
+    StackMapTable(StackMapTableAttribute),
+    BootstrapMethods(BootstrapMethodsAttribute),
     NestHost,            // TODO
     NestMembers,         // TODO
     PermittedSubclasses, // TODO
-    Exceptions,          // TODO
-    InnerClasses,        // TODO
-    EnclosingMethod,     // TODO
-    Synthetic,           // TODO
-    Signature,           // TODO
-    Record,              // TODO
+    Exceptions(ExceptionsAttribute),
+    InnerClasses(InnerClassesAttribute),
+    EnclosingMethod, // TODO
+    Synthetic,       // TODO
+    Signature,       // TODO
+    Record,          // TODO
     SourceFile(SourceFileAttribute),
     LineNumberTable(LineNumberTableAttribute),
     LocalVariableTable,     // TODO
     LocalVariableTypeTable, // TODO
     // other attributes but not critical (see specs 4.7.3)
-    Unknown(String), // TODO: String for debug pupose, remove after
+    // the raw content is kept to be able to write the attribute back
+    Unknown(Vec<u8>),
 }
 
 #[derive(Debug, Clone)]
@@ -36,12 +45,18 @@ pub struct AttributeInfo {
     attribute: Attribute,
 }
 
+impl AttributeInfo {
+    pub fn attribute(&self) -> &Attribute {
+        &self.attribute
+    }
+}
+
 fn get_attribute_name(
     constant_pool: &ConstantPool,
     attribute_name_index: usize,
 ) -> Result<&str, ParseError> {
     if let Some(ConstantInfo::Utf8(str)) = constant_pool.get(attribute_name_index) {
-        Ok(&str)
+        Ok(str.as_str())
     } else {
         let pool_size = constant_pool.size();
         Err(ParseError::BadConstPoolIndex {
@@ -67,13 +82,14 @@ where
         "Code" => Attribute::Code(parse_code_attribute(bytes, constant_pool)?),
         "LineNumberTable" => Attribute::LineNumberTable(parse_line_number_table_attribute(bytes)?),
         "SourceFile" => Attribute::SourceFile(parse_source_file_attribute(bytes)?),
-        _ => {
-            // silently ignore unknown attributes
-
-            // still need to skip the bytes
-            skip_n(bytes, attribute_len)?;
-            Attribute::Unknown(name.to_string())
+        "Exceptions" => Attribute::Exceptions(parse_exceptions_attribute(bytes)?),
+        "StackMapTable" => Attribute::StackMapTable(parse_stack_map_table_attribute(bytes)?),
+        "BootstrapMethods" => {
+            Attribute::BootstrapMethods(parse_bootstrap_methods_attribute(bytes)?)
         }
+        "InnerClasses" => Attribute::InnerClasses(parse_inner_classes_attribute(bytes)?),
+        // silently ignore unknown attributes
+        _ => Attribute::Unknown(pop_n(bytes, attribute_len)?),
     };
 
     Ok(AttributeInfo {
@@ -87,6 +103,16 @@ pub struct Attributes {
     attributes: Vec<AttributeInfo>,
 }
 
+impl Attributes {
+    pub fn iter(&self) -> std::slice::Iter<'_, AttributeInfo> {
+        self.attributes.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.attributes.len()
+    }
+}
+
 pub fn parse_n_attributes<I>(
     bytes: &mut I,
     attributes_count: usize,
@@ -119,11 +145,93 @@ where
     Ok(Attributes { attributes })
 }
 
+/// `opcode_offsets` are the byte offsets of the code for the attributes of a Code attribute
+fn write_attribute_info(
+    bytes: &mut Vec<u8>,
+    info: &AttributeInfo,
+    opcode_offsets: Option<&[usize]>,
+) -> Result<(), ParseError> {
+    push_u2_index(bytes, info.attribute_name_index)?;
+
+    // the content is written first to know its length
+    let mut content = Vec::new();
+    match &info.attribute {
+        Attribute::ConstantValue(constant_value) => {
+            push_u2_index(&mut content, constant_value.constant_value_index)?
+        }
+        Attribute::Code(code) => write_code_attribute(&mut content, code)?,
+        Attribute::LineNumberTable(line_number_table) => {
+            write_line_number_table_attribute(&mut content, line_number_table)?
+        }
+        Attribute::SourceFile(source_file) => {
+            push_u2_index(&mut content, source_file.source_file_index)?
+        }
+        Attribute::Exceptions(exceptions) => write_exceptions_attribute(&mut content, exceptions)?,
+        Attribute::Unknown(raw) => content.extend_from_slice(raw),
+        Attribute::StackMapTable(stack_map_table) => {
+            // only valid in a Code attribute
+            let opcode_offsets =
+                opcode_offsets.ok_or(ParseError::UnsupportedAttribute("StackMapTable"))?;
+            write_stack_map_table_attribute(&mut content, stack_map_table, opcode_offsets)?
+        }
+        Attribute::BootstrapMethods(bootstrap_methods) => {
+            write_bootstrap_methods_attribute(&mut content, bootstrap_methods)?
+        }
+        Attribute::NestHost => return Err(ParseError::UnsupportedAttribute("NestHost")),
+        Attribute::NestMembers => return Err(ParseError::UnsupportedAttribute("NestMembers")),
+        Attribute::PermittedSubclasses => {
+            return Err(ParseError::UnsupportedAttribute("PermittedSubclasses"))
+        }
+        Attribute::InnerClasses(inner_classes) => {
+            write_inner_classes_attribute(&mut content, inner_classes)?
+        }
+        Attribute::EnclosingMethod => {
+            return Err(ParseError::UnsupportedAttribute("EnclosingMethod"))
+        }
+        Attribute::Synthetic => return Err(ParseError::UnsupportedAttribute("Synthetic")),
+        Attribute::Signature => return Err(ParseError::UnsupportedAttribute("Signature")),
+        Attribute::Record => return Err(ParseError::UnsupportedAttribute("Record")),
+        Attribute::LocalVariableTable => {
+            return Err(ParseError::UnsupportedAttribute("LocalVariableTable"))
+        }
+        Attribute::LocalVariableTypeTable => {
+            return Err(ParseError::UnsupportedAttribute("LocalVariableTypeTable"))
+        }
+    }
+
+    push_u4_index(bytes, content.len())?;
+    bytes.extend_from_slice(&content);
+    Ok(())
+}
+
+pub fn write_n_attributes(
+    bytes: &mut Vec<u8>,
+    attributes: &[AttributeInfo],
+) -> Result<(), ParseError> {
+    for attribute_info in attributes {
+        write_attribute_info(bytes, attribute_info, None)?;
+    }
+
+    Ok(())
+}
+
+pub fn write_attributes(bytes: &mut Vec<u8>, attributes: &Attributes) -> Result<(), ParseError> {
+    push_u2_index(bytes, attributes.len())?;
+
+    write_n_attributes(bytes, &attributes.attributes)
+}
+
 #[derive(Debug, Clone)]
 pub struct ConstantValueAttribute {
     constant_value_index: usize,
 }
 
+impl ConstantValueAttribute {
+    pub fn constant_value_index(&self) -> usize {
+        self.constant_value_index
+    }
+}
+
 fn parse_constant_value<I>(
     bytes: &mut I,
     attribute_len: usize,
@@ -140,20 +248,70 @@ where
 
 #[derive(Debug, Clone)]
 pub struct ExceptionTableInfo {
-    code_range: RangeInclusive<usize>,
+    code_range: Range<usize>,
     handler_pc: usize,
     catch_type: usize,
 }
 
+impl ExceptionTableInfo {
+    pub fn code_range(&self) -> &Range<usize> {
+        &self.code_range
+    }
+
+    pub fn handler_pc(&self) -> usize {
+        self.handler_pc
+    }
+
+    /// 0 means any exception (used for finally blocks)
+    pub fn catch_type(&self) -> usize {
+        self.catch_type
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CodeAttribute {
     max_stack: usize,
     max_locals: usize,
     code: Vec<OpCode>,
+    // byte offset of each opcode in the class file, followed by the code length
+    opcode_offsets: Vec<usize>,
     exception_table: Vec<ExceptionTableInfo>,
     attributes: Vec<AttributeInfo>,
 }
 
+impl CodeAttribute {
+    pub fn max_stack(&self) -> usize {
+        self.max_stack
+    }
+
+    pub fn max_locals(&self) -> usize {
+        self.max_locals
+    }
+
+    pub fn code(&self) -> &[OpCode] {
+        &self.code
+    }
+
+    /// Jumps and exception table ranges are opcode indexes, this give back the original byte offset
+    ///
+    /// The index after the last opcode gives the code length.
+    pub fn opcode_offset(&self, index: usize) -> Option<usize> {
+        self.opcode_offsets.get(index).copied()
+    }
+
+    pub fn opcode_offsets(&self) -> &[usize] {
+        &self.opcode_offsets
+    }
+
+    pub fn exception_table(&self) -> &[ExceptionTableInfo] {
+        &self.exception_table
+    }
+
+    pub fn attributes(&self) -> &[AttributeInfo] {
+        &self.attributes
+    }
+}
+
 fn parse_exception_table_info<I>(bytes: &mut I) -> Result<ExceptionTableInfo, ParseError>
 where
     I: Iterator<Item = FileByte>,
@@ -164,7 +322,8 @@ where
     let catch_type = pop_u2_as_index(bytes)?;
 
     Ok(ExceptionTableInfo {
-        code_range: start_pc..=end_pc,
+        // end_pc is exclusive
+        code_range: start_pc..end_pc,
         handler_pc,
         catch_type,
     })
@@ -179,11 +338,11 @@ fn update_exception_table_jumps(
         handler_pc,
         ..
     } = exception_table;
-    let mut start_pc = *code_range.start();
-    let mut end_pc = *code_range.end();
+    let mut start_pc = code_range.start;
+    let mut end_pc = code_range.end;
     update_jump(&mut start_pc, jump_table, "exception_table")?;
     update_jump(&mut end_pc, jump_table, "exception_table")?;
-    *code_range = start_pc..=end_pc;
+    *code_range = start_pc..end_pc;
     update_jump(handler_pc, jump_table, "exception_table")
 }
 
@@ -197,7 +356,7 @@ where
     let max_stack = pop_u2_as_index(bytes)?;
     let max_locals = pop_u2_as_index(bytes)?;
     let code_length = pop_u4_as_index(bytes)?;
-    let (code, jump_table) = parse_n_opcodes(bytes, code_length)?;
+    let (code, opcode_offsets, jump_table) = parse_n_opcodes(bytes, code_length)?;
     let exception_table_len = pop_u2_as_index(bytes)?;
 
     let mut exception_table = Vec::with_capacity(exception_table_len);
@@ -210,23 +369,76 @@ where
 
     let attributes_count = pop_u2_as_index(bytes)?;
 
-    let attributes = parse_n_attributes(bytes, attributes_count, constant_pool)?;
+    let mut attributes = parse_n_attributes(bytes, attributes_count, constant_pool)?;
+
+    for attribute_info in &mut attributes {
+        if let Attribute::StackMapTable(stack_map_table) = &mut attribute_info.attribute {
+            update_stack_map_table_jumps(stack_map_table, &jump_table)?;
+        }
+    }
 
     Ok(CodeAttribute {
         max_stack,
         max_locals,
         code,
+        opcode_offsets,
         exception_table,
         attributes,
     })
 }
 
+fn write_code_attribute(bytes: &mut Vec<u8>, code: &CodeAttribute) -> Result<(), ParseError> {
+    push_u2_index(bytes, code.max_stack)?;
+    push_u2_index(bytes, code.max_locals)?;
+
+    // the offsets are recomputed, the stored ones are only valid for the parsed code
+    let (code_bytes, opcode_offsets) = write_opcodes(&code.code)?;
+    push_u4_index(bytes, code_bytes.len())?;
+    bytes.extend_from_slice(&code_bytes);
+
+    let offset = |index: usize| {
+        opcode_offsets
+            .get(index)
+            .copied()
+            .ok_or(ParseError::InvalidOpcodeJumpIndex {
+                opcode: "exception_table",
+                jump_target: index,
+            })
+    };
+
+    push_u2_index(bytes, code.exception_table.len())?;
+    for exception_info in &code.exception_table {
+        push_u2_index(bytes, offset(exception_info.code_range.start)?)?;
+        push_u2_index(bytes, offset(exception_info.code_range.end)?)?;
+        push_u2_index(bytes, offset(exception_info.handler_pc)?)?;
+        push_u2_index(bytes, exception_info.catch_type)?;
+    }
+
+    push_u2_index(bytes, code.attributes.len())?;
+    for attribute_info in &code.attributes {
+        write_attribute_info(bytes, attribute_info, Some(&opcode_offsets))?;
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub struct LineNumberTableInfo {
+    // byte offset, not converted to an opcode index
     start_pc: usize,
     line_number: usize,
 }
 
+impl LineNumberTableInfo {
+    pub fn start_pc(&self) -> usize {
+        self.start_pc
+    }
+
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+}
+
 fn parse_line_number_table_info<I>(bytes: &mut I) -> Result<LineNumberTableInfo, ParseError>
 where
     I: Iterator<Item = FileByte>,
@@ -245,6 +457,12 @@ pub struct LineNumberTableAttribute {
     infos: Vec<LineNumberTableInfo>,
 }
 
+impl LineNumberTableAttribute {
+    pub fn infos(&self) -> &[LineNumberTableInfo] {
+        &self.infos
+    }
+}
+
 pub fn parse_line_number_table_attribute<I>(
     bytes: &mut I,
 ) -> Result<LineNumberTableAttribute, ParseError>
@@ -263,11 +481,31 @@ where
     Ok(LineNumberTableAttribute { infos })
 }
 
+fn write_line_number_table_attribute(
+    bytes: &mut Vec<u8>,
+    line_number_table: &LineNumberTableAttribute,
+) -> Result<(), ParseError> {
+    push_u2_index(bytes, line_number_table.infos.len())?;
+
+    for info in &line_number_table.infos {
+        push_u2_index(bytes, info.start_pc)?;
+        push_u2_index(bytes, info.line_number)?;
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub struct SourceFileAttribute {
     source_file_index: usize,
 }
 
+impl SourceFileAttribute {
+    pub fn source_file_index(&self) -> usize {
+        self.source_file_index
+    }
+}
+
 fn parse_source_file_attribute<I>(bytes: &mut I) -> Result<SourceFileAttribute, ParseError>
 where
     I: Iterator<Item = FileByte>,
@@ -276,3 +514,195 @@ where
 
     Ok(SourceFileAttribute { source_file_index })
 }
+
+#[derive(Debug, Clone)]
+pub struct ExceptionsAttribute {
+    // Class constants of the checked exceptions the method declares
+    exception_indexes: Vec<usize>,
+}
+
+impl ExceptionsAttribute {
+    pub fn exception_indexes(&self) -> &[usize] {
+        &self.exception_indexes
+    }
+}
+
+fn parse_exceptions_attribute<I>(bytes: &mut I) -> Result<ExceptionsAttribute, ParseError>
+where
+    I: Iterator<Item = FileByte>,
+{
+    let exceptions_count = pop_u2_as_index(bytes)?;
+
+    let mut exception_indexes = Vec::with_capacity(exceptions_count);
+
+    for _ in 0..exceptions_count {
+        let exception_index = pop_u2_as_index(bytes)?;
+        exception_indexes.push(exception_index);
+    }
+
+    Ok(ExceptionsAttribute { exception_indexes })
+}
+
+fn write_exceptions_attribute(
+    bytes: &mut Vec<u8>,
+    exceptions: &ExceptionsAttribute,
+) -> Result<(), ParseError> {
+    push_u2_index(bytes, exceptions.exception_indexes.len())?;
+
+    for exception_index in &exceptions.exception_indexes {
+        push_u2_index(bytes, *exception_index)?;
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct BootstrapMethodInfo {
+    // the MethodHandle constant of the bootstrap method
+    bootstrap_method_ref: usize,
+    // the loadable constants given to the bootstrap method after the call site
+    bootstrap_arguments: Vec<usize>,
+}
+
+impl BootstrapMethodInfo {
+    pub fn bootstrap_method_ref(&self) -> usize {
+        self.bootstrap_method_ref
+    }
+
+    pub fn bootstrap_arguments(&self) -> &[usize] {
+        &self.bootstrap_arguments
+    }
+}
+
+/// The bootstrap methods of the class, referenced by the InvokeDynamic and Dynamic constants
+#[derive(Debug, Clone)]
+pub struct BootstrapMethodsAttribute {
+    bootstrap_methods: Vec<BootstrapMethodInfo>,
+}
+
+impl BootstrapMethodsAttribute {
+    pub fn bootstrap_methods(&self) -> &[BootstrapMethodInfo] {
+        &self.bootstrap_methods
+    }
+}
+
+fn parse_bootstrap_methods_attribute<I>(
+    bytes: &mut I,
+) -> Result<BootstrapMethodsAttribute, ParseError>
+where
+    I: Iterator<Item = FileByte>,
+{
+    let bootstrap_methods_count = pop_u2_as_index(bytes)?;
+    let mut bootstrap_methods = Vec::with_capacity(bootstrap_methods_count);
+
+    for _ in 0..bootstrap_methods_count {
+        let bootstrap_method_ref = pop_u2_as_index(bytes)?;
+        let arguments_count = pop_u2_as_index(bytes)?;
+        let mut bootstrap_arguments = Vec::with_capacity(arguments_count);
+        for _ in 0..arguments_count {
+            bootstrap_arguments.push(pop_u2_as_index(bytes)?);
+        }
+        bootstrap_methods.push(BootstrapMethodInfo {
+            bootstrap_method_ref,
+            bootstrap_arguments,
+        });
+    }
+
+    Ok(BootstrapMethodsAttribute { bootstrap_methods })
+}
+
+fn write_bootstrap_methods_attribute(
+    bytes: &mut Vec<u8>,
+    attribute: &BootstrapMethodsAttribute,
+) -> Result<(), ParseError> {
+    push_u2_index(bytes, attribute.bootstrap_methods.len())?;
+
+    for bootstrap_method in &attribute.bootstrap_methods {
+        push_u2_index(bytes, bootstrap_method.bootstrap_method_ref)?;
+        push_u2_index(bytes, bootstrap_method.bootstrap_arguments.len())?;
+        for argument in &bootstrap_method.bootstrap_arguments {
+            push_u2_index(bytes, *argument)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct InnerClassInfo {
+    inner_class_info_index: usize,
+    // 0 for the local and anonymous classes
+    outer_class_info_index: usize,
+    // 0 for the anonymous classes
+    inner_name_index: usize,
+    inner_class_access_flags: u16,
+}
+
+impl InnerClassInfo {
+    pub fn inner_class_info_index(&self) -> usize {
+        self.inner_class_info_index
+    }
+
+    pub fn outer_class_info_index(&self) -> usize {
+        self.outer_class_info_index
+    }
+
+    pub fn inner_name_index(&self) -> usize {
+        self.inner_name_index
+    }
+
+    pub fn inner_class_access_flags(&self) -> AccesFlag {
+        AccesFlag::new(self.inner_class_access_flags)
+    }
+}
+
+/// The nested classes referenced by the class, with the flags they are declared with
+#[derive(Debug, Clone)]
+pub struct InnerClassesAttribute {
+    classes: Vec<InnerClassInfo>,
+}
+
+impl InnerClassesAttribute {
+    pub fn classes(&self) -> &[InnerClassInfo] {
+        &self.classes
+    }
+}
+
+fn parse_inner_classes_attribute<I>(bytes: &mut I) -> Result<InnerClassesAttribute, ParseError>
+where
+    I: Iterator<Item = FileByte>,
+{
+    let classes_count = pop_u2_as_index(bytes)?;
+    let mut classes = Vec::with_capacity(classes_count);
+
+    for _ in 0..classes_count {
+        let inner_class_info_index = pop_u2_as_index(bytes)?;
+        let outer_class_info_index = pop_u2_as_index(bytes)?;
+        let inner_name_index = pop_u2_as_index(bytes)?;
+        let inner_class_access_flags = pop_u16(bytes)?;
+        classes.push(InnerClassInfo {
+            inner_class_info_index,
+            outer_class_info_index,
+            inner_name_index,
+            inner_class_access_flags,
+        });
+    }
+
+    Ok(InnerClassesAttribute { classes })
+}
+
+fn write_inner_classes_attribute(
+    bytes: &mut Vec<u8>,
+    attribute: &InnerClassesAttribute,
+) -> Result<(), ParseError> {
+    push_u2_index(bytes, attribute.classes.len())?;
+
+    for class in &attribute.classes {
+        push_u2_index(bytes, class.inner_class_info_index)?;
+        push_u2_index(bytes, class.outer_class_info_index)?;
+        push_u2_index(bytes, class.inner_name_index)?;
+        bytes.extend_from_slice(&class.inner_class_access_flags.to_be_bytes());
+    }
+
+    Ok(())
+}
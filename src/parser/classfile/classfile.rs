@@ -1,16 +1,51 @@
-use crate::parser::utils::{pop4, pop_u16, pop_u2_as_index, FileByte, ParseError};
+use crate::parser::utils::{pop4, pop_u16, pop_u2_as_index, push_u2_index, FileByte, ParseError};
 
 use super::{
-    attributes::{parse_attributes, Attributes},
-    constant_pool::{parse_constant_pool, ConstantPool},
-    fields::{parse_fields, Fields},
-    interfaces::{parse_interfaces, Interfaces},
-    methods::{parse_methods, Methods},
+    attributes::{parse_attributes, write_attributes, Attribute, Attributes, BootstrapMethodInfo},
+    constant_pool::{parse_constant_pool, write_constant_pool, ConstantPool},
+    fields::{parse_fields, write_fields, Fields},
+    interfaces::{parse_interfaces, write_interfaces, Interfaces},
+    methods::{parse_methods, write_methods, Methods},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct AccesFlag(u16);
 
+// flags sharing the same bit have a different meaning for classes, fields and methods
+impl AccesFlag {
+    pub const PUBLIC: u16 = 0x0001;
+    pub const PRIVATE: u16 = 0x0002;
+    pub const PROTECTED: u16 = 0x0004;
+    pub const STATIC: u16 = 0x0008;
+    pub const FINAL: u16 = 0x0010;
+    pub const SUPER: u16 = 0x0020;
+    pub const SYNCHRONIZED: u16 = 0x0020;
+    pub const VOLATILE: u16 = 0x0040;
+    pub const BRIDGE: u16 = 0x0040;
+    pub const TRANSIENT: u16 = 0x0080;
+    pub const VARARGS: u16 = 0x0080;
+    pub const NATIVE: u16 = 0x0100;
+    pub const INTERFACE: u16 = 0x0200;
+    pub const ABSTRACT: u16 = 0x0400;
+    pub const STRICT: u16 = 0x0800;
+    pub const SYNTHETIC: u16 = 0x1000;
+    pub const ANNOTATION: u16 = 0x2000;
+    pub const ENUM: u16 = 0x4000;
+    pub const MODULE: u16 = 0x8000;
+
+    pub fn new(bits: u16) -> Self {
+        AccesFlag(bits)
+    }
+
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+
+    pub fn contains(&self, flag: u16) -> bool {
+        self.0 & flag != 0
+    }
+}
+
 /*
     U4:                 magic
     U2:                 minor_version
@@ -73,6 +108,33 @@ where
     })
 }
 
+/// Encode the class file back to bytes, following the same layout as the parsing
+///
+/// An unmodified class file gives back the exact bytes it was parsed from.
+pub fn write_class_file(class_file: &ClassFile) -> Result<Vec<u8>, ParseError> {
+    let mut bytes = Vec::new();
+
+    bytes.extend_from_slice(&class_file.magic.to_be_bytes());
+    bytes.extend_from_slice(&class_file.minor_version.to_be_bytes());
+    bytes.extend_from_slice(&class_file.major_version.to_be_bytes());
+
+    write_constant_pool(&mut bytes, &class_file.constant_pool)?;
+
+    bytes.extend_from_slice(&class_file.acces_flag.bits().to_be_bytes());
+    push_u2_index(&mut bytes, class_file.this_class)?;
+    push_u2_index(&mut bytes, class_file.super_class)?;
+
+    write_interfaces(&mut bytes, &class_file.interfaces)?;
+
+    write_fields(&mut bytes, &class_file.fields)?;
+
+    write_methods(&mut bytes, &class_file.methods)?;
+
+    write_attributes(&mut bytes, &class_file.attributes)?;
+
+    Ok(bytes)
+}
+
 #[derive(Debug, Clone)]
 pub struct ClassFile {
     magic: u32,
@@ -87,3 +149,56 @@ pub struct ClassFile {
     methods: Methods,
     attributes: Attributes,
 }
+
+impl ClassFile {
+    pub fn minor_version(&self) -> u16 {
+        self.minor_version
+    }
+
+    pub fn major_version(&self) -> u16 {
+        self.major_version
+    }
+
+    pub fn this_class(&self) -> usize {
+        self.this_class
+    }
+
+    pub fn super_class(&self) -> usize {
+        self.super_class
+    }
+
+    pub fn constant_pool(&self) -> &ConstantPool {
+        &self.constant_pool
+    }
+
+    pub fn acces_flag(&self) -> AccesFlag {
+        self.acces_flag
+    }
+
+    pub fn interfaces(&self) -> &Interfaces {
+        &self.interfaces
+    }
+
+    pub fn fields(&self) -> &Fields {
+        &self.fields
+    }
+
+    pub fn methods(&self) -> &Methods {
+        &self.methods
+    }
+
+    pub fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    /// Empty if the class has no BootstrapMethods attribute
+    pub fn bootstrap_methods(&self) -> &[BootstrapMethodInfo] {
+        self.attributes
+            .iter()
+            .find_map(|attribute_info| match attribute_info.attribute() {
+                Attribute::BootstrapMethods(attribute) => Some(attribute.bootstrap_methods()),
+                _ => None,
+            })
+            .unwrap_or(&[])
+    }
+}
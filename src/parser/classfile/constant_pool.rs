@@ -1,4 +1,7 @@
-use crate::parser::utils::{pop1, pop2, pop4, pop8, pop_n, pop_u2_as_index, FileByte, ParseError};
+use crate::parser::{
+    modified_utf8::{decode_modified_utf8, encode_modified_utf8, ModifiedUtf8},
+    utils::{pop1, pop2, pop4, pop8, pop_n, pop_u2_as_index, push_u2_index, FileByte, ParseError},
+};
 
 /*
     cp_info {
@@ -127,6 +130,22 @@ pub enum MethodHandleKind {
     InvokeInterface,
 }
 
+impl From<MethodHandleKind> for u8 {
+    fn from(kind: MethodHandleKind) -> Self {
+        match kind {
+            MethodHandleKind::GetField => 1,
+            MethodHandleKind::GetStatic => 2,
+            MethodHandleKind::PutField => 3,
+            MethodHandleKind::PutStatic => 4,
+            MethodHandleKind::InvokeVirtual => 5,
+            MethodHandleKind::InvokeStatic => 6,
+            MethodHandleKind::InvokeSpecial => 7,
+            MethodHandleKind::NewInvokeSpecial => 8,
+            MethodHandleKind::InvokeInterface => 9,
+        }
+    }
+}
+
 impl TryFrom<u8> for MethodHandleKind {
     type Error = ParseError;
 
@@ -174,7 +193,7 @@ pub enum ConstantInfo {
         name_index: usize,
         descriptor_index: usize,
     },
-    Utf8(String),
+    Utf8(ModifiedUtf8),
     MethodHandle {
         reference_kind: MethodHandleKind,
         reference_index: usize,
@@ -216,8 +235,7 @@ where
     let len = u16::from_be_bytes(len_bits).into();
     let string_bytes = pop_n(bytes, len)?;
 
-    let s = String::from_utf8(string_bytes).unwrap();
-    Ok(ConstantInfo::Utf8(s))
+    decode_modified_utf8(&string_bytes).map(ConstantInfo::Utf8)
 }
 
 fn parse_integer<I>(bytes: &mut I) -> Result<ConstantInfo, ParseError>
@@ -430,6 +448,125 @@ where
     Ok(ConstantPool { infos })
 }
 
+fn write_constant_info(bytes: &mut Vec<u8>, info: &ConstantInfo) -> Result<(), ParseError> {
+    match info {
+        ConstantInfo::Utf8(s) => {
+            let encoded = encode_modified_utf8(s.units());
+            bytes.push(1);
+            push_u2_index(bytes, encoded.len())?;
+            bytes.extend_from_slice(&encoded);
+        }
+        ConstantInfo::Integer(value) => {
+            bytes.push(3);
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+        ConstantInfo::Float(value) => {
+            bytes.push(4);
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+        ConstantInfo::Long(value) => {
+            bytes.push(5);
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+        ConstantInfo::Double(value) => {
+            bytes.push(6);
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+        ConstantInfo::Class { name_index } => {
+            bytes.push(7);
+            push_u2_index(bytes, *name_index)?;
+        }
+        ConstantInfo::String { string_index } => {
+            bytes.push(8);
+            push_u2_index(bytes, *string_index)?;
+        }
+        ConstantInfo::FieldRef {
+            class_index,
+            name_and_type_index,
+        } => {
+            bytes.push(9);
+            push_u2_index(bytes, *class_index)?;
+            push_u2_index(bytes, *name_and_type_index)?;
+        }
+        ConstantInfo::MethodRef {
+            class_index,
+            name_and_type_index,
+        } => {
+            bytes.push(10);
+            push_u2_index(bytes, *class_index)?;
+            push_u2_index(bytes, *name_and_type_index)?;
+        }
+        ConstantInfo::InterfaceMethodRef {
+            class_index,
+            name_and_type_index,
+        } => {
+            bytes.push(11);
+            push_u2_index(bytes, *class_index)?;
+            push_u2_index(bytes, *name_and_type_index)?;
+        }
+        ConstantInfo::NameAndType {
+            name_index,
+            descriptor_index,
+        } => {
+            bytes.push(12);
+            push_u2_index(bytes, *name_index)?;
+            push_u2_index(bytes, *descriptor_index)?;
+        }
+        ConstantInfo::MethodHandle {
+            reference_kind,
+            reference_index,
+        } => {
+            bytes.push(15);
+            bytes.push((*reference_kind).into());
+            push_u2_index(bytes, *reference_index)?;
+        }
+        ConstantInfo::MethodType { descriptor_index } => {
+            bytes.push(16);
+            push_u2_index(bytes, *descriptor_index)?;
+        }
+        ConstantInfo::Dynamic {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+        } => {
+            bytes.push(17);
+            push_u2_index(bytes, *bootstrap_method_attr_index)?;
+            push_u2_index(bytes, *name_and_type_index)?;
+        }
+        ConstantInfo::InvokeDynamic {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+        } => {
+            bytes.push(18);
+            push_u2_index(bytes, *bootstrap_method_attr_index)?;
+            push_u2_index(bytes, *name_and_type_index)?;
+        }
+        ConstantInfo::Module { name_index } => {
+            bytes.push(19);
+            push_u2_index(bytes, *name_index)?;
+        }
+        ConstantInfo::Package { name_index } => {
+            bytes.push(20);
+            push_u2_index(bytes, *name_index)?;
+        }
+        // the entry after a long or a double only exists for the indexes
+        ConstantInfo::Padding => {}
+    }
+    Ok(())
+}
+
+pub fn write_constant_pool(
+    bytes: &mut Vec<u8>,
+    constant_pool: &ConstantPool,
+) -> Result<(), ParseError> {
+    push_u2_index(bytes, constant_pool.size())?;
+
+    for info in &constant_pool.infos {
+        write_constant_info(bytes, info)?;
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub struct ConstantPool {
     infos: Vec<ConstantInfo>,
@@ -437,10 +574,75 @@ pub struct ConstantPool {
 
 impl ConstantPool {
     pub fn get(&self, index: usize) -> Option<&ConstantInfo> {
-        self.infos.get(index - 1)
+        self.infos.get(index.checked_sub(1)?)
     }
 
     pub fn size(&self) -> usize {
         self.infos.len() + 1
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &ConstantInfo)> {
+        // indexes start at 1
+        self.infos.iter().enumerate().map(|(i, info)| (i + 1, info))
+    }
+
+    fn bad_index(&self, index: usize) -> ParseError {
+        ParseError::BadConstPoolIndex {
+            target_index: index,
+            pool_size: self.size(),
+        }
+    }
+
+    pub fn get_checked(&self, index: usize) -> Result<&ConstantInfo, ParseError> {
+        self.get(index).ok_or_else(|| self.bad_index(index))
+    }
+
+    pub fn get_utf8(&self, index: usize) -> Result<&str, ParseError> {
+        match self.get_checked(index)? {
+            ConstantInfo::Utf8(s) => Ok(s.as_str()),
+            _ => Err(ParseError::UnexpectedConstant {
+                index,
+                expected: "Utf8",
+            }),
+        }
+    }
+
+    /// The UTF-16 code units of a Utf8 constant, with its lone surrogates
+    pub fn get_utf16(&self, index: usize) -> Result<&[u16], ParseError> {
+        match self.get_checked(index)? {
+            ConstantInfo::Utf8(s) => Ok(s.units()),
+            _ => Err(ParseError::UnexpectedConstant {
+                index,
+                expected: "Utf8",
+            }),
+        }
+    }
+
+    pub fn get_class_name(&self, index: usize) -> Result<&str, ParseError> {
+        match self.get_checked(index)? {
+            ConstantInfo::Class { name_index } => self.get_utf8(*name_index),
+            _ => Err(ParseError::UnexpectedConstant {
+                index,
+                expected: "Class",
+            }),
+        }
+    }
+
+    /// return (name, descriptor)
+    pub fn get_name_and_type(&self, index: usize) -> Result<(&str, &str), ParseError> {
+        match self.get_checked(index)? {
+            ConstantInfo::NameAndType {
+                name_index,
+                descriptor_index,
+            } => {
+                let name = self.get_utf8(*name_index)?;
+                let descriptor = self.get_utf8(*descriptor_index)?;
+                Ok((name, descriptor))
+            }
+            _ => Err(ParseError::UnexpectedConstant {
+                index,
+                expected: "NameAndType",
+            }),
+        }
+    }
 }
@@ -1,7 +1,8 @@
-use crate::parser::utils::{pop_u16, pop_u2_as_index, FileByte, ParseError};
+use crate::parser::utils::{pop_u16, pop_u2_as_index, push_u2_index, FileByte, ParseError};
 
 use super::{
-    attributes::{parse_n_attributes, AttributeInfo},
+    attributes::{parse_n_attributes, write_n_attributes, AttributeInfo},
+    classfile::AccesFlag,
     constant_pool::ConstantPool,
 };
 
@@ -13,6 +14,24 @@ pub struct FieldInfo {
     attributes: Vec<AttributeInfo>,
 }
 
+impl FieldInfo {
+    pub fn access_flags(&self) -> AccesFlag {
+        AccesFlag::new(self.access_flags)
+    }
+
+    pub fn name_index(&self) -> usize {
+        self.name_index
+    }
+
+    pub fn descriptor_index(&self) -> usize {
+        self.descriptor_index
+    }
+
+    pub fn attributes(&self) -> &[AttributeInfo] {
+        &self.attributes
+    }
+}
+
 fn parse_field_info<I>(bytes: &mut I, constant_pool: &ConstantPool) -> Result<FieldInfo, ParseError>
 where
     I: Iterator<Item = FileByte>,
@@ -36,6 +55,16 @@ pub struct Fields {
     fields: Vec<FieldInfo>,
 }
 
+impl Fields {
+    pub fn iter(&self) -> std::slice::Iter<'_, FieldInfo> {
+        self.fields.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+}
+
 pub fn parse_fields<I>(bytes: &mut I, constant_pool: &ConstantPool) -> Result<Fields, ParseError>
 where
     I: Iterator<Item = FileByte>,
@@ -51,3 +80,21 @@ where
 
     Ok(Fields { fields })
 }
+
+fn write_field_info(bytes: &mut Vec<u8>, field_info: &FieldInfo) -> Result<(), ParseError> {
+    bytes.extend_from_slice(&field_info.access_flags.to_be_bytes());
+    push_u2_index(bytes, field_info.name_index)?;
+    push_u2_index(bytes, field_info.descriptor_index)?;
+    push_u2_index(bytes, field_info.attributes.len())?;
+    write_n_attributes(bytes, &field_info.attributes)
+}
+
+pub fn write_fields(bytes: &mut Vec<u8>, fields: &Fields) -> Result<(), ParseError> {
+    push_u2_index(bytes, fields.len())?;
+
+    for field_info in fields.iter() {
+        write_field_info(bytes, field_info)?;
+    }
+
+    Ok(())
+}
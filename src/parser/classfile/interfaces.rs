@@ -1,10 +1,20 @@
-use crate::parser::utils::{pop_u2_as_index, FileByte, ParseError};
+use crate::parser::utils::{pop_u2_as_index, push_u2_index, FileByte, ParseError};
 
 #[derive(Debug, Clone)]
 pub struct Interfaces {
     interfaces: Vec<usize>,
 }
 
+impl Interfaces {
+    pub fn iter(&self) -> std::slice::Iter<'_, usize> {
+        self.interfaces.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.interfaces.len()
+    }
+}
+
 pub fn parse_interfaces<I>(bytes: &mut I) -> Result<Interfaces, ParseError>
 where
     I: Iterator<Item = FileByte>,
@@ -20,3 +30,13 @@ where
 
     Ok(Interfaces { interfaces })
 }
+
+pub fn write_interfaces(bytes: &mut Vec<u8>, interfaces: &Interfaces) -> Result<(), ParseError> {
+    push_u2_index(bytes, interfaces.len())?;
+
+    for interface_index in interfaces.iter() {
+        push_u2_index(bytes, *interface_index)?;
+    }
+
+    Ok(())
+}
@@ -1,7 +1,10 @@
-use crate::parser::utils::{pop_u16, pop_u2_as_index, FileByte, ParseError};
+use crate::parser::utils::{pop_u16, pop_u2_as_index, push_u2_index, FileByte, ParseError};
 
 use super::{
-    attributes::{parse_attribute_info, AttributeInfo},
+    attributes::{
+        parse_attribute_info, write_n_attributes, Attribute, AttributeInfo, CodeAttribute,
+    },
+    classfile::AccesFlag,
     constant_pool::ConstantPool,
 };
 
@@ -15,6 +18,33 @@ pub struct MethodInfo {
     attributes: Vec<AttributeInfo>,
 }
 
+impl MethodInfo {
+    pub fn access_flags(&self) -> AccesFlag {
+        AccesFlag::new(self.access_flags)
+    }
+
+    pub fn name_index(&self) -> usize {
+        self.name_index
+    }
+
+    pub fn descriptor_index(&self) -> usize {
+        self.descriptor_index
+    }
+
+    pub fn attributes(&self) -> &[AttributeInfo] {
+        &self.attributes
+    }
+
+    pub fn get_code(&self) -> Option<&CodeAttribute> {
+        self.attributes
+            .iter()
+            .find_map(|attribute_info| match attribute_info.attribute() {
+                Attribute::Code(code) => Some(code),
+                _ => None,
+            })
+    }
+}
+
 fn parse_method_info<I>(
     bytes: &mut I,
     constant_pool: &ConstantPool,
@@ -47,6 +77,16 @@ pub struct Methods {
     methods: Vec<MethodInfo>,
 }
 
+impl Methods {
+    pub fn iter(&self) -> std::slice::Iter<'_, MethodInfo> {
+        self.methods.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.methods.len()
+    }
+}
+
 pub fn parse_methods<I>(bytes: &mut I, constant_pool: &ConstantPool) -> Result<Methods, ParseError>
 where
     I: Iterator<Item = FileByte>,
@@ -62,3 +102,21 @@ where
 
     Ok(Methods { methods })
 }
+
+fn write_method_info(bytes: &mut Vec<u8>, method_info: &MethodInfo) -> Result<(), ParseError> {
+    bytes.extend_from_slice(&method_info.access_flags.to_be_bytes());
+    push_u2_index(bytes, method_info.name_index)?;
+    push_u2_index(bytes, method_info.descriptor_index)?;
+    push_u2_index(bytes, method_info.attributes.len())?;
+    write_n_attributes(bytes, &method_info.attributes)
+}
+
+pub fn write_methods(bytes: &mut Vec<u8>, methods: &Methods) -> Result<(), ParseError> {
+    push_u2_index(bytes, methods.len())?;
+
+    for method_info in methods.iter() {
+        write_method_info(bytes, method_info)?;
+    }
+
+    Ok(())
+}
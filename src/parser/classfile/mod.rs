@@ -5,3 +5,4 @@ pub mod fields;
 pub mod interfaces;
 pub mod methods;
 pub mod opcode;
+pub mod stack_map_table;
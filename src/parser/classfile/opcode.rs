@@ -41,6 +41,26 @@ impl TryFrom<u8> for ArrayType {
     }
 }
 
+impl TryFrom<&ArrayType> for u8 {
+    type Error = ParseError;
+
+    fn try_from(array_type: &ArrayType) -> Result<Self, Self::Error> {
+        use ArrayType::*;
+        match array_type {
+            Boolean => Ok(4),
+            Char => Ok(5),
+            Float => Ok(6),
+            Double => Ok(7),
+            Byte => Ok(8),
+            Short => Ok(9),
+            Int => Ok(10),
+            Long => Ok(11),
+            // only created from anewarray, which has its own opcode
+            Reference(_) => Err(ParseError::InvalidOpCode),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 #[allow(non_camel_case_types)]
 pub enum OpCode {
@@ -642,7 +662,7 @@ where
         }
         0xb3 => {
             let index = pop_u2_as_index(bytes)?;
-            putfield(index)
+            putstatic(index)
         }
         0xa9 => {
             let index = pop_u1_as_index(bytes)?;
@@ -673,10 +693,15 @@ where
     Ok(op_code)
 }
 
+// byte offset -> opcode index
+pub type JumpTable = HashMap<usize, usize>;
+
+/// Return the opcodes, the byte offset of each opcode (followed by the code length),
+/// and the table mapping byte offsets to opcode indexes
 pub fn parse_n_opcodes<I>(
     bytes: &mut I,
     code_length: usize,
-) -> Result<(Vec<OpCode>, HashMap<usize, usize>), ParseError>
+) -> Result<(Vec<OpCode>, Vec<usize>, JumpTable), ParseError>
 where
     I: Iterator<Item = FileByte>,
 {
@@ -693,6 +718,7 @@ where
     let mut current_opcode_line = 0;
 
     let mut opcodes = Vec::new();
+    let mut opcode_offsets = Vec::new();
 
     // TODO: bench if array and do a binary search is not faster
     // pushing the lines one by one will have the array already sorted
@@ -703,13 +729,18 @@ where
         let total_bytes_taken = *byte_count.borrow();
         let opcode_size = total_bytes_taken - current_opcode_line;
         jump_table.insert(current_opcode_line, opcodes.len());
+        opcode_offsets.push(current_opcode_line);
         current_opcode_line += opcode_size;
         opcodes.push(opcode);
     }
 
+    // the end of the code is a valid target for exclusive bounds (exception table ranges, ...)
+    jump_table.insert(current_opcode_line, opcodes.len());
+    opcode_offsets.push(current_opcode_line);
+
     correct_jump_instructions(&mut opcodes, &jump_table)?;
 
-    Ok((opcodes, jump_table))
+    Ok((opcodes, opcode_offsets, jump_table))
 }
 
 pub fn update_jump(
@@ -754,6 +785,7 @@ where
             OpCode::ifle(line) => update_jump(line, jump_table, "ifle")?,
             OpCode::ifnonnull(line) => update_jump(line, jump_table, "ifnonnull")?,
             OpCode::ifnull(line) => update_jump(line, jump_table, "ifnull")?,
+            OpCode::jsr(line) => update_jump(line, jump_table, "jsr")?,
             OpCode::jsr_w(line) => update_jump(line, jump_table, "jsr_w")?,
             OpCode::lookupswitch(lus) => correct_lookupswitch_jumps(lus, jump_table)?,
             OpCode::tableswitch(ts) => correct_tableswitch_jumps(ts, jump_table)?,
@@ -782,6 +814,14 @@ impl LookupSwitch {
             .find_map(|pair| (pair.value == value).then_some(pair.jump))
             .unwrap_or(self.default)
     }
+
+    pub fn default(&self) -> usize {
+        self.default
+    }
+
+    pub fn pairs(&self) -> &[LookupSwitchPair] {
+        &self.pairs
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -790,6 +830,16 @@ pub struct LookupSwitchPair {
     jump: usize,
 }
 
+impl LookupSwitchPair {
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+
+    pub fn jump(&self) -> usize {
+        self.jump
+    }
+}
+
 fn parse_lookupswitch<I>(bytes: &mut I, current_line: usize) -> Result<LookupSwitch, ParseError>
 where
     I: Iterator<Item = FileByte>,
@@ -858,13 +908,31 @@ impl TableSwitch {
         let index = index - self.offset;
         self.get_jump_index(index).unwrap_or(self.default)
     }
+
+    pub fn default(&self) -> usize {
+        self.default
+    }
+
+    pub fn low(&self) -> i32 {
+        self.offset
+    }
+
+    pub fn high(&self) -> i32 {
+        // there is always at least one jump, low <= high
+        self.offset + self.jumps.len() as i32 - 1
+    }
+
+    /// Jumps for the values from low to high
+    pub fn jumps(&self) -> &[usize] {
+        &self.jumps
+    }
 }
 
 fn parse_tableswitch<I>(bytes: &mut I, current_line: usize) -> Result<TableSwitch, ParseError>
 where
     I: Iterator<Item = FileByte>,
 {
-    let padding = 4 - ((current_line + 1) % 4);
+    let padding = (4 - ((current_line + 1) % 4)) % 4;
 
     skip_n(bytes, padding)?;
     let default = parse_u4_index_offset(bytes, current_line)?;
@@ -888,7 +956,7 @@ where
 
     Ok(TableSwitch {
         default,
-        offset: high - low,
+        offset: low,
         jumps,
     })
 }
@@ -954,3 +1022,381 @@ where
         _ => Err(ParseError::InvalidWideOpCode),
     }
 }
+
+// writing
+
+/// Tag of the opcodes without operands
+fn simple_opcode_tag(opcode: &OpCode) -> Option<u8> {
+    use OpCode::*;
+    let tag = match opcode {
+        aaload => 0x32,
+        aastore => 0x53,
+        aconst_null => 0x01,
+        aload_0 => 0x2a,
+        aload_1 => 0x2b,
+        aload_2 => 0x2c,
+        aload_3 => 0x2d,
+        areturn => 0xb0,
+        arraylength => 0xbe,
+        astore_0 => 0x4b,
+        astore_1 => 0x4c,
+        astore_2 => 0x4d,
+        astore_3 => 0x4e,
+        athrow => 0xbf,
+        baload => 0x33,
+        bastore => 0x54,
+        caload => 0x34,
+        castore => 0x55,
+        d2f => 0x90,
+        d2i => 0x8e,
+        d2l => 0x8f,
+        dadd => 0x63,
+        daload => 0x31,
+        dastore => 0x52,
+        dcmpg => 0x98,
+        dcmpl => 0x97,
+        dconst_0 => 0x0e,
+        dconst_1 => 0x0f,
+        ddiv => 0x6f,
+        dload_0 => 0x26,
+        dload_1 => 0x27,
+        dload_2 => 0x28,
+        dload_3 => 0x29,
+        dmul => 0x6b,
+        dneg => 0x77,
+        drem => 0x73,
+        dreturn => 0xaf,
+        dstore_0 => 0x47,
+        dstore_1 => 0x48,
+        dstore_2 => 0x49,
+        dstore_3 => 0x4a,
+        dsub => 0x67,
+        dup => 0x59,
+        dup2 => 0x5c,
+        dup2_x1 => 0x5d,
+        dup2_x2 => 0x5e,
+        dup_x1 => 0x5a,
+        dup_x2 => 0x5b,
+        f2d => 0x8d,
+        f2i => 0x8b,
+        f2l => 0x8c,
+        fadd => 0x62,
+        faload => 0x30,
+        fastore => 0x51,
+        fcmpg => 0x96,
+        fcmpl => 0x95,
+        fconst_0 => 0x0b,
+        fconst_1 => 0x0c,
+        fconst_2 => 0x0d,
+        fdiv => 0x6e,
+        fload_0 => 0x22,
+        fload_1 => 0x23,
+        fload_2 => 0x24,
+        fload_3 => 0x25,
+        fmul => 0x6a,
+        fneg => 0x76,
+        frem => 0x72,
+        freturn => 0xae,
+        fstore_0 => 0x43,
+        fstore_1 => 0x44,
+        fstore_2 => 0x45,
+        fstore_3 => 0x46,
+        fsub => 0x66,
+        i2b => 0x91,
+        i2c => 0x92,
+        i2d => 0x87,
+        i2f => 0x86,
+        i2l => 0x85,
+        i2s => 0x93,
+        iadd => 0x60,
+        iaload => 0x2e,
+        iand => 0x7e,
+        iastore => 0x4f,
+        iconst_0 => 0x03,
+        iconst_1 => 0x04,
+        iconst_2 => 0x05,
+        iconst_3 => 0x06,
+        iconst_4 => 0x07,
+        iconst_5 => 0x08,
+        iconst_m1 => 0x02,
+        idiv => 0x6c,
+        iload_0 => 0x1a,
+        iload_1 => 0x1b,
+        iload_2 => 0x1c,
+        iload_3 => 0x1d,
+        imul => 0x68,
+        ineg => 0x74,
+        ior => 0x80,
+        irem => 0x70,
+        ireturn => 0xac,
+        ishl => 0x78,
+        ishr => 0x7a,
+        istore_0 => 0x3b,
+        istore_1 => 0x3c,
+        istore_2 => 0x3d,
+        istore_3 => 0x3e,
+        isub => 0x64,
+        iushr => 0x7c,
+        ixor => 0x82,
+        l2d => 0x8a,
+        l2f => 0x89,
+        l2i => 0x88,
+        ladd => 0x61,
+        laload => 0x2f,
+        land => 0x7f,
+        lastore => 0x50,
+        lcmp => 0x94,
+        lconst_0 => 0x09,
+        lconst_1 => 0x0a,
+        ldiv => 0x6d,
+        lload_0 => 0x1e,
+        lload_1 => 0x1f,
+        lload_2 => 0x20,
+        lload_3 => 0x21,
+        lmul => 0x69,
+        lneg => 0x75,
+        lor => 0x81,
+        lrem => 0x71,
+        lreturn => 0xad,
+        lshl => 0x79,
+        lshr => 0x7b,
+        lstore_0 => 0x3f,
+        lstore_1 => 0x40,
+        lstore_2 => 0x41,
+        lstore_3 => 0x42,
+        lsub => 0x65,
+        lushr => 0x7d,
+        lxor => 0x83,
+        monitorenter => 0xc2,
+        monitorexit => 0xc3,
+        nop => 0x00,
+        pop => 0x57,
+        pop2 => 0x58,
+        retrn => 0xb1,
+        saload => 0x35,
+        sastore => 0x56,
+        swap => 0x5f,
+        _ => return None,
+    };
+    Some(tag)
+}
+
+fn write_wide(bytes: &mut Vec<u8>, wide: &Wide) -> Result<(), ParseError> {
+    let (tag, index) = match wide {
+        Wide::aload(index) => (0x19, index),
+        Wide::dload(index) => (0x18, index),
+        Wide::fload(index) => (0x17, index),
+        Wide::iload(index) => (0x15, index),
+        Wide::lload(index) => (0x16, index),
+        Wide::astore(index) => (0x3a, index),
+        Wide::dstore(index) => (0x39, index),
+        Wide::fstore(index) => (0x38, index),
+        Wide::istore(index) => (0x36, index),
+        Wide::lstore(index) => (0x37, index),
+        Wide::ret(index) => (0xa9, index),
+        Wide::iinc(index, _) => (0x84, index),
+    };
+    bytes.push(tag);
+    utils::push_u2_index(bytes, *index)?;
+    if let Wide::iinc(_, delta) = wide {
+        let delta =
+            i16::try_from(*delta).map_err(|_| ParseError::ValueOutOfRange((*delta).into()))?;
+        bytes.extend_from_slice(&delta.to_be_bytes());
+    }
+    Ok(())
+}
+
+fn write_switch_padding(bytes: &mut Vec<u8>, current_line: usize) {
+    let padding = (4 - ((current_line + 1) % 4)) % 4;
+    bytes.resize(bytes.len() + padding, 0);
+}
+
+/// `jump_offset` gives the offset from the current opcode to the target opcode index
+fn write_opcode<F>(
+    bytes: &mut Vec<u8>,
+    opcode: &OpCode,
+    current_line: usize,
+    jump_offset: F,
+) -> Result<(), ParseError>
+where
+    F: Fn(usize, &'static str) -> Result<i64, ParseError>,
+{
+    use OpCode::*;
+
+    if let Some(tag) = simple_opcode_tag(opcode) {
+        bytes.push(tag);
+        return Ok(());
+    }
+
+    match opcode {
+        aload(index) => write_u1_index_opcode(bytes, 0x19, *index)?,
+        astore(index) => write_u1_index_opcode(bytes, 0x3a, *index)?,
+        dload(index) => write_u1_index_opcode(bytes, 0x18, *index)?,
+        dstore(index) => write_u1_index_opcode(bytes, 0x39, *index)?,
+        fload(index) => write_u1_index_opcode(bytes, 0x17, *index)?,
+        fstore(index) => write_u1_index_opcode(bytes, 0x38, *index)?,
+        iload(index) => write_u1_index_opcode(bytes, 0x15, *index)?,
+        istore(index) => write_u1_index_opcode(bytes, 0x36, *index)?,
+        lload(index) => write_u1_index_opcode(bytes, 0x16, *index)?,
+        lstore(index) => write_u1_index_opcode(bytes, 0x37, *index)?,
+        ret(index) => write_u1_index_opcode(bytes, 0xa9, *index)?,
+        ldc(index) => write_u1_index_opcode(bytes, 0x12, *index)?,
+        anewarray(index) => write_u2_index_opcode(bytes, 0xbd, *index)?,
+        checkcast(index) => write_u2_index_opcode(bytes, 0xc0, *index)?,
+        getfield(index) => write_u2_index_opcode(bytes, 0xb4, *index)?,
+        getstatic(index) => write_u2_index_opcode(bytes, 0xb2, *index)?,
+        instanceof(index) => write_u2_index_opcode(bytes, 0xc1, *index)?,
+        invokespecial(index) => write_u2_index_opcode(bytes, 0xb7, *index)?,
+        invokestatic(index) => write_u2_index_opcode(bytes, 0xb8, *index)?,
+        invokevirtual(index) => write_u2_index_opcode(bytes, 0xb6, *index)?,
+        ldc_w(index) => write_u2_index_opcode(bytes, 0x13, *index)?,
+        ldc2_w(index) => write_u2_index_opcode(bytes, 0x14, *index)?,
+        new(index) => write_u2_index_opcode(bytes, 0xbb, *index)?,
+        putfield(index) => write_u2_index_opcode(bytes, 0xb5, *index)?,
+        putstatic(index) => write_u2_index_opcode(bytes, 0xb3, *index)?,
+        bipush(value) => {
+            let value =
+                i8::try_from(*value).map_err(|_| ParseError::ValueOutOfRange((*value).into()))?;
+            bytes.push(0x10);
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+        sipush(value) => {
+            let value =
+                i16::try_from(*value).map_err(|_| ParseError::ValueOutOfRange((*value).into()))?;
+            bytes.push(0x11);
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+        iinc(index, delta) => {
+            let delta =
+                i8::try_from(*delta).map_err(|_| ParseError::ValueOutOfRange((*delta).into()))?;
+            write_u1_index_opcode(bytes, 0x84, *index)?;
+            bytes.extend_from_slice(&delta.to_be_bytes());
+        }
+        goto(line) => write_u2_jump_opcode(bytes, 0xa7, jump_offset(*line, "goto")?)?,
+        if_acmpeq(line) => write_u2_jump_opcode(bytes, 0xa5, jump_offset(*line, "if_acmpeq")?)?,
+        if_acmpne(line) => write_u2_jump_opcode(bytes, 0xa6, jump_offset(*line, "if_acmpne")?)?,
+        if_icmpeq(line) => write_u2_jump_opcode(bytes, 0x9f, jump_offset(*line, "if_icmpeq")?)?,
+        if_icmpne(line) => write_u2_jump_opcode(bytes, 0xa0, jump_offset(*line, "if_icmpne")?)?,
+        if_icmplt(line) => write_u2_jump_opcode(bytes, 0xa1, jump_offset(*line, "if_icmplt")?)?,
+        if_icmpge(line) => write_u2_jump_opcode(bytes, 0xa2, jump_offset(*line, "if_icmpge")?)?,
+        if_icmpgt(line) => write_u2_jump_opcode(bytes, 0xa3, jump_offset(*line, "if_icmpgt")?)?,
+        if_icmple(line) => write_u2_jump_opcode(bytes, 0xa4, jump_offset(*line, "if_icmple")?)?,
+        ifeq(line) => write_u2_jump_opcode(bytes, 0x99, jump_offset(*line, "ifeq")?)?,
+        ifne(line) => write_u2_jump_opcode(bytes, 0x9a, jump_offset(*line, "ifne")?)?,
+        iflt(line) => write_u2_jump_opcode(bytes, 0x9b, jump_offset(*line, "iflt")?)?,
+        ifge(line) => write_u2_jump_opcode(bytes, 0x9c, jump_offset(*line, "ifge")?)?,
+        ifgt(line) => write_u2_jump_opcode(bytes, 0x9d, jump_offset(*line, "ifgt")?)?,
+        ifle(line) => write_u2_jump_opcode(bytes, 0x9e, jump_offset(*line, "ifle")?)?,
+        ifnonnull(line) => write_u2_jump_opcode(bytes, 0xc7, jump_offset(*line, "ifnonnull")?)?,
+        ifnull(line) => write_u2_jump_opcode(bytes, 0xc6, jump_offset(*line, "ifnull")?)?,
+        jsr(line) => write_u2_jump_opcode(bytes, 0xa8, jump_offset(*line, "jsr")?)?,
+        goto_w(line) => {
+            bytes.push(0xc8);
+            utils::push_u4_as_offset(bytes, jump_offset(*line, "goto_w")?)?;
+        }
+        jsr_w(line) => {
+            bytes.push(0xc9);
+            utils::push_u4_as_offset(bytes, jump_offset(*line, "jsr_w")?)?;
+        }
+        invokedynamic(index) => {
+            write_u2_index_opcode(bytes, 0xba, *index)?;
+            bytes.extend_from_slice(&[0, 0]);
+        }
+        invokeinterface(index, count) => {
+            write_u2_index_opcode(bytes, 0xb9, *index)?;
+            utils::push_u1_index(bytes, *count)?;
+            bytes.push(0);
+        }
+        multinewarray(index, dimensions) => {
+            write_u2_index_opcode(bytes, 0xc5, *index)?;
+            utils::push_u1_index(bytes, *dimensions)?;
+        }
+        newarray(array_type) => {
+            bytes.push(0xbc);
+            bytes.push(array_type.try_into()?);
+        }
+        wide(w) => {
+            bytes.push(0xc4);
+            write_wide(bytes, w)?;
+        }
+        tableswitch(table_switch) => {
+            bytes.push(0xaa);
+            write_switch_padding(bytes, current_line);
+            utils::push_u4_as_offset(
+                bytes,
+                jump_offset(table_switch.default, "tableswitch_default")?,
+            )?;
+            bytes.extend_from_slice(&table_switch.low().to_be_bytes());
+            bytes.extend_from_slice(&table_switch.high().to_be_bytes());
+            for target_line in &table_switch.jumps {
+                utils::push_u4_as_offset(bytes, jump_offset(*target_line, "tableswitch_target")?)?;
+            }
+        }
+        lookupswitch(lookup_switch) => {
+            bytes.push(0xab);
+            write_switch_padding(bytes, current_line);
+            utils::push_u4_as_offset(
+                bytes,
+                jump_offset(lookup_switch.default, "lookupswitch_default")?,
+            )?;
+            utils::push_u4_index(bytes, lookup_switch.pairs.len())?;
+            for pair in &lookup_switch.pairs {
+                bytes.extend_from_slice(&pair.value.to_be_bytes());
+                utils::push_u4_as_offset(bytes, jump_offset(pair.jump, "lookupswitch_pair")?)?;
+            }
+        }
+        // handled by simple_opcode_tag
+        _ => return Err(ParseError::InvalidOpCode),
+    }
+
+    Ok(())
+}
+
+fn write_u1_index_opcode(bytes: &mut Vec<u8>, tag: u8, index: usize) -> Result<(), ParseError> {
+    bytes.push(tag);
+    utils::push_u1_index(bytes, index)
+}
+
+fn write_u2_index_opcode(bytes: &mut Vec<u8>, tag: u8, index: usize) -> Result<(), ParseError> {
+    bytes.push(tag);
+    utils::push_u2_index(bytes, index)
+}
+
+fn write_u2_jump_opcode(bytes: &mut Vec<u8>, tag: u8, offset: i64) -> Result<(), ParseError> {
+    bytes.push(tag);
+    utils::push_u2_as_offset(bytes, offset)
+}
+
+/// Encode the opcodes back to bytes, the jumps going from opcode indexes to byte offsets
+///
+/// Return the bytes and the offset of each opcode, followed by the code length.
+/// The jumps keep their encoding, a goto too far away for a u2 offset is an error,
+/// not converted to a goto_w.
+pub fn write_opcodes(opcodes: &[OpCode]) -> Result<(Vec<u8>, Vec<usize>), ParseError> {
+    // the size of an opcode only depends of its position (switch padding),
+    // so a first pass with dummy jumps gives the offsets
+    let mut opcode_offsets = Vec::with_capacity(opcodes.len() + 1);
+    let mut layout = Vec::new();
+    for opcode in opcodes {
+        let current_line = layout.len();
+        opcode_offsets.push(current_line);
+        write_opcode(&mut layout, opcode, current_line, |_, _| Ok(0))?;
+    }
+    opcode_offsets.push(layout.len());
+
+    let mut bytes = Vec::with_capacity(layout.len());
+    for (opcode, current_line) in opcodes.iter().zip(&opcode_offsets) {
+        write_opcode(&mut bytes, opcode, *current_line, |target, opcode| {
+            let target_line =
+                opcode_offsets
+                    .get(target)
+                    .ok_or(ParseError::InvalidOpcodeJumpIndex {
+                        opcode,
+                        jump_target: target,
+                    })?;
+            Ok(*target_line as i64 - *current_line as i64)
+        })?;
+    }
+
+    Ok((bytes, opcode_offsets))
+}
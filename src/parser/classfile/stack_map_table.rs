@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+
+use crate::parser::utils::{pop1, pop_u2_as_index, push_u2_index, FileByte, ParseError};
+
+use super::opcode::update_jump;
+
+/*
+    StackMapTable_attribute {
+        u2 attribute_name_index;
+        u4 attribute_length;
+        u2 number_of_entries;
+        stack_map_frame entries[number_of_entries];
+    }
+
+    frame_type:
+        0-63:       same_frame                                  | offset_delta = frame_type
+        64-127:     same_locals_1_stack_item_frame              | offset_delta = frame_type - 64, 1 stack item
+        128-246:    reserved
+        247:        same_locals_1_stack_item_frame_extended     | u2 offset_delta, 1 stack item
+        248-250:    chop_frame                                  | u2 offset_delta, 251 - frame_type locals removed
+        251:        same_frame_extended                         | u2 offset_delta
+        252-254:    append_frame                                | u2 offset_delta, frame_type - 251 locals added
+        255:        full_frame                                  | u2 offset_delta, u2 locals count, locals, u2 stack count, stack
+
+    The first frame applies at offset_delta, the next ones at previous_offset + offset_delta + 1.
+
+    verification_type_info:
+        u1 tag;
+        u2 cpool_index; (Object, tag = 7)
+        u2 offset; (Uninitialized, tag = 8)
+*/
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationTypeInfo {
+    Top,
+    Integer,
+    Float,
+    Long,
+    Double,
+    Null,
+    UninitializedThis,
+    // Class constant
+    Object(usize),
+    // opcode index of the `new` creating the object
+    Uninitialized(usize),
+}
+
+#[derive(Debug, Clone)]
+pub enum StackMapFrameType {
+    Same,
+    SameLocals1StackItem(VerificationTypeInfo),
+    SameLocals1StackItemExtended(VerificationTypeInfo),
+    // number of removed locals, from 1 to 3
+    Chop(usize),
+    SameExtended,
+    // 1 to 3 new locals
+    Append(Vec<VerificationTypeInfo>),
+    Full {
+        locals: Vec<VerificationTypeInfo>,
+        stack: Vec<VerificationTypeInfo>,
+    },
+}
+
+impl StackMapFrameType {
+    /// The frame_type byte of the frame
+    ///
+    /// The short forms are used when the offset delta fits in the frame type.
+    pub fn frame_type(&self, offset_delta: usize) -> u8 {
+        match self {
+            StackMapFrameType::Same if offset_delta < 64 => offset_delta as u8,
+            StackMapFrameType::SameLocals1StackItem(_) if offset_delta < 64 => {
+                64 + offset_delta as u8
+            }
+            StackMapFrameType::Same | StackMapFrameType::SameExtended => 251,
+            StackMapFrameType::SameLocals1StackItem(_)
+            | StackMapFrameType::SameLocals1StackItemExtended(_) => 247,
+            StackMapFrameType::Chop(count) => 251 - *count as u8,
+            StackMapFrameType::Append(locals) => 251 + locals.len() as u8,
+            StackMapFrameType::Full { .. } => 255,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StackMapFrame {
+    // opcode index where the frame applies
+    index: usize,
+    frame_type: StackMapFrameType,
+}
+
+impl StackMapFrame {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn frame_type(&self) -> &StackMapFrameType {
+        &self.frame_type
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StackMapTableAttribute {
+    frames: Vec<StackMapFrame>,
+}
+
+impl StackMapTableAttribute {
+    pub fn frames(&self) -> &[StackMapFrame] {
+        &self.frames
+    }
+}
+
+fn parse_verification_type_info<I>(bytes: &mut I) -> Result<VerificationTypeInfo, ParseError>
+where
+    I: Iterator<Item = FileByte>,
+{
+    let tag = pop1(bytes)?;
+    let info = match tag {
+        0 => VerificationTypeInfo::Top,
+        1 => VerificationTypeInfo::Integer,
+        2 => VerificationTypeInfo::Float,
+        3 => VerificationTypeInfo::Double,
+        4 => VerificationTypeInfo::Long,
+        5 => VerificationTypeInfo::Null,
+        6 => VerificationTypeInfo::UninitializedThis,
+        7 => VerificationTypeInfo::Object(pop_u2_as_index(bytes)?),
+        8 => VerificationTypeInfo::Uninitialized(pop_u2_as_index(bytes)?),
+        _ => return Err(ParseError::InvalidVerificationType(tag)),
+    };
+    Ok(info)
+}
+
+fn parse_n_verification_type_infos<I>(
+    bytes: &mut I,
+    count: usize,
+) -> Result<Vec<VerificationTypeInfo>, ParseError>
+where
+    I: Iterator<Item = FileByte>,
+{
+    let mut infos = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let info = parse_verification_type_info(bytes)?;
+        infos.push(info);
+    }
+
+    Ok(infos)
+}
+
+/// Return the frame and its offset delta
+fn parse_stack_map_frame<I>(bytes: &mut I) -> Result<(StackMapFrameType, usize), ParseError>
+where
+    I: Iterator<Item = FileByte>,
+{
+    let frame_type = pop1(bytes)?;
+    let frame = match frame_type {
+        0..=63 => (StackMapFrameType::Same, frame_type.into()),
+        64..=127 => {
+            let stack_item = parse_verification_type_info(bytes)?;
+            let offset_delta = (frame_type - 64).into();
+            (
+                StackMapFrameType::SameLocals1StackItem(stack_item),
+                offset_delta,
+            )
+        }
+        247 => {
+            let offset_delta = pop_u2_as_index(bytes)?;
+            let stack_item = parse_verification_type_info(bytes)?;
+            (
+                StackMapFrameType::SameLocals1StackItemExtended(stack_item),
+                offset_delta,
+            )
+        }
+        248..=250 => {
+            let offset_delta = pop_u2_as_index(bytes)?;
+            let count = (251 - frame_type).into();
+            (StackMapFrameType::Chop(count), offset_delta)
+        }
+        251 => {
+            let offset_delta = pop_u2_as_index(bytes)?;
+            (StackMapFrameType::SameExtended, offset_delta)
+        }
+        252..=254 => {
+            let offset_delta = pop_u2_as_index(bytes)?;
+            let count = (frame_type - 251).into();
+            let locals = parse_n_verification_type_infos(bytes, count)?;
+            (StackMapFrameType::Append(locals), offset_delta)
+        }
+        255 => {
+            let offset_delta = pop_u2_as_index(bytes)?;
+            let locals_count = pop_u2_as_index(bytes)?;
+            let locals = parse_n_verification_type_infos(bytes, locals_count)?;
+            let stack_count = pop_u2_as_index(bytes)?;
+            let stack = parse_n_verification_type_infos(bytes, stack_count)?;
+            (StackMapFrameType::Full { locals, stack }, offset_delta)
+        }
+        _ => return Err(ParseError::InvalidStackMapFrameType(frame_type)),
+    };
+    Ok(frame)
+}
+
+/// The frames hold byte offsets, they are converted to opcode indexes
+/// by `update_stack_map_table_jumps` once the code is known
+pub fn parse_stack_map_table_attribute<I>(
+    bytes: &mut I,
+) -> Result<StackMapTableAttribute, ParseError>
+where
+    I: Iterator<Item = FileByte>,
+{
+    let frames_count = pop_u2_as_index(bytes)?;
+
+    let mut frames = Vec::with_capacity(frames_count);
+    let mut previous_offset = None;
+
+    for _ in 0..frames_count {
+        let (frame_type, offset_delta) = parse_stack_map_frame(bytes)?;
+        let offset = match previous_offset {
+            None => offset_delta,
+            Some(previous_offset) => previous_offset + offset_delta + 1,
+        };
+        previous_offset = Some(offset);
+        frames.push(StackMapFrame {
+            index: offset,
+            frame_type,
+        });
+    }
+
+    Ok(StackMapTableAttribute { frames })
+}
+
+fn update_verification_type_infos_jumps<'a, I>(
+    infos: I,
+    jump_table: &HashMap<usize, usize>,
+) -> Result<(), ParseError>
+where
+    I: IntoIterator<Item = &'a mut VerificationTypeInfo>,
+{
+    for info in infos {
+        if let VerificationTypeInfo::Uninitialized(line) = info {
+            update_jump(line, jump_table, "stack_map_uninitialized")?;
+        }
+    }
+    Ok(())
+}
+
+pub fn update_stack_map_table_jumps(
+    stack_map_table: &mut StackMapTableAttribute,
+    jump_table: &HashMap<usize, usize>,
+) -> Result<(), ParseError> {
+    for frame in &mut stack_map_table.frames {
+        update_jump(&mut frame.index, jump_table, "stack_map_frame")?;
+        match &mut frame.frame_type {
+            StackMapFrameType::SameLocals1StackItem(stack_item)
+            | StackMapFrameType::SameLocals1StackItemExtended(stack_item) => {
+                update_verification_type_infos_jumps([stack_item], jump_table)?
+            }
+            StackMapFrameType::Append(locals) => {
+                update_verification_type_infos_jumps(locals, jump_table)?
+            }
+            StackMapFrameType::Full { locals, stack } => {
+                update_verification_type_infos_jumps(locals.iter_mut().chain(stack), jump_table)?
+            }
+            StackMapFrameType::Same
+            | StackMapFrameType::Chop(_)
+            | StackMapFrameType::SameExtended => {}
+        }
+    }
+    Ok(())
+}
+
+/// Offset delta of each frame, from the byte offset of each opcode
+pub fn stack_map_offset_deltas(
+    stack_map_table: &StackMapTableAttribute,
+    opcode_offsets: &[usize],
+) -> Result<Vec<usize>, ParseError> {
+    let mut offset_deltas = Vec::with_capacity(stack_map_table.frames.len());
+    let mut previous_offset: Option<usize> = None;
+
+    for frame in &stack_map_table.frames {
+        let offset = opcode_offset(opcode_offsets, frame.index, "stack_map_frame")?;
+        let offset_delta = match previous_offset {
+            None => Some(offset),
+            Some(previous_offset) => offset.checked_sub(previous_offset + 1),
+        };
+        // frames must be sorted and on distinct opcodes
+        let offset_delta = offset_delta.ok_or(ParseError::InvalidOpcodeJumpIndex {
+            opcode: "stack_map_frame",
+            jump_target: frame.index,
+        })?;
+        offset_deltas.push(offset_delta);
+        previous_offset = Some(offset);
+    }
+
+    Ok(offset_deltas)
+}
+
+fn opcode_offset(
+    opcode_offsets: &[usize],
+    index: usize,
+    opcode: &'static str,
+) -> Result<usize, ParseError> {
+    opcode_offsets
+        .get(index)
+        .copied()
+        .ok_or(ParseError::InvalidOpcodeJumpIndex {
+            opcode,
+            jump_target: index,
+        })
+}
+
+fn write_verification_type_info(
+    bytes: &mut Vec<u8>,
+    info: &VerificationTypeInfo,
+    opcode_offsets: &[usize],
+) -> Result<(), ParseError> {
+    match info {
+        VerificationTypeInfo::Top => bytes.push(0),
+        VerificationTypeInfo::Integer => bytes.push(1),
+        VerificationTypeInfo::Float => bytes.push(2),
+        VerificationTypeInfo::Double => bytes.push(3),
+        VerificationTypeInfo::Long => bytes.push(4),
+        VerificationTypeInfo::Null => bytes.push(5),
+        VerificationTypeInfo::UninitializedThis => bytes.push(6),
+        VerificationTypeInfo::Object(class_index) => {
+            bytes.push(7);
+            push_u2_index(bytes, *class_index)?;
+        }
+        VerificationTypeInfo::Uninitialized(index) => {
+            bytes.push(8);
+            let offset = opcode_offset(opcode_offsets, *index, "stack_map_uninitialized")?;
+            push_u2_index(bytes, offset)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_n_verification_type_infos(
+    bytes: &mut Vec<u8>,
+    infos: &[VerificationTypeInfo],
+    opcode_offsets: &[usize],
+) -> Result<(), ParseError> {
+    for info in infos {
+        write_verification_type_info(bytes, info, opcode_offsets)?;
+    }
+    Ok(())
+}
+
+pub fn write_stack_map_table_attribute(
+    bytes: &mut Vec<u8>,
+    stack_map_table: &StackMapTableAttribute,
+    opcode_offsets: &[usize],
+) -> Result<(), ParseError> {
+    push_u2_index(bytes, stack_map_table.frames.len())?;
+
+    let offset_deltas = stack_map_offset_deltas(stack_map_table, opcode_offsets)?;
+
+    for (frame, offset_delta) in stack_map_table.frames.iter().zip(offset_deltas) {
+        let frame_type = frame.frame_type.frame_type(offset_delta);
+        bytes.push(frame_type);
+        // only the short forms have the offset delta in the frame type
+        if frame_type > 127 {
+            push_u2_index(bytes, offset_delta)?;
+        }
+        match &frame.frame_type {
+            StackMapFrameType::SameLocals1StackItem(stack_item)
+            | StackMapFrameType::SameLocals1StackItemExtended(stack_item) => {
+                write_verification_type_info(bytes, stack_item, opcode_offsets)?
+            }
+            StackMapFrameType::Append(locals) => {
+                write_n_verification_type_infos(bytes, locals, opcode_offsets)?
+            }
+            StackMapFrameType::Full { locals, stack } => {
+                push_u2_index(bytes, locals.len())?;
+                write_n_verification_type_infos(bytes, locals, opcode_offsets)?;
+                push_u2_index(bytes, stack.len())?;
+                write_n_verification_type_infos(bytes, stack, opcode_offsets)?;
+            }
+            StackMapFrameType::Same
+            | StackMapFrameType::Chop(_)
+            | StackMapFrameType::SameExtended => {}
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use std::{fmt, iter::Peekable, str::Chars};
+
+use super::utils::ParseError;
+
+/*
+    FieldDescriptor:
+        FieldType
+
+    FieldType:
+        BaseType        | B C D F I J S Z
+        ObjectType      | L ClassName ;
+        ArrayType       | [ ComponentType
+
+    MethodDescriptor:
+        ( {ParameterDescriptor} ) ReturnDescriptor
+
+    ReturnDescriptor:
+        FieldType
+        V
+*/
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    Byte,
+    Char,
+    Double,
+    Float,
+    Int,
+    Long,
+    Short,
+    Boolean,
+    Object(String),
+    Array(Box<FieldType>),
+}
+
+impl FieldType {
+    /// Number of slots taken by a value of this type in the locals or on the stack
+    pub fn slot_size(&self) -> usize {
+        match self {
+            FieldType::Double | FieldType::Long => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn is_reference(&self) -> bool {
+        matches!(self, FieldType::Object(_) | FieldType::Array(_))
+    }
+
+    /// The descriptor of the type, the inverse of parse_field_descriptor
+    pub fn descriptor(&self) -> String {
+        match self {
+            FieldType::Byte => "B".to_string(),
+            FieldType::Char => "C".to_string(),
+            FieldType::Double => "D".to_string(),
+            FieldType::Float => "F".to_string(),
+            FieldType::Int => "I".to_string(),
+            FieldType::Long => "J".to_string(),
+            FieldType::Short => "S".to_string(),
+            FieldType::Boolean => "Z".to_string(),
+            FieldType::Object(class_name) => format!("L{};", class_name),
+            FieldType::Array(component_type) => format!("[{}", component_type.descriptor()),
+        }
+    }
+}
+
+/// Display the type like in java source code (java.lang.String[], int, ...)
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::Byte => write!(f, "byte"),
+            FieldType::Char => write!(f, "char"),
+            FieldType::Double => write!(f, "double"),
+            FieldType::Float => write!(f, "float"),
+            FieldType::Int => write!(f, "int"),
+            FieldType::Long => write!(f, "long"),
+            FieldType::Short => write!(f, "short"),
+            FieldType::Boolean => write!(f, "boolean"),
+            FieldType::Object(class_name) => write!(f, "{}", class_name.replace('/', ".")),
+            FieldType::Array(component_type) => write!(f, "{}[]", component_type),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodDescriptor {
+    parameters: Vec<FieldType>,
+    // None for void
+    return_type: Option<FieldType>,
+}
+
+impl MethodDescriptor {
+    pub fn parameters(&self) -> &[FieldType] {
+        &self.parameters
+    }
+
+    pub fn return_type(&self) -> Option<&FieldType> {
+        self.return_type.as_ref()
+    }
+
+    /// Number of local slots taken by the parameters, without the receiver
+    pub fn parameters_size(&self) -> usize {
+        self.parameters.iter().map(FieldType::slot_size).sum()
+    }
+}
+
+fn invalid_descriptor(descriptor: &str) -> ParseError {
+    ParseError::InvalidDescriptor(descriptor.to_string())
+}
+
+fn parse_field_type(
+    chars: &mut Peekable<Chars>,
+    descriptor: &str,
+) -> Result<FieldType, ParseError> {
+    let field_type = match chars.next() {
+        Some('B') => FieldType::Byte,
+        Some('C') => FieldType::Char,
+        Some('D') => FieldType::Double,
+        Some('F') => FieldType::Float,
+        Some('I') => FieldType::Int,
+        Some('J') => FieldType::Long,
+        Some('S') => FieldType::Short,
+        Some('Z') => FieldType::Boolean,
+        Some('L') => {
+            let class_name: String = chars.by_ref().take_while(|c| *c != ';').collect();
+            if class_name.is_empty() {
+                return Err(invalid_descriptor(descriptor));
+            }
+            FieldType::Object(class_name)
+        }
+        Some('[') => {
+            let component_type = parse_field_type(chars, descriptor)?;
+            FieldType::Array(Box::new(component_type))
+        }
+        _ => return Err(invalid_descriptor(descriptor)),
+    };
+    Ok(field_type)
+}
+
+pub fn parse_field_descriptor(descriptor: &str) -> Result<FieldType, ParseError> {
+    let mut chars = descriptor.chars().peekable();
+    let field_type = parse_field_type(&mut chars, descriptor)?;
+    if chars.next().is_some() {
+        return Err(invalid_descriptor(descriptor));
+    }
+    Ok(field_type)
+}
+
+pub fn parse_method_descriptor(descriptor: &str) -> Result<MethodDescriptor, ParseError> {
+    let mut chars = descriptor.chars().peekable();
+
+    if chars.next() != Some('(') {
+        return Err(invalid_descriptor(descriptor));
+    }
+
+    let mut parameters = Vec::new();
+    while chars.next_if_eq(&')').is_none() {
+        let parameter = parse_field_type(&mut chars, descriptor)?;
+        parameters.push(parameter);
+    }
+
+    let return_type = if chars.next_if_eq(&'V').is_some() {
+        None
+    } else {
+        Some(parse_field_type(&mut chars, descriptor)?)
+    };
+
+    if chars.next().is_some() {
+        return Err(invalid_descriptor(descriptor));
+    }
+
+    Ok(MethodDescriptor {
+        parameters,
+        return_type,
+    })
+}
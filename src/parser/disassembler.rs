@@ -0,0 +1,1099 @@
+use super::{
+    classfile::{
+        attributes::{
+            Attribute, BootstrapMethodsAttribute, CodeAttribute, ExceptionsAttribute,
+            InnerClassesAttribute, LineNumberTableAttribute,
+        },
+        classfile::{AccesFlag, ClassFile},
+        constant_pool::{ConstantInfo, ConstantPool, MethodHandleKind},
+        fields::FieldInfo,
+        methods::MethodInfo,
+        opcode::{ArrayType, LookupSwitch, OpCode, TableSwitch, Wide},
+        stack_map_table::{
+            stack_map_offset_deltas, StackMapFrameType, StackMapTableAttribute,
+            VerificationTypeInfo,
+        },
+    },
+    descriptor::{parse_field_descriptor, parse_method_descriptor},
+    utils::ParseError,
+};
+
+/*
+    Render a class file like `javap -c -v -p` does, without the header giving
+    the path, modification date and checksum of the file.
+
+    Comments ("// ...") are aligned on a column, or separated by a single space
+    if the line is already too long.
+*/
+
+const COMMENT_COLUMN: usize = 42;
+const CODE_COMMENT_COLUMN: usize = 46;
+
+// the modifiers javap shows for the nested classes, `abstract` is implied by `interface`
+const INNER_CLASS_MODIFIERS: [(u16, &str); 6] = [
+    (AccesFlag::PUBLIC, "public"),
+    (AccesFlag::PRIVATE, "private"),
+    (AccesFlag::PROTECTED, "protected"),
+    (AccesFlag::STATIC, "static"),
+    (AccesFlag::FINAL, "final"),
+    (AccesFlag::ABSTRACT, "abstract"),
+];
+
+const CLASS_FLAGS: [(u16, &str); 9] = [
+    (AccesFlag::PUBLIC, "ACC_PUBLIC"),
+    (AccesFlag::FINAL, "ACC_FINAL"),
+    (AccesFlag::SUPER, "ACC_SUPER"),
+    (AccesFlag::INTERFACE, "ACC_INTERFACE"),
+    (AccesFlag::ABSTRACT, "ACC_ABSTRACT"),
+    (AccesFlag::SYNTHETIC, "ACC_SYNTHETIC"),
+    (AccesFlag::ANNOTATION, "ACC_ANNOTATION"),
+    (AccesFlag::ENUM, "ACC_ENUM"),
+    (AccesFlag::MODULE, "ACC_MODULE"),
+];
+
+const FIELD_FLAGS: [(u16, &str); 9] = [
+    (AccesFlag::PUBLIC, "ACC_PUBLIC"),
+    (AccesFlag::PRIVATE, "ACC_PRIVATE"),
+    (AccesFlag::PROTECTED, "ACC_PROTECTED"),
+    (AccesFlag::STATIC, "ACC_STATIC"),
+    (AccesFlag::FINAL, "ACC_FINAL"),
+    (AccesFlag::VOLATILE, "ACC_VOLATILE"),
+    (AccesFlag::TRANSIENT, "ACC_TRANSIENT"),
+    (AccesFlag::SYNTHETIC, "ACC_SYNTHETIC"),
+    (AccesFlag::ENUM, "ACC_ENUM"),
+];
+
+const METHOD_FLAGS: [(u16, &str); 12] = [
+    (AccesFlag::PUBLIC, "ACC_PUBLIC"),
+    (AccesFlag::PRIVATE, "ACC_PRIVATE"),
+    (AccesFlag::PROTECTED, "ACC_PROTECTED"),
+    (AccesFlag::STATIC, "ACC_STATIC"),
+    (AccesFlag::FINAL, "ACC_FINAL"),
+    (AccesFlag::SYNCHRONIZED, "ACC_SYNCHRONIZED"),
+    (AccesFlag::BRIDGE, "ACC_BRIDGE"),
+    (AccesFlag::VARARGS, "ACC_VARARGS"),
+    (AccesFlag::NATIVE, "ACC_NATIVE"),
+    (AccesFlag::ABSTRACT, "ACC_ABSTRACT"),
+    (AccesFlag::STRICT, "ACC_STRICT"),
+    (AccesFlag::SYNTHETIC, "ACC_SYNTHETIC"),
+];
+
+fn with_comment(text: String, column: usize, comment: &str) -> String {
+    let padding = column.saturating_sub(text.chars().count()).max(1);
+    format!("{}{}// {}", text, " ".repeat(padding), comment)
+}
+
+fn render_flags(flags: AccesFlag, names: &[(u16, &str)]) -> String {
+    let names: Vec<&str> = names
+        .iter()
+        .filter(|(flag, _)| flags.contains(*flag))
+        .map(|(_, name)| *name)
+        .collect();
+    let mut line = format!("flags: (0x{:04x})", flags.bits());
+    if !names.is_empty() {
+        line.push(' ');
+        line.push_str(&names.join(", "));
+    }
+    line
+}
+
+fn java_name(binary_name: &str) -> String {
+    binary_name.replace('/', ".")
+}
+
+/// Escape a string like java source code
+fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    for c in string.chars() {
+        match c {
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            '"' => escaped.push_str("\\\""),
+            '\'' => escaped.push_str("\\'"),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Java prints floating numbers with at least one decimal,
+/// and switches to scientific notation outside of [10^-3, 10^7[
+fn java_floating(value: f64, display: String, scientific: String) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        let sign = if value < 0.0 { "-" } else { "" };
+        return format!("{}Infinity", sign);
+    }
+    let magnitude = value.abs();
+    if magnitude == 0.0 || (1e-3..1e7).contains(&magnitude) {
+        if display.contains('.') {
+            display
+        } else {
+            format!("{}.0", display)
+        }
+    } else {
+        let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+        if mantissa.contains('.') {
+            format!("{}E{}", mantissa, exponent)
+        } else {
+            format!("{}.0E{}", mantissa, exponent)
+        }
+    }
+}
+
+fn java_float(value: f32) -> String {
+    java_floating(value.into(), format!("{}", value), format!("{:e}", value))
+}
+
+fn java_double(value: f64) -> String {
+    java_floating(value, format!("{}", value), format!("{:e}", value))
+}
+
+fn method_handle_kind(kind: MethodHandleKind) -> (u8, &'static str) {
+    match kind {
+        MethodHandleKind::GetField => (1, "REF_getField"),
+        MethodHandleKind::GetStatic => (2, "REF_getStatic"),
+        MethodHandleKind::PutField => (3, "REF_putField"),
+        MethodHandleKind::PutStatic => (4, "REF_putStatic"),
+        MethodHandleKind::InvokeVirtual => (5, "REF_invokeVirtual"),
+        MethodHandleKind::InvokeStatic => (6, "REF_invokeStatic"),
+        MethodHandleKind::InvokeSpecial => (7, "REF_invokeSpecial"),
+        MethodHandleKind::NewInvokeSpecial => (8, "REF_newInvokeSpecial"),
+        MethodHandleKind::InvokeInterface => (9, "REF_invokeInterface"),
+    }
+}
+
+struct Disassembler<'a> {
+    class_file: &'a ClassFile,
+    pool: &'a ConstantPool,
+    this_class: &'a str,
+    lines: Vec<String>,
+}
+
+impl<'a> Disassembler<'a> {
+    fn class_name(&self, index: usize) -> Result<String, ParseError> {
+        let name = self.pool.get_class_name(index)?;
+        // array classes are quoted
+        if name.starts_with('[') {
+            Ok(format!("\"{}\"", name))
+        } else {
+            Ok(name.to_string())
+        }
+    }
+
+    fn name_and_type(&self, index: usize) -> Result<String, ParseError> {
+        let (name, descriptor) = self.pool.get_name_and_type(index)?;
+        // <init> and <clinit> are quoted
+        if name.starts_with('<') {
+            Ok(format!("\"{}\":{}", name, descriptor))
+        } else {
+            Ok(format!("{}:{}", name, descriptor))
+        }
+    }
+
+    /// Members of this class are displayed without the class name in the code
+    fn member_ref(
+        &self,
+        class_index: usize,
+        name_and_type_index: usize,
+        hide_this_class: bool,
+    ) -> Result<String, ParseError> {
+        let name_and_type = self.name_and_type(name_and_type_index)?;
+        let class_name = self.pool.get_class_name(class_index)?;
+        if hide_this_class && class_name == self.this_class {
+            Ok(name_and_type)
+        } else {
+            Ok(format!(
+                "{}.{}",
+                self.class_name(class_index)?,
+                name_and_type
+            ))
+        }
+    }
+
+    /// Text used in the comments to describe a constant
+    fn describe_constant(&self, index: usize, in_code: bool) -> Result<String, ParseError> {
+        let description = match self.pool.get_checked(index)? {
+            ConstantInfo::Class { .. } => self.class_name(index)?,
+            ConstantInfo::FieldRef {
+                class_index,
+                name_and_type_index,
+            }
+            | ConstantInfo::MethodRef {
+                class_index,
+                name_and_type_index,
+            }
+            | ConstantInfo::InterfaceMethodRef {
+                class_index,
+                name_and_type_index,
+            } => self.member_ref(*class_index, *name_and_type_index, in_code)?,
+            ConstantInfo::String { string_index } => escape(self.pool.get_utf8(*string_index)?),
+            ConstantInfo::Integer(value) => value.to_string(),
+            ConstantInfo::Float(value) => format!("{}f", java_float(*value)),
+            ConstantInfo::Long(value) => format!("{}l", value),
+            ConstantInfo::Double(value) => format!("{}d", java_double(*value)),
+            ConstantInfo::NameAndType { .. } => self.name_and_type(index)?,
+            ConstantInfo::Utf8(string) => escape(string.as_str()),
+            ConstantInfo::MethodHandle {
+                reference_kind,
+                reference_index,
+            } => {
+                let (_, kind_name) = method_handle_kind(*reference_kind);
+                let reference = self.describe_constant(*reference_index, false)?;
+                format!("{} {}", kind_name, reference)
+            }
+            ConstantInfo::MethodType { descriptor_index } => {
+                self.pool.get_utf8(*descriptor_index)?.to_string()
+            }
+            ConstantInfo::Dynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            }
+            | ConstantInfo::InvokeDynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            } => format!(
+                "#{}:{}",
+                bootstrap_method_attr_index,
+                self.name_and_type(*name_and_type_index)?
+            ),
+            ConstantInfo::Module { name_index } | ConstantInfo::Package { name_index } => {
+                self.pool.get_utf8(*name_index)?.to_string()
+            }
+            ConstantInfo::Padding => {
+                return Err(ParseError::UnexpectedConstant {
+                    index,
+                    expected: "non padding constant",
+                })
+            }
+        };
+        Ok(description)
+    }
+
+    /// Comment of an instruction using a constant, prefixed by the kind of constant
+    fn describe_code_constant(&self, index: usize) -> Result<String, ParseError> {
+        let kind = match self.pool.get_checked(index)? {
+            ConstantInfo::Class { .. } => "class",
+            ConstantInfo::FieldRef { .. } => "Field",
+            ConstantInfo::MethodRef { .. } => "Method",
+            ConstantInfo::InterfaceMethodRef { .. } => "InterfaceMethod",
+            ConstantInfo::String { .. } => "String",
+            ConstantInfo::Integer(_) => "int",
+            ConstantInfo::Float(_) => "float",
+            ConstantInfo::Long(_) => "long",
+            ConstantInfo::Double(_) => "double",
+            ConstantInfo::MethodHandle { .. } => "MethodHandle",
+            ConstantInfo::MethodType { .. } => "MethodType",
+            ConstantInfo::Dynamic { .. } => "Dynamic",
+            ConstantInfo::InvokeDynamic { .. } => "InvokeDynamic",
+            _ => "",
+        };
+        Ok(format!("{} {}", kind, self.describe_constant(index, true)?))
+    }
+
+    fn render_header(&mut self) -> Result<(), ParseError> {
+        let class_file = self.class_file;
+        let source_file = class_file
+            .attributes()
+            .iter()
+            .find_map(|info| match info.attribute() {
+                Attribute::SourceFile(source_file) => Some(source_file.source_file_index()),
+                _ => None,
+            });
+        if let Some(index) = source_file {
+            let source_file = self.pool.get_utf8(index)?;
+            self.lines
+                .push(format!("  Compiled from \"{}\"", escape(source_file)));
+        }
+
+        self.lines.push(self.render_class_declaration()?);
+        self.lines
+            .push(format!("  minor version: {}", class_file.minor_version()));
+        self.lines
+            .push(format!("  major version: {}", class_file.major_version()));
+        self.lines.push(format!(
+            "  {}",
+            render_flags(class_file.acces_flag(), &CLASS_FLAGS)
+        ));
+
+        let this_class = format!("  this_class: #{}", class_file.this_class());
+        let this_class_name = self.class_name(class_file.this_class())?;
+        self.lines
+            .push(with_comment(this_class, COMMENT_COLUMN, &this_class_name));
+        let super_class = format!("  super_class: #{}", class_file.super_class());
+        if class_file.super_class() == 0 {
+            self.lines.push(super_class);
+        } else {
+            let super_class_name = self.class_name(class_file.super_class())?;
+            self.lines
+                .push(with_comment(super_class, COMMENT_COLUMN, &super_class_name));
+        }
+
+        self.lines.push(format!(
+            "  interfaces: {}, fields: {}, methods: {}, attributes: {}",
+            class_file.interfaces().len(),
+            class_file.fields().len(),
+            class_file.methods().len(),
+            class_file.attributes().len()
+        ));
+        Ok(())
+    }
+
+    fn render_class_declaration(&self) -> Result<String, ParseError> {
+        let class_file = self.class_file;
+        let flags = class_file.acces_flag();
+        let is_interface = flags.contains(AccesFlag::INTERFACE);
+
+        let mut declaration = String::new();
+        if flags.contains(AccesFlag::PUBLIC) {
+            declaration.push_str("public ");
+        }
+        if flags.contains(AccesFlag::FINAL) {
+            declaration.push_str("final ");
+        }
+        if is_interface {
+            declaration.push_str("interface ");
+        } else {
+            if flags.contains(AccesFlag::ABSTRACT) {
+                declaration.push_str("abstract ");
+            }
+            declaration.push_str("class ");
+        }
+        declaration.push_str(&java_name(self.this_class));
+
+        if !is_interface && class_file.super_class() != 0 {
+            let super_class = self.pool.get_class_name(class_file.super_class())?;
+            if super_class != "java/lang/Object" {
+                declaration.push_str(" extends ");
+                declaration.push_str(&java_name(super_class));
+            }
+        }
+
+        let interfaces = class_file
+            .interfaces()
+            .iter()
+            .map(|index| self.pool.get_class_name(*index).map(java_name))
+            .collect::<Result<Vec<_>, _>>()?;
+        if !interfaces.is_empty() {
+            // interfaces extend other interfaces, classes implement them
+            let keyword = if is_interface {
+                "extends"
+            } else {
+                "implements"
+            };
+            // javap doesn't put a space after the commas here
+            declaration.push_str(&format!(" {} {}", keyword, interfaces.join(",")));
+        }
+        Ok(declaration)
+    }
+
+    fn render_constant_pool(&mut self) -> Result<(), ParseError> {
+        self.lines.push("Constant pool:".to_string());
+        let index_width = (self.pool.size() - 1).to_string().len() + 3;
+        for (index, info) in self.pool.iter() {
+            let (kind, operand, has_comment) = match info {
+                ConstantInfo::Padding => continue,
+                ConstantInfo::Class { name_index } => ("Class", format!("#{}", name_index), true),
+                ConstantInfo::FieldRef {
+                    class_index,
+                    name_and_type_index,
+                } => (
+                    "Fieldref",
+                    format!("#{}.#{}", class_index, name_and_type_index),
+                    true,
+                ),
+                ConstantInfo::MethodRef {
+                    class_index,
+                    name_and_type_index,
+                } => (
+                    "Methodref",
+                    format!("#{}.#{}", class_index, name_and_type_index),
+                    true,
+                ),
+                ConstantInfo::InterfaceMethodRef {
+                    class_index,
+                    name_and_type_index,
+                } => (
+                    "InterfaceMethodref",
+                    format!("#{}.#{}", class_index, name_and_type_index),
+                    true,
+                ),
+                ConstantInfo::String { string_index } => {
+                    ("String", format!("#{}", string_index), true)
+                }
+                ConstantInfo::Integer(_) => {
+                    ("Integer", self.describe_constant(index, false)?, false)
+                }
+                ConstantInfo::Float(_) => ("Float", self.describe_constant(index, false)?, false),
+                ConstantInfo::Long(_) => ("Long", self.describe_constant(index, false)?, false),
+                ConstantInfo::Double(_) => ("Double", self.describe_constant(index, false)?, false),
+                ConstantInfo::NameAndType {
+                    name_index,
+                    descriptor_index,
+                } => (
+                    "NameAndType",
+                    format!("#{}:#{}", name_index, descriptor_index),
+                    true,
+                ),
+                ConstantInfo::Utf8(string) => ("Utf8", escape(string.as_str()), false),
+                ConstantInfo::MethodHandle {
+                    reference_kind,
+                    reference_index,
+                } => {
+                    let (kind, _) = method_handle_kind(*reference_kind);
+                    (
+                        "MethodHandle",
+                        format!("{}:#{}", kind, reference_index),
+                        true,
+                    )
+                }
+                ConstantInfo::MethodType { descriptor_index } => {
+                    ("MethodType", format!("#{}", descriptor_index), true)
+                }
+                ConstantInfo::Dynamic {
+                    bootstrap_method_attr_index,
+                    name_and_type_index,
+                } => (
+                    "Dynamic",
+                    format!("#{}:#{}", bootstrap_method_attr_index, name_and_type_index),
+                    true,
+                ),
+                ConstantInfo::InvokeDynamic {
+                    bootstrap_method_attr_index,
+                    name_and_type_index,
+                } => (
+                    "InvokeDynamic",
+                    format!("#{}:#{}", bootstrap_method_attr_index, name_and_type_index),
+                    true,
+                ),
+                ConstantInfo::Module { name_index } => ("Module", format!("#{}", name_index), true),
+                ConstantInfo::Package { name_index } => {
+                    ("Package", format!("#{}", name_index), true)
+                }
+            };
+            let line = format!(
+                "{:>width$} = {:<18} {}",
+                format!("#{}", index),
+                kind,
+                operand,
+                width = index_width
+            );
+            if has_comment {
+                let mut comment = self.describe_constant(index, false)?;
+                // javap separates the descriptor of a method type by two spaces in the pool
+                if let ConstantInfo::MethodType { .. } = info {
+                    comment.insert(0, ' ');
+                }
+                self.lines
+                    .push(with_comment(line, COMMENT_COLUMN, &comment));
+            } else {
+                self.lines.push(line);
+            }
+        }
+        Ok(())
+    }
+
+    fn render_field(&mut self, field: &FieldInfo) -> Result<(), ParseError> {
+        let flags = field.access_flags();
+        let name = self.pool.get_utf8(field.name_index())?;
+        let descriptor = self.pool.get_utf8(field.descriptor_index())?;
+        let field_type = parse_field_descriptor(descriptor)?;
+
+        let mut declaration = String::from("  ");
+        push_access_modifiers(&mut declaration, flags);
+        for (flag, modifier) in [
+            (AccesFlag::STATIC, "static "),
+            (AccesFlag::FINAL, "final "),
+            (AccesFlag::TRANSIENT, "transient "),
+            (AccesFlag::VOLATILE, "volatile "),
+        ] {
+            if flags.contains(flag) {
+                declaration.push_str(modifier);
+            }
+        }
+        self.lines
+            .push(format!("{}{} {};", declaration, field_type, name));
+        self.lines.push(format!("    descriptor: {}", descriptor));
+        self.lines
+            .push(format!("    {}", render_flags(flags, &FIELD_FLAGS)));
+
+        for info in field.attributes() {
+            if let Attribute::ConstantValue(constant_value) = info.attribute() {
+                let comment = self.describe_code_constant(constant_value.constant_value_index())?;
+                self.lines.push(format!("    ConstantValue: {}", comment));
+            }
+        }
+        Ok(())
+    }
+
+    fn render_method(&mut self, method: &MethodInfo) -> Result<(), ParseError> {
+        let flags = method.access_flags();
+        let name = self.pool.get_utf8(method.name_index())?;
+        let descriptor = self.pool.get_utf8(method.descriptor_index())?;
+
+        self.lines
+            .push(self.render_method_declaration(method, name, descriptor)?);
+        self.lines.push(format!("    descriptor: {}", descriptor));
+        self.lines
+            .push(format!("    {}", render_flags(flags, &METHOD_FLAGS)));
+
+        for info in method.attributes() {
+            match info.attribute() {
+                Attribute::Code(code) => {
+                    let is_static = flags.contains(AccesFlag::STATIC);
+                    // javap counts the parameters, not the slots they use
+                    let args_size = parse_method_descriptor(descriptor)?.parameters().len()
+                        + !is_static as usize;
+                    self.render_code(code, args_size)?;
+                }
+                Attribute::Exceptions(exceptions) => {
+                    self.lines.push("    Exceptions:".to_string());
+                    let throws = self.render_throws(exceptions)?;
+                    self.lines.push(format!("      throws {}", throws));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn render_throws(&self, exceptions: &ExceptionsAttribute) -> Result<String, ParseError> {
+        let exceptions = exceptions
+            .exception_indexes()
+            .iter()
+            .map(|index| self.pool.get_class_name(*index).map(java_name))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(exceptions.join(", "))
+    }
+
+    fn render_method_declaration(
+        &self,
+        method: &MethodInfo,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<String, ParseError> {
+        let flags = method.access_flags();
+        if name == "<clinit>" {
+            return Ok("  static {};".to_string());
+        }
+
+        let is_interface = self.class_file.acces_flag().contains(AccesFlag::INTERFACE);
+        let mut declaration = String::from("  ");
+        push_access_modifiers(&mut declaration, flags);
+        if flags.contains(AccesFlag::ABSTRACT) {
+            declaration.push_str("abstract ");
+        } else if is_interface && !flags.contains(AccesFlag::STATIC) {
+            declaration.push_str("default ");
+        }
+        for (flag, modifier) in [
+            (AccesFlag::STATIC, "static "),
+            (AccesFlag::FINAL, "final "),
+            (AccesFlag::SYNCHRONIZED, "synchronized "),
+            (AccesFlag::NATIVE, "native "),
+        ] {
+            if flags.contains(flag) {
+                declaration.push_str(modifier);
+            }
+        }
+
+        let method_descriptor = parse_method_descriptor(descriptor)?;
+        if name == "<init>" {
+            declaration.push_str(&java_name(self.this_class));
+        } else {
+            match method_descriptor.return_type() {
+                Some(return_type) => declaration.push_str(&return_type.to_string()),
+                None => declaration.push_str("void"),
+            }
+            declaration.push(' ');
+            declaration.push_str(name);
+        }
+
+        let mut parameters: Vec<String> = method_descriptor
+            .parameters()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        if flags.contains(AccesFlag::VARARGS) {
+            if let Some(last) = parameters.last_mut() {
+                if let Some(component) = last.strip_suffix("[]") {
+                    *last = format!("{}...", component);
+                }
+            }
+        }
+        declaration.push_str(&format!("({})", parameters.join(", ")));
+
+        let exceptions = method
+            .attributes()
+            .iter()
+            .find_map(|info| match info.attribute() {
+                Attribute::Exceptions(exceptions) => Some(exceptions),
+                _ => None,
+            });
+        if let Some(exceptions) = exceptions {
+            declaration.push_str(&format!(" throws {}", self.render_throws(exceptions)?));
+        }
+        declaration.push(';');
+        Ok(declaration)
+    }
+
+    fn render_code(&mut self, code: &CodeAttribute, args_size: usize) -> Result<(), ParseError> {
+        self.lines.push("    Code:".to_string());
+        self.lines.push(format!(
+            "      stack={}, locals={}, args_size={}",
+            code.max_stack(),
+            code.max_locals(),
+            args_size
+        ));
+
+        for (index, opcode) in code.code().iter().enumerate() {
+            self.render_opcode(code, index, opcode)?;
+        }
+
+        if !code.exception_table().is_empty() {
+            self.lines.push("      Exception table:".to_string());
+            self.lines
+                .push("         from    to  target type".to_string());
+            for info in code.exception_table() {
+                let catch_type = match info.catch_type() {
+                    0 => "any".to_string(),
+                    index => format!("Class {}", self.class_name(index)?),
+                };
+                self.lines.push(format!(
+                    "{:>14}{:>6}{:>6}   {}",
+                    jump_offset(code, info.code_range().start)?,
+                    jump_offset(code, info.code_range().end)?,
+                    jump_offset(code, info.handler_pc())?,
+                    catch_type
+                ));
+            }
+        }
+
+        for info in code.attributes() {
+            match info.attribute() {
+                Attribute::LineNumberTable(line_number_table) => {
+                    self.render_line_number_table(line_number_table)
+                }
+                Attribute::StackMapTable(stack_map_table) => {
+                    self.render_stack_map_table(code, stack_map_table)?
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn render_bootstrap_methods(
+        &mut self,
+        bootstrap_methods: &BootstrapMethodsAttribute,
+    ) -> Result<(), ParseError> {
+        self.lines.push("BootstrapMethods:".to_string());
+        for (index, info) in bootstrap_methods.bootstrap_methods().iter().enumerate() {
+            let method_ref = info.bootstrap_method_ref();
+            self.lines.push(format!(
+                "  {}: #{} {}",
+                index,
+                method_ref,
+                self.describe_constant(method_ref, false)?
+            ));
+            self.lines.push("    Method arguments:".to_string());
+            for argument in info.bootstrap_arguments() {
+                self.lines.push(format!(
+                    "      #{} {}",
+                    argument,
+                    self.describe_constant(*argument, false)?
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn render_inner_classes(
+        &mut self,
+        inner_classes: &InnerClassesAttribute,
+    ) -> Result<(), ParseError> {
+        self.lines.push("InnerClasses:".to_string());
+        for info in inner_classes.classes() {
+            let flags = info.inner_class_access_flags();
+            let mut line = "  ".to_string();
+            for (flag, modifier) in INNER_CLASS_MODIFIERS {
+                let implied = flag == AccesFlag::ABSTRACT && flags.contains(AccesFlag::INTERFACE);
+                if flags.contains(flag) && !implied {
+                    line.push_str(modifier);
+                    line.push(' ');
+                }
+            }
+
+            // anonymous classes have no name, local classes have no outer class
+            let mut comment = String::new();
+            let name_index = info.inner_name_index();
+            if name_index != 0 {
+                line.push_str(&format!("#{}= ", name_index));
+                comment.push_str(&format!("{}=", self.pool.get_utf8(name_index)?));
+            }
+            let inner_index = info.inner_class_info_index();
+            line.push_str(&format!("#{}", inner_index));
+            comment.push_str(&self.describe_code_constant(inner_index)?);
+            let outer_index = info.outer_class_info_index();
+            if outer_index != 0 {
+                line.push_str(&format!(" of #{}", outer_index));
+                comment.push_str(&format!(
+                    " of {}",
+                    self.describe_code_constant(outer_index)?
+                ));
+            }
+            line.push(';');
+            self.lines
+                .push(with_comment(line, COMMENT_COLUMN, &comment));
+        }
+        Ok(())
+    }
+
+    fn render_stack_map_table(
+        &mut self,
+        code: &CodeAttribute,
+        stack_map_table: &StackMapTableAttribute,
+    ) -> Result<(), ParseError> {
+        let frames = stack_map_table.frames();
+        self.lines.push(format!(
+            "      StackMapTable: number_of_entries = {}",
+            frames.len()
+        ));
+        let offset_deltas = stack_map_offset_deltas(stack_map_table, code.opcode_offsets())?;
+        for (frame, offset_delta) in frames.iter().zip(offset_deltas) {
+            let frame_type = frame.frame_type();
+            let (name, locals, stack) = match frame_type {
+                StackMapFrameType::Same if offset_delta < 64 => ("same", None, None),
+                StackMapFrameType::Same | StackMapFrameType::SameExtended => {
+                    ("same_frame_extended", None, None)
+                }
+                StackMapFrameType::SameLocals1StackItem(stack_item) if offset_delta < 64 => (
+                    "same_locals_1_stack_item",
+                    None,
+                    Some(std::slice::from_ref(stack_item)),
+                ),
+                StackMapFrameType::SameLocals1StackItem(stack_item)
+                | StackMapFrameType::SameLocals1StackItemExtended(stack_item) => (
+                    "same_locals_1_stack_item_frame_extended",
+                    None,
+                    Some(std::slice::from_ref(stack_item)),
+                ),
+                StackMapFrameType::Chop(_) => ("chop", None, None),
+                StackMapFrameType::Append(locals) => ("append", Some(locals.as_slice()), None),
+                StackMapFrameType::Full { locals, stack } => (
+                    "full_frame",
+                    Some(locals.as_slice()),
+                    Some(stack.as_slice()),
+                ),
+            };
+
+            let frame_type = frame_type.frame_type(offset_delta);
+            self.lines.push(format!(
+                "        frame_type = {} /* {} */",
+                frame_type, name
+            ));
+            // the short forms have the offset delta in the frame type
+            if frame_type > 127 {
+                self.lines
+                    .push(format!("          offset_delta = {}", offset_delta));
+            }
+            if let Some(locals) = locals {
+                let locals = self.render_verification_types(code, locals)?;
+                self.lines.push(format!("          locals = {}", locals));
+            }
+            if let Some(stack) = stack {
+                let stack = self.render_verification_types(code, stack)?;
+                self.lines.push(format!("          stack = {}", stack));
+            }
+        }
+        Ok(())
+    }
+
+    fn render_verification_types(
+        &self,
+        code: &CodeAttribute,
+        infos: &[VerificationTypeInfo],
+    ) -> Result<String, ParseError> {
+        if infos.is_empty() {
+            return Ok("[]".to_string());
+        }
+        let infos = infos
+            .iter()
+            .map(|info| {
+                let info = match info {
+                    VerificationTypeInfo::Top => "top".to_string(),
+                    VerificationTypeInfo::Integer => "int".to_string(),
+                    VerificationTypeInfo::Float => "float".to_string(),
+                    VerificationTypeInfo::Long => "long".to_string(),
+                    VerificationTypeInfo::Double => "double".to_string(),
+                    VerificationTypeInfo::Null => "null".to_string(),
+                    VerificationTypeInfo::UninitializedThis => "this".to_string(),
+                    VerificationTypeInfo::Object(index) => {
+                        format!("class {}", self.class_name(*index)?)
+                    }
+                    VerificationTypeInfo::Uninitialized(index) => {
+                        format!("uninitialized {}", jump_offset(code, *index)?)
+                    }
+                };
+                Ok(info)
+            })
+            .collect::<Result<Vec<_>, ParseError>>()?;
+        Ok(format!("[ {} ]", infos.join(", ")))
+    }
+
+    fn render_line_number_table(&mut self, line_number_table: &LineNumberTableAttribute) {
+        self.lines.push("      LineNumberTable:".to_string());
+        for info in line_number_table.infos() {
+            self.lines.push(format!(
+                "        line {}: {}",
+                info.line_number(),
+                info.start_pc()
+            ));
+        }
+    }
+
+    fn render_opcode(
+        &mut self,
+        code: &CodeAttribute,
+        index: usize,
+        opcode: &OpCode,
+    ) -> Result<(), ParseError> {
+        use OpCode::*;
+        let prefix = format!("{:>10}: ", jump_offset(code, index)?);
+        let name = opcode_name(opcode);
+        let with_operand = |operand: String| format!("{}{:<13} {}", prefix, name, operand);
+
+        let line = match opcode {
+            aload(index) | astore(index) | dload(index) | dstore(index) | fload(index)
+            | fstore(index) | iload(index) | istore(index) | lload(index) | lstore(index)
+            | ret(index) => with_operand(index.to_string()),
+            bipush(value) | sipush(value) => with_operand(value.to_string()),
+            iinc(index, delta) => with_operand(format!("{}, {}", index, delta)),
+            goto(target) | goto_w(target) | if_acmpeq(target) | if_acmpne(target)
+            | if_icmpeq(target) | if_icmpne(target) | if_icmplt(target) | if_icmpge(target)
+            | if_icmpgt(target) | if_icmple(target) | ifeq(target) | ifne(target)
+            | iflt(target) | ifge(target) | ifgt(target) | ifle(target) | ifnonnull(target)
+            | ifnull(target) | jsr(target) | jsr_w(target) => {
+                with_operand(jump_offset(code, *target)?.to_string())
+            }
+            anewarray(index) | checkcast(index) | getfield(index) | getstatic(index)
+            | instanceof(index) | invokespecial(index) | invokestatic(index)
+            | invokevirtual(index) | ldc(index) | ldc_w(index) | ldc2_w(index) | new(index)
+            | putfield(index) | putstatic(index) => with_comment(
+                with_operand(format!("#{}", index)),
+                CODE_COMMENT_COLUMN,
+                &self.describe_code_constant(*index)?,
+            ),
+            invokeinterface(index, count) | multinewarray(index, count) => with_comment(
+                with_operand(format!("#{},  {}", index, count)),
+                CODE_COMMENT_COLUMN,
+                &self.describe_code_constant(*index)?,
+            ),
+            invokedynamic(index) => with_comment(
+                with_operand(format!("#{},  0", index)),
+                CODE_COMMENT_COLUMN,
+                &self.describe_code_constant(*index)?,
+            ),
+            newarray(array_type) => {
+                format!("{}{:<14} {}", prefix, name, array_type_name(array_type))
+            }
+            wide(wide_opcode) => format!("{}{}", prefix, render_wide(wide_opcode)),
+            tableswitch(table_switch) => {
+                return self.render_tableswitch(code, prefix, table_switch);
+            }
+            lookupswitch(lookup_switch) => {
+                return self.render_lookupswitch(code, prefix, lookup_switch);
+            }
+            _ => format!("{}{}", prefix, name),
+        };
+        self.lines.push(line);
+        Ok(())
+    }
+
+    fn render_tableswitch(
+        &mut self,
+        code: &CodeAttribute,
+        prefix: String,
+        table_switch: &TableSwitch,
+    ) -> Result<(), ParseError> {
+        self.lines.push(format!(
+            "{}{:<13} {{ // {} to {}",
+            prefix,
+            "tableswitch",
+            table_switch.low(),
+            table_switch.high()
+        ));
+        for (value, jump) in (table_switch.low()..).zip(table_switch.jumps()) {
+            self.lines
+                .push(format!("{:>24}: {}", value, jump_offset(code, *jump)?));
+        }
+        self.render_switch_default(code, table_switch.default())
+    }
+
+    fn render_lookupswitch(
+        &mut self,
+        code: &CodeAttribute,
+        prefix: String,
+        lookup_switch: &LookupSwitch,
+    ) -> Result<(), ParseError> {
+        self.lines.push(format!(
+            "{}{:<13} {{ // {}",
+            prefix,
+            "lookupswitch",
+            lookup_switch.pairs().len()
+        ));
+        for pair in lookup_switch.pairs() {
+            self.lines.push(format!(
+                "{:>24}: {}",
+                pair.value(),
+                jump_offset(code, pair.jump())?
+            ));
+        }
+        self.render_switch_default(code, lookup_switch.default())
+    }
+
+    fn render_switch_default(
+        &mut self,
+        code: &CodeAttribute,
+        default: usize,
+    ) -> Result<(), ParseError> {
+        self.lines.push(format!(
+            "{:>24}: {}",
+            "default",
+            jump_offset(code, default)?
+        ));
+        self.lines.push(format!("{:>13}", "}"));
+        Ok(())
+    }
+}
+
+fn push_access_modifiers(declaration: &mut String, flags: AccesFlag) {
+    if flags.contains(AccesFlag::PUBLIC) {
+        declaration.push_str("public ");
+    } else if flags.contains(AccesFlag::PRIVATE) {
+        declaration.push_str("private ");
+    } else if flags.contains(AccesFlag::PROTECTED) {
+        declaration.push_str("protected ");
+    }
+}
+
+/// Jumps are stored as opcode indexes
+fn jump_offset(code: &CodeAttribute, index: usize) -> Result<usize, ParseError> {
+    code.opcode_offset(index)
+        .ok_or(ParseError::InvalidOpcodeJumpIndex {
+            opcode: "disassembler",
+            jump_target: index,
+        })
+}
+
+fn opcode_name(opcode: &OpCode) -> String {
+    match opcode {
+        // names that are keywords or were misspelled in the enum
+        OpCode::retrn => "return".to_string(),
+        OpCode::multinewarray(..) => "multianewarray".to_string(),
+        // the other variants are named after their mnemonic
+        _ => {
+            let debug = format!("{:?}", opcode);
+            let end = debug.find(['(', ' ']).unwrap_or(debug.len());
+            debug[..end].to_string()
+        }
+    }
+}
+
+fn array_type_name(array_type: &ArrayType) -> String {
+    match array_type {
+        ArrayType::Boolean => "boolean".to_string(),
+        ArrayType::Char => "char".to_string(),
+        ArrayType::Float => "float".to_string(),
+        ArrayType::Double => "double".to_string(),
+        ArrayType::Byte => "byte".to_string(),
+        ArrayType::Short => "short".to_string(),
+        ArrayType::Int => "int".to_string(),
+        ArrayType::Long => "long".to_string(),
+        ArrayType::Reference(class) => class.get_name().to_string(),
+    }
+}
+
+/// javap shows the wide instructions as `<opcode>_w`
+fn render_wide(wide: &Wide) -> String {
+    let (name, operand) = match wide {
+        Wide::iload(index) => ("iload_w", index.to_string()),
+        Wide::fload(index) => ("fload_w", index.to_string()),
+        Wide::aload(index) => ("aload_w", index.to_string()),
+        Wide::lload(index) => ("lload_w", index.to_string()),
+        Wide::dload(index) => ("dload_w", index.to_string()),
+        Wide::istore(index) => ("istore_w", index.to_string()),
+        Wide::fstore(index) => ("fstore_w", index.to_string()),
+        Wide::astore(index) => ("astore_w", index.to_string()),
+        Wide::lstore(index) => ("lstore_w", index.to_string()),
+        Wide::dstore(index) => ("dstore_w", index.to_string()),
+        Wide::ret(index) => ("ret_w", index.to_string()),
+        Wide::iinc(index, delta) => ("iinc_w", format!("{}, {}", index, delta)),
+    };
+    format!("{:<13} {}", name, operand)
+}
+
+/// Render the class file like `javap -c -v -p`, one line per element
+pub fn disassemble(class_file: &ClassFile) -> Result<Vec<String>, ParseError> {
+    let pool = class_file.constant_pool();
+    let mut disassembler = Disassembler {
+        class_file,
+        pool,
+        this_class: pool.get_class_name(class_file.this_class())?,
+        lines: Vec::new(),
+    };
+
+    disassembler.render_header()?;
+    disassembler.render_constant_pool()?;
+
+    disassembler.lines.push("{".to_string());
+    let mut first_member = true;
+    for field in class_file.fields().iter() {
+        if !first_member {
+            disassembler.lines.push(String::new());
+        }
+        first_member = false;
+        disassembler.render_field(field)?;
+    }
+    for method in class_file.methods().iter() {
+        if !first_member {
+            disassembler.lines.push(String::new());
+        }
+        first_member = false;
+        disassembler.render_method(method)?;
+    }
+    disassembler.lines.push("}".to_string());
+
+    // in the order of the class file, like javap
+    for info in class_file.attributes().iter() {
+        match info.attribute() {
+            Attribute::SourceFile(source_file) => {
+                let source_file = pool.get_utf8(source_file.source_file_index())?;
+                disassembler
+                    .lines
+                    .push(format!("SourceFile: \"{}\"", escape(source_file)));
+            }
+            Attribute::BootstrapMethods(bootstrap_methods) => {
+                disassembler.render_bootstrap_methods(bootstrap_methods)?
+            }
+            Attribute::InnerClasses(inner_classes) => {
+                disassembler.render_inner_classes(inner_classes)?
+            }
+            _ => {}
+        }
+    }
+
+    // like javap, don't keep trailing whitespaces (e.g. from strings in comments)
+    let lines = disassembler
+        .lines
+        .into_iter()
+        .map(|line| line.trim_end().to_string())
+        .collect();
+    Ok(lines)
+}
@@ -0,0 +1,290 @@
+use crate::parser::utils::ParseError;
+
+// Decoder for the DEFLATE format (RFC 1951), used by zip entries with compression method 8
+//
+// The stream is a sequence of blocks, each block is either:
+//  - stored: the raw bytes, aligned on a byte boundary
+//  - compressed with the fixed huffman codes defined by the specs
+//  - compressed with dynamic huffman codes, themselves huffman encoded at the start of the block
+//
+// Compressed blocks contain literals and (length, distance) pairs refering to already decoded bytes.
+
+const MAX_BITS: usize = 15;
+const MAX_LITERAL_LENGTH_CODES: usize = 286;
+const MAX_DISTANCE_CODES: usize = 30;
+const FIXED_LITERAL_LENGTH_CODES: usize = 288;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// order in which the code lengths of the code lengths alphabet are stored
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn invalid_stream(reason: &'static str) -> ParseError {
+    ParseError::InvalidDeflateStream(reason)
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    bit_buffer: u32,
+    bit_count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        BitReader {
+            bytes,
+            position: 0,
+            bit_buffer: 0,
+            bit_count: 0,
+        }
+    }
+
+    /// bits are packed starting with the least significant bit of each byte
+    fn bits(&mut self, count: u32) -> Result<u32, ParseError> {
+        while self.bit_count < count {
+            let byte = *self
+                .bytes
+                .get(self.position)
+                .ok_or(ParseError::EndOfStream)?;
+            self.position += 1;
+            self.bit_buffer |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+        }
+        let value = self.bit_buffer & ((1 << count) - 1);
+        self.bit_buffer >>= count;
+        self.bit_count -= count;
+        Ok(value)
+    }
+
+    /// drop the remaining bits of the current byte
+    fn align_to_byte(&mut self) {
+        self.bit_buffer = 0;
+        self.bit_count = 0;
+    }
+
+    fn take_bytes(&mut self, count: usize) -> Result<&'a [u8], ParseError> {
+        let end = self.position + count;
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or(ParseError::EndOfStream)?;
+        self.position = end;
+        Ok(bytes)
+    }
+}
+
+/// Canonical huffman code, stored as the number of codes for each length
+/// and the symbols ordered by code
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, ParseError> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for length in lengths {
+            counts[*length as usize] += 1;
+        }
+
+        // check the code is not over-subscribed, incomplete codes are allowed
+        let mut left: i32 = 1;
+        for count in &counts[1..] {
+            left <<= 1;
+            left -= *count as i32;
+            if left < 0 {
+                return Err(invalid_stream("over-subscribed huffman code"));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for length in 1..MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, length) in lengths.iter().enumerate() {
+            if *length != 0 {
+                let offset = &mut offsets[*length as usize];
+                symbols[*offset as usize] = symbol as u16;
+                *offset += 1;
+            }
+        }
+
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, ParseError> {
+        // code: bits read so far, first: first code of the current length,
+        // index: index of the first symbol of the current length
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for count in &self.counts[1..] {
+            code |= reader.bits(1)? as i32;
+            let count = *count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+        Err(invalid_stream("invalid huffman code"))
+    }
+}
+
+fn inflate_stored(reader: &mut BitReader, output: &mut Vec<u8>) -> Result<(), ParseError> {
+    reader.align_to_byte();
+    let header = reader.take_bytes(4)?;
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    let nlen = u16::from_le_bytes([header[2], header[3]]);
+    if len != !nlen {
+        return Err(invalid_stream("stored block length mismatch"));
+    }
+    let bytes = reader.take_bytes(len.into())?;
+    output.extend_from_slice(bytes);
+    Ok(())
+}
+
+fn inflate_codes(
+    reader: &mut BitReader,
+    output: &mut Vec<u8>,
+    literal_length: &Huffman,
+    distance: &Huffman,
+) -> Result<(), ParseError> {
+    loop {
+        let symbol = literal_length.decode(reader)? as usize;
+        match symbol {
+            0..=255 => output.push(symbol as u8),
+            256 => return Ok(()), // end of block
+            _ => {
+                let length_code = symbol - 257;
+                if length_code >= LENGTH_BASE.len() {
+                    return Err(invalid_stream("invalid length code"));
+                }
+                let length = LENGTH_BASE[length_code] as usize
+                    + reader.bits(LENGTH_EXTRA[length_code].into())? as usize;
+
+                let distance_code = distance.decode(reader)? as usize;
+                if distance_code >= DISTANCE_BASE.len() {
+                    return Err(invalid_stream("invalid distance code"));
+                }
+                let distance = DISTANCE_BASE[distance_code] as usize
+                    + reader.bits(DISTANCE_EXTRA[distance_code].into())? as usize;
+
+                if distance > output.len() {
+                    return Err(invalid_stream("distance too far back"));
+                }
+
+                // the copy can overlap with the bytes being written, so copy one by one
+                let start = output.len() - distance;
+                for i in 0..length {
+                    let byte = output[start + i];
+                    output.push(byte);
+                }
+            }
+        }
+    }
+}
+
+fn fixed_codes() -> Result<(Huffman, Huffman), ParseError> {
+    let mut lengths = [0u8; FIXED_LITERAL_LENGTH_CODES];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    let literal_length = Huffman::new(&lengths)?;
+    let distance = Huffman::new(&[5; MAX_DISTANCE_CODES])?;
+    Ok((literal_length, distance))
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), ParseError> {
+    let literal_length_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+
+    if literal_length_count > MAX_LITERAL_LENGTH_CODES || distance_count > MAX_DISTANCE_CODES {
+        return Err(invalid_stream("too many codes"));
+    }
+
+    let mut code_lengths = [0u8; 19];
+    for index in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[*index] = reader.bits(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&code_lengths)?;
+
+    // the literal/length and distance code lengths are encoded together
+    let total = literal_length_count + distance_count;
+    let mut lengths = Vec::with_capacity(total);
+    while lengths.len() < total {
+        let symbol = code_length_code.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths
+                    .last()
+                    .ok_or(invalid_stream("repeat with no previous length"))?;
+                (previous, 3 + reader.bits(2)?)
+            }
+            17 => (0, 3 + reader.bits(3)?),
+            18 => (0, 11 + reader.bits(7)?),
+            _ => return Err(invalid_stream("invalid code length code")),
+        };
+        if lengths.len() + repeat as usize > total {
+            return Err(invalid_stream("too many code lengths"));
+        }
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+
+    if lengths[256] == 0 {
+        return Err(invalid_stream("missing end of block code"));
+    }
+
+    let literal_length = Huffman::new(&lengths[..literal_length_count])?;
+    let distance = Huffman::new(&lengths[literal_length_count..])?;
+    Ok((literal_length, distance))
+}
+
+/// Decompress a raw deflate stream (no zlib or gzip header)
+pub fn inflate(bytes: &[u8], size_hint: usize) -> Result<Vec<u8>, ParseError> {
+    let mut reader = BitReader::new(bytes);
+    let mut output = Vec::with_capacity(size_hint);
+
+    loop {
+        let is_last = reader.bits(1)? == 1;
+        let block_type = reader.bits(2)?;
+        match block_type {
+            0 => inflate_stored(&mut reader, &mut output)?,
+            1 => {
+                let (literal_length, distance) = fixed_codes()?;
+                inflate_codes(&mut reader, &mut output, &literal_length, &distance)?;
+            }
+            2 => {
+                let (literal_length, distance) = dynamic_codes(&mut reader)?;
+                inflate_codes(&mut reader, &mut output, &literal_length, &distance)?;
+            }
+            _ => return Err(invalid_stream("invalid block type")),
+        }
+        if is_last {
+            return Ok(output);
+        }
+    }
+}
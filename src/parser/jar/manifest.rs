@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+/*
+    META-INF/MANIFEST.MF is a list of sections separated by empty lines,
+    each section is a list of "Name: value" headers.
+    Lines can't be longer than 72 bytes, longer values continue on the next lines,
+    which start with a single space.
+
+    The first section is the main section, describing the whole archive.
+*/
+
+pub const MANIFEST_PATH: &str = "META-INF/MANIFEST.MF";
+
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    main_attributes: HashMap<String, String>,
+}
+
+fn join_continuation_lines(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        match (line.strip_prefix(' '), lines.last_mut()) {
+            (Some(continuation), Some(previous)) if !previous.is_empty() => {
+                previous.push_str(continuation)
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+pub fn parse_manifest(content: &str) -> Manifest {
+    let main_attributes = join_continuation_lines(content)
+        .into_iter()
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            Some((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect();
+
+    Manifest { main_attributes }
+}
+
+impl Manifest {
+    pub fn get_main_attribute(&self, name: &str) -> Option<&str> {
+        self.main_attributes.get(name).map(String::as_str)
+    }
+
+    /// Binary name of the entry point class (with '/' separators)
+    pub fn get_main_class(&self) -> Option<String> {
+        self.get_main_attribute("Main-Class")
+            .map(|main_class| main_class.replace('.', "/"))
+    }
+}
@@ -0,0 +1,3 @@
+pub mod inflate;
+pub mod manifest;
+pub mod zip;
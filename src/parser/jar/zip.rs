@@ -0,0 +1,246 @@
+use std::{fs, path::Path};
+
+use crate::parser::utils::ParseError;
+
+use super::inflate::inflate;
+
+/*
+    All the values are little endian, zip64 is not supported
+
+    end of central directory record:
+        U4:     signature = 0x06054b50
+        U2:     number of this disk
+        U2:     disk where central directory starts
+        U2:     number of central directory records on this disk
+        U2:     total number of central directory records
+        U4:     size of central directory
+        U4:     offset of start of central directory
+        U2:     comment length
+        U1[]:   comment
+
+    central directory file header:
+        U4:     signature = 0x02014b50
+        U2:     version made by
+        U2:     version needed to extract
+        U2:     general purpose bit flag
+        U2:     compression method
+        U2:     last modification time
+        U2:     last modification date
+        U4:     crc-32
+        U4:     compressed size
+        U4:     uncompressed size
+        U2:     file name length
+        U2:     extra field length
+        U2:     file comment length
+        U2:     disk number where file starts
+        U2:     internal file attributes
+        U4:     external file attributes
+        U4:     offset of local file header
+        U1[]:   file name
+        U1[]:   extra field
+        U1[]:   file comment
+
+    local file header:
+        U4:     signature = 0x04034b50
+        U2:     version needed to extract
+        U2:     general purpose bit flag
+        U2:     compression method
+        U2:     last modification time
+        U2:     last modification date
+        U4:     crc-32
+        U4:     compressed size
+        U4:     uncompressed size
+        U2:     file name length
+        U2:     extra field length
+        U1[]:   file name
+        U1[]:   extra field
+        U1[]:   data
+*/
+
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x02014b50;
+const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x04034b50;
+
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+const LOCAL_FILE_HEADER_SIZE: usize = 30;
+const MAX_COMMENT_SIZE: usize = u16::MAX as usize;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, ParseError> {
+    let bits = bytes
+        .get(offset..offset + 2)
+        .ok_or(ParseError::EndOfStream)?;
+    Ok(u16::from_le_bytes([bits[0], bits[1]]))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, ParseError> {
+    let bits = bytes
+        .get(offset..offset + 4)
+        .ok_or(ParseError::EndOfStream)?;
+    Ok(u32::from_le_bytes([bits[0], bits[1], bits[2], bits[3]]))
+}
+
+fn read_signature(bytes: &[u8], offset: usize, expected: u32) -> Result<(), ParseError> {
+    let signature = read_u32(bytes, offset)?;
+    if signature == expected {
+        Ok(())
+    } else {
+        Err(ParseError::BadZipSignature(signature))
+    }
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb88320 & mask);
+        }
+    }
+    !crc
+}
+
+#[derive(Debug, Clone)]
+pub struct ZipEntry {
+    name: String,
+    compression_method: u16,
+    crc32: u32,
+    compressed_size: usize,
+    uncompressed_size: usize,
+    local_header_offset: usize,
+}
+
+impl ZipEntry {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_directory(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    pub fn size(&self) -> usize {
+        self.uncompressed_size
+    }
+}
+
+/// Zip archive fully loaded in memory, the entries are decompressed on demand
+#[derive(Debug)]
+pub struct ZipArchive {
+    bytes: Vec<u8>,
+    entries: Vec<ZipEntry>,
+}
+
+fn find_end_of_central_directory(bytes: &[u8]) -> Result<usize, ParseError> {
+    // the record is at the end of the file, followed by a comment of variable size
+    let last_possible = bytes
+        .len()
+        .checked_sub(END_OF_CENTRAL_DIRECTORY_SIZE)
+        .ok_or(ParseError::EndOfStream)?;
+    let first_possible = last_possible.saturating_sub(MAX_COMMENT_SIZE);
+    (first_possible..=last_possible)
+        .rev()
+        .find(|offset| read_u32(bytes, *offset).ok() == Some(END_OF_CENTRAL_DIRECTORY_SIGNATURE))
+        .ok_or(ParseError::BadZipSignature(0))
+}
+
+/// return the entry and the size of its header
+fn parse_central_directory_header(
+    bytes: &[u8],
+    offset: usize,
+) -> Result<(ZipEntry, usize), ParseError> {
+    read_signature(bytes, offset, CENTRAL_DIRECTORY_SIGNATURE)?;
+    let compression_method = read_u16(bytes, offset + 10)?;
+    let crc32 = read_u32(bytes, offset + 16)?;
+    let compressed_size = read_u32(bytes, offset + 20)? as usize;
+    let uncompressed_size = read_u32(bytes, offset + 24)? as usize;
+    let name_len = read_u16(bytes, offset + 28)? as usize;
+    let extra_len = read_u16(bytes, offset + 30)? as usize;
+    let comment_len = read_u16(bytes, offset + 32)? as usize;
+    let local_header_offset = read_u32(bytes, offset + 42)? as usize;
+
+    let name_start = offset + 46;
+    let name_bytes = bytes
+        .get(name_start..name_start + name_len)
+        .ok_or(ParseError::EndOfStream)?;
+    let name = String::from_utf8_lossy(name_bytes).into_owned();
+
+    let entry = ZipEntry {
+        name,
+        compression_method,
+        crc32,
+        compressed_size,
+        uncompressed_size,
+        local_header_offset,
+    };
+    let header_size = 46 + name_len + extra_len + comment_len;
+
+    Ok((entry, header_size))
+}
+
+impl ZipArchive {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ParseError> {
+        let bytes = fs::read(path).map_err(ParseError::IoError)?;
+        Self::new(bytes)
+    }
+
+    pub fn new(bytes: Vec<u8>) -> Result<Self, ParseError> {
+        let end_offset = find_end_of_central_directory(&bytes)?;
+        let entries_count = read_u16(&bytes, end_offset + 10)? as usize;
+        let mut offset = read_u32(&bytes, end_offset + 16)? as usize;
+
+        let mut entries = Vec::with_capacity(entries_count);
+        for _ in 0..entries_count {
+            let (entry, header_size) = parse_central_directory_header(&bytes, offset)?;
+            offset += header_size;
+            entries.push(entry);
+        }
+
+        Ok(ZipArchive { bytes, entries })
+    }
+
+    pub fn entries(&self) -> &[ZipEntry] {
+        &self.entries
+    }
+
+    pub fn get_entry(&self, name: &str) -> Option<&ZipEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    fn get_compressed_data(&self, entry: &ZipEntry) -> Result<&[u8], ParseError> {
+        let offset = entry.local_header_offset;
+        read_signature(&self.bytes, offset, LOCAL_FILE_HEADER_SIGNATURE)?;
+        // the sizes in the local header can be 0 if a data descriptor is used,
+        // so only the lengths of the name and extra field are taken from it
+        let name_len = read_u16(&self.bytes, offset + 26)? as usize;
+        let extra_len = read_u16(&self.bytes, offset + 28)? as usize;
+        let data_start = offset + LOCAL_FILE_HEADER_SIZE + name_len + extra_len;
+        self.bytes
+            .get(data_start..data_start + entry.compressed_size)
+            .ok_or(ParseError::EndOfStream)
+    }
+
+    pub fn read_entry(&self, entry: &ZipEntry) -> Result<Vec<u8>, ParseError> {
+        let data = self.get_compressed_data(entry)?;
+        let bytes = match entry.compression_method {
+            STORED => data.to_vec(),
+            DEFLATED => inflate(data, entry.uncompressed_size)?,
+            method => return Err(ParseError::UnsupportedCompression(method)),
+        };
+
+        if bytes.len() != entry.uncompressed_size || crc32(&bytes) != entry.crc32 {
+            return Err(ParseError::CorruptedZipEntry(entry.name.clone()));
+        }
+
+        Ok(bytes)
+    }
+
+    pub fn read(&self, name: &str) -> Result<Option<Vec<u8>>, ParseError> {
+        self.get_entry(name)
+            .map(|entry| self.read_entry(entry))
+            .transpose()
+    }
+}
@@ -1,3 +1,10 @@
 pub mod classfile;
+pub mod descriptor;
+pub mod disassembler;
+pub mod jar;
+pub mod modified_utf8;
 pub mod types;
 pub mod utils;
+
+#[cfg(test)]
+mod test;
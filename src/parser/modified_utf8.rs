@@ -0,0 +1,95 @@
+use super::utils::ParseError;
+
+/*
+    Modified UTF-8 (JVMS §4.4.7), the encoding of the CONSTANT_Utf8 entries:
+    the UTF-16 code units are encoded one by one, so the supplementary characters
+    are two 3-byte sequences, and the null character is encoded on 2 bytes
+
+        U+0001 - U+007F             | 0xxxxxxx
+        U+0000, U+0080 - U+07FF     | 110xxxxx 10xxxxxx
+        U+0800 - U+FFFF             | 1110xxxx 10xxxxxx 10xxxxxx
+*/
+
+fn continuation_bits(bytes: &[u8], offset: usize) -> Result<u16, ParseError> {
+    match bytes.get(offset) {
+        Some(byte) if byte & 0xC0 == 0x80 => Ok((byte & 0x3F) as u16),
+        _ => Err(ParseError::InvalidModifiedUtf8(offset)),
+    }
+}
+
+// the UTF-16 code units encoded by the bytes
+fn decode_utf16_units(bytes: &[u8]) -> Result<Vec<u16>, ParseError> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut offset = 0;
+    while let Some(&byte) = bytes.get(offset) {
+        let (unit, size) = match byte {
+            0x01..=0x7F => (byte as u16, 1),
+            0xC0..=0xDF => {
+                let unit = ((byte & 0x1F) as u16) << 6 | continuation_bits(bytes, offset + 1)?;
+                (unit, 2)
+            }
+            0xE0..=0xEF => {
+                let unit = ((byte & 0x0F) as u16) << 12
+                    | continuation_bits(bytes, offset + 1)? << 6
+                    | continuation_bits(bytes, offset + 2)?;
+                (unit, 3)
+            }
+            // 0, a continuation byte, or the start of a 4-byte sequence
+            _ => return Err(ParseError::InvalidModifiedUtf8(offset)),
+        };
+        units.push(unit);
+        offset += size;
+    }
+    Ok(units)
+}
+
+/// The text of a CONSTANT_Utf8
+///
+/// Its UTF-16 code units are kept as is, since a string literal can hold a surrogate that
+/// isn't part of a pair like any Java string. A Rust string can't, so the lone surrogates
+/// are replaced by U+FFFD in the text read for the names and descriptors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModifiedUtf8 {
+    units: Box<[u16]>,
+    text: String,
+}
+
+impl ModifiedUtf8 {
+    pub fn units(&self) -> &[u16] {
+        &self.units
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+/// Decode the bytes of a CONSTANT_Utf8
+pub fn decode_modified_utf8(bytes: &[u8]) -> Result<ModifiedUtf8, ParseError> {
+    let units = decode_utf16_units(bytes)?;
+    let text = String::from_utf16_lossy(&units);
+    Ok(ModifiedUtf8 {
+        units: units.into_boxed_slice(),
+        text,
+    })
+}
+
+/// Encode UTF-16 code units the way they are written in a CONSTANT_Utf8
+pub fn encode_modified_utf8(units: &[u16]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(units.len());
+    for &unit in units {
+        match unit {
+            0x0001..=0x007F => bytes.push(unit as u8),
+            0x0000 | 0x0080..=0x07FF => {
+                bytes.push(0xC0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+            _ => {
+                bytes.push(0xE0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+        }
+    }
+    bytes
+}
@@ -0,0 +1,198 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, Read},
+};
+
+use super::{
+    classfile::{
+        attributes::Attribute,
+        classfile::{parse_class_file, write_class_file, ClassFile},
+        stack_map_table::{StackMapFrameType, VerificationTypeInfo},
+    },
+    disassembler::disassemble,
+    modified_utf8::{decode_modified_utf8, encode_modified_utf8},
+    utils::ParseError,
+};
+
+fn load_sample_class(path: &str) -> ClassFile {
+    let file = File::open(path).unwrap();
+    let mut bytes = BufReader::new(file).bytes();
+    parse_class_file(&mut bytes).unwrap()
+}
+
+fn assert_disassembly(class_path: &str, javap_path: &str) {
+    let class_file = load_sample_class(class_path);
+    let lines = disassemble(&class_file).unwrap();
+    let expected = fs::read_to_string(javap_path).unwrap();
+    let expected: Vec<&str> = expected.lines().collect();
+    for (line_number, (line, expected)) in lines.iter().zip(&expected).enumerate() {
+        assert_eq!(line, expected, "line {}", line_number + 1);
+    }
+    assert_eq!(lines.len(), expected.len());
+}
+
+#[test]
+fn test_disassemble_hello_world() {
+    assert_disassembly(
+        "./sample/HelloWorld.class",
+        "./sample/disasm/HelloWorld.javap.txt",
+    );
+}
+
+#[test]
+fn test_disassemble_class() {
+    assert_disassembly(
+        "./sample/disasm/Shapes.class",
+        "./sample/disasm/Shapes.javap.txt",
+    );
+}
+
+#[test]
+fn test_disassemble_stack_map_frames() {
+    assert_disassembly(
+        "./sample/disasm/Frames.class",
+        "./sample/disasm/Frames.javap.txt",
+    );
+}
+
+#[test]
+fn test_disassemble_interface() {
+    assert_disassembly(
+        "./sample/disasm/Greeter.class",
+        "./sample/disasm/Greeter.javap.txt",
+    );
+}
+
+#[test]
+fn test_disassemble_lambdas() {
+    assert_disassembly(
+        "./sample/disasm/Lambdas.class",
+        "./sample/disasm/Lambdas.javap.txt",
+    );
+}
+
+const SAMPLE_CLASSES: [&str; 11] = [
+    "./sample/HelloWorld.class",
+    "./sample/disasm/Shapes.class",
+    "./sample/disasm/Frames.class",
+    "./sample/disasm/Greeter.class",
+    "./sample/disasm/Lambdas.class",
+    "./sample/class_loader/Animal.class",
+    "./sample/class_loader/Dog.class",
+    "./sample/class_loader/Pet.class",
+    "./sample/class_loader/java/lang/Object.class",
+    "./sample/launcher/Main.class",
+    // literals with a null character and a supplementary character
+    "./sample/strings/Strings.class",
+];
+
+#[test]
+fn test_write_class_file_identical() {
+    for path in SAMPLE_CLASSES {
+        let original = fs::read(path).unwrap();
+        let class_file = load_sample_class(path);
+        let written = write_class_file(&class_file).unwrap();
+        assert!(
+            original == written,
+            "{} is not written back identically",
+            path
+        );
+    }
+}
+
+#[test]
+fn test_write_class_file_round_trip() {
+    for path in SAMPLE_CLASSES {
+        let class_file = load_sample_class(path);
+        let written = write_class_file(&class_file).unwrap();
+        let mut bytes = written.iter().copied().map(Ok);
+        let reparsed = parse_class_file(&mut bytes).unwrap();
+        // the jumps are opcode indexes, so the listing only matches if they were re-encoded correctly
+        assert_eq!(
+            disassemble(&class_file).unwrap(),
+            disassemble(&reparsed).unwrap(),
+            "{}",
+            path
+        );
+        assert_eq!(write_class_file(&reparsed).unwrap(), written);
+    }
+}
+
+#[test]
+fn test_parse_stack_map_table() {
+    let class_file = load_sample_class("./sample/disasm/Frames.class");
+    let constant_pool = class_file.constant_pool();
+    let method = class_file
+        .methods()
+        .iter()
+        .find(|method| constant_pool.get_utf8(method.name_index()).unwrap() == "create")
+        .unwrap();
+    let stack_map_table = method
+        .get_code()
+        .unwrap()
+        .attributes()
+        .iter()
+        .find_map(|info| match info.attribute() {
+            Attribute::StackMapTable(stack_map_table) => Some(stack_map_table),
+            _ => None,
+        })
+        .unwrap();
+
+    // frames are on iconst_4 (byte 12) and invokespecial (byte 13),
+    // the objects are created by the `new` at byte 0
+    let frames = stack_map_table.frames();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].index(), 6);
+    assert_eq!(frames[1].index(), 7);
+    match frames[1].frame_type() {
+        StackMapFrameType::Full { locals, stack } => {
+            assert_eq!(locals, &[VerificationTypeInfo::Integer]);
+            assert_eq!(
+                stack,
+                &[
+                    VerificationTypeInfo::Uninitialized(0),
+                    VerificationTypeInfo::Uninitialized(0),
+                    VerificationTypeInfo::Integer
+                ]
+            );
+        }
+        frame_type => panic!("expected a full frame, got {:?}", frame_type),
+    }
+}
+
+#[test]
+fn test_modified_utf8() {
+    let bytes = b"a\xC0\x80\xC3\xA9\xE2\x82\xAC";
+    let decoded = decode_modified_utf8(bytes).unwrap();
+    assert_eq!(decoded.as_str(), "a\0é€");
+    assert_eq!(encode_modified_utf8(decoded.units()), bytes);
+
+    // the supplementary characters are a surrogate pair, not a 4-byte sequence
+    let smiley = b"\xED\xA0\xBD\xED\xB8\x80";
+    let decoded = decode_modified_utf8(smiley).unwrap();
+    assert_eq!(decoded.as_str(), "\u{1F600}");
+    assert_eq!(decoded.units(), [0xD83D, 0xDE00]);
+    assert_eq!(encode_modified_utf8(decoded.units()), smiley);
+
+    // a lone surrogate is kept as is
+    let lone = decode_modified_utf8(b"\xED\xA0\xBDa").unwrap();
+    assert_eq!(lone.units(), [0xD83D, 'a' as u16]);
+    assert_eq!(encode_modified_utf8(lone.units()), b"\xED\xA0\xBDa");
+
+    assert!(matches!(
+        decode_modified_utf8(b"a\x00"),
+        Err(ParseError::InvalidModifiedUtf8(1))
+    ));
+    assert!(matches!(
+        decode_modified_utf8("\u{1F600}".as_bytes()),
+        Err(ParseError::InvalidModifiedUtf8(0))
+    ));
+    assert!(matches!(
+        decode_modified_utf8(b"\xE2\x82"),
+        Err(ParseError::InvalidModifiedUtf8(2))
+    ));
+    assert!(matches!(
+        decode_modified_utf8(b"\xC3a"),
+        Err(ParseError::InvalidModifiedUtf8(1))
+    ));
+}
@@ -20,6 +20,22 @@ pub enum ParseError {
     InvalidOpCode,
     InvalidWideOpCode,
     InvalidMethodHandleKind(u8),
+    InvalidStackMapFrameType(u8),
+    InvalidVerificationType(u8),
+    InvalidDescriptor(String),
+    UnexpectedConstant {
+        index: usize,
+        expected: &'static str,
+    },
+    BadZipSignature(u32),
+    UnsupportedCompression(u16),
+    InvalidDeflateStream(&'static str),
+    CorruptedZipEntry(String),
+    // a value doesn't fit in the bytes the class file gives it
+    ValueOutOfRange(i64),
+    UnsupportedAttribute(&'static str),
+    // offset of the malformed byte in the CONSTANT_Utf8
+    InvalidModifiedUtf8(usize),
 }
 
 pub fn pop1<I>(bytes: &mut I) -> Result<u8, ParseError>
@@ -140,3 +156,33 @@ where
         Ok(())
     }
 }
+
+pub fn push_u1_index(bytes: &mut Vec<u8>, index: usize) -> Result<(), ParseError> {
+    let index = u8::try_from(index).map_err(|_| ParseError::ValueOutOfRange(index as i64))?;
+    bytes.push(index);
+    Ok(())
+}
+
+pub fn push_u2_index(bytes: &mut Vec<u8>, index: usize) -> Result<(), ParseError> {
+    let index = u16::try_from(index).map_err(|_| ParseError::ValueOutOfRange(index as i64))?;
+    bytes.extend_from_slice(&index.to_be_bytes());
+    Ok(())
+}
+
+pub fn push_u4_index(bytes: &mut Vec<u8>, index: usize) -> Result<(), ParseError> {
+    let index = u32::try_from(index).map_err(|_| ParseError::ValueOutOfRange(index as i64))?;
+    bytes.extend_from_slice(&index.to_be_bytes());
+    Ok(())
+}
+
+pub fn push_u2_as_offset(bytes: &mut Vec<u8>, offset: i64) -> Result<(), ParseError> {
+    let offset = i16::try_from(offset).map_err(|_| ParseError::ValueOutOfRange(offset))?;
+    bytes.extend_from_slice(&offset.to_be_bytes());
+    Ok(())
+}
+
+pub fn push_u4_as_offset(bytes: &mut Vec<u8>, offset: i64) -> Result<(), ParseError> {
+    let offset = i32::try_from(offset).map_err(|_| ParseError::ValueOutOfRange(offset))?;
+    bytes.extend_from_slice(&offset.to_be_bytes());
+    Ok(())
+}
@@ -0,0 +1,95 @@
+// the name of a class file and its bytes, embedded in the executable
+macro_rules! class_file {
+    ($name:literal) => {
+        (
+            concat!($name, ".class"),
+            include_bytes!(concat!("../../classlib/", $name, ".class")).as_slice(),
+        )
+    };
+}
+
+/// Shown as the location of the library classes, like the directory of a classpath entry
+pub const CLASS_LIBRARY_PATH: &str = "classlib";
+
+/// The class files of the class library bundled with the virtual machine,
+/// so programs run without a JDK
+///
+/// They are compiled from the sources in classlib/, from that directory with
+/// `javac --patch-module java.base=. -d . $(find . -name '*.java')`.
+/// The native methods they declare are implemented in runtime::natives.
+const CLASS_FILES: &[(&str, &[u8])] = &[
+    class_file!("java/io/FileDescriptor"),
+    class_file!("java/io/FileOutputStream"),
+    class_file!("java/io/IOException"),
+    class_file!("java/io/OutputStream"),
+    class_file!("java/io/PrintStream"),
+    class_file!("java/io/Serializable"),
+    class_file!("java/lang/AbstractMethodError"),
+    class_file!("java/lang/ArithmeticException"),
+    class_file!("java/lang/ArrayIndexOutOfBoundsException"),
+    class_file!("java/lang/ArrayStoreException"),
+    class_file!("java/lang/BootstrapMethodError"),
+    class_file!("java/lang/Class"),
+    class_file!("java/lang/ClassCastException"),
+    class_file!("java/lang/ClassCircularityError"),
+    class_file!("java/lang/ClassFormatError"),
+    class_file!("java/lang/Cloneable"),
+    class_file!("java/lang/CloneNotSupportedException"),
+    class_file!("java/lang/Error"),
+    class_file!("java/lang/Exception"),
+    class_file!("java/lang/ExceptionInInitializerError"),
+    class_file!("java/lang/IllegalArgumentException"),
+    class_file!("java/lang/IllegalMonitorStateException"),
+    class_file!("java/lang/IllegalStateException"),
+    class_file!("java/lang/IllegalThreadStateException"),
+    class_file!("java/lang/IncompatibleClassChangeError"),
+    class_file!("java/lang/IndexOutOfBoundsException"),
+    class_file!("java/lang/InstantiationError"),
+    class_file!("java/lang/Integer"),
+    class_file!("java/lang/InternalError"),
+    class_file!("java/lang/InterruptedException"),
+    class_file!("java/lang/invoke/MethodHandles"),
+    class_file!("java/lang/invoke/MethodHandles$Lookup"),
+    class_file!("java/lang/LinkageError"),
+    class_file!("java/lang/Long"),
+    class_file!("java/lang/Math"),
+    class_file!("java/lang/NegativeArraySizeException"),
+    class_file!("java/lang/NoClassDefFoundError"),
+    class_file!("java/lang/NoSuchFieldError"),
+    class_file!("java/lang/NoSuchMethodError"),
+    class_file!("java/lang/NullPointerException"),
+    class_file!("java/lang/Number"),
+    class_file!("java/lang/NumberFormatException"),
+    class_file!("java/lang/Object"),
+    class_file!("java/lang/OutOfMemoryError"),
+    class_file!("java/lang/Runnable"),
+    class_file!("java/lang/Runtime"),
+    class_file!("java/lang/RuntimeException"),
+    class_file!("java/lang/StackOverflowError"),
+    class_file!("java/lang/String"),
+    class_file!("java/lang/StringBuilder"),
+    class_file!("java/lang/StringIndexOutOfBoundsException"),
+    class_file!("java/lang/System"),
+    class_file!("java/lang/Thread"),
+    class_file!("java/lang/Thread$UncaughtExceptionHandler"),
+    class_file!("java/lang/Throwable"),
+    class_file!("java/lang/UnsatisfiedLinkError"),
+    class_file!("java/lang/UnsupportedOperationException"),
+    class_file!("java/lang/VerifyError"),
+    class_file!("java/lang/VirtualMachineError"),
+    class_file!("java/util/function/BiFunction"),
+    class_file!("java/util/function/Consumer"),
+    class_file!("java/util/function/Function"),
+    class_file!("java/util/function/IntBinaryOperator"),
+    class_file!("java/util/function/Predicate"),
+    class_file!("java/util/function/Supplier"),
+    class_file!("java/util/Objects"),
+];
+
+/// The bytes of a class file of the library, like `java/lang/Object.class`
+pub fn find_class_file(file_name: &str) -> Option<&'static [u8]> {
+    CLASS_FILES
+        .iter()
+        .find(|(name, _)| *name == file_name)
+        .map(|(_, bytes)| *bytes)
+}
@@ -0,0 +1,429 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs::File,
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use crate::{
+    parser::{
+        classfile::{
+            classfile::{parse_class_file, AccesFlag, ClassFile},
+            opcode::ArrayType,
+        },
+        descriptor::{parse_field_descriptor, FieldType},
+        jar::zip::ZipArchive,
+        utils::ParseError,
+    },
+    runtime_types::Class,
+};
+
+use super::{
+    class_library::{self, CLASS_LIBRARY_PATH},
+    lowering::lower_class,
+    verifier::{verify_class, ClassHierarchy, VerifyError, VerifyErrorKind},
+};
+
+#[derive(Debug)]
+pub enum ClassLoadingError {
+    NotFound(String),
+    InvalidClassFile {
+        class_name: String,
+        error: ParseError,
+    },
+    WrongClassName {
+        expected: String,
+        found: String,
+    },
+    CircularInheritance(String),
+    VerifyError(Box<VerifyError>),
+    // an IncompatibleClassChangeError, with the overridden method
+    OverridesFinalMethod {
+        class_name: String,
+        method: String,
+    },
+}
+
+#[derive(Debug)]
+pub enum ClassPathEntry {
+    Directory(PathBuf),
+    Jar(PathBuf, ZipArchive),
+    // the classes bundled with the virtual machine, searched before the classpath
+    ClassLibrary,
+}
+
+impl ClassPathEntry {
+    /// Jar and zip files are opened as archives, anything else is considered a directory
+    pub fn open(path: PathBuf) -> Result<Self, ParseError> {
+        let is_archive = path
+            .extension()
+            .is_some_and(|extension| extension == "jar" || extension == "zip");
+        if is_archive {
+            let archive = ZipArchive::open(&path)?;
+            Ok(ClassPathEntry::Jar(path, archive))
+        } else {
+            Ok(ClassPathEntry::Directory(path))
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            ClassPathEntry::Directory(path) | ClassPathEntry::Jar(path, _) => path,
+            ClassPathEntry::ClassLibrary => Path::new(CLASS_LIBRARY_PATH),
+        }
+    }
+
+    fn find_class_file(&self, file_name: &str) -> Result<Option<ClassFile>, ParseError> {
+        match self {
+            ClassPathEntry::Directory(directory) => {
+                let path = directory.join(file_name);
+                if !path.is_file() {
+                    return Ok(None);
+                }
+                let file = File::open(path).map_err(ParseError::IoError)?;
+                let mut bytes = BufReader::new(file).bytes();
+                parse_class_file(&mut bytes).map(Some)
+            }
+            ClassPathEntry::Jar(_, archive) => {
+                let Some(bytes) = archive.read(file_name)? else {
+                    return Ok(None);
+                };
+                let mut bytes = bytes.into_iter().map(Ok);
+                parse_class_file(&mut bytes).map(Some)
+            }
+            ClassPathEntry::ClassLibrary => {
+                let Some(bytes) = class_library::find_class_file(file_name) else {
+                    return Ok(None);
+                };
+                let mut bytes = bytes.iter().copied().map(Ok);
+                parse_class_file(&mut bytes).map(Some)
+            }
+        }
+    }
+}
+
+/// Load the classes from the classpath and keep track of the loaded ones
+///
+/// A class is only loaded once, so two classes with the same name are always the same Arc.
+/// This is needed for Class::is_subclass that only compare pointers.
+///
+/// Classes are verified when linked, the first time they are returned by load_class,
+/// and not when loaded, as verifying a class can load the classes it uses.
+#[derive(Debug)]
+pub struct ClassLoader {
+    classpath: Vec<ClassPathEntry>,
+    registry: Mutex<HashMap<String, Arc<Class>>>,
+    // class files of the loaded classes that are not verified yet
+    unlinked: Mutex<HashMap<String, ClassFile>>,
+    // log every loaded class, like -verbose:class
+    verbose: bool,
+}
+
+impl ClassLoader {
+    /// The bundled class library is searched first, like the boot class path of the JDK
+    pub fn new(classpath: Vec<ClassPathEntry>) -> Self {
+        let classpath = std::iter::once(ClassPathEntry::ClassLibrary)
+            .chain(classpath)
+            .collect();
+        ClassLoader {
+            classpath,
+            registry: Mutex::new(HashMap::new()),
+            unlinked: Mutex::new(HashMap::new()),
+            verbose: false,
+        }
+    }
+
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    fn registry(&self) -> MutexGuard<'_, HashMap<String, Arc<Class>>> {
+        // the registry is never left in an inconsistent state, so a poisoned lock is still usable
+        self.registry.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn unlinked(&self) -> MutexGuard<'_, HashMap<String, ClassFile>> {
+        self.unlinked.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn get_loaded_class(&self, name: &str) -> Option<Arc<Class>> {
+        self.registry().get(name).cloned()
+    }
+
+    /// Register a class created by the virtual machine, like the classes of the lambdas
+    ///
+    /// It has no class file, so it is not verified.
+    pub fn define_generated_class(&self, class: Arc<Class>) -> Arc<Class> {
+        let name = class.get_name().to_string();
+        if self.verbose {
+            println!("[Loaded {} from __JVM_LookupDefineClass__]", name);
+        }
+        self.registry().entry(name).or_insert(class).clone()
+    }
+
+    /// The class of a primitive type, None for the other types
+    ///
+    /// It is registered with the name of the type, like int, which is a keyword in Java.
+    pub fn load_primitive_class(&self, primitive: &FieldType) -> Option<Arc<Class>> {
+        if matches!(primitive, FieldType::Object(_) | FieldType::Array(_)) {
+            return None;
+        }
+        let name = primitive.to_string();
+        let mut registry = self.registry();
+        let class = registry
+            .entry(name.clone())
+            .or_insert_with(|| Class::new_primitive(name));
+        Some(class.clone())
+    }
+
+    /// Load the class and link it, so it is ready to be executed
+    pub fn load_class(&self, name: &str) -> Result<Arc<Class>, ClassLoadingError> {
+        let mut loading_chain = Vec::new();
+        let class = self.load_class_in_chain(name, &mut loading_chain)?;
+        self.link_class(&class)?;
+        Ok(class)
+    }
+
+    // the super class and the interfaces are linked first
+    fn link_class(&self, class: &Arc<Class>) -> Result<(), ClassLoadingError> {
+        let name = class.get_name();
+        let Some(class_file) = self.unlinked().remove(name) else {
+            return Ok(());
+        };
+
+        let result = self
+            .link_super_types(class)
+            .and_then(|_| check_final_methods(class))
+            .and_then(|_| verify_class(&class_file, self).map_err(ClassLoadingError::VerifyError));
+
+        // keep the class unlinked, so the next load fails the same way
+        if result.is_err() {
+            self.unlinked().insert(name.to_string(), class_file);
+        }
+        result
+    }
+
+    fn link_super_types(&self, class: &Arc<Class>) -> Result<(), ClassLoadingError> {
+        if let Some(super_class) = class.get_superclass() {
+            self.link_class(super_class)?;
+        }
+        for interface in class.get_interfaces() {
+            self.link_class(interface)?;
+        }
+        Ok(())
+    }
+
+    fn load_class_in_chain(
+        &self,
+        name: &str,
+        loading_chain: &mut Vec<String>,
+    ) -> Result<Arc<Class>, ClassLoadingError> {
+        if let Some(class) = self.get_loaded_class(name) {
+            return Ok(class);
+        }
+
+        if name.starts_with('[') {
+            return self.define_array_class(name, loading_chain);
+        }
+
+        // if the class is already being loaded lower in the chain, it is its own super class
+        if loading_chain.iter().any(|loading| loading == name) {
+            return Err(ClassLoadingError::CircularInheritance(name.to_string()));
+        }
+
+        let (class_file, entry) = self.find_class_file(name)?;
+
+        loading_chain.push(name.to_string());
+        let class = self.define_class(name, class_file, loading_chain);
+        loading_chain.pop();
+
+        if self.verbose && class.is_ok() {
+            println!("[Loaded {} from {}]", name, entry.path().display());
+        }
+
+        class
+    }
+
+    fn find_class_file(
+        &self,
+        name: &str,
+    ) -> Result<(ClassFile, &ClassPathEntry), ClassLoadingError> {
+        let file_name = format!("{}.class", name);
+        for entry in &self.classpath {
+            let class_file = entry.find_class_file(&file_name).map_err(|error| {
+                ClassLoadingError::InvalidClassFile {
+                    class_name: name.to_string(),
+                    error,
+                }
+            })?;
+            if let Some(class_file) = class_file {
+                return Ok((class_file, entry));
+            }
+        }
+        Err(ClassLoadingError::NotFound(name.to_string()))
+    }
+
+    fn define_class(
+        &self,
+        name: &str,
+        class_file: ClassFile,
+        loading_chain: &mut Vec<String>,
+    ) -> Result<Arc<Class>, ClassLoadingError> {
+        let invalid_class_file = |error| ClassLoadingError::InvalidClassFile {
+            class_name: name.to_string(),
+            error,
+        };
+        let constant_pool = class_file.constant_pool();
+
+        let this_class = constant_pool
+            .get_class_name(class_file.this_class())
+            .map_err(invalid_class_file)?;
+        if this_class != name {
+            return Err(ClassLoadingError::WrongClassName {
+                expected: name.to_string(),
+                found: this_class.to_string(),
+            });
+        }
+
+        // only java/lang/Object has no super class
+        let super_class = match class_file.super_class() {
+            0 => None,
+            index => {
+                let super_name = constant_pool
+                    .get_class_name(index)
+                    .map_err(invalid_class_file)?;
+                Some(self.load_class_in_chain(super_name, loading_chain)?)
+            }
+        };
+
+        let mut interfaces = Vec::new();
+        for index in class_file.interfaces().iter() {
+            let interface_name = constant_pool
+                .get_class_name(*index)
+                .map_err(invalid_class_file)?;
+            let interface = self.load_class_in_chain(interface_name, loading_chain)?;
+            interfaces.push(interface);
+        }
+
+        let class =
+            lower_class(&class_file, super_class, interfaces).map_err(invalid_class_file)?;
+
+        // another thread may have loaded the same class in the meantime, keep the first one
+        let mut registry = self.registry();
+        let class = match registry.entry(name.to_string()) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => {
+                self.unlinked().insert(name.to_string(), class_file);
+                entry.insert(class).clone()
+            }
+        };
+
+        Ok(class)
+    }
+}
+
+const OBJECT_CLASS: &str = "java/lang/Object";
+const CLONEABLE_INTERFACE: &str = "java/lang/Cloneable";
+const SERIALIZABLE_INTERFACE: &str = "java/io/Serializable";
+
+impl ClassLoader {
+    /// Array classes don't have a class file, they are created from the name of
+    /// their component type, which is loaded first (JVMS §5.3.3)
+    ///
+    /// They only have the members of Object, and can't be instantiated with new.
+    /// Like in HotSpot they are public if their component is, and always final and abstract.
+    fn define_array_class(
+        &self,
+        name: &str,
+        loading_chain: &mut Vec<String>,
+    ) -> Result<Arc<Class>, ClassLoadingError> {
+        let Ok(FieldType::Array(component)) = parse_field_descriptor(name) else {
+            return Err(ClassLoadingError::NotFound(name.to_string()));
+        };
+        let component_type = match *component {
+            FieldType::Boolean => ArrayType::Boolean,
+            FieldType::Char => ArrayType::Char,
+            FieldType::Float => ArrayType::Float,
+            FieldType::Double => ArrayType::Double,
+            FieldType::Byte => ArrayType::Byte,
+            FieldType::Short => ArrayType::Short,
+            FieldType::Int => ArrayType::Int,
+            FieldType::Long => ArrayType::Long,
+            FieldType::Object(component) => {
+                ArrayType::Reference(self.load_class_in_chain(&component, loading_chain)?)
+            }
+            FieldType::Array(_) => {
+                ArrayType::Reference(self.load_class_in_chain(&name[1..], loading_chain)?)
+            }
+        };
+        let is_public = match &component_type {
+            ArrayType::Reference(component) => {
+                component.get_access_flags().contains(AccesFlag::PUBLIC)
+            }
+            _ => true,
+        };
+        let mut access_flags = AccesFlag::FINAL | AccesFlag::ABSTRACT;
+        if is_public {
+            access_flags |= AccesFlag::PUBLIC;
+        }
+
+        let object = self.load_class_in_chain(OBJECT_CLASS, loading_chain)?;
+        let interfaces = vec![
+            self.load_class_in_chain(CLONEABLE_INTERFACE, loading_chain)?,
+            self.load_class_in_chain(SERIALIZABLE_INTERFACE, loading_chain)?,
+        ];
+        let class = Class::new_array(
+            name.to_string(),
+            AccesFlag::new(access_flags),
+            object,
+            interfaces,
+            component_type,
+        );
+        Ok(self
+            .registry()
+            .entry(name.to_string())
+            .or_insert(class)
+            .clone())
+    }
+}
+
+/// A final method can't be overridden, which is checked by HotSpot when the class is linked
+fn check_final_methods(class: &Class) -> Result<(), ClassLoadingError> {
+    let Some((_, final_method)) = class.find_overridden_final_method() else {
+        return Ok(());
+    };
+    let final_class = final_method
+        .get_class()
+        .map(|final_class| final_class.get_name().replace('/', "."))
+        .unwrap_or_default();
+    Err(ClassLoadingError::OverridesFinalMethod {
+        class_name: class.get_name().to_string(),
+        method: format!(
+            "{}.{}{}",
+            final_class,
+            final_method.get_name(),
+            final_method.get_descriptor()
+        ),
+    })
+}
+
+// The verifier only needs the hierarchy, the classes it loads are not linked.
+impl ClassHierarchy for ClassLoader {
+    fn super_class(&self, class_name: &str) -> Result<Option<String>, VerifyErrorKind> {
+        let class = self
+            .load_class_in_chain(class_name, &mut Vec::new())
+            .map_err(|error| VerifyErrorKind::ClassLoading(Box::new(error)))?;
+        let super_class = class
+            .get_superclass()
+            .map(|class| class.get_name().to_string());
+        Ok(super_class)
+    }
+
+    fn is_interface(&self, class_name: &str) -> Result<bool, VerifyErrorKind> {
+        let class = self
+            .load_class_in_chain(class_name, &mut Vec::new())
+            .map_err(|error| VerifyErrorKind::ClassLoading(Box::new(error)))?;
+        Ok(class.is_interface())
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{
+    parser::descriptor::FieldType,
+    rethrow_exception,
+    runtime::jvm::Jvm,
+    runtime_types::{
+        format_floating, new_utf16_string, read_utf16_string, throw_exception, CallSiteRef,
+        ExecResult, InternalError, Object, ResultValue, RuntimeConstant, Stack,
+        BOOTSTRAP_METHOD_ERROR, STRING_CLASS,
+    },
+};
+
+use super::CallSite;
+
+// the tags of the recipe of makeConcatWithConstants
+const TAG_ARGUMENT: u16 = 0x1;
+const TAG_CONSTANT: u16 = 0x2;
+
+const VALUE_OF_DESCRIPTOR: &str = "(Ljava/lang/Object;)Ljava/lang/String;";
+
+#[derive(Debug)]
+enum ConcatPart {
+    // the literal parts of the recipe and the constants, in UTF-16
+    Text(Vec<u16>),
+    Argument(usize),
+}
+
+/// How an indy string concatenation builds its string from the arguments of the call site
+#[derive(Debug)]
+pub struct ConcatRecipe {
+    parts: Vec<ConcatPart>,
+    argument_types: Vec<FieldType>,
+}
+
+/// StringConcatFactory.makeConcatWithConstants, or makeConcat without a recipe
+///
+/// In the recipe, \1 is replaced by the next argument of the call site and \2 by the next
+/// constant given after the recipe. makeConcat concatenates every argument.
+pub fn make_concat(
+    jvm: &Jvm,
+    call_site: &CallSiteRef,
+    with_constants: bool,
+) -> ExecResult<CallSite> {
+    let descriptor = call_site.parsed_descriptor();
+    let argument_types = descriptor.parameters().to_vec();
+    let returns_string =
+        matches!(descriptor.return_type(), Some(FieldType::Object(name)) if name == STRING_CLASS);
+    let parts = if with_constants {
+        let arguments = call_site.bootstrap_method()?.arguments();
+        parse_recipe(arguments, argument_types.len())
+    } else {
+        Ok((0..argument_types.len())
+            .map(ConcatPart::Argument)
+            .collect())
+    };
+    match parts {
+        Ok(parts) if returns_string => Ok(Ok(CallSite::StringConcat(ConcatRecipe {
+            parts,
+            argument_types,
+        }))),
+        Ok(_) => {
+            let message = "StringConcatFactory call site should return a String".to_string();
+            throw_exception(jvm, BOOTSTRAP_METHOD_ERROR, Some(message))
+        }
+        Err(message) => throw_exception(jvm, BOOTSTRAP_METHOD_ERROR, Some(message)),
+    }
+}
+
+// the constants are formatted once, with the literal parts around them
+fn parse_recipe(
+    arguments: &[RuntimeConstant],
+    arguments_count: usize,
+) -> Result<Vec<ConcatPart>, String> {
+    let Some((RuntimeConstant::String(recipe), constants)) = arguments.split_first() else {
+        return Err("StringConcatFactory expects a recipe".to_string());
+    };
+    let mut constants = constants.iter();
+    let mut parts = Vec::new();
+    let mut text = Vec::new();
+    let mut next_argument = 0;
+    // the units are copied as they are, a lone surrogate of the recipe is kept
+    for &unit in recipe.units() {
+        match unit {
+            TAG_ARGUMENT => {
+                if !text.is_empty() {
+                    parts.push(ConcatPart::Text(std::mem::take(&mut text)));
+                }
+                parts.push(ConcatPart::Argument(next_argument));
+                next_argument += 1;
+            }
+            TAG_CONSTANT => {
+                let constant = match constants.next() {
+                    Some(RuntimeConstant::String(string)) => {
+                        text.extend(string.units());
+                        continue;
+                    }
+                    Some(RuntimeConstant::Integer(value)) => value.to_string(),
+                    Some(RuntimeConstant::Long(value)) => value.to_string(),
+                    Some(RuntimeConstant::Float(value)) => format_floating(*value),
+                    Some(RuntimeConstant::Double(value)) => format_floating(*value),
+                    _ => return Err("Mismatched number of concat constants".to_string()),
+                };
+                text.extend(constant.encode_utf16());
+            }
+            _ => text.push(unit),
+        }
+    }
+    if !text.is_empty() {
+        parts.push(ConcatPart::Text(text));
+    }
+    if next_argument != arguments_count || constants.next().is_some() {
+        return Err("Mismatched number of concat arguments".to_string());
+    }
+    Ok(parts)
+}
+
+impl ConcatRecipe {
+    /// Pop the arguments of the call site and push the concatenated string
+    pub fn concat(&self, jvm: &Jvm, stack: &mut Stack) -> ExecResult {
+        let mut arguments = Vec::with_capacity(self.argument_types.len());
+        for _ in &self.argument_types {
+            arguments.push(stack.pop()?);
+        }
+        arguments.reverse();
+
+        let mut units = Vec::new();
+        for part in &self.parts {
+            match part {
+                ConcatPart::Text(text) => units.extend(text),
+                ConcatPart::Argument(index) => {
+                    let argument_type = &self.argument_types[*index];
+                    let argument = &arguments[*index];
+                    rethrow_exception!(append_value(jvm, &mut units, argument_type, argument)?);
+                }
+            }
+        }
+        let string = rethrow_exception!(new_utf16_string(jvm, units.into_boxed_slice())?);
+        Ok(Ok(ResultValue::Object(Object::Reference(Some(string)))))
+    }
+}
+
+/// Append the value like String.valueOf, the ints are formatted according to their type
+fn append_value(
+    jvm: &Jvm,
+    units: &mut Vec<u16>,
+    value_type: &FieldType,
+    value: &Object,
+) -> ExecResult<()> {
+    let text = match (value_type, value) {
+        (FieldType::Char, Object::Int(value)) => {
+            units.push(*value as u16);
+            return Ok(Ok(()));
+        }
+        (FieldType::Boolean, Object::Int(value)) => (*value != 0).to_string(),
+        (_, Object::Int(value)) => value.to_string(),
+        (_, Object::Long(value)) => value.to_string(),
+        (_, Object::Float(value)) => format_floating(*value),
+        (_, Object::Double(value)) => format_floating(*value),
+        (_, Object::Reference(None) | Object::Array(None)) => "null".to_string(),
+        (_, Object::Reference(Some(string))) if string.get_class().get_name() == STRING_CLASS => {
+            units.extend(read_utf16_string(string)?);
+            return Ok(Ok(()));
+        }
+        (_, Object::Reference(Some(_)) | Object::Array(Some(_))) => {
+            units.extend(rethrow_exception!(value_of(jvm, value)?));
+            return Ok(Ok(()));
+        }
+        _ => return Err(InternalError::WrongType),
+    };
+    units.extend(text.encode_utf16());
+    Ok(Ok(()))
+}
+
+// String.valueOf(Object) of the class library, which calls toString
+fn value_of(jvm: &Jvm, object: &Object) -> ExecResult<Vec<u16>> {
+    let class = jvm
+        .class_loader()
+        .load_class(STRING_CLASS)
+        .map_err(|_| InternalError::ClassLoadingFailed(STRING_CLASS.to_string()))?;
+    rethrow_exception!(class.initialize(jvm)?);
+    let method = class
+        .find_method("valueOf", VALUE_OF_DESCRIPTOR)
+        .ok_or(InternalError::WrongType)?;
+    let mut stack = Stack::new(1);
+    stack.push(object.clone());
+    match rethrow_exception!(method.execute(jvm, &mut stack)?) {
+        Some(Object::Reference(Some(string))) => Ok(Ok(read_utf16_string(&string)?)),
+        // toString returned null
+        Some(Object::Reference(None)) => Ok(Ok("null".encode_utf16().collect())),
+        _ => Err(InternalError::WrongType),
+    }
+}
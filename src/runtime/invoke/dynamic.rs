@@ -0,0 +1,327 @@
+use std::sync::Arc;
+
+use crate::{
+    parser::{
+        classfile::{classfile::AccesFlag, constant_pool::MethodHandleKind},
+        descriptor::FieldType,
+    },
+    rethrow_exception,
+    runtime::jvm::Jvm,
+    runtime_types::{
+        intern_string, resolve_method, throw_exception, wrap_in_error, Array, ClassRef, DynamicRef,
+        ExecResult, InternalError, Object, RuntimeConstant, Stack, BOOTSTRAP_METHOD_ERROR,
+        CLASS_CAST_EXCEPTION, CLASS_CLASS, NULL_POINTER_EXCEPTION, STRING_CLASS,
+    },
+};
+
+use super::{class_name, load_class, unwrapped, wrapper};
+
+const OBJECT_CLASS: &str = "java/lang/Object";
+const LOOKUP_CLASS: &str = "java/lang/invoke/MethodHandles$Lookup";
+const LOOKUP_CLASS_FIELD: &str = "lookupClass";
+
+/// Run the bootstrap method of a dynamically-computed constant, `caller` is the class whose
+/// constant pool holds it
+///
+/// Unlike the ones of the call sites, these bootstrap methods are not generated by javac, so
+/// they are run like any static method. They are given a Lookup on the caller, the name and
+/// the type of the constant, then the static arguments, boxed or unboxed to the types of their
+/// parameters. A varargs bootstrap method gets its trailing static arguments in an array.
+/// The result is converted to the type of the constant the same way.
+///
+/// Errors are thrown as is, other exceptions are wrapped in a BootstrapMethodError.
+pub fn resolve_dynamic_constant(
+    jvm: &Jvm,
+    constant: &DynamicRef,
+    caller: &Arc<ClassRef>,
+) -> ExecResult<Object> {
+    match run_bootstrap_method(jvm, constant, caller)? {
+        Ok(value) => Ok(Ok(value)),
+        Err(exception) => wrap_in_error(jvm, exception, BOOTSTRAP_METHOD_ERROR).map(Err),
+    }
+}
+
+fn run_bootstrap_method(
+    jvm: &Jvm,
+    constant: &DynamicRef,
+    caller: &Arc<ClassRef>,
+) -> ExecResult<Object> {
+    let bootstrap_method = constant.bootstrap_method()?;
+    let method_handle = bootstrap_method.method_handle();
+    let method = match method_handle.method() {
+        Some(method) if method_handle.kind() == MethodHandleKind::InvokeStatic => method,
+        _ => {
+            let message = "Bootstrap method is not a static method".to_string();
+            return throw_exception(jvm, BOOTSTRAP_METHOD_ERROR, Some(message));
+        }
+    };
+    let descriptor = method.parsed_descriptor();
+    let method_name = format!(
+        "{}.{}",
+        method.class().name().replace('/', "."),
+        method.name()
+    );
+    let Some(return_type) = descriptor.return_type() else {
+        let message = format!("Bootstrap method {} returns void", method_name);
+        return throw_exception(jvm, BOOTSTRAP_METHOD_ERROR, Some(message));
+    };
+    let (class, resolved) = rethrow_exception!(resolve_method(jvm, method, true)?);
+    let static_arguments = bootstrap_method.arguments();
+    let parameters = descriptor.parameters();
+    // a varargs bootstrap method takes the trailing static arguments in an array
+    let varargs = resolved.get_access_flags().contains(AccesFlag::VARARGS)
+        && matches!(parameters.last(), Some(FieldType::Array(_)));
+    let arguments_count = static_arguments.len() + 3;
+    let wrong_count = if varargs {
+        arguments_count < parameters.len() - 1
+    } else {
+        arguments_count != parameters.len()
+    };
+    if wrong_count {
+        let message = format!(
+            "Wrong number of arguments for bootstrap method {}",
+            method_name
+        );
+        return throw_exception(jvm, BOOTSTRAP_METHOD_ERROR, Some(message));
+    }
+
+    let name = rethrow_exception!(intern_name(jvm, constant.name())?);
+    let mut arguments = vec![
+        (
+            rethrow_exception!(new_lookup(jvm, caller)?),
+            FieldType::Object(LOOKUP_CLASS.to_string()),
+        ),
+        (name, FieldType::Object(STRING_CLASS.to_string())),
+        (
+            rethrow_exception!(type_mirror(jvm, constant.descriptor())?),
+            FieldType::Object(CLASS_CLASS.to_string()),
+        ),
+    ];
+    for argument in static_arguments {
+        arguments.push(rethrow_exception!(static_argument(jvm, argument, caller)?));
+    }
+    if let (true, Some(array_type)) = (varargs, parameters.last()) {
+        // like invokeWithArguments, an array given for the varargs is passed as is
+        let spread = arguments.len() == parameters.len()
+            && arguments.last().map(|(_, value_type)| value_type) == Some(array_type);
+        if !spread {
+            let trailing = arguments.split_off(parameters.len() - 1);
+            let array = rethrow_exception!(collect_varargs(jvm, trailing, array_type)?);
+            arguments.push((array, array_type.clone()));
+        }
+    }
+
+    let mut stack = Stack::new(descriptor.parameters_size());
+    for ((value, value_type), parameter) in arguments.into_iter().zip(parameters) {
+        let value = rethrow_exception!(convert(jvm, value, &value_type, parameter)?);
+        stack.push(value);
+    }
+    rethrow_exception!(class.initialize(jvm)?);
+    let value = rethrow_exception!(resolved.execute(jvm, &mut stack)?);
+    let value = value.ok_or(InternalError::WrongType)?;
+    convert(jvm, value, return_type, constant.descriptor())
+}
+
+fn intern_name(jvm: &Jvm, name: &str) -> ExecResult<Object> {
+    let name = rethrow_exception!(intern_string(jvm, name)?);
+    Ok(Ok(Object::Reference(Some(name))))
+}
+
+// a Lookup on the caller, the bootstrap methods can only ask for its class
+fn new_lookup(jvm: &Jvm, caller: &ClassRef) -> ExecResult<Object> {
+    let class = load_class(jvm, LOOKUP_CLASS)?;
+    rethrow_exception!(class.initialize(jvm)?);
+    let lookup = rethrow_exception!(jvm.heap().new_object(jvm, class.clone())?);
+    let field = class
+        .find_field(
+            LOOKUP_CLASS_FIELD,
+            &FieldType::Object(CLASS_CLASS.to_string()),
+        )
+        .ok_or(InternalError::WrongType)?;
+    let caller = load_class(jvm, caller.name())?;
+    let mirror = rethrow_exception!(caller.get_mirror(jvm)?);
+    lookup.set_field(field.get_slot(), Object::Reference(Some(mirror)))?;
+    Ok(Ok(Object::Reference(Some(lookup))))
+}
+
+// the class of the type, which is a primitive class for the primitive types
+fn type_mirror(jvm: &Jvm, field_type: &FieldType) -> ExecResult<Object> {
+    let class = match field_type {
+        FieldType::Object(_) | FieldType::Array(_) => load_class(jvm, &class_name(field_type))?,
+        primitive => jvm
+            .class_loader()
+            .load_primitive_class(primitive)
+            .ok_or(InternalError::WrongType)?,
+    };
+    let mirror = rethrow_exception!(class.get_mirror(jvm)?);
+    Ok(Ok(Object::Reference(Some(mirror))))
+}
+
+// the value of a static argument, with its type before it is converted to the one of
+// the parameter, the dynamic constants given as arguments are resolved first
+fn static_argument(
+    jvm: &Jvm,
+    argument: &RuntimeConstant,
+    caller: &Arc<ClassRef>,
+) -> ExecResult<(Object, FieldType)> {
+    let argument = match argument {
+        RuntimeConstant::Integer(value) => (Object::Int(*value), FieldType::Int),
+        RuntimeConstant::Float(value) => (Object::Float(*value), FieldType::Float),
+        RuntimeConstant::Long(value) => (Object::Long(*value), FieldType::Long),
+        RuntimeConstant::Double(value) => (Object::Double(*value), FieldType::Double),
+        RuntimeConstant::String(string) => {
+            let string = rethrow_exception!(string.resolve(jvm)?);
+            (
+                Object::Reference(Some(string.clone())),
+                FieldType::Object(STRING_CLASS.to_string()),
+            )
+        }
+        RuntimeConstant::Class(class) => {
+            let class = load_class(jvm, class.name())?;
+            let mirror = rethrow_exception!(class.get_mirror(jvm)?);
+            (
+                Object::Reference(Some(mirror)),
+                FieldType::Object(CLASS_CLASS.to_string()),
+            )
+        }
+        RuntimeConstant::Dynamic(constant) => {
+            let value = rethrow_exception!(constant.resolve(jvm, caller)?);
+            (value, constant.descriptor().clone())
+        }
+        // the class library has no MethodHandle and MethodType objects
+        _ => {
+            let message = "Unsupported bootstrap method argument".to_string();
+            return throw_exception(jvm, BOOTSTRAP_METHOD_ERROR, Some(message));
+        }
+    };
+    Ok(Ok(argument))
+}
+
+// the trailing static arguments of a varargs bootstrap method, converted to the component type
+fn collect_varargs(
+    jvm: &Jvm,
+    arguments: Vec<(Object, FieldType)>,
+    array_type: &FieldType,
+) -> ExecResult<Object> {
+    let FieldType::Array(component_type) = array_type else {
+        return Err(InternalError::WrongType);
+    };
+    // the values are boxed before the array is created, like the inner arrays of multianewarray
+    let mut values = Vec::with_capacity(arguments.len());
+    for (value, value_type) in arguments {
+        values.push(rethrow_exception!(convert(
+            jvm,
+            value,
+            &value_type,
+            component_type
+        )?));
+    }
+    let array_class = load_class(jvm, &class_name(array_type))?;
+    let array = rethrow_exception!(Array::new_multi_dimensional(
+        jvm,
+        &array_class,
+        &[values.len()]
+    )?);
+    for (index, value) in values.into_iter().enumerate() {
+        rethrow_exception!(array.store_index(jvm, index as i32, value)?);
+    }
+    Ok(Ok(Object::Array(Some(array))))
+}
+
+/// Convert a value from one type to another like the method handles of the JDK,
+/// by boxing, unboxing, widening or casting it
+fn convert(jvm: &Jvm, value: Object, from: &FieldType, to: &FieldType) -> ExecResult<Object> {
+    if from == to {
+        return Ok(Ok(value));
+    }
+    match (from.is_reference(), to.is_reference()) {
+        (true, true) => check_cast(jvm, value, to),
+        // unboxed to the type of the wrapper, then widened
+        (true, false) => {
+            let Some(class) = value.get_class(jvm)? else {
+                return throw_exception(jvm, NULL_POINTER_EXCEPTION, None);
+            };
+            let Some(primitive) = unwrapped(class.get_name()) else {
+                let (wrapper, _) = wrapper(to).ok_or(InternalError::WrongType)?;
+                return throw_cast_exception(jvm, class.get_name(), wrapper);
+            };
+            let (wrapper, unbox) = wrapper(&primitive).ok_or(InternalError::WrongType)?;
+            let descriptor = format!("(){}", primitive.descriptor());
+            let unboxed = rethrow_exception!(call_method(jvm, wrapper, unbox, &descriptor, value)?);
+            convert(jvm, unboxed, &primitive, to)
+        }
+        (false, true) => {
+            let (wrapper, _) = wrapper(from).ok_or(InternalError::WrongType)?;
+            let descriptor = format!("({})L{};", from.descriptor(), wrapper);
+            let boxed =
+                rethrow_exception!(call_method(jvm, wrapper, "valueOf", &descriptor, value)?);
+            check_cast(jvm, boxed, to)
+        }
+        (false, false) => {
+            use FieldType as T;
+            let widened = match (from, to, value) {
+                (T::Byte, T::Short, value) | (T::Byte | T::Short | T::Char, T::Int, value) => value,
+                (T::Byte | T::Short | T::Char | T::Int, T::Long, Object::Int(value)) => {
+                    Object::Long(value as i64)
+                }
+                (T::Byte | T::Short | T::Char | T::Int, T::Float, Object::Int(value)) => {
+                    Object::Float(value as f32)
+                }
+                (T::Byte | T::Short | T::Char | T::Int, T::Double, Object::Int(value)) => {
+                    Object::Double(value as f64)
+                }
+                (T::Long, T::Float, Object::Long(value)) => Object::Float(value as f32),
+                (T::Long, T::Double, Object::Long(value)) => Object::Double(value as f64),
+                (T::Float, T::Double, Object::Float(value)) => Object::Double(value as f64),
+                _ => {
+                    let message = format!("Type mismatch: {} is not convertible to {}", from, to);
+                    return throw_exception(jvm, BOOTSTRAP_METHOD_ERROR, Some(message));
+                }
+            };
+            Ok(Ok(widened))
+        }
+    }
+}
+
+// like checkcast, null can be cast to any type
+fn check_cast(jvm: &Jvm, value: Object, to: &FieldType) -> ExecResult<Object> {
+    let to = class_name(to);
+    if to == OBJECT_CLASS {
+        return Ok(Ok(value));
+    }
+    if let Some(class) = value.get_class(jvm)? {
+        if !class.is_subclass(&load_class(jvm, &to)?) {
+            return throw_cast_exception(jvm, class.get_name(), &to);
+        }
+    }
+    Ok(Ok(value))
+}
+
+fn throw_cast_exception<T>(jvm: &Jvm, from: &str, to: &str) -> ExecResult<T> {
+    let message = format!(
+        "class {} cannot be cast to class {}",
+        from.replace('/', "."),
+        to.replace('/', ".")
+    );
+    throw_exception(jvm, CLASS_CAST_EXCEPTION, Some(message))
+}
+
+// the methods boxing and unboxing the values of the wrapper classes, which take one argument
+fn call_method(
+    jvm: &Jvm,
+    class_name: &str,
+    name: &str,
+    descriptor: &str,
+    argument: Object,
+) -> ExecResult<Object> {
+    let class = load_class(jvm, class_name)?;
+    rethrow_exception!(class.initialize(jvm)?);
+    let method = class
+        .find_method(name, descriptor)
+        .ok_or(InternalError::WrongType)?;
+    // a long or a double takes two slots
+    let mut stack = Stack::new(2);
+    stack.push(argument);
+    let value = rethrow_exception!(method.execute(jvm, &mut stack)?);
+    Ok(Ok(value.ok_or(InternalError::WrongType)?))
+}
@@ -0,0 +1,403 @@
+use std::{
+    iter,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    parser::{
+        classfile::{classfile::AccesFlag, constant_pool::MethodHandleKind},
+        descriptor::{parse_method_descriptor, FieldType, MethodDescriptor},
+    },
+    rethrow_exception,
+    runtime::jvm::Jvm,
+    runtime_types::{
+        throw_exception, CallSiteRef, Class, ClassRef, Code, ExceptionTable, ExecResult, Field,
+        FieldRef, Method, MethodHandleRef, MethodRef, MethodTypeRef, Object, OpCode, ResultValue,
+        RuntimeConstant, Stack, BOOTSTRAP_METHOD_ERROR,
+    },
+};
+
+use super::{class_name, load_class, unwrapped, wrapper, CallSite};
+
+const OBJECT_CLASS: &str = "java/lang/Object";
+const SERIALIZABLE_INTERFACE: &str = "java/io/Serializable";
+
+// the flags of altMetafactory
+const FLAG_SERIALIZABLE: i32 = 1 << 0;
+const FLAG_MARKERS: i32 = 1 << 1;
+const FLAG_BRIDGES: i32 = 1 << 2;
+
+// the lambda classes are numbered, so their names are unique
+static LAMBDA_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// The static arguments given by javac to the metafactory
+struct LambdaArguments {
+    // the type of the interface method, after erasure
+    method_type: Arc<MethodTypeRef>,
+    implementation: Arc<MethodHandleRef>,
+    // the rest is only given to altMetafactory
+    serializable: bool,
+    markers: Vec<Arc<ClassRef>>,
+    bridges: Vec<Arc<MethodTypeRef>>,
+}
+
+impl LambdaArguments {
+    // the instantiated method type, the third argument, is only checked by the JDK
+    fn parse(arguments: &[RuntimeConstant], alternate: bool) -> Option<Self> {
+        let mut arguments = arguments.iter();
+        let (
+            Some(RuntimeConstant::MethodType(method_type)),
+            Some(RuntimeConstant::MethodHandle(implementation)),
+            Some(RuntimeConstant::MethodType(_)),
+        ) = (arguments.next(), arguments.next(), arguments.next())
+        else {
+            return None;
+        };
+        let mut lambda_arguments = LambdaArguments {
+            method_type: method_type.clone(),
+            implementation: implementation.clone(),
+            serializable: false,
+            markers: Vec::new(),
+            bridges: Vec::new(),
+        };
+        if !alternate {
+            return Some(lambda_arguments);
+        }
+
+        let as_int = |argument: Option<&RuntimeConstant>| match argument {
+            Some(RuntimeConstant::Integer(value)) => Some(*value),
+            _ => None,
+        };
+        let flags = as_int(arguments.next())?;
+        lambda_arguments.serializable = flags & FLAG_SERIALIZABLE != 0;
+        if flags & FLAG_MARKERS != 0 {
+            for _ in 0..as_int(arguments.next())? {
+                match arguments.next() {
+                    Some(RuntimeConstant::Class(marker)) => {
+                        lambda_arguments.markers.push(marker.clone())
+                    }
+                    _ => return None,
+                }
+            }
+        }
+        if flags & FLAG_BRIDGES != 0 {
+            for _ in 0..as_int(arguments.next())? {
+                match arguments.next() {
+                    Some(RuntimeConstant::MethodType(bridge)) => {
+                        lambda_arguments.bridges.push(bridge.clone())
+                    }
+                    _ => return None,
+                }
+            }
+        }
+        Some(lambda_arguments)
+    }
+}
+
+fn captured_field_name(index: usize) -> String {
+    format!("arg${}", index + 1)
+}
+
+/// LambdaMetafactory.metafactory, or altMetafactory when `alternate` is set
+///
+/// Like the JDK, a final class is defined for the call site. It implements the functional
+/// interface returned by the call site, and keeps the arguments of the call site in its fields.
+/// Its only method, with the bridges asked by altMetafactory, calls the implementation method
+/// with the captured arguments followed by its own, boxing or casting them when needed.
+pub fn metafactory(
+    jvm: &Jvm,
+    call_site: &CallSiteRef,
+    caller: &ClassRef,
+    alternate: bool,
+) -> ExecResult<CallSite> {
+    let bootstrap_method = call_site.bootstrap_method()?;
+    let arguments = LambdaArguments::parse(bootstrap_method.arguments(), alternate);
+    let descriptor = call_site.parsed_descriptor();
+    let (Some(arguments), Some(FieldType::Object(interface))) =
+        (arguments, descriptor.return_type())
+    else {
+        let message = "Invalid call site for LambdaMetafactory".to_string();
+        return throw_exception(jvm, BOOTSTRAP_METHOD_ERROR, Some(message));
+    };
+
+    let name = format!(
+        "{}$$Lambda${}",
+        caller.name(),
+        LAMBDA_COUNT.fetch_add(1, Ordering::Relaxed)
+    );
+    let this_class = Arc::new(ClassRef::new(name.clone()));
+    let captured = descriptor.parameters();
+    let mut methods = Vec::new();
+    for method_type in iter::once(&arguments.method_type).chain(&arguments.bridges) {
+        let parsed_descriptor = method_type.parsed_descriptor();
+        let code = lambda_code(
+            &this_class,
+            captured,
+            parsed_descriptor,
+            &arguments.implementation,
+        );
+        let code = match code {
+            Ok(code) => code,
+            Err(message) => return throw_exception(jvm, BOOTSTRAP_METHOD_ERROR, Some(message)),
+        };
+        let args_count = parsed_descriptor.parameters_size() + 1;
+        methods.push((method_type.descriptor().to_string(), args_count, code));
+    }
+
+    let mut interfaces = vec![load_class(jvm, interface)?];
+    for marker in &arguments.markers {
+        interfaces.push(load_class(jvm, marker.name())?);
+    }
+    if arguments.serializable {
+        interfaces.push(load_class(jvm, SERIALIZABLE_INTERFACE)?);
+    }
+    let object = load_class(jvm, OBJECT_CLASS)?;
+
+    let mut slot = object.instance_size();
+    let mut fields = Vec::with_capacity(captured.len());
+    for (index, field_type) in captured.iter().enumerate() {
+        fields.push(Field::new(
+            captured_field_name(index),
+            field_type.clone(),
+            AccesFlag::new(AccesFlag::PRIVATE | AccesFlag::FINAL),
+            slot,
+            None,
+        ));
+        slot += field_type.slot_size();
+    }
+
+    let class = Class::new(
+        name,
+        AccesFlag::new(AccesFlag::FINAL | AccesFlag::SYNTHETIC),
+        Some(object),
+        interfaces,
+        fields,
+        |class| {
+            methods
+                .into_iter()
+                .map(|(descriptor, args_count, code)| {
+                    Method::new(
+                        call_site.name().to_string(),
+                        descriptor,
+                        AccesFlag::new(AccesFlag::PUBLIC),
+                        class.clone(),
+                        args_count,
+                        Some(code),
+                    )
+                })
+                .collect()
+        },
+    );
+    let class = jvm.class_loader().define_generated_class(class);
+    Ok(Ok(CallSite::Lambda(class)))
+}
+
+/// Create an instance of the lambda class, with the captured arguments popped from the stack
+pub fn new_lambda(jvm: &Jvm, stack: &mut Stack, class: &Arc<Class>) -> ExecResult {
+    rethrow_exception!(class.initialize(jvm)?);
+    let lambda = rethrow_exception!(jvm.heap().new_object(jvm, class.clone())?);
+    for field in class.get_fields().iter().rev() {
+        lambda.set_field(field.get_slot(), stack.pop()?)?;
+    }
+    Ok(Ok(ResultValue::Object(Object::Reference(Some(lambda)))))
+}
+
+/// The code of the method implementing the interface method with the given descriptor
+///
+/// An error message is returned when the implementation method can't be called with
+/// the parameters of the interface method.
+fn lambda_code(
+    this_class: &Arc<ClassRef>,
+    captured: &[FieldType],
+    descriptor: &MethodDescriptor,
+    implementation: &MethodHandleRef,
+) -> Result<Code, String> {
+    let kind = implementation.kind();
+    let unsupported = || format!("Unsupported MethodHandle kind: {:?}", kind);
+    let method = implementation.method().ok_or_else(unsupported)?;
+    let mut opcodes = Vec::new();
+
+    // the types expected by the implementation method, the receiver first
+    let mut parameters = Vec::new();
+    match kind {
+        MethodHandleKind::NewInvokeSpecial => {
+            let class = method.class().clone();
+            opcodes.extend([OpCode::new { class }, OpCode::dup]);
+        }
+        MethodHandleKind::InvokeVirtual
+        | MethodHandleKind::InvokeInterface
+        | MethodHandleKind::InvokeSpecial => {
+            parameters.push(reference_type(method.class().name()));
+        }
+        MethodHandleKind::InvokeStatic => {}
+        _ => return Err(unsupported()),
+    }
+    parameters.extend(method.parsed_descriptor().parameters().iter().cloned());
+    if captured.len() + descriptor.parameters().len() != parameters.len() {
+        return Err(format!(
+            "Incorrect number of parameters for {}.{}{}",
+            method.class().name().replace('/', "."),
+            method.name(),
+            method.descriptor()
+        ));
+    }
+
+    for (index, field_type) in captured.iter().enumerate() {
+        let field = FieldRef::new(
+            this_class.clone(),
+            captured_field_name(index),
+            field_type.clone(),
+        );
+        opcodes.extend([
+            OpCode::load_0,
+            OpCode::getfield {
+                field: Arc::new(field),
+            },
+        ]);
+    }
+    let mut local_index = 1;
+    for (from, to) in descriptor
+        .parameters()
+        .iter()
+        .zip(&parameters[captured.len()..])
+    {
+        opcodes.push(OpCode::load_i { local_index });
+        local_index += from.slot_size();
+        convert(from, to, &mut opcodes)?;
+    }
+
+    let returned = match kind {
+        MethodHandleKind::NewInvokeSpecial => {
+            opcodes.push(OpCode::invokespecial {
+                method: method.clone(),
+                caller: method.class().clone(),
+            });
+            Some(reference_type(method.class().name()))
+        }
+        _ => {
+            opcodes.push(match kind {
+                MethodHandleKind::InvokeStatic => OpCode::invokestatic {
+                    method: method.clone(),
+                },
+                MethodHandleKind::InvokeVirtual => OpCode::invokevirtual {
+                    method: method.clone(),
+                },
+                MethodHandleKind::InvokeInterface => OpCode::invokeinterface {
+                    method: method.clone(),
+                    count: method.parsed_descriptor().parameters_size() + 1,
+                },
+                // a private method of the class of the implementation, not a super call
+                _ => OpCode::invokespecial {
+                    method: method.clone(),
+                    caller: method.class().clone(),
+                },
+            });
+            method.parsed_descriptor().return_type().cloned()
+        }
+    };
+    match (returned, descriptor.return_type()) {
+        (None, None) => opcodes.push(OpCode::retrn),
+        (Some(returned), None) => {
+            let discard = if returned.slot_size() == 2 {
+                OpCode::pop2
+            } else {
+                OpCode::pop
+            };
+            opcodes.extend([discard, OpCode::retrn]);
+        }
+        (Some(returned), Some(return_type)) => {
+            convert(&returned, return_type, &mut opcodes)?;
+            opcodes.push(OpCode::return_v);
+        }
+        (None, Some(return_type)) => {
+            return Err(format!(
+                "Type mismatch for lambda return: void is not convertible to {}",
+                return_type
+            ))
+        }
+    }
+
+    // room for the new instance, or for a value widened to long
+    let max_stack = parameters.iter().map(FieldType::slot_size).sum::<usize>() + 2;
+    Ok(Code::new(
+        max_stack,
+        local_index,
+        opcodes,
+        local_index,
+        ExceptionTable::new(None),
+    ))
+}
+
+// the type of the instances of the class, which is an array class when its name is a descriptor
+fn reference_type(class_name: &str) -> FieldType {
+    FieldType::Object(class_name.to_string())
+}
+
+fn method_ref(class_name: &str, name: &str, descriptor: String) -> Option<Arc<MethodRef>> {
+    let parsed_descriptor = parse_method_descriptor(&descriptor).ok()?;
+    Some(Arc::new(MethodRef::new(
+        Arc::new(ClassRef::new(class_name.to_string())),
+        name.to_string(),
+        descriptor,
+        parsed_descriptor,
+    )))
+}
+
+/// Add the opcodes turning the value on top of the stack from one type to another,
+/// like the conversions of the JDK for the lambdas (boxing, unboxing, widening and casting)
+fn convert(from: &FieldType, to: &FieldType, opcodes: &mut Vec<OpCode>) -> Result<(), String> {
+    if from == to {
+        return Ok(());
+    }
+    let mismatch = || format!("Type mismatch: {} is not convertible to {}", from, to);
+    match (from.is_reference(), to.is_reference()) {
+        (true, true) => {
+            if class_name(to) != OBJECT_CLASS {
+                let class = Arc::new(ClassRef::new(class_name(to)));
+                opcodes.push(OpCode::checkcast { class });
+            }
+        }
+        // unboxed to the type of the wrapper if it is known, then widened
+        (true, false) => {
+            let primitive = match from {
+                FieldType::Object(class_name) => unwrapped(class_name),
+                _ => None,
+            };
+            let primitive = primitive.unwrap_or_else(|| to.clone());
+            let (wrapper, unbox) = wrapper(&primitive).ok_or_else(mismatch)?;
+            if class_name(from) != wrapper {
+                let class = Arc::new(ClassRef::new(wrapper.to_string()));
+                opcodes.push(OpCode::checkcast { class });
+            }
+            let descriptor = format!("(){}", primitive.descriptor());
+            let method = method_ref(wrapper, unbox, descriptor).ok_or_else(mismatch)?;
+            opcodes.push(OpCode::invokevirtual { method });
+            convert(&primitive, to, opcodes)?;
+        }
+        (false, true) => {
+            let (wrapper, _) = wrapper(from).ok_or_else(mismatch)?;
+            let descriptor = format!("({})L{};", from.descriptor(), wrapper);
+            let method = method_ref(wrapper, "valueOf", descriptor).ok_or_else(mismatch)?;
+            opcodes.push(OpCode::invokestatic { method });
+            convert(&reference_type(wrapper), to, opcodes)?;
+        }
+        (false, false) => {
+            use FieldType as T;
+            let widening = match (from, to) {
+                (T::Byte, T::Short) | (T::Byte | T::Short | T::Char, T::Int) => None,
+                (T::Byte | T::Short | T::Char | T::Int, T::Long) => Some(OpCode::i2l),
+                (T::Byte | T::Short | T::Char | T::Int, T::Float) => Some(OpCode::i2f),
+                (T::Byte | T::Short | T::Char | T::Int, T::Double) => Some(OpCode::i2d),
+                (T::Long, T::Float) => Some(OpCode::l2f),
+                (T::Long, T::Double) => Some(OpCode::l2d),
+                (T::Float, T::Double) => Some(OpCode::f2d),
+                _ => return Err(mismatch()),
+            };
+            opcodes.extend(widening);
+        }
+    }
+    Ok(())
+}
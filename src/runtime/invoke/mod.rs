@@ -0,0 +1,125 @@
+mod concat;
+mod dynamic;
+mod lambda;
+
+use std::sync::Arc;
+
+use crate::{
+    parser::descriptor::FieldType,
+    runtime_types::{
+        throw_exception, CallSiteRef, Class, ClassRef, ExecResult, InternalError, Stack,
+        BOOTSTRAP_METHOD_ERROR,
+    },
+};
+
+use super::jvm::Jvm;
+
+use concat::ConcatRecipe;
+
+pub use dynamic::resolve_dynamic_constant;
+
+const LAMBDA_METAFACTORY: &str = "java/lang/invoke/LambdaMetafactory";
+const STRING_CONCAT_FACTORY: &str = "java/lang/invoke/StringConcatFactory";
+
+/// What an invokedynamic does once its call site is linked
+#[derive(Debug)]
+pub enum CallSite {
+    /// Create an instance of the class implementing the functional interface,
+    /// holding the captured arguments
+    Lambda(Arc<Class>),
+    /// Concatenate the arguments into a String, for the + operator
+    StringConcat(ConcatRecipe),
+}
+
+impl CallSite {
+    /// Run the call site with the dynamic arguments on the stack, its result is pushed on it
+    pub fn invoke(&self, jvm: &Jvm, stack: &mut Stack) -> ExecResult {
+        match self {
+            CallSite::Lambda(class) => lambda::new_lambda(jvm, stack, class),
+            CallSite::StringConcat(recipe) => recipe.concat(jvm, stack),
+        }
+    }
+}
+
+/// Run the bootstrap method of the call site, `caller` is the class running the invokedynamic
+///
+/// The bootstrap methods of the JDK are written with java.lang.invoke, which the class library
+/// doesn't have, so the ones used by javac are implemented by the virtual machine instead.
+pub fn link_call_site(
+    jvm: &Jvm,
+    call_site: &CallSiteRef,
+    caller: &ClassRef,
+) -> ExecResult<CallSite> {
+    let bootstrap_method = call_site.bootstrap_method()?;
+    let method = bootstrap_method.method_handle().method();
+    match method.map(|method| (method.class().name(), method.name())) {
+        Some((LAMBDA_METAFACTORY, "metafactory")) => {
+            lambda::metafactory(jvm, call_site, caller, false)
+        }
+        Some((LAMBDA_METAFACTORY, "altMetafactory")) => {
+            lambda::metafactory(jvm, call_site, caller, true)
+        }
+        Some((STRING_CONCAT_FACTORY, "makeConcatWithConstants")) => {
+            concat::make_concat(jvm, call_site, true)
+        }
+        Some((STRING_CONCAT_FACTORY, "makeConcat")) => concat::make_concat(jvm, call_site, false),
+        Some((class_name, name)) => {
+            let message = format!(
+                "Unsupported bootstrap method {}.{}",
+                class_name.replace('/', "."),
+                name
+            );
+            throw_exception(jvm, BOOTSTRAP_METHOD_ERROR, Some(message))
+        }
+        None => {
+            let message = "Bootstrap method is not a method".to_string();
+            throw_exception(jvm, BOOTSTRAP_METHOD_ERROR, Some(message))
+        }
+    }
+}
+
+fn load_class(jvm: &Jvm, name: &str) -> Result<Arc<Class>, InternalError> {
+    jvm.class_loader()
+        .load_class(name)
+        .map_err(|_| InternalError::ClassLoadingFailed(name.to_string()))
+}
+
+// the name of the class a reference type is checked against by checkcast
+fn class_name(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Object(class_name) => class_name.clone(),
+        _ => field_type.descriptor(),
+    }
+}
+
+// the wrapper class of a primitive type, and the method unboxing it
+fn wrapper(primitive: &FieldType) -> Option<(&'static str, &'static str)> {
+    let wrapper = match primitive {
+        FieldType::Boolean => ("java/lang/Boolean", "booleanValue"),
+        FieldType::Byte => ("java/lang/Byte", "byteValue"),
+        FieldType::Char => ("java/lang/Character", "charValue"),
+        FieldType::Short => ("java/lang/Short", "shortValue"),
+        FieldType::Int => ("java/lang/Integer", "intValue"),
+        FieldType::Long => ("java/lang/Long", "longValue"),
+        FieldType::Float => ("java/lang/Float", "floatValue"),
+        FieldType::Double => ("java/lang/Double", "doubleValue"),
+        FieldType::Object(_) | FieldType::Array(_) => return None,
+    };
+    Some(wrapper)
+}
+
+// the primitive type wrapped by a class, None if it is not a wrapper
+fn unwrapped(class_name: &str) -> Option<FieldType> {
+    [
+        FieldType::Boolean,
+        FieldType::Byte,
+        FieldType::Char,
+        FieldType::Short,
+        FieldType::Int,
+        FieldType::Long,
+        FieldType::Float,
+        FieldType::Double,
+    ]
+    .into_iter()
+    .find(|primitive| wrapper(primitive).is_some_and(|(wrapper, _)| wrapper == class_name))
+}
@@ -0,0 +1,105 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Weak},
+};
+
+use crate::runtime_types::{Heap, InternTable, DEFAULT_MAX_HEAP_SIZE};
+
+use super::{
+    class_loader::ClassLoader,
+    monitors::Monitors,
+    natives::{NativeMethod, NativeRegistry},
+    threads::Threads,
+};
+
+/// State shared by everything running in the virtual machine
+#[derive(Debug)]
+pub struct Jvm {
+    class_loader: ClassLoader,
+    // set with -Dkey=value on the command line
+    system_properties: HashMap<String, String>,
+    natives: NativeRegistry,
+    intern_table: InternTable,
+    heap: Heap,
+    threads: Threads,
+    monitors: Monitors,
+    // the virtual machine shared with the threads it starts, set by into_shared
+    this: Weak<Jvm>,
+}
+
+impl Jvm {
+    /// The native methods of the bundled class library are registered
+    pub fn new(class_loader: ClassLoader, system_properties: HashMap<String, String>) -> Self {
+        Jvm {
+            class_loader,
+            system_properties,
+            natives: NativeRegistry::with_class_library(),
+            intern_table: InternTable::new(),
+            heap: Heap::new(DEFAULT_MAX_HEAP_SIZE),
+            threads: Threads::new(),
+            monitors: Monitors::new(),
+            this: Weak::new(),
+        }
+    }
+
+    /// Once configured, the virtual machine is shared so the program can start threads
+    pub fn into_shared(self) -> Arc<Self> {
+        Arc::new_cyclic(|this| Jvm {
+            this: this.clone(),
+            ..self
+        })
+    }
+
+    /// None if the virtual machine was not made shared with into_shared
+    pub fn get_shared(&self) -> Option<Arc<Self>> {
+        self.this.upgrade()
+    }
+
+    pub fn class_loader(&self) -> &ClassLoader {
+        &self.class_loader
+    }
+
+    pub fn intern_table(&self) -> &InternTable {
+        &self.intern_table
+    }
+
+    pub fn heap(&self) -> &Heap {
+        &self.heap
+    }
+
+    /// The heap is configured before running the program, like with -Xmx
+    pub fn heap_mut(&mut self) -> &mut Heap {
+        &mut self.heap
+    }
+
+    pub fn threads(&self) -> &Threads {
+        &self.threads
+    }
+
+    pub fn monitors(&self) -> &Monitors {
+        &self.monitors
+    }
+
+    pub fn get_system_property(&self, key: &str) -> Option<&str> {
+        self.system_properties.get(key).map(String::as_str)
+    }
+
+    pub fn register_native(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+        native: NativeMethod,
+    ) {
+        self.natives.register(class_name, name, descriptor, native);
+    }
+
+    pub fn find_native(
+        &self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Option<NativeMethod> {
+        self.natives.get(class_name, name, descriptor)
+    }
+}
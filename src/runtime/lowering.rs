@@ -0,0 +1,742 @@
+use std::sync::Arc;
+
+use crate::{
+    parser::{
+        classfile::{
+            attributes::{Attribute, BootstrapMethodInfo, CodeAttribute},
+            classfile::{AccesFlag, ClassFile},
+            constant_pool::{ConstantInfo, ConstantPool},
+            fields::FieldInfo,
+            opcode::{OpCode as ParsedOpCode, Wide},
+        },
+        descriptor::{parse_field_descriptor, parse_method_descriptor, MethodDescriptor},
+        utils::ParseError,
+    },
+    runtime_types::{
+        BootstrapMethod, CallSiteRef, Class, ClassRef, Code, ConstantNumerical, DynamicRef,
+        ExceptionTable, ExceptionTableInfo, Field, FieldRef, LoadableConstant, Method,
+        MethodHandleRef, MethodHandleTarget, MethodRef, MethodTypeRef, OpCode, RuntimeConstant,
+        RuntimeConstantPool, StringRef,
+    },
+};
+
+// The lowering happens in two steps:
+// first the constant pool of the class is turned into a RuntimeConstantPool,
+// where all the symbolic references (classes, fields, methods) are created once,
+// then the code of each method is lowered using that pool,
+// so every opcode refering to the same constant share the same reference (and its resolution).
+
+fn lower_class_constant(
+    constant_pool: &ConstantPool,
+    info: &ConstantInfo,
+) -> Result<Option<RuntimeConstant>, ParseError> {
+    let ConstantInfo::Class { name_index } = info else {
+        return Ok(None);
+    };
+    let name = constant_pool.get_utf8(*name_index)?;
+    let class_ref = ClassRef::new(name.to_string());
+    Ok(Some(RuntimeConstant::Class(Arc::new(class_ref))))
+}
+
+fn get_class_ref(constants: &[RuntimeConstant], index: usize) -> Result<Arc<ClassRef>, ParseError> {
+    match index.checked_sub(1).and_then(|i| constants.get(i)) {
+        Some(RuntimeConstant::Class(class_ref)) => Ok(class_ref.clone()),
+        _ => Err(ParseError::UnexpectedConstant {
+            index,
+            expected: "Class",
+        }),
+    }
+}
+
+fn lower_method_ref(
+    constant_pool: &ConstantPool,
+    constants: &[RuntimeConstant],
+    class_index: usize,
+    name_and_type_index: usize,
+) -> Result<MethodRef, ParseError> {
+    let class = get_class_ref(constants, class_index)?;
+    let (name, descriptor) = constant_pool.get_name_and_type(name_and_type_index)?;
+    let parsed_descriptor = parse_method_descriptor(descriptor)?;
+    Ok(MethodRef::new(
+        class,
+        name.to_string(),
+        descriptor.to_string(),
+        parsed_descriptor,
+    ))
+}
+
+fn lower_constant(
+    constant_pool: &ConstantPool,
+    constants: &[RuntimeConstant],
+    index: usize,
+    info: &ConstantInfo,
+) -> Result<RuntimeConstant, ParseError> {
+    let constant = match info {
+        // already lowered in the first pass
+        ConstantInfo::Class { .. } => {
+            return get_class_ref(constants, index).map(RuntimeConstant::Class)
+        }
+        ConstantInfo::FieldRef {
+            class_index,
+            name_and_type_index,
+        } => {
+            let class = get_class_ref(constants, *class_index)?;
+            let (name, descriptor) = constant_pool.get_name_and_type(*name_and_type_index)?;
+            let descriptor = parse_field_descriptor(descriptor)?;
+            let field_ref = FieldRef::new(class, name.to_string(), descriptor);
+            RuntimeConstant::Field(Arc::new(field_ref))
+        }
+        ConstantInfo::MethodRef {
+            class_index,
+            name_and_type_index,
+        } => {
+            let method_ref =
+                lower_method_ref(constant_pool, constants, *class_index, *name_and_type_index)?;
+            RuntimeConstant::Method(Arc::new(method_ref))
+        }
+        ConstantInfo::InterfaceMethodRef {
+            class_index,
+            name_and_type_index,
+        } => {
+            let method_ref =
+                lower_method_ref(constant_pool, constants, *class_index, *name_and_type_index)?;
+            RuntimeConstant::InterfaceMethod(Arc::new(method_ref))
+        }
+        ConstantInfo::String { string_index } => {
+            let string = constant_pool.get_utf16(*string_index)?;
+            RuntimeConstant::String(Arc::new(StringRef::new(string.into())))
+        }
+        ConstantInfo::Integer(value) => RuntimeConstant::Integer(*value),
+        ConstantInfo::Float(value) => RuntimeConstant::Float(*value),
+        ConstantInfo::Long(value) => RuntimeConstant::Long(*value),
+        ConstantInfo::Double(value) => RuntimeConstant::Double(*value),
+        ConstantInfo::MethodType { descriptor_index } => {
+            let descriptor = constant_pool.get_utf8(*descriptor_index)?;
+            let parsed_descriptor = parse_method_descriptor(descriptor)?;
+            let method_type = MethodTypeRef::new(descriptor.to_string(), parsed_descriptor);
+            RuntimeConstant::MethodType(Arc::new(method_type))
+        }
+        // the bootstrap method is set once all the constants are lowered
+        ConstantInfo::InvokeDynamic {
+            name_and_type_index,
+            ..
+        } => {
+            let (name, descriptor) = constant_pool.get_name_and_type(*name_and_type_index)?;
+            let parsed_descriptor = parse_method_descriptor(descriptor)?;
+            let call_site =
+                CallSiteRef::new(name.to_string(), descriptor.to_string(), parsed_descriptor);
+            RuntimeConstant::InvokeDynamic(Arc::new(call_site))
+        }
+        ConstantInfo::Dynamic {
+            name_and_type_index,
+            ..
+        } => {
+            let (name, descriptor) = constant_pool.get_name_and_type(*name_and_type_index)?;
+            let descriptor = parse_field_descriptor(descriptor)?;
+            RuntimeConstant::Dynamic(Arc::new(DynamicRef::new(name.to_string(), descriptor)))
+        }
+        _ => RuntimeConstant::Unusable,
+    };
+    Ok(constant)
+}
+
+// the method handles share the references lowered in the second pass
+fn lower_method_handle(
+    constants: &[RuntimeConstant],
+    info: &ConstantInfo,
+) -> Result<Option<RuntimeConstant>, ParseError> {
+    let ConstantInfo::MethodHandle {
+        reference_kind,
+        reference_index,
+    } = info
+    else {
+        return Ok(None);
+    };
+    let target = match reference_index
+        .checked_sub(1)
+        .and_then(|i| constants.get(i))
+    {
+        Some(RuntimeConstant::Field(field)) => MethodHandleTarget::Field(field.clone()),
+        Some(RuntimeConstant::Method(method) | RuntimeConstant::InterfaceMethod(method)) => {
+            MethodHandleTarget::Method(method.clone())
+        }
+        _ => {
+            return Err(ParseError::UnexpectedConstant {
+                index: *reference_index,
+                expected: "Fieldref, Methodref or InterfaceMethodref",
+            })
+        }
+    };
+    let method_handle = MethodHandleRef::new(*reference_kind, target);
+    Ok(Some(RuntimeConstant::MethodHandle(Arc::new(method_handle))))
+}
+
+fn lower_bootstrap_method(
+    constants: &[RuntimeConstant],
+    bootstrap_method: &BootstrapMethodInfo,
+) -> Result<BootstrapMethod, ParseError> {
+    let get_constant = |index: usize| index.checked_sub(1).and_then(|i| constants.get(i));
+    let index = bootstrap_method.bootstrap_method_ref();
+    let Some(RuntimeConstant::MethodHandle(method_handle)) = get_constant(index) else {
+        return Err(ParseError::UnexpectedConstant {
+            index,
+            expected: "MethodHandle",
+        });
+    };
+    let arguments = bootstrap_method
+        .bootstrap_arguments()
+        .iter()
+        .map(|&index| match get_constant(index) {
+            Some(RuntimeConstant::Unusable) | None => Err(ParseError::UnexpectedConstant {
+                index,
+                expected: "loadable constant",
+            }),
+            Some(constant) => Ok(constant.clone()),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(BootstrapMethod::new(method_handle.clone(), arguments))
+}
+
+/// `bootstrap_methods` comes from the BootstrapMethods attribute of the class
+pub fn lower_constant_pool(
+    constant_pool: &ConstantPool,
+    bootstrap_methods: &[BootstrapMethodInfo],
+) -> Result<RuntimeConstantPool, ParseError> {
+    // first pass for the classes, as the fields and methods references need them
+    let mut constants = Vec::with_capacity(constant_pool.size());
+    for (_, info) in constant_pool.iter() {
+        let constant = lower_class_constant(constant_pool, info)?;
+        constants.push(constant.unwrap_or(RuntimeConstant::Unusable));
+    }
+
+    let mut constants = constant_pool
+        .iter()
+        .map(|(index, info)| lower_constant(constant_pool, &constants, index, info))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // then the method handles, which refer to fields and methods
+    for (index, info) in constant_pool.iter() {
+        if let Some(method_handle) = lower_method_handle(&constants, info)? {
+            constants[index - 1] = method_handle;
+        }
+    }
+
+    // and last the bootstrap methods, which can take any of the constants as arguments
+    let bootstrap_methods = bootstrap_methods
+        .iter()
+        .map(|bootstrap_method| lower_bootstrap_method(&constants, bootstrap_method).map(Arc::new))
+        .collect::<Result<Vec<_>, _>>()?;
+    for (index, info) in constant_pool.iter() {
+        let (ConstantInfo::InvokeDynamic {
+            bootstrap_method_attr_index,
+            ..
+        }
+        | ConstantInfo::Dynamic {
+            bootstrap_method_attr_index,
+            ..
+        }) = info
+        else {
+            continue;
+        };
+        let bootstrap_method = bootstrap_methods.get(*bootstrap_method_attr_index);
+        match (bootstrap_method, &constants[index - 1]) {
+            (Some(bootstrap_method), RuntimeConstant::InvokeDynamic(call_site)) => {
+                call_site.set_bootstrap_method(bootstrap_method.clone())
+            }
+            (Some(bootstrap_method), RuntimeConstant::Dynamic(constant)) => {
+                constant.set_bootstrap_method(bootstrap_method.clone())
+            }
+            _ => {
+                return Err(ParseError::UnexpectedConstant {
+                    index,
+                    expected: "Dynamic or InvokeDynamic with a bootstrap method",
+                })
+            }
+        }
+    }
+
+    Ok(RuntimeConstantPool::new(constants))
+}
+
+fn get_any_method(
+    constant_pool: &RuntimeConstantPool,
+    index: usize,
+) -> Result<Arc<MethodRef>, ParseError> {
+    // invokestatic and invokespecial can target both class and interface methods
+    match constant_pool.get_checked(index)? {
+        RuntimeConstant::Method(method) | RuntimeConstant::InterfaceMethod(method) => {
+            Ok(method.clone())
+        }
+        _ => Err(ParseError::UnexpectedConstant {
+            index,
+            expected: "Methodref or InterfaceMethodref",
+        }),
+    }
+}
+
+fn lower_ldc(
+    constant_pool: &RuntimeConstantPool,
+    index: usize,
+    this_class: &Arc<ClassRef>,
+) -> Result<OpCode, ParseError> {
+    let constant = match constant_pool.get_checked(index)? {
+        RuntimeConstant::Integer(value) => LoadableConstant::Int(*value),
+        RuntimeConstant::Float(value) => LoadableConstant::Float(*value),
+        RuntimeConstant::String(value) => LoadableConstant::String(value.clone()),
+        RuntimeConstant::Class(class) => LoadableConstant::Class(class.clone()),
+        RuntimeConstant::Dynamic(constant) if constant.descriptor().slot_size() == 1 => {
+            LoadableConstant::Dynamic {
+                constant: constant.clone(),
+                caller: this_class.clone(),
+            }
+        }
+        _ => {
+            return Err(ParseError::UnexpectedConstant {
+                index,
+                expected: "Integer, Float, String, Class or Dynamic",
+            })
+        }
+    };
+    Ok(OpCode::ldc(constant))
+}
+
+fn lower_ldc2_w(
+    constant_pool: &RuntimeConstantPool,
+    index: usize,
+    this_class: &Arc<ClassRef>,
+) -> Result<OpCode, ParseError> {
+    let constant = match constant_pool.get_checked(index)? {
+        RuntimeConstant::Long(value) => ConstantNumerical::Long(*value),
+        RuntimeConstant::Double(value) => ConstantNumerical::Double(*value),
+        // the long and double dynamic constants are pushed by ldc like the other ones,
+        // the stack knows their size from their value
+        RuntimeConstant::Dynamic(constant) if constant.descriptor().slot_size() == 2 => {
+            return Ok(OpCode::ldc(LoadableConstant::Dynamic {
+                constant: constant.clone(),
+                caller: this_class.clone(),
+            }))
+        }
+        _ => {
+            return Err(ParseError::UnexpectedConstant {
+                index,
+                expected: "Long, Double or Dynamic",
+            })
+        }
+    };
+    Ok(OpCode::ldc2_w(constant))
+}
+
+fn lower_wide(wide: &Wide) -> OpCode {
+    match wide {
+        Wide::iload(local_index)
+        | Wide::fload(local_index)
+        | Wide::aload(local_index)
+        | Wide::lload(local_index)
+        | Wide::dload(local_index) => OpCode::load_i {
+            local_index: *local_index,
+        },
+        Wide::istore(local_index)
+        | Wide::fstore(local_index)
+        | Wide::astore(local_index)
+        | Wide::lstore(local_index)
+        | Wide::dstore(local_index) => OpCode::store_i {
+            local_index: *local_index,
+        },
+        Wide::ret(local_index) => OpCode::ret {
+            local_index: *local_index,
+        },
+        Wide::iinc(local_index, delta) => OpCode::iinc {
+            local_index: *local_index,
+            delta: *delta,
+        },
+    }
+}
+
+/// `this_class` is the class declaring the code, needed by invokespecial
+pub fn lower_opcode(
+    opcode: &ParsedOpCode,
+    constant_pool: &RuntimeConstantPool,
+    this_class: &Arc<ClassRef>,
+) -> Result<OpCode, ParseError> {
+    use ParsedOpCode as P;
+    let opcode = match opcode {
+        P::aaload
+        | P::baload
+        | P::caload
+        | P::daload
+        | P::faload
+        | P::iaload
+        | P::laload
+        | P::saload => OpCode::aload,
+        P::aastore
+        | P::bastore
+        | P::castore
+        | P::dastore
+        | P::fastore
+        | P::iastore
+        | P::lastore
+        | P::sastore => OpCode::astore,
+        P::aconst_null => OpCode::aconst_null,
+        P::aload(local_index)
+        | P::dload(local_index)
+        | P::fload(local_index)
+        | P::iload(local_index)
+        | P::lload(local_index) => OpCode::load_i {
+            local_index: *local_index,
+        },
+        P::aload_0 | P::dload_0 | P::fload_0 | P::iload_0 | P::lload_0 => OpCode::load_0,
+        P::aload_1 | P::dload_1 | P::fload_1 | P::iload_1 | P::lload_1 => OpCode::load_1,
+        P::aload_2 | P::dload_2 | P::fload_2 | P::iload_2 | P::lload_2 => OpCode::load_2,
+        P::aload_3 | P::dload_3 | P::fload_3 | P::iload_3 | P::lload_3 => OpCode::load_3,
+        P::anewarray(index) => OpCode::anewarray {
+            class: constant_pool.get_class(*index)?.clone(),
+        },
+        P::areturn | P::dreturn | P::freturn | P::ireturn | P::lreturn => OpCode::return_v,
+        P::arraylength => OpCode::arraylength,
+        P::astore(local_index)
+        | P::dstore(local_index)
+        | P::fstore(local_index)
+        | P::istore(local_index)
+        | P::lstore(local_index) => OpCode::store_i {
+            local_index: *local_index,
+        },
+        P::astore_0 | P::dstore_0 | P::fstore_0 | P::istore_0 | P::lstore_0 => OpCode::store_0,
+        P::astore_1 | P::dstore_1 | P::fstore_1 | P::istore_1 | P::lstore_1 => OpCode::store_1,
+        P::astore_2 | P::dstore_2 | P::fstore_2 | P::istore_2 | P::lstore_2 => OpCode::store_2,
+        P::astore_3 | P::dstore_3 | P::fstore_3 | P::istore_3 | P::lstore_3 => OpCode::store_3,
+        P::athrow => OpCode::athrow,
+        P::bipush(value) => OpCode::bipush(*value),
+        P::checkcast(index) => OpCode::checkcast {
+            class: constant_pool.get_class(*index)?.clone(),
+        },
+        P::d2f => OpCode::d2f,
+        P::d2i => OpCode::d2i,
+        P::d2l => OpCode::d2l,
+        P::f2d => OpCode::f2d,
+        P::f2i => OpCode::f2i,
+        P::f2l => OpCode::f2l,
+        P::i2b => OpCode::i2b,
+        P::i2c => OpCode::i2c,
+        P::i2d => OpCode::i2d,
+        P::i2f => OpCode::i2f,
+        P::i2l => OpCode::i2l,
+        P::i2s => OpCode::i2s,
+        P::l2d => OpCode::l2d,
+        P::l2f => OpCode::l2f,
+        P::l2i => OpCode::l2i,
+        P::dadd | P::fadd | P::iadd | P::ladd => OpCode::add,
+        P::dsub | P::fsub | P::isub | P::lsub => OpCode::sub,
+        P::dmul | P::fmul | P::imul | P::lmul => OpCode::mul,
+        P::ddiv | P::fdiv | P::idiv | P::ldiv => OpCode::div,
+        P::drem | P::frem | P::irem | P::lrem => OpCode::rem,
+        P::dneg | P::fneg | P::ineg | P::lneg => OpCode::neg,
+        P::dcmpg => OpCode::dcmpg,
+        P::dcmpl => OpCode::dcmpl,
+        P::fcmpg => OpCode::fcmpg,
+        P::fcmpl => OpCode::fcmpl,
+        P::lcmp => OpCode::lcmp,
+        P::dconst_0 => OpCode::dconst_0,
+        P::dconst_1 => OpCode::dconst_1,
+        P::fconst_0 => OpCode::fconst_0,
+        P::fconst_1 => OpCode::fconst_1,
+        P::fconst_2 => OpCode::fconst_2,
+        P::iconst_m1 => OpCode::iconst_m1,
+        P::iconst_0 => OpCode::iconst_0,
+        P::iconst_1 => OpCode::iconst_1,
+        P::iconst_2 => OpCode::iconst_2,
+        P::iconst_3 => OpCode::iconst_3,
+        P::iconst_4 => OpCode::iconst_4,
+        P::iconst_5 => OpCode::iconst_5,
+        P::lconst_0 => OpCode::lconst_0,
+        P::lconst_1 => OpCode::lconst_1,
+        P::dup => OpCode::dup,
+        P::dup_x1 => OpCode::dup_x1,
+        P::dup_x2 => OpCode::dup_x2,
+        P::dup2 => OpCode::dup2,
+        P::dup2_x1 => OpCode::dup2_x1,
+        P::dup2_x2 => OpCode::dup2_x2,
+        P::pop => OpCode::pop,
+        P::pop2 => OpCode::pop2,
+        P::swap => OpCode::swap,
+        P::getfield(index) => OpCode::getfield {
+            field: constant_pool.get_field(*index)?.clone(),
+        },
+        P::putfield(index) => OpCode::putfield {
+            field: constant_pool.get_field(*index)?.clone(),
+        },
+        P::getstatic(index) => OpCode::getstatic {
+            field: constant_pool.get_field(*index)?.clone(),
+        },
+        P::putstatic(index) => OpCode::putstatic {
+            field: constant_pool.get_field(*index)?.clone(),
+        },
+        P::goto(jump) => OpCode::goto(*jump),
+        P::goto_w(jump) => OpCode::goto_w(*jump),
+        P::if_acmpeq(jump) => OpCode::if_acmpeq(*jump),
+        P::if_acmpne(jump) => OpCode::if_acmpne(*jump),
+        P::if_icmpeq(jump) => OpCode::if_icmpeq(*jump),
+        P::if_icmpne(jump) => OpCode::if_icmpne(*jump),
+        P::if_icmplt(jump) => OpCode::if_icmplt(*jump),
+        P::if_icmpge(jump) => OpCode::if_icmpge(*jump),
+        P::if_icmpgt(jump) => OpCode::if_icmpgt(*jump),
+        P::if_icmple(jump) => OpCode::if_icmple(*jump),
+        P::ifeq(jump) => OpCode::ifeq(*jump),
+        P::ifne(jump) => OpCode::ifne(*jump),
+        P::iflt(jump) => OpCode::iflt(*jump),
+        P::ifge(jump) => OpCode::ifge(*jump),
+        P::ifgt(jump) => OpCode::ifgt(*jump),
+        P::ifle(jump) => OpCode::ifle(*jump),
+        P::ifnonnull(jump) => OpCode::ifnonnull(*jump),
+        P::ifnull(jump) => OpCode::ifnull(*jump),
+        P::iinc(local_index, delta) => OpCode::iinc {
+            local_index: *local_index,
+            delta: *delta,
+        },
+        P::instanceof(index) => OpCode::instanceof {
+            class: constant_pool.get_class(*index)?.clone(),
+        },
+        P::invokedynamic(index) => OpCode::invokedynamic {
+            call_site: constant_pool.get_invoke_dynamic(*index)?.clone(),
+            caller: this_class.clone(),
+        },
+        P::invokeinterface(index, count) => OpCode::invokeinterface {
+            method: constant_pool.get_interface_method(*index)?.clone(),
+            count: *count,
+        },
+        P::invokespecial(index) => OpCode::invokespecial {
+            method: get_any_method(constant_pool, *index)?,
+            caller: this_class.clone(),
+        },
+        P::invokestatic(index) => OpCode::invokestatic {
+            method: get_any_method(constant_pool, *index)?,
+        },
+        P::invokevirtual(index) => OpCode::invokevirtual {
+            method: constant_pool.get_method(*index)?.clone(),
+        },
+        P::iand | P::land => OpCode::and,
+        P::ior | P::lor => OpCode::or,
+        P::ixor | P::lxor => OpCode::xor,
+        P::ishl | P::lshl => OpCode::shl,
+        P::ishr | P::lshr => OpCode::shr,
+        P::iushr => OpCode::iushr,
+        P::lushr => OpCode::lushr,
+        P::jsr(jump) | P::jsr_w(jump) => OpCode::jsr(*jump),
+        P::ret(local_index) => OpCode::ret {
+            local_index: *local_index,
+        },
+        P::ldc(index) | P::ldc_w(index) => lower_ldc(constant_pool, *index, this_class)?,
+        P::ldc2_w(index) => lower_ldc2_w(constant_pool, *index, this_class)?,
+        P::lookupswitch(lookup_switch) => OpCode::lookupswitch(lookup_switch.clone()),
+        P::tableswitch(table_switch) => OpCode::tableswitch(table_switch.clone()),
+        P::monitorenter => OpCode::monitorenter,
+        P::monitorexit => OpCode::monitorexit,
+        P::multinewarray(index, dimensions) => OpCode::multinewarray {
+            class: constant_pool.get_class(*index)?.clone(),
+            dimensions: *dimensions,
+        },
+        P::new(index) => OpCode::new {
+            class: constant_pool.get_class(*index)?.clone(),
+        },
+        P::newarray(array_type) => OpCode::newarray(array_type.clone()),
+        P::nop => OpCode::nop,
+        P::retrn => OpCode::retrn,
+        P::sipush(value) => OpCode::sipush(*value),
+        P::wide(wide) => lower_wide(wide),
+    };
+    Ok(opcode)
+}
+
+fn lower_exception_table(
+    code_attribute: &CodeAttribute,
+    constant_pool: &RuntimeConstantPool,
+) -> Result<ExceptionTable, ParseError> {
+    let exception_table = code_attribute.exception_table();
+    if exception_table.is_empty() {
+        return Ok(ExceptionTable::new(None));
+    }
+
+    let mut infos = Vec::with_capacity(exception_table.len());
+    for info in exception_table {
+        let catch_type = match info.catch_type() {
+            0 => None,
+            index => Some(constant_pool.get_class(index)?.clone()),
+        };
+        let info =
+            ExceptionTableInfo::new(info.code_range().clone(), info.handler_pc(), catch_type);
+        infos.push(info);
+    }
+
+    Ok(ExceptionTable::new(Some(infos)))
+}
+
+/// Lower the parsed code of a method into its executable form
+///
+/// `is_static` is needed to know if the receiver takes the first local
+pub fn lower_code(
+    code_attribute: &CodeAttribute,
+    constant_pool: &RuntimeConstantPool,
+    this_class: &Arc<ClassRef>,
+    descriptor: &MethodDescriptor,
+    is_static: bool,
+) -> Result<Code, ParseError> {
+    let opcodes = code_attribute
+        .code()
+        .iter()
+        .map(|opcode| lower_opcode(opcode, constant_pool, this_class))
+        .collect::<Result<_, _>>()?;
+
+    let receiver_size = if is_static { 0 } else { 1 };
+    let args_count = descriptor.parameters_size() + receiver_size;
+
+    let exception_table = lower_exception_table(code_attribute, constant_pool)?;
+
+    Ok(Code::new(
+        code_attribute.max_stack(),
+        code_attribute.max_locals(),
+        opcodes,
+        args_count,
+        exception_table,
+    ))
+}
+
+fn lower_constant_value(
+    field_info: &FieldInfo,
+    constant_pool: &RuntimeConstantPool,
+) -> Result<Option<RuntimeConstant>, ParseError> {
+    let constant_value = field_info.attributes().iter().find_map(|attribute_info| {
+        match attribute_info.attribute() {
+            Attribute::ConstantValue(constant_value) => Some(constant_value),
+            _ => None,
+        }
+    });
+    let Some(constant_value) = constant_value else {
+        return Ok(None);
+    };
+    let index = constant_value.constant_value_index();
+    match constant_pool.get_checked(index)? {
+        constant @ (RuntimeConstant::Integer(_)
+        | RuntimeConstant::Float(_)
+        | RuntimeConstant::Long(_)
+        | RuntimeConstant::Double(_)
+        | RuntimeConstant::String(_)) => Ok(Some(constant.clone())),
+        _ => Err(ParseError::UnexpectedConstant {
+            index,
+            expected: "Integer, Float, Long, Double or String",
+        }),
+    }
+}
+
+/// Lay out the fields declared by the class
+///
+/// The instance fields are put after the fields inherited from the super class,
+/// the static fields are numbered separately as they are stored by the class.
+fn lower_fields(
+    class_file: &ClassFile,
+    runtime_pool: &RuntimeConstantPool,
+    inherited_slots: usize,
+) -> Result<Vec<Field>, ParseError> {
+    let constant_pool = class_file.constant_pool();
+    let mut instance_slot = inherited_slots;
+    let mut static_slot = 0;
+    let mut fields = Vec::with_capacity(class_file.fields().len());
+    for field_info in class_file.fields().iter() {
+        let name = constant_pool.get_utf8(field_info.name_index())?;
+        let descriptor = constant_pool.get_utf8(field_info.descriptor_index())?;
+        let descriptor = parse_field_descriptor(descriptor)?;
+        let access_flags = field_info.access_flags();
+        let is_static = access_flags.contains(AccesFlag::STATIC);
+        let next_slot = if is_static {
+            &mut static_slot
+        } else {
+            &mut instance_slot
+        };
+        let slot = *next_slot;
+        *next_slot += descriptor.slot_size();
+        // the attribute is ignored on instance fields
+        let constant_value = if is_static {
+            lower_constant_value(field_info, runtime_pool)?
+        } else {
+            None
+        };
+        fields.push(Field::new(
+            name.to_string(),
+            descriptor,
+            access_flags,
+            slot,
+            constant_value,
+        ));
+    }
+    Ok(fields)
+}
+
+/// Lower a parsed class, the super class and the interfaces have to be loaded beforehand
+pub fn lower_class(
+    class_file: &ClassFile,
+    super_class: Option<Arc<Class>>,
+    interfaces: Vec<Arc<Class>>,
+) -> Result<Arc<Class>, ParseError> {
+    let constant_pool = class_file.constant_pool();
+    let runtime_pool = lower_constant_pool(constant_pool, class_file.bootstrap_methods())?;
+    let class_name = constant_pool.get_class_name(class_file.this_class())?;
+    let this_class = runtime_pool.get_class(class_file.this_class())?;
+
+    let mut methods = Vec::new();
+    for method_info in class_file.methods().iter() {
+        let name = constant_pool.get_utf8(method_info.name_index())?;
+        let descriptor = constant_pool.get_utf8(method_info.descriptor_index())?;
+        let access_flags = method_info.access_flags();
+        let parsed_descriptor = parse_method_descriptor(descriptor)?;
+        let is_static = access_flags.contains(AccesFlag::STATIC);
+        let receiver_size = if is_static { 0 } else { 1 };
+        let args_count = parsed_descriptor.parameters_size() + receiver_size;
+        let code = method_info
+            .get_code()
+            .map(|code| {
+                lower_code(
+                    code,
+                    &runtime_pool,
+                    this_class,
+                    &parsed_descriptor,
+                    is_static,
+                )
+            })
+            .transpose()?;
+        methods.push((
+            name.to_string(),
+            descriptor.to_string(),
+            access_flags,
+            args_count,
+            code,
+        ));
+    }
+
+    let inherited_slots = super_class
+        .as_ref()
+        .map_or(0, |super_class| super_class.instance_size());
+    let fields = lower_fields(class_file, &runtime_pool, inherited_slots)?;
+
+    let class = Class::new(
+        class_name.to_string(),
+        class_file.acces_flag(),
+        super_class,
+        interfaces,
+        fields,
+        |class| {
+            methods
+                .into_iter()
+                .map(|(name, descriptor, access_flags, args_count, code)| {
+                    Method::new(
+                        name,
+                        descriptor,
+                        access_flags,
+                        class.clone(),
+                        args_count,
+                        code,
+                    )
+                })
+                .collect()
+        },
+    );
+
+    Ok(class)
+}
@@ -1 +1,13 @@
+pub mod class_library;
+pub mod class_loader;
 mod execution;
+pub mod invoke;
+pub mod jvm;
+pub mod lowering;
+pub mod monitors;
+pub mod natives;
+pub mod threads;
+pub mod verifier;
+
+#[cfg(test)]
+mod test;
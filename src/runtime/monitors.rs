@@ -0,0 +1,219 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Condvar, Mutex},
+    thread::{self, ThreadId},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    rethrow_exception,
+    runtime_types::{
+        throw_exception, ExecResult, InternalError, Object, ILLEGAL_MONITOR_STATE_EXCEPTION,
+        INTERRUPTED_EXCEPTION, NULL_POINTER_EXCEPTION,
+    },
+};
+
+use super::jvm::Jvm;
+
+const NOT_OWNER_MESSAGE: &str = "current thread is not owner";
+
+#[derive(Debug, Default)]
+struct MonitorState {
+    // the host thread that entered the monitor, and how many times it did
+    owner: Option<ThreadId>,
+    count: usize,
+    // the threads in Object.wait, in the order they called it
+    wait_set: VecDeque<u64>,
+    // the waiting threads chosen by notify, which have not woken up yet
+    notified: HashSet<u64>,
+    next_waiter: u64,
+}
+
+/// The reentrant lock of an object, with its wait set
+#[derive(Debug)]
+pub struct Monitor {
+    // keeps the object alive, so its address is not reused while the monitor is inflated
+    object: Object,
+    state: Mutex<MonitorState>,
+    // signaled when the owner releases the monitor
+    released: Condvar,
+    // signaled by notify, and when a waiting thread is interrupted
+    notified: Condvar,
+}
+
+impl Monitor {
+    fn new(object: Object) -> Self {
+        Monitor {
+            object,
+            state: Mutex::new(MonitorState::default()),
+            released: Condvar::new(),
+            notified: Condvar::new(),
+        }
+    }
+
+    /// Wake up the waiting threads, so the interrupted ones throw an InterruptedException
+    pub fn wake_waiters(&self) -> Result<(), InternalError> {
+        // locked so a thread about to wait can't miss it
+        let _state = self.state.lock()?;
+        self.notified.notify_all();
+        Ok(())
+    }
+}
+
+/// The monitors of the objects and arrays, locked by synchronized and used by Object.wait
+///
+/// Objects have no header to store a monitor. It is inflated in this table by the address
+/// of the object the first time a thread enters it, and deflated once no thread owns it,
+/// waits to enter it or waits on it.
+#[derive(Debug, Default)]
+pub struct Monitors {
+    monitors: Mutex<HashMap<usize, Arc<Monitor>>>,
+}
+
+impl Monitors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn inflate(&self, address: usize, object: &Object) -> Result<Arc<Monitor>, InternalError> {
+        let mut monitors = self.monitors.lock()?;
+        let monitor = monitors
+            .entry(address)
+            .or_insert_with(|| Arc::new(Monitor::new(object.clone())));
+        Ok(monitor.clone())
+    }
+
+    // the monitors are only shared under the lock of the table, so when the table holds the
+    // last other reference, no thread waits for the monitor or waits on it. The owner doesn't
+    // keep a reference, another thread may have entered it since it was released.
+    fn deflate(&self, monitor: Arc<Monitor>) -> Result<(), InternalError> {
+        let address = monitor.object.address()?.ok_or(InternalError::WrongType)?;
+        let mut monitors = self.monitors.lock()?;
+        if Arc::strong_count(&monitor) == 2 && monitor.state.lock()?.owner.is_none() {
+            monitors.remove(&address);
+        }
+        Ok(())
+    }
+
+    // the inflated monitor of the object, owned by the current thread
+    fn owned(&self, jvm: &Jvm, object: &Object) -> ExecResult<Arc<Monitor>> {
+        let Some(address) = object.address()? else {
+            return throw_exception(jvm, NULL_POINTER_EXCEPTION, None);
+        };
+        let monitor = self.monitors.lock()?.get(&address).cloned();
+        match monitor {
+            Some(monitor) if monitor.state.lock()?.owner == Some(thread::current().id()) => {
+                Ok(Ok(monitor))
+            }
+            _ => {
+                let message = NOT_OWNER_MESSAGE.to_string();
+                throw_exception(jvm, ILLEGAL_MONITOR_STATE_EXCEPTION, Some(message))
+            }
+        }
+    }
+
+    /// Enter the monitor of the object, waiting until its owner releases it
+    ///
+    /// The thread can enter a monitor it already owns, it is released after as many exits.
+    pub fn enter(&self, jvm: &Jvm, object: &Object) -> ExecResult<()> {
+        let Some(address) = object.address()? else {
+            return throw_exception(jvm, NULL_POINTER_EXCEPTION, None);
+        };
+        let monitor = self.inflate(address, object)?;
+        let current = thread::current().id();
+        let mut state = monitor.state.lock()?;
+        while state.owner.is_some_and(|owner| owner != current) {
+            state = monitor.released.wait(state)?;
+        }
+        state.owner = Some(current);
+        state.count += 1;
+        Ok(Ok(()))
+    }
+
+    /// An IllegalMonitorStateException is thrown if the current thread doesn't own the monitor
+    pub fn exit(&self, jvm: &Jvm, object: &Object) -> ExecResult<()> {
+        let monitor = rethrow_exception!(self.owned(jvm, object)?);
+        {
+            let mut state = monitor.state.lock()?;
+            state.count -= 1;
+            if state.count > 0 {
+                return Ok(Ok(()));
+            }
+            state.owner = None;
+            monitor.released.notify_all();
+        }
+        self.deflate(monitor)?;
+        Ok(Ok(()))
+    }
+
+    /// Release the monitor until the thread is notified, the timeout passes or the thread is
+    /// interrupted, then enter it again as many times as before
+    ///
+    /// The thread waits without timeout when it is zero.
+    pub fn wait(&self, jvm: &Jvm, object: &Object, timeout: Duration) -> ExecResult<()> {
+        let monitor = rethrow_exception!(self.owned(jvm, object)?);
+        let deadline = (!timeout.is_zero()).then(|| Instant::now() + timeout);
+        let threads = jvm.threads();
+        threads.set_waiting_monitor(Some(monitor.clone()))?;
+
+        let current = thread::current().id();
+        let mut state = monitor.state.lock()?;
+        let count = std::mem::take(&mut state.count);
+        state.owner = None;
+        monitor.released.notify_all();
+        let waiter = state.next_waiter;
+        state.next_waiter += 1;
+        state.wait_set.push_back(waiter);
+
+        // a notification is not lost when the thread is interrupted too, it returns normally
+        // and stays interrupted
+        let interrupted = loop {
+            if state.notified.remove(&waiter) {
+                break false;
+            }
+            if threads.take_interrupt()? {
+                break true;
+            }
+            state = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break false;
+                    }
+                    monitor.notified.wait_timeout(state, deadline - now)?.0
+                }
+                None => monitor.notified.wait(state)?,
+            };
+        };
+        state.wait_set.retain(|&other| other != waiter);
+
+        while state.owner.is_some() {
+            state = monitor.released.wait(state)?;
+        }
+        state.owner = Some(current);
+        state.count = count;
+        drop(state);
+        threads.set_waiting_monitor(None)?;
+
+        if interrupted {
+            return throw_exception(jvm, INTERRUPTED_EXCEPTION, None);
+        }
+        Ok(Ok(()))
+    }
+
+    /// Wake up the thread waiting on the monitor for the longest time, or all of them
+    pub fn notify(&self, jvm: &Jvm, object: &Object, all: bool) -> ExecResult<()> {
+        let monitor = rethrow_exception!(self.owned(jvm, object)?);
+        let mut state = monitor.state.lock()?;
+        let woken: Vec<u64> = if all {
+            state.wait_set.drain(..).collect()
+        } else {
+            state.wait_set.pop_front().into_iter().collect()
+        };
+        if !woken.is_empty() {
+            state.notified.extend(woken);
+            monitor.notified.notify_all();
+        }
+        Ok(Ok(()))
+    }
+}
@@ -0,0 +1,71 @@
+use std::io::{self, Write};
+
+use crate::{
+    native_arg,
+    parser::descriptor::FieldType,
+    runtime::jvm::Jvm,
+    runtime_types::{throw_exception, Array, InternalError, MethodCallResult, Object, Reference},
+};
+
+use super::NativeRegistry;
+
+const FILE_OUTPUT_STREAM: &str = "java/io/FileOutputStream";
+const FILE_DESCRIPTOR: &str = "java/io/FileDescriptor";
+const IO_EXCEPTION: &str = "java/io/IOException";
+
+const STDOUT: i32 = 1;
+const STDERR: i32 = 2;
+
+pub fn register_natives(registry: &mut NativeRegistry) {
+    registry.register(FILE_OUTPUT_STREAM, "writeBytes", "([BII)V", write_bytes);
+}
+
+fn get_field(
+    reference: &Reference,
+    name: &str,
+    descriptor: FieldType,
+) -> Result<Object, InternalError> {
+    let (_, field) = reference
+        .get_class()
+        .resolve_field(name, &descriptor)
+        .ok_or(InternalError::WrongType)?;
+    reference.get_field(field.get_slot())
+}
+
+/// The bounds are checked by FileOutputStream.write, only stdout and stderr can be written
+fn write_bytes(jvm: &Jvm, args: &[Object]) -> MethodCallResult {
+    let stream = native_arg!(args, 0, Object::Reference).ok_or(InternalError::WrongType)?;
+    let Some(Array::Byte(bytes)) = native_arg!(args, 1, Object::Array) else {
+        return Err(InternalError::WrongType);
+    };
+    let offset = native_arg!(args, 2, Object::Int) as usize;
+    let length = native_arg!(args, 3, Object::Int) as usize;
+
+    let descriptor = FieldType::Object(FILE_DESCRIPTOR.to_string());
+    let Object::Reference(Some(file_descriptor)) = get_field(&stream, "fd", descriptor)? else {
+        return Err(InternalError::WrongType);
+    };
+    let Object::Int(fd) = get_field(&file_descriptor, "fd", FieldType::Int)? else {
+        return Err(InternalError::WrongType);
+    };
+
+    let bytes: Vec<u8> = bytes.lock()?[offset..offset + length]
+        .iter()
+        .map(|&byte| byte as u8)
+        .collect();
+    // written without buffering, so the output isn't lost when the process exits
+    let written = match fd {
+        STDOUT => io::stdout()
+            .write_all(&bytes)
+            .and_then(|_| io::stdout().flush()),
+        STDERR => io::stderr().write_all(&bytes),
+        _ => {
+            let message = Some("Stream Closed".to_string());
+            return throw_exception(jvm, IO_EXCEPTION, message);
+        }
+    };
+    match written {
+        Ok(()) => Ok(Ok(None)),
+        Err(error) => throw_exception(jvm, IO_EXCEPTION, Some(error.to_string())),
+    }
+}
@@ -0,0 +1,651 @@
+use std::{
+    io::{self, Write},
+    process,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    native_arg,
+    parser::descriptor::parse_field_descriptor,
+    rethrow_exception,
+    runtime::jvm::Jvm,
+    runtime_types::{
+        fill_in_stack_trace, format_floating, read_string, read_utf16_string, throw_exception,
+        Array, Class, ExecResult, GcCause, InternalError, MethodCallResult, Object, Reference,
+        Stack, ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION, ARRAY_STORE_EXCEPTION,
+        CLONE_NOT_SUPPORTED_EXCEPTION, ILLEGAL_ARGUMENT_EXCEPTION, NULL_POINTER_EXCEPTION,
+        NUMBER_FORMAT_EXCEPTION,
+    },
+};
+
+use super::{return_string, return_value, NativeMethod, NativeRegistry, OBJECT};
+
+const STRING: &str = "java/lang/String";
+const CLASS: &str = "java/lang/Class";
+const INTEGER: &str = "java/lang/Integer";
+const LONG: &str = "java/lang/Long";
+const MATH: &str = "java/lang/Math";
+const SYSTEM: &str = "java/lang/System";
+const RUNTIME: &str = "java/lang/Runtime";
+const THROWABLE: &str = "java/lang/Throwable";
+const CLONEABLE: &str = "java/lang/Cloneable";
+
+pub fn register_natives(registry: &mut NativeRegistry) {
+    registry.register(OBJECT, "getClass", "()Ljava/lang/Class;", object_get_class);
+    registry.register(OBJECT, "hashCode", "()I", object_hash_code);
+    registry.register(OBJECT, "toString", "()Ljava/lang/String;", object_to_string);
+    registry.register(OBJECT, "clone", "()Ljava/lang/Object;", object_clone);
+
+    registry.register(STRING, "getBytes", "()[B", string_get_bytes);
+    registry.register(STRING, "hashCode", "()I", string_hash_code);
+    registry.register(STRING, "intern", "()Ljava/lang/String;", string_intern);
+    registry.register(STRING, "valueOf", "(F)Ljava/lang/String;", |jvm, args| {
+        return_string(jvm, &format_floating(native_arg!(args, 0, Object::Float)))
+    });
+    registry.register(STRING, "valueOf", "(D)Ljava/lang/String;", |jvm, args| {
+        return_string(jvm, &format_floating(native_arg!(args, 0, Object::Double)))
+    });
+
+    registry.register(CLASS, "getName", "()Ljava/lang/String;", |jvm, args| {
+        return_string(jvm, &mirrored_class(args)?.get_name().replace('/', "."))
+    });
+    registry.register(CLASS, "isInterface", "()Z", |_, args| {
+        return_value(Object::Int(mirrored_class(args)?.is_interface() as i32))
+    });
+    registry.register(CLASS, "isArray", "()Z", |_, args| {
+        return_value(Object::Int(mirrored_class(args)?.is_array() as i32))
+    });
+    registry.register(CLASS, "isPrimitive", "()Z", |_, args| {
+        return_value(Object::Int(mirrored_class(args)?.is_primitive() as i32))
+    });
+
+    registry.register(INTEGER, "parseInt", "(Ljava/lang/String;)I", |jvm, args| {
+        let parsed = rethrow_exception!(parse_integer::<i32>(jvm, &args[0])?);
+        return_value(Object::Int(parsed))
+    });
+    registry.register(INTEGER, "toString", "(I)Ljava/lang/String;", |jvm, args| {
+        return_string(jvm, &native_arg!(args, 0, Object::Int).to_string())
+    });
+    registry.register(
+        INTEGER,
+        "toHexString",
+        "(I)Ljava/lang/String;",
+        |jvm, args| return_string(jvm, &format!("{:x}", native_arg!(args, 0, Object::Int))),
+    );
+
+    registry.register(LONG, "parseLong", "(Ljava/lang/String;)J", |jvm, args| {
+        let parsed = rethrow_exception!(parse_integer::<i64>(jvm, &args[0])?);
+        return_value(Object::Long(parsed))
+    });
+    registry.register(LONG, "toString", "(J)Ljava/lang/String;", |jvm, args| {
+        return_string(jvm, &native_arg!(args, 0, Object::Long).to_string())
+    });
+    registry.register(LONG, "toHexString", "(J)Ljava/lang/String;", |jvm, args| {
+        return_string(jvm, &format!("{:x}", native_arg!(args, 0, Object::Long)))
+    });
+    registry.register(LONG, "compare", "(JJ)I", |_, args| {
+        let x = native_arg!(args, 0, Object::Long);
+        let y = native_arg!(args, 1, Object::Long);
+        return_value(Object::Int(x.cmp(&y) as i32))
+    });
+    registry.register(LONG, "hashCode", "(J)I", |_, args| {
+        let value = native_arg!(args, 0, Object::Long);
+        return_value(Object::Int((value ^ (value >> 32)) as i32))
+    });
+
+    register_math_natives(registry);
+
+    registry.register(
+        SYSTEM,
+        "arraycopy",
+        "(Ljava/lang/Object;ILjava/lang/Object;II)V",
+        arraycopy,
+    );
+    registry.register(SYSTEM, "currentTimeMillis", "()J", |_, _| {
+        let elapsed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        return_value(Object::Long(elapsed.as_millis() as i64))
+    });
+    registry.register(SYSTEM, "nanoTime", "()J", |_, _| {
+        static START: OnceLock<Instant> = OnceLock::new();
+        let elapsed = START.get_or_init(Instant::now).elapsed();
+        return_value(Object::Long(elapsed.as_nanos() as i64))
+    });
+    registry.register(
+        SYSTEM,
+        "identityHashCode",
+        "(Ljava/lang/Object;)I",
+        |_, args| {
+            let hash_code = match &args[0] {
+                Object::Reference(Some(reference)) => reference.identity_hash_code(),
+                Object::Array(Some(array)) => array.identity_hash_code(),
+                _ => 0,
+            };
+            return_value(Object::Int(hash_code))
+        },
+    );
+    registry.register(
+        SYSTEM,
+        "getProperty",
+        "(Ljava/lang/String;)Ljava/lang/String;",
+        get_property,
+    );
+    registry.register(SYSTEM, "exit", "(I)V", |_, args| {
+        let status = native_arg!(args, 0, Object::Int);
+        // the exit skips the destructors flushing the streams
+        let _ = io::stdout().flush();
+        let _ = io::stderr().flush();
+        process::exit(status)
+    });
+    registry.register(SYSTEM, "gc", "()V", |jvm, _| {
+        jvm.heap().collect_garbage(GcCause::SystemGc)?;
+        Ok(Ok(None))
+    });
+
+    registry.register(RUNTIME, "maxMemory", "()J", |jvm, _| {
+        return_value(Object::Long(jvm.heap().get_max_size() as i64))
+    });
+    registry.register(RUNTIME, "freeMemory", "()J", |jvm, _| {
+        let heap = jvm.heap();
+        let free = heap.get_max_size().saturating_sub(heap.get_size()?);
+        return_value(Object::Long(free as i64))
+    });
+
+    registry.register(
+        THROWABLE,
+        "getClassName",
+        "()Ljava/lang/String;",
+        |jvm, args| {
+            let throwable =
+                native_arg!(args, 0, Object::Reference).ok_or(InternalError::WrongType)?;
+            return_string(jvm, &throwable.get_class().get_name().replace('/', "."))
+        },
+    );
+    registry.register(
+        THROWABLE,
+        "fillInStackTrace",
+        "()Ljava/lang/Throwable;",
+        |jvm, args| {
+            let throwable =
+                native_arg!(args, 0, Object::Reference).ok_or(InternalError::WrongType)?;
+            rethrow_exception!(fill_in_stack_trace(jvm, &throwable)?);
+            return_value(Object::Reference(Some(throwable)))
+        },
+    );
+}
+
+fn register_math_natives(registry: &mut NativeRegistry) {
+    registry.register(MATH, "abs", "(I)I", |_, args| {
+        return_value(Object::Int(
+            native_arg!(args, 0, Object::Int).wrapping_abs(),
+        ))
+    });
+    registry.register(MATH, "abs", "(J)J", |_, args| {
+        return_value(Object::Long(
+            native_arg!(args, 0, Object::Long).wrapping_abs(),
+        ))
+    });
+    registry.register(MATH, "abs", "(F)F", |_, args| {
+        return_value(Object::Float(native_arg!(args, 0, Object::Float).abs()))
+    });
+    registry.register(MATH, "abs", "(D)D", |_, args| {
+        return_value(Object::Double(native_arg!(args, 0, Object::Double).abs()))
+    });
+
+    registry.register(MATH, "max", "(II)I", |_, args| {
+        let (a, b) = (
+            native_arg!(args, 0, Object::Int),
+            native_arg!(args, 1, Object::Int),
+        );
+        return_value(Object::Int(a.max(b)))
+    });
+    registry.register(MATH, "max", "(JJ)J", |_, args| {
+        let (a, b) = (
+            native_arg!(args, 0, Object::Long),
+            native_arg!(args, 1, Object::Long),
+        );
+        return_value(Object::Long(a.max(b)))
+    });
+    registry.register(MATH, "max", "(FF)F", |_, args| {
+        let (a, b) = (
+            native_arg!(args, 0, Object::Float),
+            native_arg!(args, 1, Object::Float),
+        );
+        return_value(Object::Float(floating_max(a.into(), b.into()) as f32))
+    });
+    registry.register(MATH, "max", "(DD)D", |_, args| {
+        let (a, b) = (
+            native_arg!(args, 0, Object::Double),
+            native_arg!(args, 1, Object::Double),
+        );
+        return_value(Object::Double(floating_max(a, b)))
+    });
+    registry.register(MATH, "min", "(II)I", |_, args| {
+        let (a, b) = (
+            native_arg!(args, 0, Object::Int),
+            native_arg!(args, 1, Object::Int),
+        );
+        return_value(Object::Int(a.min(b)))
+    });
+    registry.register(MATH, "min", "(JJ)J", |_, args| {
+        let (a, b) = (
+            native_arg!(args, 0, Object::Long),
+            native_arg!(args, 1, Object::Long),
+        );
+        return_value(Object::Long(a.min(b)))
+    });
+    registry.register(MATH, "min", "(FF)F", |_, args| {
+        let (a, b) = (
+            native_arg!(args, 0, Object::Float),
+            native_arg!(args, 1, Object::Float),
+        );
+        return_value(Object::Float(floating_min(a.into(), b.into()) as f32))
+    });
+    registry.register(MATH, "min", "(DD)D", |_, args| {
+        let (a, b) = (
+            native_arg!(args, 0, Object::Double),
+            native_arg!(args, 1, Object::Double),
+        );
+        return_value(Object::Double(floating_min(a, b)))
+    });
+
+    let unary_functions: [(&str, NativeMethod); 11] = [
+        ("sqrt", |_, args| double_function(args, f64::sqrt)),
+        ("cbrt", |_, args| double_function(args, f64::cbrt)),
+        ("exp", |_, args| double_function(args, f64::exp)),
+        ("log", |_, args| double_function(args, f64::ln)),
+        ("log10", |_, args| double_function(args, f64::log10)),
+        ("sin", |_, args| double_function(args, f64::sin)),
+        ("cos", |_, args| double_function(args, f64::cos)),
+        ("tan", |_, args| double_function(args, f64::tan)),
+        ("floor", |_, args| double_function(args, f64::floor)),
+        ("ceil", |_, args| double_function(args, f64::ceil)),
+        ("rint", |_, args| {
+            double_function(args, f64::round_ties_even)
+        }),
+    ];
+    for (name, native) in unary_functions {
+        registry.register(MATH, name, "(D)D", native);
+    }
+    registry.register(MATH, "pow", "(DD)D", |_, args| {
+        let (a, b) = (
+            native_arg!(args, 0, Object::Double),
+            native_arg!(args, 1, Object::Double),
+        );
+        return_value(Object::Double(a.powf(b)))
+    });
+    registry.register(MATH, "atan2", "(DD)D", |_, args| {
+        let (y, x) = (
+            native_arg!(args, 0, Object::Double),
+            native_arg!(args, 1, Object::Double),
+        );
+        return_value(Object::Double(y.atan2(x)))
+    });
+    // the casts saturate and give 0 for NaN, like Java
+    registry.register(MATH, "round", "(D)J", |_, args| {
+        let a = native_arg!(args, 0, Object::Double);
+        return_value(Object::Long(round_half_up(a) as i64))
+    });
+    registry.register(MATH, "round", "(F)I", |_, args| {
+        let a = native_arg!(args, 0, Object::Float);
+        return_value(Object::Int(round_half_up(a.into()) as i32))
+    });
+}
+
+fn double_function(args: &[Object], function: fn(f64) -> f64) -> MethodCallResult {
+    return_value(Object::Double(function(native_arg!(
+        args,
+        0,
+        Object::Double
+    ))))
+}
+
+// NaN wins and 0.0 is greater than -0.0, unlike f64::max
+fn floating_max(a: f64, b: f64) -> f64 {
+    if a.is_nan() {
+        a
+    } else if a == b {
+        if a.is_sign_negative() {
+            b
+        } else {
+            a
+        }
+    } else if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+fn floating_min(a: f64, b: f64) -> f64 {
+    if a.is_nan() {
+        a
+    } else if a == b {
+        if a.is_sign_negative() {
+            a
+        } else {
+            b
+        }
+    } else if a < b {
+        a
+    } else {
+        b
+    }
+}
+
+// ties are rounded towards positive infinity, -2.5 gives -2
+fn round_half_up(a: f64) -> f64 {
+    let floor = a.floor();
+    if a - floor >= 0.5 {
+        floor + 1.0
+    } else {
+        floor
+    }
+}
+
+/// Parse a decimal integer like Integer.parseInt, with an optional sign
+fn parse_integer<T: std::str::FromStr>(jvm: &Jvm, string: &Object) -> ExecResult<T> {
+    let Object::Reference(Some(string)) = string else {
+        let message = "Cannot parse null string: null".to_string();
+        return throw_exception(jvm, NUMBER_FORMAT_EXCEPTION, Some(message));
+    };
+    let text = read_string(string)?;
+    // Rust also accepts an empty number after the sign, Java doesn't
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(&text);
+    let parsed = digits
+        .chars()
+        .all(|char| char.is_ascii_digit())
+        .then(|| text.parse().ok())
+        .flatten();
+    match parsed {
+        Some(parsed) => Ok(Ok(parsed)),
+        None => {
+            let message = format!("For input string: \"{}\"", text);
+            throw_exception(jvm, NUMBER_FORMAT_EXCEPTION, Some(message))
+        }
+    }
+}
+
+/// The same mirror for all the objects of a class, arrays included
+fn object_get_class(jvm: &Jvm, args: &[Object]) -> MethodCallResult {
+    let class = args[0].get_class(jvm)?.ok_or(InternalError::WrongType)?;
+    let mirror = rethrow_exception!(class.get_mirror(jvm)?);
+    return_value(Object::Reference(Some(mirror)))
+}
+
+fn object_hash_code(_: &Jvm, args: &[Object]) -> MethodCallResult {
+    let hash_code = match &args[0] {
+        Object::Reference(Some(reference)) => reference.identity_hash_code(),
+        Object::Array(Some(array)) => array.identity_hash_code(),
+        _ => return Err(InternalError::WrongType),
+    };
+    return_value(Object::Int(hash_code))
+}
+
+/// The class name and the hexadecimal hash code, hashCode may be overridden
+fn object_to_string(jvm: &Jvm, args: &[Object]) -> MethodCallResult {
+    let (class_name, hash_code) = match &args[0] {
+        Object::Reference(Some(reference)) => {
+            let hash_code = rethrow_exception!(call_hash_code(jvm, reference)?);
+            (reference.get_class().get_name().to_string(), hash_code)
+        }
+        Object::Array(Some(array)) => (array.get_class_name(), array.identity_hash_code()),
+        _ => return Err(InternalError::WrongType),
+    };
+    let text = format!("{}@{:x}", class_name.replace('/', "."), hash_code);
+    return_string(jvm, &text)
+}
+
+/// A shallow copy of an array or of an object whose class implements Cloneable
+fn object_clone(jvm: &Jvm, args: &[Object]) -> MethodCallResult {
+    let object = match &args[0] {
+        Object::Array(Some(array)) => {
+            let copy = rethrow_exception!(jvm.heap().allocate_array(jvm, array.copy()?)?);
+            return return_value(Object::Array(Some(copy)));
+        }
+        Object::Reference(Some(object)) => object,
+        _ => return Err(InternalError::WrongType),
+    };
+    let class = object.get_class();
+    let cloneable = jvm
+        .class_loader()
+        .load_class(CLONEABLE)
+        .map_err(|_| InternalError::ClassLoadingFailed(CLONEABLE.to_string()))?;
+    if !class.is_subclass(&cloneable) {
+        let message = class.get_name().replace('/', ".");
+        return throw_exception(jvm, CLONE_NOT_SUPPORTED_EXCEPTION, Some(message));
+    }
+    let copy = rethrow_exception!(jvm.heap().new_object(jvm, class.clone())?);
+    // the fields are read before the copy is locked
+    let fields = object.get_fields().lock()?.clone();
+    *copy.get_fields().lock()? = fields;
+    return_value(Object::Reference(Some(copy)))
+}
+
+fn call_hash_code(jvm: &Jvm, reference: &Reference) -> ExecResult<i32> {
+    let class = reference.get_class();
+    let (resolved_class, resolved) = class
+        .resolve_method("hashCode", "()I")
+        .ok_or(InternalError::WrongType)?;
+    let Some(Ok(Some(method))) = class.select_method(resolved_class, resolved) else {
+        return Err(InternalError::WrongType);
+    };
+    let mut stack = Stack::new(1);
+    stack.push(Object::Reference(Some(reference.clone())));
+    match method.execute(jvm, &mut stack)? {
+        Ok(Some(Object::Int(hash_code))) => Ok(Ok(hash_code)),
+        Ok(_) => Err(InternalError::WrongType),
+        Err(exception) => Ok(Err(exception)),
+    }
+}
+
+fn receiver_string(args: &[Object]) -> Result<Vec<u16>, InternalError> {
+    let string = native_arg!(args, 0, Object::Reference).ok_or(InternalError::WrongType)?;
+    read_utf16_string(&string)
+}
+
+/// The lone surrogates are encoded as '?', like the UTF-8 encoder of the JDK
+fn string_get_bytes(jvm: &Jvm, args: &[Object]) -> MethodCallResult {
+    let text: String = char::decode_utf16(receiver_string(args)?)
+        .map(|char| char.unwrap_or('?'))
+        .collect();
+    let bytes = text.bytes().map(|byte| byte as i8).collect();
+    let bytes = Array::Byte(Arc::new(Mutex::new(bytes)));
+    let bytes = rethrow_exception!(jvm.heap().allocate_array(jvm, bytes)?);
+    return_value(Object::Array(Some(bytes)))
+}
+
+/// s[0]*31^(n-1) + s[1]*31^(n-2) + ... + s[n-1], on the UTF-16 code units
+fn string_hash_code(_: &Jvm, args: &[Object]) -> MethodCallResult {
+    let hash_code = receiver_string(args)?
+        .into_iter()
+        .fold(0i32, |hash_code, unit| {
+            hash_code.wrapping_mul(31).wrapping_add(unit as i32)
+        });
+    return_value(Object::Int(hash_code))
+}
+
+/// The string itself is added to the table if there isn't one with the same characters
+fn string_intern(jvm: &Jvm, args: &[Object]) -> MethodCallResult {
+    let string = native_arg!(args, 0, Object::Reference).ok_or(InternalError::WrongType)?;
+    let units = read_utf16_string(&string)?.into_boxed_slice();
+    let interned = jvm.intern_table().insert(units, string)?;
+    return_value(Object::Reference(Some(interned)))
+}
+
+fn mirrored_class(args: &[Object]) -> Result<Arc<Class>, InternalError> {
+    let mirror = native_arg!(args, 0, Object::Reference).ok_or(InternalError::WrongType)?;
+    mirror.get_mirrored_class().ok_or(InternalError::WrongType)
+}
+
+fn get_property(jvm: &Jvm, args: &[Object]) -> MethodCallResult {
+    let Some(key) = native_arg!(args, 0, Object::Reference) else {
+        let message = Some("key can't be null".to_string());
+        return throw_exception(jvm, NULL_POINTER_EXCEPTION, message);
+    };
+    let key = read_string(&key)?;
+    if key.is_empty() {
+        let message = Some("key can't be empty".to_string());
+        return throw_exception(jvm, ILLEGAL_ARGUMENT_EXCEPTION, message);
+    }
+    match jvm.get_system_property(&key) {
+        Some(value) => return_string(jvm, value),
+        None => return_value(Object::Reference(None)),
+    }
+}
+
+/// The name of the type of an array in the messages of arraycopy, like `int[]`
+fn array_type_name(array: &Array) -> String {
+    let class_name = array.get_class_name();
+    parse_field_descriptor(&class_name)
+        .map(|array_type| array_type.to_string())
+        .unwrap_or(class_name)
+}
+
+// the two kinds of null are accepted, objects that are not arrays are rejected
+fn arraycopy_operand(jvm: &Jvm, operand: &Object, role: &str) -> ExecResult<Array> {
+    match operand {
+        Object::Array(Some(array)) => Ok(Ok(array.clone())),
+        Object::Array(None) | Object::Reference(None) => {
+            throw_exception(jvm, NULL_POINTER_EXCEPTION, None)
+        }
+        Object::Reference(Some(reference)) => {
+            let message = format!(
+                "arraycopy: {} type {} is not an array",
+                role,
+                reference.get_class().get_name().replace('/', ".")
+            );
+            throw_exception(jvm, ARRAY_STORE_EXCEPTION, Some(message))
+        }
+        _ => Err(InternalError::WrongType),
+    }
+}
+
+// copied through a buffer, so overlapping ranges of the same array are copied correctly
+fn copy_range<T: Clone>(
+    src: &Mutex<Box<[T]>>,
+    src_pos: usize,
+    dest: &Mutex<Box<[T]>>,
+    dest_pos: usize,
+    length: usize,
+) -> Result<(), InternalError> {
+    let copied = src.lock()?[src_pos..src_pos + length].to_vec();
+    dest.lock()?[dest_pos..dest_pos + length].clone_from_slice(&copied);
+    Ok(())
+}
+
+fn arraycopy(jvm: &Jvm, args: &[Object]) -> MethodCallResult {
+    let src = rethrow_exception!(arraycopy_operand(jvm, &args[0], "source")?);
+    let src_pos = native_arg!(args, 1, Object::Int);
+    let dest = rethrow_exception!(arraycopy_operand(jvm, &args[2], "destination")?);
+    let dest_pos = native_arg!(args, 3, Object::Int);
+    let length = native_arg!(args, 4, Object::Int);
+
+    let same_type = matches!(
+        (&src, &dest),
+        (Array::Boolean(_), Array::Boolean(_))
+            | (Array::Char(_), Array::Char(_))
+            | (Array::Float(_), Array::Float(_))
+            | (Array::Double(_), Array::Double(_))
+            | (Array::Byte(_), Array::Byte(_))
+            | (Array::Short(_), Array::Short(_))
+            | (Array::Int(_), Array::Int(_))
+            | (Array::Long(_), Array::Long(_))
+            | (Array::Reference(_), Array::Reference(_))
+    );
+    if !same_type {
+        let message = format!(
+            "arraycopy: type mismatch: can not copy {} into {}",
+            array_type_name(&src),
+            array_type_name(&dest)
+        );
+        return throw_exception(jvm, ARRAY_STORE_EXCEPTION, Some(message));
+    }
+
+    let describe = |array: &Array, size: i32| match array {
+        Array::Reference(_) => format!("object array[{}]", size),
+        _ => array_type_name(array).replace("[]", &format!("[{}]", size)),
+    };
+    let (src_size, dest_size) = (src.size()?, dest.size()?);
+    let out_of_bounds = if src_pos < 0 {
+        Some(format!(
+            "source index {} out of bounds for {}",
+            src_pos,
+            describe(&src, src_size)
+        ))
+    } else if dest_pos < 0 {
+        Some(format!(
+            "destination index {} out of bounds for {}",
+            dest_pos,
+            describe(&dest, dest_size)
+        ))
+    } else if length < 0 {
+        Some(format!("length {} is negative", length))
+    } else if src_pos as i64 + length as i64 > src_size as i64 {
+        Some(format!(
+            "last source index {} out of bounds for {}",
+            src_pos as i64 + length as i64,
+            describe(&src, src_size)
+        ))
+    } else if dest_pos as i64 + length as i64 > dest_size as i64 {
+        Some(format!(
+            "last destination index {} out of bounds for {}",
+            dest_pos as i64 + length as i64,
+            describe(&dest, dest_size)
+        ))
+    } else {
+        None
+    };
+    if let Some(message) = out_of_bounds {
+        let message = format!("arraycopy: {}", message);
+        return throw_exception(jvm, ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION, Some(message));
+    }
+
+    let (src_pos, dest_pos, length) = (src_pos as usize, dest_pos as usize, length as usize);
+    match (&src, &dest) {
+        (Array::Boolean(src), Array::Boolean(dest)) => {
+            copy_range(src, src_pos, dest, dest_pos, length)?
+        }
+        (Array::Char(src), Array::Char(dest)) => copy_range(src, src_pos, dest, dest_pos, length)?,
+        (Array::Float(src), Array::Float(dest)) => {
+            copy_range(src, src_pos, dest, dest_pos, length)?
+        }
+        (Array::Double(src), Array::Double(dest)) => {
+            copy_range(src, src_pos, dest, dest_pos, length)?
+        }
+        (Array::Byte(src), Array::Byte(dest)) => copy_range(src, src_pos, dest, dest_pos, length)?,
+        (Array::Short(src), Array::Short(dest)) => {
+            copy_range(src, src_pos, dest, dest_pos, length)?
+        }
+        (Array::Int(src), Array::Int(dest)) => copy_range(src, src_pos, dest, dest_pos, length)?,
+        (Array::Long(src), Array::Long(dest)) => copy_range(src, src_pos, dest, dest_pos, length)?,
+        (Array::Reference(src_array), Array::Reference(dest_array)) => {
+            // the elements are copied until one can't be stored in the destination
+            let copied = src_array.lock()?[src_pos..src_pos + length].to_vec();
+            let mut accepted = Vec::with_capacity(length);
+            for element in copied {
+                if !dest_array.can_accept(jvm, &element)? {
+                    break;
+                }
+                accepted.push(dest_array.as_item(element));
+            }
+            dest_array.lock()?[dest_pos..dest_pos + accepted.len()].clone_from_slice(&accepted);
+            if accepted.len() < length {
+                let message = format!(
+                    "arraycopy: element type mismatch: can not cast one of the elements of {} \
+                     to the type of the destination array, {}",
+                    array_type_name(&src),
+                    dest_array
+                        .get_component_class()
+                        .get_name()
+                        .replace('/', ".")
+                );
+                return throw_exception(jvm, ARRAY_STORE_EXCEPTION, Some(message));
+            }
+        }
+        _ => return Err(InternalError::WrongType),
+    }
+    Ok(Ok(None))
+}
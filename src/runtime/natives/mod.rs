@@ -0,0 +1,86 @@
+mod io;
+mod lang;
+mod thread;
+
+use std::collections::HashMap;
+
+use crate::runtime_types::{new_string, MethodCallResult, Object};
+
+use super::jvm::Jvm;
+
+const OBJECT: &str = "java/lang/Object";
+
+/// The implementation of a native method, given the arguments with the receiver first
+///
+/// Longs and doubles take a single argument, without the padding they have on the stack.
+pub type NativeMethod = fn(&Jvm, &[Object]) -> MethodCallResult;
+
+/// The Rust functions run for the methods declared with ACC_NATIVE,
+/// by class, name and descriptor
+#[derive(Debug, Default)]
+pub struct NativeRegistry {
+    methods: HashMap<(String, String, String), NativeMethod>,
+}
+
+impl NativeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// With the native methods of the bundled class library
+    pub fn with_class_library() -> Self {
+        let mut registry = Self::new();
+        lang::register_natives(&mut registry);
+        io::register_natives(&mut registry);
+        thread::register_natives(&mut registry);
+        registry
+    }
+
+    /// Replace the native method if one was already registered
+    pub fn register(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+        native: NativeMethod,
+    ) {
+        let key = (
+            class_name.to_string(),
+            name.to_string(),
+            descriptor.to_string(),
+        );
+        self.methods.insert(key, native);
+    }
+
+    pub fn get(&self, class_name: &str, name: &str, descriptor: &str) -> Option<NativeMethod> {
+        let key = (
+            class_name.to_string(),
+            name.to_string(),
+            descriptor.to_string(),
+        );
+        self.methods.get(&key).copied()
+    }
+}
+
+/// Get an argument of a native method, checking its type
+#[macro_export]
+macro_rules! native_arg {
+    ($args:expr, $index:expr, $pattern:path) => {
+        match $args.get($index) {
+            Some($pattern(value)) => value.clone(),
+            _ => return Err($crate::runtime_types::InternalError::WrongType),
+        }
+    };
+}
+
+fn return_value(value: Object) -> MethodCallResult {
+    Ok(Ok(Some(value)))
+}
+
+fn return_string(jvm: &Jvm, text: &str) -> MethodCallResult {
+    let string = match new_string(jvm, text)? {
+        Ok(string) => string,
+        Err(exception) => return Ok(Err(exception)),
+    };
+    return_value(Object::Reference(Some(string)))
+}
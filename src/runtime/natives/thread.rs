@@ -0,0 +1,105 @@
+use std::{thread, time::Duration};
+
+use crate::{
+    native_arg, rethrow_exception,
+    runtime::{jvm::Jvm, threads::THREAD_CLASS},
+    runtime_types::{
+        throw_exception, ExecResult, InternalError, MethodCallResult, Object, Reference,
+        ILLEGAL_ARGUMENT_EXCEPTION, INTERRUPTED_EXCEPTION,
+    },
+};
+
+use super::{return_value, NativeRegistry, OBJECT};
+
+pub fn register_natives(registry: &mut NativeRegistry) {
+    registry.register(OBJECT, "wait", "(J)V", object_wait);
+    registry.register(OBJECT, "notify", "()V", |jvm, args| {
+        rethrow_exception!(jvm.monitors().notify(jvm, &args[0], false)?);
+        Ok(Ok(None))
+    });
+    registry.register(OBJECT, "notifyAll", "()V", |jvm, args| {
+        rethrow_exception!(jvm.monitors().notify(jvm, &args[0], true)?);
+        Ok(Ok(None))
+    });
+
+    registry.register(
+        THREAD_CLASS,
+        "currentThread",
+        "()Ljava/lang/Thread;",
+        |jvm, _| {
+            let thread = rethrow_exception!(jvm.threads().current_thread(jvm)?);
+            return_value(Object::Reference(Some(thread)))
+        },
+    );
+    registry.register(THREAD_CLASS, "nextThreadNumber", "()I", |jvm, _| {
+        return_value(Object::Int(jvm.threads().next_thread_number()))
+    });
+    registry.register(THREAD_CLASS, "sleep", "(J)V", thread_sleep);
+    registry.register(THREAD_CLASS, "yield", "()V", |_, _| {
+        thread::yield_now();
+        Ok(Ok(None))
+    });
+    registry.register(THREAD_CLASS, "interrupted", "()Z", |jvm, _| {
+        let thread = rethrow_exception!(jvm.threads().current_thread(jvm)?);
+        let interrupted = jvm.threads().is_interrupted(&thread, true)?;
+        return_value(Object::Int(interrupted as i32))
+    });
+
+    registry.register(THREAD_CLASS, "start0", "()V", |jvm, args| {
+        rethrow_exception!(jvm.threads().start(jvm, receiver(args)?)?);
+        Ok(Ok(None))
+    });
+    registry.register(THREAD_CLASS, "isAlive", "()Z", |jvm, args| {
+        let alive = jvm.threads().is_alive(&receiver(args)?)?;
+        return_value(Object::Int(alive as i32))
+    });
+    registry.register(THREAD_CLASS, "join", "(J)V", thread_join);
+    registry.register(THREAD_CLASS, "interrupt", "()V", |jvm, args| {
+        jvm.threads().interrupt(&receiver(args)?)?;
+        Ok(Ok(None))
+    });
+    registry.register(THREAD_CLASS, "isInterrupted", "()Z", |jvm, args| {
+        let interrupted = jvm.threads().is_interrupted(&receiver(args)?, false)?;
+        return_value(Object::Int(interrupted as i32))
+    });
+}
+
+fn receiver(args: &[Object]) -> Result<Reference, InternalError> {
+    native_arg!(args, 0, Object::Reference).ok_or(InternalError::WrongType)
+}
+
+// a negative duration is rejected, like the JDK
+fn duration_millis(jvm: &Jvm, millis: i64) -> ExecResult<Duration> {
+    if millis < 0 {
+        let message = "timeout value is negative".to_string();
+        return throw_exception(jvm, ILLEGAL_ARGUMENT_EXCEPTION, Some(message));
+    }
+    Ok(Ok(Duration::from_millis(millis as u64)))
+}
+
+fn throw_interrupted(jvm: &Jvm, message: &str) -> MethodCallResult {
+    throw_exception(jvm, INTERRUPTED_EXCEPTION, Some(message.to_string()))
+}
+
+fn object_wait(jvm: &Jvm, args: &[Object]) -> MethodCallResult {
+    let timeout = rethrow_exception!(duration_millis(jvm, native_arg!(args, 1, Object::Long))?);
+    rethrow_exception!(jvm.monitors().wait(jvm, &args[0], timeout)?);
+    Ok(Ok(None))
+}
+
+fn thread_sleep(jvm: &Jvm, args: &[Object]) -> MethodCallResult {
+    let duration = rethrow_exception!(duration_millis(jvm, native_arg!(args, 0, Object::Long))?);
+    if !jvm.threads().sleep(duration)? {
+        return throw_interrupted(jvm, "sleep interrupted");
+    }
+    Ok(Ok(None))
+}
+
+fn thread_join(jvm: &Jvm, args: &[Object]) -> MethodCallResult {
+    let thread = receiver(args)?;
+    let timeout = rethrow_exception!(duration_millis(jvm, native_arg!(args, 1, Object::Long))?);
+    if !jvm.threads().join(&thread, timeout)? {
+        return throw_interrupted(jvm, "join interrupted");
+    }
+    Ok(Ok(None))
+}
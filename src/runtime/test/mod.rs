@@ -0,0 +1,1633 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read},
+    path::PathBuf,
+    sync::Arc,
+    thread,
+};
+
+use crate::{
+    parser::{
+        classfile::{
+            classfile::{parse_class_file, ClassFile},
+            opcode::ArrayType,
+        },
+        descriptor::{parse_method_descriptor, FieldType},
+        jar::{
+            manifest::{parse_manifest, MANIFEST_PATH},
+            zip::ZipArchive,
+        },
+    },
+    runtime_types::{
+        describe_exception, get_exception_message, get_stack_trace, intern_string, new_string,
+        read_string, read_utf16_string, Class, ClassRef, Code, ExceptionTable, FieldRef, GcCause,
+        MethodCallResult, Object, OpCode, Reference, Stack, MAX_FRAMES, THREAD_STACK_SIZE,
+    },
+};
+
+use super::{
+    class_loader::{ClassLoader, ClassLoadingError, ClassPathEntry},
+    jvm::Jvm,
+    lowering::{lower_code, lower_constant_pool},
+    verifier::{types::VerificationType, VerifyErrorKind},
+};
+
+fn load_sample_class(path: &str) -> ClassFile {
+    let file = File::open(path).unwrap();
+    let mut bytes = BufReader::new(file).bytes();
+    parse_class_file(&mut bytes).unwrap()
+}
+
+fn lower_static_method(class_file: &ClassFile, method_name: &str) -> Code {
+    let constant_pool = class_file.constant_pool();
+    let runtime_pool = lower_constant_pool(constant_pool, class_file.bootstrap_methods()).unwrap();
+    let method = class_file
+        .methods()
+        .iter()
+        .find(|method| constant_pool.get_utf8(method.name_index()).unwrap() == method_name)
+        .unwrap();
+    let descriptor = constant_pool.get_utf8(method.descriptor_index()).unwrap();
+    let descriptor = parse_method_descriptor(descriptor).unwrap();
+    let this_class = runtime_pool.get_class(class_file.this_class()).unwrap();
+    let code = method.get_code().unwrap();
+    lower_code(code, &runtime_pool, this_class, &descriptor, true).unwrap()
+}
+
+fn empty_jvm() -> Jvm {
+    Jvm::new(ClassLoader::new(vec![]), HashMap::new())
+}
+
+/// A virtual machine with the classes of a sample directory on its classpath
+fn sample_jvm(directory: &str) -> Jvm {
+    let class_loader = ClassLoader::new(vec![ClassPathEntry::Directory(PathBuf::from(directory))]);
+    // read by the natives samples, like -Duser.name=duke
+    let properties = HashMap::from([("user.name".to_string(), "duke".to_string())]);
+    Jvm::new(class_loader, properties)
+}
+
+#[test]
+fn test_lowered_add() {
+    let class_file = load_sample_class("sample/HelloWorld.class");
+    let code = lower_static_method(&class_file, "add");
+    let mut stack = Stack::new(2);
+    stack.push(Object::Int(3));
+    stack.push(Object::Int(8));
+    let result = code.execute(&empty_jvm(), &mut stack);
+
+    assert_eq!(result, Ok(Ok(Some(Object::Int(11)))));
+}
+
+#[test]
+fn test_lowered_fibonacci() {
+    let class_file = load_sample_class("sample/HelloWorld.class");
+    let code = lower_static_method(&class_file, "fib");
+    let mut stack = Stack::new(1);
+    stack.push(Object::Int(30));
+    let result = code.execute(&empty_jvm(), &mut stack);
+
+    assert_eq!(result, Ok(Ok(Some(Object::Int(832040)))));
+}
+
+// a static method of the arithmetic sample, lowered on its own and run with the arguments
+fn run_arithmetic(method_name: &str, arguments: &[Object]) -> Object {
+    let class_file = load_sample_class("sample/arithmetic/Arithmetic.class");
+    let code = lower_static_method(&class_file, method_name);
+    let mut stack = Stack::new(4);
+    for argument in arguments {
+        stack.push(argument.clone());
+    }
+    match code.execute(&empty_jvm(), &mut stack) {
+        Ok(Ok(Some(value))) => value,
+        result => panic!("{} should return a value: {:?}", method_name, result),
+    }
+}
+
+#[test]
+fn test_lowered_comparisons() {
+    let compare = |method_name, a, b| run_arithmetic(method_name, &[a, b]) == Object::Int(1);
+
+    assert!(compare("longGreater", Object::Long(5), Object::Long(3)));
+    assert!(!compare("longGreater", Object::Long(3), Object::Long(5)));
+    assert!(!compare("longGreater", Object::Long(3), Object::Long(3)));
+    assert!(compare("longLess", Object::Long(1), Object::Long(2)));
+    assert!(!compare(
+        "longLess",
+        Object::Long(i64::MAX),
+        Object::Long(i64::MIN)
+    ));
+
+    let doubles: fn(f64) -> Object = Object::Double;
+    let floats: fn(f64) -> Object = |value| Object::Float(value as f32);
+    for (greater, less, value) in [
+        ("doubleGreater", "doubleLess", doubles),
+        ("floatGreater", "floatLess", floats),
+    ] {
+        assert!(compare(greater, value(2.0), value(1.0)));
+        assert!(!compare(greater, value(1.0), value(2.0)));
+        assert!(compare(less, value(1.0), value(2.0)));
+        assert!(!compare(less, value(2.0), value(1.0)));
+        assert!(!compare(less, value(-0.0), value(0.0)));
+        // every comparison with NaN is false, whichever side it is on
+        for (a, b) in [(value(f64::NAN), value(1.0)), (value(1.0), value(f64::NAN))] {
+            assert!(!compare(greater, a.clone(), b.clone()));
+            assert!(!compare(less, a, b));
+        }
+    }
+}
+
+#[test]
+fn test_lowered_overflow() {
+    assert_eq!(
+        run_arithmetic("increment", &[Object::Int(i32::MAX)]),
+        Object::Int(i32::MIN)
+    );
+    assert_eq!(
+        run_arithmetic("add", &[Object::Int(i32::MAX), Object::Int(2)]),
+        Object::Int(i32::MIN + 1)
+    );
+    assert_eq!(
+        run_arithmetic("multiply", &[Object::Long(i64::MAX), Object::Long(2)]),
+        Object::Long(-2)
+    );
+    assert_eq!(
+        run_arithmetic("subtract", &[Object::Long(i64::MIN), Object::Long(1)]),
+        Object::Long(i64::MAX)
+    );
+    assert_eq!(
+        run_arithmetic("negate", &[Object::Int(i32::MIN)]),
+        Object::Int(i32::MIN)
+    );
+}
+
+fn sample_class_loader() -> ClassLoader {
+    ClassLoader::new(vec![ClassPathEntry::Directory(PathBuf::from(
+        "sample/class_loader",
+    ))])
+}
+
+#[test]
+fn test_load_class_hierarchy() {
+    let class_loader = sample_class_loader();
+    let dog = class_loader.load_class("Dog").unwrap();
+    let animal = class_loader.load_class("Animal").unwrap();
+    let object = class_loader.load_class("java/lang/Object").unwrap();
+
+    assert!(Arc::ptr_eq(dog.get_superclass().unwrap(), &animal));
+    assert!(Arc::ptr_eq(animal.get_superclass().unwrap(), &object));
+    assert!(dog.is_subclass(&object));
+    assert!(!animal.is_subclass(&dog));
+
+    let pet = dog.get_interfaces().first().unwrap();
+    assert_eq!(pet.get_name(), "Pet");
+    assert!(pet.is_interface());
+    assert!(Arc::ptr_eq(pet, &class_loader.load_class("Pet").unwrap()));
+
+    assert!(dog.find_method("name", "()Ljava/lang/String;").is_some());
+}
+
+#[test]
+fn test_load_class_errors() {
+    let class_loader = sample_class_loader();
+    assert!(matches!(
+        class_loader.load_class("Cat"),
+        Err(ClassLoadingError::NotFound(name)) if name == "Cat"
+    ));
+    // copy of Animal.class
+    assert!(matches!(
+        class_loader.load_class("WrongName"),
+        Err(ClassLoadingError::WrongClassName { found, .. }) if found == "Animal"
+    ));
+
+    // A.class extends B and B.class extends A, compiled separately
+    let class_loader = ClassLoader::new(vec![ClassPathEntry::Directory(PathBuf::from(
+        "sample/class_loader_circular",
+    ))]);
+    assert!(matches!(
+        class_loader.load_class("A"),
+        Err(ClassLoadingError::CircularInheritance(name)) if name == "A"
+    ));
+}
+
+#[test]
+fn test_read_jar_entries() {
+    // animals.jar is deflated, animals_stored.jar is not compressed
+    for path in ["sample/jar/animals.jar", "sample/jar/animals_stored.jar"] {
+        let archive = ZipArchive::open(path).unwrap();
+        for class in ["Animal", "Pet", "Dog", "java/lang/Object"] {
+            let file_name = format!("{}.class", class);
+            let bytes = archive.read(&file_name).unwrap().unwrap();
+            let expected = std::fs::read(format!("sample/class_loader/{}", file_name)).unwrap();
+            assert_eq!(bytes, expected);
+        }
+        assert_eq!(archive.read("Cat.class").unwrap(), None);
+        assert!(archive.get_entry("META-INF/").unwrap().is_directory());
+    }
+
+    let archive = ZipArchive::open("sample/jar/animals.jar").unwrap();
+    let bytes = archive.read("HelloWorld.class").unwrap().unwrap();
+    assert_eq!(bytes, std::fs::read("sample/HelloWorld.class").unwrap());
+
+    let manifest = archive.read(MANIFEST_PATH).unwrap().unwrap();
+    let manifest = parse_manifest(&String::from_utf8(manifest).unwrap());
+    assert_eq!(manifest.get_main_class().as_deref(), Some("Dog"));
+}
+
+#[test]
+fn test_load_class_from_jar() {
+    let entry = ClassPathEntry::open(PathBuf::from("sample/jar/animals.jar")).unwrap();
+    assert!(matches!(entry, ClassPathEntry::Jar(..)));
+    let class_loader = ClassLoader::new(vec![entry]);
+
+    let dog = class_loader.load_class("Dog").unwrap();
+    let object = class_loader.load_class("java/lang/Object").unwrap();
+    assert!(dog.is_subclass(&object));
+    assert_eq!(dog.get_interfaces().first().unwrap().get_name(), "Pet");
+}
+
+fn verifier_class_loader(sample: &str) -> ClassLoader {
+    ClassLoader::new(vec![
+        ClassPathEntry::Directory(PathBuf::from(sample)),
+        ClassPathEntry::Directory(PathBuf::from("sample/class_loader")),
+    ])
+}
+
+#[test]
+fn test_verify_valid_class() {
+    let class_loader = verifier_class_loader("sample/verifier");
+    let verified = class_loader.load_class("Verified").unwrap();
+    assert!(verified.find_method("sum", "([I)I").is_some());
+}
+
+// sample, method name, instruction index, check of the error kind
+type ExpectedVerifyError = (
+    &'static str,
+    &'static str,
+    usize,
+    fn(&VerifyErrorKind) -> bool,
+);
+
+// Each sample is Verified.class with a few bytes patched:
+// bad_operand_type:        add uses fadd instead of iadd
+// stack_overflow:          add has a max_stack of 1
+// local_out_of_bounds:     twice has a max_locals of 1
+// branch_out_of_code:      the loop exit of sum jumps to the end of the code
+// uninitialized_object:    create pops the arguments of <init> instead of calling it
+// constructor_not_called:  the constructor doesn't call super()
+// missing_stack_map_frame: the StackMapTable attribute is renamed, so it is ignored
+#[test]
+fn test_verify_errors() {
+    let expected_errors: [ExpectedVerifyError; 7] = [
+        ("bad_operand_type", "add", 2, |kind| {
+            matches!(
+                kind,
+                VerifyErrorKind::BadOperandType {
+                    found: VerificationType::Integer,
+                    ..
+                }
+            )
+        }),
+        ("stack_overflow", "add", 1, |kind| {
+            matches!(kind, VerifyErrorKind::StackOverflow)
+        }),
+        ("local_out_of_bounds", "twice", 3, |kind| {
+            matches!(kind, VerifyErrorKind::LocalIndexOutOfBounds(1))
+        }),
+        ("branch_out_of_code", "sum", 7, |kind| {
+            matches!(kind, VerifyErrorKind::InvalidBranchTarget(18))
+        }),
+        ("uninitialized_object", "create", 8, |kind| {
+            matches!(
+                kind,
+                VerifyErrorKind::UninitializedObject(VerificationType::Uninitialized(0))
+            )
+        }),
+        ("constructor_not_called", "<init>", 7, |kind| {
+            matches!(kind, VerifyErrorKind::ConstructorNotCalled)
+        }),
+        ("missing_stack_map_frame", "sum", 7, |kind| {
+            matches!(kind, VerifyErrorKind::MissingStackMapFrame(16))
+        }),
+    ];
+
+    for (sample, method_name, index, is_expected_kind) in expected_errors {
+        let class_loader = verifier_class_loader(&format!("sample/verifier/{}", sample));
+        let Err(ClassLoadingError::VerifyError(error)) = class_loader.load_class("Verified") else {
+            panic!("{} should not pass verification", sample);
+        };
+        assert_eq!(error.class_name, "Verified", "{}", sample);
+        assert_eq!(error.method_name, method_name, "{}", sample);
+        assert_eq!(error.index, Some(index), "{}", sample);
+        assert!(
+            is_expected_kind(&error.kind),
+            "{}: {:?}",
+            sample,
+            error.kind
+        );
+
+        // the class stays unusable
+        assert!(matches!(
+            class_loader.load_class("Verified"),
+            Err(ClassLoadingError::VerifyError(_))
+        ));
+    }
+}
+
+#[test]
+fn test_verify_legacy_class() {
+    // version 49 class without StackMapTable, using jsr/ret, see Legacy.javap.txt
+    let class_loader = verifier_class_loader("sample/verifier/legacy");
+    let legacy = class_loader.load_class("Legacy").unwrap();
+    assert!(legacy.find_method("withFinally", "(I)I").is_some());
+}
+
+// Each sample is legacy/Legacy.class with a single instruction changed:
+// inconsistent_stack_height:  legs dups the Dog instead of storing it before the merge
+// ret_without_return_address: the subroutine of withFinally returns with the int local
+// recursive_subroutine:       the subroutine of withFinally calls itself
+// merged_to_top:              select stores a float instead of an int on one branch
+#[test]
+fn test_verify_legacy_errors() {
+    let expected_errors: [ExpectedVerifyError; 4] = [
+        ("inconsistent_stack_height", "legs", 10, |kind| {
+            matches!(kind, VerifyErrorKind::InconsistentStackHeight)
+        }),
+        ("ret_without_return_address", "withFinally", 13, |kind| {
+            matches!(kind, VerifyErrorKind::BadLocalType { index: 1, .. })
+        }),
+        ("recursive_subroutine", "withFinally", 12, |kind| {
+            matches!(kind, VerifyErrorKind::RecursiveSubroutine(11))
+        }),
+        ("merged_to_top", "select", 7, |kind| {
+            matches!(
+                kind,
+                VerifyErrorKind::BadLocalType {
+                    index: 1,
+                    found: VerificationType::Top,
+                    ..
+                }
+            )
+        }),
+    ];
+
+    for (sample, method_name, index, is_expected_kind) in expected_errors {
+        let class_loader = verifier_class_loader(&format!("sample/verifier/legacy/{}", sample));
+        let Err(ClassLoadingError::VerifyError(error)) = class_loader.load_class("Legacy") else {
+            panic!("{} should not pass verification", sample);
+        };
+        assert_eq!(error.method_name, method_name, "{}", sample);
+        assert_eq!(error.index, Some(index), "{}", sample);
+        assert!(
+            is_expected_kind(&error.kind),
+            "{}: {:?}",
+            sample,
+            error.kind
+        );
+    }
+}
+
+#[test]
+fn test_runtime_exceptions() {
+    let expected_exceptions = [
+        (
+            "nullArray",
+            "()I",
+            None,
+            "java/lang/NullPointerException",
+            None,
+        ),
+        (
+            "outOfBounds",
+            "(I)I",
+            Some(3),
+            "java/lang/ArrayIndexOutOfBoundsException",
+            Some("Index 3 out of bounds for length 3"),
+        ),
+        (
+            "outOfBounds",
+            "(I)I",
+            Some(-1),
+            "java/lang/ArrayIndexOutOfBoundsException",
+            Some("Index -1 out of bounds for length 3"),
+        ),
+        (
+            "negativeSize",
+            "(I)I",
+            Some(-5),
+            "java/lang/NegativeArraySizeException",
+            Some("-5"),
+        ),
+        (
+            "divide",
+            "(I)I",
+            Some(0),
+            "java/lang/ArithmeticException",
+            Some("/ by zero"),
+        ),
+        (
+            "remainder",
+            "(I)I",
+            Some(0),
+            "java/lang/ArithmeticException",
+            Some("/ by zero"),
+        ),
+        (
+            "arrayStore",
+            "()[Ljava/lang/Object;",
+            None,
+            "java/lang/ArrayStoreException",
+            Some("java.lang.NullPointerException"),
+        ),
+        (
+            "classCast",
+            "()Ljava/lang/String;",
+            None,
+            "java/lang/ClassCastException",
+            Some("class java.lang.NullPointerException cannot be cast to class java.lang.String"),
+        ),
+    ];
+
+    let jvm = sample_jvm("sample/exceptions");
+    for (name, descriptor, argument, class_name, message) in expected_exceptions {
+        let arguments = argument.map(Object::Int);
+        let result = call_static(&jvm, "Faults", name, descriptor, arguments.as_slice());
+        let Ok(Err(exception)) = result else {
+            panic!("{} should throw {}: {:?}", name, class_name, result);
+        };
+        assert_eq!(exception.get_class().get_name(), class_name, "{}", name);
+        assert_eq!(
+            get_exception_message(&exception).unwrap().as_deref(),
+            message,
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_catch_runtime_exception() {
+    let jvm = sample_jvm("sample/exceptions");
+    let call = |name, argument| call_static(&jvm, "Faults", name, "(I)I", &[Object::Int(argument)]);
+
+    assert_eq!(call("caughtOutOfBounds", 1), Ok(Ok(Some(Object::Int(0)))));
+    assert_eq!(call("caughtOutOfBounds", 5), Ok(Ok(Some(Object::Int(-1)))));
+    assert_eq!(call("divide", 7), Ok(Ok(Some(Object::Int(14)))));
+    assert_eq!(call("remainder", 7), Ok(Ok(Some(Object::Int(-2)))));
+}
+
+#[test]
+fn test_stack_traces() {
+    let jvm = sample_jvm("sample/exceptions");
+    let stack_trace = |name| {
+        let Ok(Err(exception)) = call_static(&jvm, "Faults", name, "()V", &[]) else {
+            panic!("{} should throw", name);
+        };
+        get_stack_trace(&exception).unwrap()
+    };
+
+    // the frames of the constructors of the exception are left out
+    assert_eq!(stack_trace("nested"), ["Faults.fail", "Faults.nested"]);
+    let Ok(Err(exception)) = call_static(&jvm, "Faults", "nullArray", "()I", &[]) else {
+        panic!("nullArray should throw");
+    };
+    assert_eq!(get_stack_trace(&exception).unwrap(), ["Faults.nullArray"]);
+}
+
+#[test]
+fn test_resolution_errors() {
+    let jvm = sample_jvm("sample/resolution");
+    for name in ["newInstance", "staticField", "staticMethod"] {
+        let result = call_static(&jvm, "Resolution", name, "()Ljava/lang/String;", &[]);
+        let Ok(Ok(Some(Object::Reference(Some(message))))) = result else {
+            panic!("{} should catch a NoClassDefFoundError: {:?}", name, result);
+        };
+        assert_eq!(read_string(&message).unwrap(), "Gone", "{}", name);
+    }
+}
+
+#[test]
+fn test_stack_overflow() {
+    // like the launcher, the methods run in a thread with the stack size of the Java threads
+    let calls = thread::Builder::new()
+        .stack_size(THREAD_STACK_SIZE)
+        .spawn(|| {
+            let jvm = sample_jvm("sample/exceptions");
+            [0, 1].map(|_| call_static(&jvm, "Faults", "stackOverflow", "()I", &[]))
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    // stackOverflow takes the first frame, the frames are popped when the error is caught
+    for call in calls {
+        assert_eq!(call, Ok(Ok(Some(Object::Int(MAX_FRAMES as i32 - 1)))));
+    }
+}
+
+/// Run the new opcode alone, without calling a constructor
+fn new_instance(jvm: &Jvm, class_name: &str) -> MethodCallResult {
+    let class = Arc::new(ClassRef::new(class_name.to_string()));
+    let opcodes = vec![OpCode::new { class }, OpCode::return_v];
+    let code = Code::new(1, 0, opcodes, 0, ExceptionTable::new(None));
+    code.execute(jvm, &mut Stack::new(0))
+}
+
+#[test]
+fn test_instance_field_layout() {
+    let jvm = sample_jvm("sample/fields");
+    let point = jvm.class_loader().load_class("Point3").unwrap();
+    // x, y, then z and weight taking two slots each, then name and values
+    assert_eq!(point.get_superclass().unwrap().instance_size(), 2);
+    assert_eq!(point.instance_size(), 8);
+    let slots: Vec<(&str, usize)> = point
+        .get_fields()
+        .iter()
+        .map(|field| (field.get_name(), field.get_slot()))
+        .collect();
+    assert_eq!(
+        slots,
+        [
+            ("created", 0),
+            ("z", 2),
+            ("weight", 4),
+            ("name", 6),
+            ("values", 7)
+        ]
+    );
+}
+
+#[test]
+fn test_instance_fields() {
+    let jvm = sample_jvm("sample/fields");
+    let Ok(Ok(Some(point))) = new_instance(&jvm, "Point3") else {
+        panic!("Point3 should be instantiated");
+    };
+    let Object::Reference(Some(reference)) = &point else {
+        panic!("new should push a reference");
+    };
+    assert_eq!(reference.get_field(7), Ok(Object::Array(None)));
+
+    let descriptor = |return_type| format!("(LPoint3;){}", return_type);
+    let call = |name, return_type| {
+        call_static(
+            &jvm,
+            "Fields",
+            name,
+            &descriptor(return_type),
+            std::slice::from_ref(&point),
+        )
+    };
+    assert_eq!(call("isDefault", "Z"), Ok(Ok(Some(Object::Int(1)))));
+    assert_eq!(call("fill", "J"), Ok(Ok(Some(Object::Long(3000000003)))));
+    assert_eq!(call("weight", "D"), Ok(Ok(Some(Object::Double(1.0)))));
+    assert_eq!(call("isDefault", "Z"), Ok(Ok(Some(Object::Int(0)))));
+    assert_eq!(reference.get_field(2), Ok(Object::Long(3000000000)));
+    assert_eq!(reference.get_field(3), Ok(Object::Padding));
+}
+
+#[test]
+fn test_instance_field_errors() {
+    let jvm = sample_jvm("sample/fields");
+    let Ok(Err(exception)) = call_static(
+        &jvm,
+        "Fields",
+        "nullX",
+        "(LPoint;)I",
+        &[Object::Reference(None)],
+    ) else {
+        panic!("nullX should throw");
+    };
+    assert_eq!(
+        exception.get_class().get_name(),
+        "java/lang/NullPointerException"
+    );
+
+    let Ok(Err(exception)) = new_instance(&jvm, "Shape") else {
+        panic!("an abstract class should not be instantiated");
+    };
+    assert_eq!(
+        exception.get_class().get_name(),
+        "java/lang/InstantiationError"
+    );
+    assert_eq!(
+        get_exception_message(&exception).unwrap().as_deref(),
+        Some("Shape")
+    );
+}
+
+fn static_field(class_name: &str, name: &str, descriptor: FieldType) -> Arc<FieldRef> {
+    let class = Arc::new(ClassRef::new(class_name.to_string()));
+    Arc::new(FieldRef::new(class, name.to_string(), descriptor))
+}
+
+/// Run getstatic alone, preceded by putstatic if a value is given
+fn access_static(
+    jvm: &Jvm,
+    class_name: &str,
+    name: &str,
+    descriptor: FieldType,
+    value: Option<OpCode>,
+) -> MethodCallResult {
+    let field = static_field(class_name, name, descriptor);
+    let mut opcodes = Vec::new();
+    if let Some(value) = value {
+        opcodes.push(value);
+        opcodes.push(OpCode::putstatic {
+            field: field.clone(),
+        });
+    }
+    opcodes.push(OpCode::getstatic { field });
+    opcodes.push(OpCode::return_v);
+    let code = Code::new(2, 0, opcodes, 0, ExceptionTable::new(None));
+    code.execute(jvm, &mut Stack::new(0))
+}
+
+#[test]
+fn test_static_initializer() {
+    let jvm = sample_jvm("sample");
+    let value = access_static(&jvm, "HelloWorld", "f", FieldType::Double, None);
+    assert_eq!(value, Ok(Ok(Some(Object::Double(90.45)))));
+}
+
+#[test]
+fn test_static_fields() {
+    let jvm = sample_jvm("sample/statics");
+    let get = |name, descriptor| access_static(&jvm, "Constants", name, descriptor, None);
+    assert_eq!(get("ANSWER", FieldType::Int), Ok(Ok(Some(Object::Int(42)))));
+    assert_eq!(
+        get("BIG", FieldType::Long),
+        Ok(Ok(Some(Object::Long(1 << 40))))
+    );
+    assert_eq!(
+        get("RATIO", FieldType::Float),
+        Ok(Ok(Some(Object::Float(1.5))))
+    );
+    assert_eq!(
+        get("HALF", FieldType::Double),
+        Ok(Ok(Some(Object::Double(0.5))))
+    );
+
+    let put =
+        |name, descriptor, value| access_static(&jvm, "Constants", name, descriptor, Some(value));
+    assert_eq!(
+        put("counter", FieldType::Long, OpCode::lconst_1),
+        Ok(Ok(Some(Object::Long(1))))
+    );
+    assert_eq!(
+        put("after", FieldType::Int, OpCode::iconst_m1),
+        Ok(Ok(Some(Object::Int(-1))))
+    );
+    // the long didn't overwrite the next field
+    assert_eq!(
+        get("counter", FieldType::Long),
+        Ok(Ok(Some(Object::Long(1))))
+    );
+}
+
+#[test]
+fn test_initialization_order() {
+    let jvm = sample_jvm("sample/statics");
+    // the field is declared by Base, so Derived is not initialized
+    let order = access_static(&jvm, "Derived", "order", FieldType::Int, None);
+    assert_eq!(order, Ok(Ok(Some(Object::Int(1)))));
+    let seen = access_static(&jvm, "Derived", "seen", FieldType::Int, None);
+    assert_eq!(seen, Ok(Ok(Some(Object::Int(12)))));
+
+    // a fresh virtual machine, where initializing Derived initializes Base first
+    let jvm = sample_jvm("sample/statics");
+    let seen = access_static(&jvm, "Derived", "seen", FieldType::Int, None);
+    assert_eq!(seen, Ok(Ok(Some(Object::Int(12)))));
+
+    let jvm = sample_jvm("sample/statics");
+    let a = access_static(&jvm, "Cycle", "a", FieldType::Int, None);
+    assert_eq!(a, Ok(Ok(Some(Object::Int(11)))));
+    let b = access_static(&jvm, "Other", "b", FieldType::Int, None);
+    assert_eq!(b, Ok(Ok(Some(Object::Int(10)))));
+}
+
+#[test]
+fn test_initialization_errors() {
+    let jvm = sample_jvm("sample/statics");
+    let Ok(Err(exception)) = access_static(&jvm, "Broken", "value", FieldType::Int, None) else {
+        panic!("the initialization of Broken should throw");
+    };
+    assert_eq!(
+        exception.get_class().get_name(),
+        "java/lang/ExceptionInInitializerError"
+    );
+    assert_eq!(
+        get_exception_message(&exception).unwrap().as_deref(),
+        Some("java.lang.ArithmeticException: / by zero")
+    );
+
+    // the class can't be used anymore, even by new
+    let Ok(Err(exception)) = new_instance(&jvm, "Broken") else {
+        panic!("Broken should be erroneous");
+    };
+    assert_eq!(
+        exception.get_class().get_name(),
+        "java/lang/NoClassDefFoundError"
+    );
+    assert_eq!(
+        get_exception_message(&exception).unwrap().as_deref(),
+        Some("Could not initialize class Broken")
+    );
+
+    // the error of the super class is thrown as is
+    let jvm = sample_jvm("sample/statics");
+    let Ok(Err(exception)) = new_instance(&jvm, "BrokenChild") else {
+        panic!("the initialization of Broken should throw");
+    };
+    assert_eq!(
+        exception.get_class().get_name(),
+        "java/lang/ExceptionInInitializerError"
+    );
+    let other = access_static(&jvm, "BrokenChild", "other", FieldType::Int, None);
+    let Ok(Err(exception)) = other else {
+        panic!("BrokenChild should be erroneous");
+    };
+    assert_eq!(
+        get_exception_message(&exception).unwrap().as_deref(),
+        Some("Could not initialize class BrokenChild")
+    );
+}
+
+fn call_static(
+    jvm: &Jvm,
+    class_name: &str,
+    name: &str,
+    descriptor: &str,
+    arguments: &[Object],
+) -> MethodCallResult {
+    let class = jvm.class_loader().load_class(class_name).unwrap();
+    let method = class.find_method(name, descriptor).unwrap();
+    let mut stack = Stack::new(arguments.len() * 2);
+    for argument in arguments {
+        stack.push(argument.clone());
+    }
+    method.execute(jvm, &mut stack)
+}
+
+#[test]
+fn test_invocations() {
+    let jvm = sample_jvm("sample/invocation");
+    let call =
+        |name, descriptor, argument| call_static(&jvm, "Calls", name, descriptor, &[argument]);
+    assert_eq!(
+        call("callMix", "(I)J", Object::Int(7)),
+        Ok(Ok(Some(Object::Long(7234))))
+    );
+    assert_eq!(
+        call("factorial", "(I)I", Object::Int(5)),
+        Ok(Ok(Some(Object::Int(120))))
+    );
+    // virtual dispatch, with a super call from Bird
+    assert_eq!(
+        call("describe", "(Z)I", Object::Int(1)),
+        Ok(Ok(Some(Object::Int(41))))
+    );
+    assert_eq!(
+        call("describe", "(Z)I", Object::Int(0)),
+        Ok(Ok(Some(Object::Int(42))))
+    );
+    // a default method, and a static interface method
+    assert_eq!(
+        call("area", "(I)I", Object::Int(3)),
+        Ok(Ok(Some(Object::Int(19))))
+    );
+    assert_eq!(
+        call("both", "(I)I", Object::Int(0)),
+        Ok(Ok(Some(Object::Int(21))))
+    );
+    assert_eq!(
+        call("safeDivide", "(I)I", Object::Int(5)),
+        Ok(Ok(Some(Object::Int(20))))
+    );
+    assert_eq!(
+        call("safeDivide", "(I)I", Object::Int(0)),
+        Ok(Ok(Some(Object::Int(-1))))
+    );
+}
+
+#[test]
+fn test_invocation_errors() {
+    let jvm = sample_jvm("sample/invocation");
+    let Ok(Err(exception)) = call_static(&jvm, "Calls", "divide", "(I)I", &[Object::Int(0)]) else {
+        panic!("the exception of the callee should be thrown to the caller");
+    };
+    assert_eq!(
+        exception.get_class().get_name(),
+        "java/lang/ArithmeticException"
+    );
+
+    let argument = Object::Reference(None);
+    let Ok(Err(exception)) = call_static(&jvm, "Calls", "nullReceiver", "(LAnimal;)I", &[argument])
+    else {
+        panic!("a null receiver should throw");
+    };
+    assert_eq!(
+        exception.get_class().get_name(),
+        "java/lang/NullPointerException"
+    );
+
+    // HotSpot throws an AbstractMethodError there, but the specification asks for this one
+    let Ok(Err(exception)) = call_static(&jvm, "Calls", "conflict", "(I)I", &[Object::Int(0)])
+    else {
+        panic!("the default methods of Left and Third should conflict");
+    };
+    assert_eq!(
+        exception.get_class().get_name(),
+        "java/lang/IncompatibleClassChangeError"
+    );
+    assert_eq!(
+        get_exception_message(&exception).unwrap().as_deref(),
+        Some("Conflicting default methods: Left.which Third.which")
+    );
+}
+
+/// The class and the name of the methods in the vtable
+fn vtable_methods(class: &Class) -> Vec<(String, String)> {
+    class
+        .get_vtable()
+        .iter()
+        .map(|method| {
+            let class = method.get_class().unwrap();
+            (class.get_name().to_string(), method.get_name().to_string())
+        })
+        .collect()
+}
+
+#[test]
+fn test_dispatch_tables() {
+    let jvm = sample_jvm("sample/invocation");
+    let owned = |methods: &[(&str, &str)]| -> Vec<(String, String)> {
+        methods
+            .iter()
+            .map(|(class, name)| (class.to_string(), name.to_string()))
+            .collect()
+    };
+    let vtable = |name| vtable_methods(&jvm.class_loader().load_class(name).unwrap());
+    // the constructors are not dispatched, and the overriding methods reuse the inherited slots
+    let object_methods = [
+        ("java/lang/Object", "getClass"),
+        ("java/lang/Object", "hashCode"),
+        ("java/lang/Object", "equals"),
+        ("java/lang/Object", "clone"),
+        ("java/lang/Object", "toString"),
+        ("java/lang/Object", "notify"),
+        ("java/lang/Object", "notifyAll"),
+        ("java/lang/Object", "wait"),
+        ("java/lang/Object", "wait"),
+    ];
+    assert_eq!(
+        vtable("Animal"),
+        owned(
+            &[
+                &object_methods[..],
+                &[
+                    ("Animal", "legs"),
+                    ("Animal", "sound"),
+                    ("Animal", "describe"),
+                ],
+            ]
+            .concat()
+        )
+    );
+    assert_eq!(
+        vtable("Bird"),
+        owned(
+            &[
+                &object_methods[..],
+                &[("Bird", "legs"), ("Bird", "sound"), ("Animal", "describe")],
+            ]
+            .concat()
+        )
+    );
+
+    let square = jvm.class_loader().load_class("Square").unwrap();
+    let [entry] = square.get_itable() else {
+        panic!("Square only implements Shape");
+    };
+    assert_eq!(entry.get_interface().get_name(), "Shape");
+    let selected_class = |index| {
+        let Some(Ok(Some(method))) = entry.get_method(index) else {
+            panic!("the method {} of Shape should be implemented", index);
+        };
+        method.get_class().unwrap().get_name().to_string()
+    };
+    assert_eq!(selected_class(0), "Square");
+    assert_eq!(selected_class(1), "Shape");
+
+    let conflict = jvm.class_loader().load_class("Conflict").unwrap();
+    let conflicting = conflict
+        .get_itable()
+        .iter()
+        .all(|entry| matches!(entry.get_method(0), Some(Err(_))));
+    assert!(conflicting);
+}
+
+#[test]
+fn test_package_private_dispatch() {
+    let jvm = sample_jvm("sample/dispatch");
+    let middle = jvm.class_loader().load_class("second/Middle").unwrap();
+    // Middle.id can't override Base.id from another package, so it takes a new slot
+    assert_eq!(
+        vtable_methods(&middle)
+            .iter()
+            .filter(|(_, name)| name == "id")
+            .count(),
+        2
+    );
+    let call = |name| call_static(&jvm, "Calls", name, "(I)I", &[Object::Int(0)]);
+    assert_eq!(call("middle"), Ok(Ok(Some(Object::Int(12)))));
+    assert_eq!(call("leaf"), Ok(Ok(Some(Object::Int(32)))));
+}
+
+#[test]
+fn test_final_method_override() {
+    let jvm = sample_jvm("sample/dispatch");
+    let error = jvm.class_loader().load_class("Breaker").unwrap_err();
+    let ClassLoadingError::OverridesFinalMethod { class_name, method } = error else {
+        panic!("Breaker should not be linked, got {:?}", error);
+    };
+    assert_eq!(class_name, "Breaker");
+    assert_eq!(method, "Sealed.value()I");
+}
+
+fn string_argument(jvm: &Jvm, text: &str) -> Object {
+    Object::Reference(Some(new_string(jvm, text).unwrap().unwrap()))
+}
+
+/// The text of the String returned by a method, None if it returned null
+fn returned_string(result: MethodCallResult) -> Option<String> {
+    match result {
+        Ok(Ok(Some(Object::Reference(Some(string))))) => Some(read_string(&string).unwrap()),
+        Ok(Ok(Some(Object::Reference(None)))) => None,
+        _ => panic!("should return a String: {:?}", result),
+    }
+}
+
+#[test]
+fn test_class_library() {
+    let jvm = sample_jvm("sample/natives");
+    let call = |name, descriptor, arguments: &[Object]| {
+        call_static(&jvm, "Library", name, descriptor, arguments)
+    };
+
+    let arguments = [
+        Object::Int(42),
+        Object::Long(-7),
+        Object::Double(1e10),
+        Object::Int(1),
+    ];
+    assert_eq!(
+        returned_string(call("build", "(IJDZ)Ljava/lang/String;", &arguments)).as_deref(),
+        Some("42,-7,1.0E10,true")
+    );
+    let arguments = [
+        Object::Int(12),
+        Object::Int('x' as i32),
+        Object::Reference(None),
+    ];
+    let printed = call(
+        "print",
+        "(ICLjava/lang/Object;)Ljava/lang/String;",
+        &arguments,
+    );
+    assert_eq!(returned_string(printed).as_deref(), Some("12xnull\n"));
+
+    // the small values are cached by valueOf
+    assert_eq!(
+        call("boxing", "(I)I", &[Object::Int(100)]),
+        Ok(Ok(Some(Object::Int(3))))
+    );
+    assert_eq!(
+        call("boxing", "(I)I", &[Object::Int(1000)]),
+        Ok(Ok(Some(Object::Int(2))))
+    );
+    assert_eq!(
+        call("longBoxing", "(J)J", &[Object::Long(5)]),
+        Ok(Ok(Some(Object::Long(10))))
+    );
+    assert_eq!(
+        call("math", "(D)D", &[Object::Double(16.0)]),
+        Ok(Ok(Some(Object::Double(1030.0))))
+    );
+
+    // the ranges of the same array overlap
+    assert_eq!(call("copy", "()I", &[]), Ok(Ok(Some(Object::Int(11234)))));
+    let Ok(Err(exception)) = call("copyOutOfBounds", "()I", &[]) else {
+        panic!("copyOutOfBounds should throw");
+    };
+    assert_eq!(
+        describe_exception(&exception).unwrap(),
+        "java.lang.ArrayIndexOutOfBoundsException: \
+         arraycopy: last destination index 4 out of bounds for int[3]"
+    );
+
+    let number = string_argument(&jvm, "-123");
+    assert_eq!(
+        call("parse", "(Ljava/lang/String;)I", &[number]),
+        Ok(Ok(Some(Object::Int(-123))))
+    );
+    let number = string_argument(&jvm, "12a");
+    assert_eq!(
+        returned_string(call(
+            "parseError",
+            "(Ljava/lang/String;)Ljava/lang/String;",
+            &[number]
+        ))
+        .as_deref(),
+        Some("java.lang.NumberFormatException: For input string: \"12a\"")
+    );
+
+    let describe = |object| {
+        returned_string(call(
+            "describe",
+            "(Ljava/lang/Object;)Ljava/lang/String;",
+            &[object],
+        ))
+    };
+    let object_class = jvm.class_loader().load_class("java/lang/Object").unwrap();
+    let object = Reference::new(object_class);
+    let description = describe(Object::Reference(Some(object.clone()))).unwrap();
+    assert_eq!(
+        description,
+        format!("java.lang.Object@{:x}", object.identity_hash_code())
+    );
+    assert_eq!(describe(Object::Reference(None)).as_deref(), Some("null"));
+
+    let hello = string_argument(&jvm, "hello");
+    assert_eq!(
+        call(
+            "stringHash",
+            "(Ljava/lang/String;)I",
+            std::slice::from_ref(&hello)
+        ),
+        Ok(Ok(Some(Object::Int(99162322))))
+    );
+    assert_eq!(
+        call("sameText", "(Ljava/lang/String;)Z", &[hello]),
+        Ok(Ok(Some(Object::Int(1))))
+    );
+
+    let property = |key| {
+        let key = string_argument(&jvm, key);
+        returned_string(call(
+            "property",
+            "(Ljava/lang/String;)Ljava/lang/String;",
+            &[key],
+        ))
+    };
+    assert_eq!(property("user.name").as_deref(), Some("duke"));
+    assert_eq!(property("user.home"), None);
+
+    let message = string_argument(&jvm, "broken");
+    let Ok(Ok(Some(Object::Reference(Some(throwable))))) = call(
+        "chained",
+        "(Ljava/lang/String;)Ljava/lang/Throwable;",
+        &[message],
+    ) else {
+        panic!("chained should return the exception");
+    };
+    assert_eq!(
+        describe_exception(&throwable).unwrap(),
+        "java.lang.IllegalStateException: broken"
+    );
+}
+
+#[test]
+fn test_native_methods() {
+    let mut jvm = sample_jvm("sample/natives");
+    let Ok(Err(exception)) = call_static(&jvm, "Library", "missing", "()I", &[]) else {
+        panic!("missing is not registered");
+    };
+    assert_eq!(
+        describe_exception(&exception).unwrap(),
+        "java.lang.UnsatisfiedLinkError: Library.missing()I"
+    );
+
+    // the long argument doesn't keep its padding
+    jvm.register_native("Library", "twice", "(JI)J", |_, args| match args {
+        [Object::Long(l), Object::Int(i)] => Ok(Ok(Some(Object::Long(l * *i as i64)))),
+        _ => panic!("unexpected arguments {:?}", args),
+    });
+    assert_eq!(
+        call_static(&jvm, "Library", "callTwice", "(J)J", &[Object::Long(5)]),
+        Ok(Ok(Some(Object::Long(16))))
+    );
+}
+
+#[test]
+fn test_string_constants() {
+    let jvm = sample_jvm("sample/strings");
+    let call = |name, descriptor| call_static(&jvm, "Strings", name, descriptor, &[]);
+    let literal = || match call("literal", "()Ljava/lang/String;") {
+        Ok(Ok(Some(Object::Reference(Some(string))))) => string,
+        result => panic!("literal should return a String: {:?}", result),
+    };
+
+    let hello = literal();
+    assert_eq!(read_string(&hello).unwrap(), "hello");
+    assert_eq!(literal(), hello);
+    assert_eq!(intern_string(&jvm, "hello").unwrap().unwrap(), hello);
+    assert_eq!(call("sameLiteral", "()Z"), Ok(Ok(Some(Object::Int(1)))));
+    assert_eq!(call("interned", "()Z"), Ok(Ok(Some(Object::Int(1)))));
+
+    // the String constant values are the interned literals
+    let class = jvm.class_loader().load_class("Strings").unwrap();
+    let descriptor = FieldType::Object("java/lang/String".to_string());
+    let greeting = class.find_field("GREETING", &descriptor).unwrap();
+    assert_eq!(
+        class.get_static(greeting.get_slot()),
+        Ok(Object::Reference(Some(hello)))
+    );
+
+    // decoded from modified UTF-8
+    assert_eq!(call("nulCharacter", "()I"), Ok(Ok(Some(Object::Int(300)))));
+    assert_eq!(
+        call("supplementary", "()I"),
+        Ok(Ok(Some(Object::Int(200000 + 0xDE00))))
+    );
+    let Ok(Ok(Some(Object::Reference(Some(lone))))) = call("loneSurrogate", "()Ljava/lang/String;")
+    else {
+        panic!("loneSurrogate should return a String");
+    };
+    assert_eq!(read_utf16_string(&lone).unwrap(), ['a' as u16, 0xD83D]);
+    assert_eq!(
+        returned_string(call("accents", "()Ljava/lang/String;")).as_deref(),
+        Some("héllo €")
+    );
+    assert_eq!(call("numbers", "()I"), Ok(Ok(Some(Object::Int(123461)))));
+
+    let Ok(Ok(Some(Object::Reference(Some(mirror))))) = call("classLiteral", "()Ljava/lang/Class;")
+    else {
+        panic!("classLiteral should return a Class");
+    };
+    assert_eq!(mirror.get_class().get_name(), "java/lang/Class");
+    assert!(Arc::ptr_eq(&mirror.get_mirrored_class().unwrap(), &class));
+    assert_eq!(call("sameClass", "()Z"), Ok(Ok(Some(Object::Int(1)))));
+    assert_eq!(
+        returned_string(call("className", "()Ljava/lang/String;")).as_deref(),
+        Some("interface Literals$Namedjava.lang.String")
+    );
+}
+
+#[test]
+fn test_multi_dimensional_arrays() {
+    let jvm = sample_jvm("sample/arrays");
+    let call = |name, descriptor, arguments: &[Object]| {
+        call_static(&jvm, "Arrays", name, descriptor, arguments)
+    };
+
+    assert_eq!(call("matrix", "()I", &[]), Ok(Ok(Some(Object::Int(347)))));
+    assert_eq!(call("partial", "()I", &[]), Ok(Ok(Some(Object::Int(3)))));
+    assert_eq!(call("jagged", "()I", &[]), Ok(Ok(Some(Object::Int(15)))));
+    assert_eq!(
+        returned_string(call("strings", "()Ljava/lang/String;", &[])).as_deref(),
+        Some("xnull")
+    );
+    let Ok(Err(exception)) = call("negative", "(I)I", &[Object::Int(-1)]) else {
+        panic!("negative should throw");
+    };
+    assert_eq!(
+        describe_exception(&exception).unwrap(),
+        "java.lang.NegativeArraySizeException: -1"
+    );
+    assert_eq!(
+        returned_string(call("arrayStore", "()Ljava/lang/String;", &[])).as_deref(),
+        Some("java.lang.Integer")
+    );
+
+    // the array classes are created with their component class
+    let matrix_class = jvm.class_loader().get_loaded_class("[[I").unwrap();
+    assert_eq!(
+        matrix_class.get_superclass().unwrap().get_name(),
+        "java/lang/Object"
+    );
+    assert!(jvm.class_loader().get_loaded_class("[I").is_some());
+    assert!(jvm.class_loader().get_loaded_class("[[J").is_some());
+
+    assert_eq!(
+        call("bytes", "(I)I", &[Object::Int(200)]),
+        Ok(Ok(Some(Object::Int(-56))))
+    );
+    assert_eq!(
+        call("chars", "(I)I", &[Object::Int(-1)]),
+        Ok(Ok(Some(Object::Int(0xFFFF))))
+    );
+    assert_eq!(
+        call("booleans", "(Z)I", &[Object::Int(1)]),
+        Ok(Ok(Some(Object::Int(1))))
+    );
+}
+
+#[test]
+fn test_array_classes() {
+    let jvm = sample_jvm("sample/arrays");
+    let call = |name, descriptor| call_static(&jvm, "ArrayClasses", name, descriptor, &[]);
+
+    assert_eq!(call("instances", "()I"), Ok(Ok(Some(Object::Int(125)))));
+    assert_eq!(
+        returned_string(call("cast", "()Ljava/lang/String;")).as_deref(),
+        Some("class [Ljava.lang.Object; cannot be cast to class [Ljava.lang.String;")
+    );
+    assert_eq!(call("covariance", "()I"), Ok(Ok(Some(Object::Int(3)))));
+    assert_eq!(
+        returned_string(call("names", "()Ljava/lang/String;")).as_deref(),
+        Some("[Ljava.lang.String; [[I true false")
+    );
+    assert_eq!(call("clones", "()I"), Ok(Ok(Some(Object::Int(31)))));
+    assert_eq!(
+        returned_string(call("notCloneable", "()Ljava/lang/String;")).as_deref(),
+        Some("ArrayClasses$Plain")
+    );
+
+    // the arrays of primitives are only subclasses of Object, Cloneable and Serializable
+    let load = |name| jvm.class_loader().load_class(name).unwrap();
+    let ints = load("[I");
+    assert!(ints.is_array());
+    assert!(matches!(ints.get_component_type(), Some(ArrayType::Int)));
+    assert_eq!(
+        ints.get_interfaces()
+            .iter()
+            .map(|interface| interface.get_name())
+            .collect::<Vec<_>>(),
+        ["java/lang/Cloneable", "java/io/Serializable"]
+    );
+    assert!(ints.is_subclass(&load("java/io/Serializable")));
+    assert!(!ints.is_subclass(&load("[J")));
+    assert!(!ints.is_subclass(&load("[Ljava/lang/Object;")));
+    assert!(load("[[I").is_subclass(&load("[Ljava/lang/Cloneable;")));
+    assert!(!load("[Ljava/lang/Object;").is_subclass(&load("[[I")));
+}
+
+#[test]
+fn test_interface_subtypes() {
+    let jvm = sample_jvm("sample/subtypes");
+    let call = |name, descriptor| call_static(&jvm, "Subtypes", name, descriptor, &[]);
+
+    assert_eq!(call("instances", "()I"), Ok(Ok(Some(Object::Int(55)))));
+    assert_eq!(
+        returned_string(call("name", "()Ljava/lang/String;")).as_deref(),
+        Some("Rex")
+    );
+    assert_eq!(
+        returned_string(call("cast", "()Ljava/lang/String;")).as_deref(),
+        Some("class Animal cannot be cast to class Pet")
+    );
+
+    // the super interfaces are inherited from the super classes and the other interfaces
+    let load = |name| jvm.class_loader().load_class(name).unwrap();
+    let (puppy, pet, named) = (load("Puppy"), load("Pet"), load("Named"));
+    assert!(puppy.implements(&named));
+    assert!(puppy.is_subclass(&pet));
+    assert!(pet.is_subclass(&named));
+    assert!(pet.is_subclass(&load("java/lang/Object")));
+    assert!(!named.is_subclass(&pet));
+    assert!(!load("Animal").is_subclass(&named));
+}
+
+#[test]
+fn test_garbage_collection() {
+    let mut jvm = sample_jvm("sample/gc");
+    let max_size = 4 * 1024 * 1024;
+    jvm.heap_mut().set_max_size(max_size);
+    let call = |name, descriptor, arguments: &[Object]| {
+        call_static(&jvm, "Cycles", name, descriptor, arguments)
+    };
+
+    // the cycles would not fit in the heap if they were not collected
+    assert_eq!(
+        call("pairs", "(I)I", &[Object::Int(20000)]),
+        Ok(Ok(Some(Object::Int(40000))))
+    );
+    let statistics = jvm.heap().get_statistics().unwrap();
+    assert!(statistics.collections > 0);
+    assert!(statistics.freed_objects > 20000);
+
+    assert_eq!(call("keep", "()I", &[]), Ok(Ok(Some(Object::Int(16)))));
+    assert_eq!(
+        returned_string(call("exhaust", "()Ljava/lang/String;", &[])).as_deref(),
+        Some("Java heap space")
+    );
+    // the blocks are freed once the error is caught
+    jvm.heap().collect_garbage(GcCause::SystemGc).unwrap();
+    assert!(jvm.heap().get_size().unwrap() < max_size / 4);
+    assert_eq!(
+        call("pairs", "(I)I", &[Object::Int(1000)]),
+        Ok(Ok(Some(Object::Int(2000))))
+    );
+
+    // the strings are allocated in the heap too
+    let Ok(Err(error)) = new_string(&jvm, &"x".repeat(max_size)) else {
+        panic!("a string larger than the heap should not be allocated");
+    };
+    assert_eq!(
+        describe_exception(&error).unwrap(),
+        "java.lang.OutOfMemoryError: Java heap space"
+    );
+}
+
+#[test]
+fn test_threads() {
+    let jvm = sample_jvm("sample/threads").into_shared();
+    let call = |name, descriptor, arguments: &[Object]| {
+        call_static(&jvm, "Threads", name, descriptor, arguments)
+    };
+
+    assert_eq!(
+        call("sum", "(II)J", &[Object::Int(8), Object::Int(1000)]),
+        Ok(Ok(Some(Object::Long(8 * 500500))))
+    );
+    assert_eq!(
+        returned_string(call("names", "()Ljava/lang/String;", &[])).as_deref(),
+        Some("main worker Thread- Thread[worker,5,main]")
+    );
+    assert_eq!(
+        returned_string(call("interruptSleep", "()Ljava/lang/String;", &[])).as_deref(),
+        Some("sleep interrupted")
+    );
+    assert_eq!(
+        returned_string(call("uncaught", "()Ljava/lang/String;", &[])).as_deref(),
+        Some("thrower: boom")
+    );
+    assert_eq!(
+        returned_string(call("lifecycle", "()Ljava/lang/String;", &[])).as_deref(),
+        Some("false false")
+    );
+    assert_eq!(
+        returned_string(call("selfInterrupt", "()Ljava/lang/String;", &[])).as_deref(),
+        Some("true true false")
+    );
+    jvm.threads().wait_for_non_daemon_threads().unwrap();
+}
+
+#[test]
+fn test_monitors() {
+    let jvm = sample_jvm("sample/monitors").into_shared();
+    let call = |name, descriptor, arguments: &[Object]| {
+        call_static(&jvm, "Monitors", name, descriptor, arguments)
+    };
+
+    assert_eq!(
+        call("count", "(II)I", &[Object::Int(8), Object::Int(500)]),
+        Ok(Ok(Some(Object::Int(4000))))
+    );
+    assert_eq!(call("reentrant", "()I", &[]), Ok(Ok(Some(Object::Int(10)))));
+    assert_eq!(
+        call("releasedOnThrow", "()Z", &[]),
+        Ok(Ok(Some(Object::Int(1))))
+    );
+    assert_eq!(
+        returned_string(call("notOwner", "()Ljava/lang/String;", &[])).as_deref(),
+        Some("current thread is not owner")
+    );
+    assert_eq!(
+        call("produce", "(I)I", &[Object::Int(100)]),
+        Ok(Ok(Some(Object::Int(5050))))
+    );
+    assert_eq!(call("timedWait", "()Z", &[]), Ok(Ok(Some(Object::Int(1)))));
+    assert_eq!(
+        returned_string(call("interruptedWait", "()Ljava/lang/String;", &[])).as_deref(),
+        Some("interrupted")
+    );
+}
+
+#[test]
+fn test_lambdas() {
+    let jvm = sample_jvm("sample/lambdas");
+    let call = |name, descriptor, arguments: &[Object]| {
+        call_static(&jvm, "Lambdas", name, descriptor, arguments)
+    };
+    let string = |text| string_argument(&jvm, text);
+
+    assert_eq!(call("runnable", "()I", &[]), Ok(Ok(Some(Object::Int(10)))));
+    assert_eq!(
+        call("function", "(I)I", &[Object::Int(20)]),
+        Ok(Ok(Some(Object::Int(41))))
+    );
+    assert_eq!(
+        call(
+            "captureWide",
+            "(JID)J",
+            &[Object::Long(1 << 40), Object::Int(2), Object::Double(3.5)]
+        ),
+        Ok(Ok(Some(Object::Long((1 << 40) + 5))))
+    );
+    assert_eq!(
+        call("staticReference", "(Ljava/lang/String;)I", &[string("123")]),
+        Ok(Ok(Some(Object::Int(123))))
+    );
+    assert_eq!(
+        returned_string(call(
+            "boundReference",
+            "(Ljava/lang/String;Ljava/lang/String;)Ljava/lang/String;",
+            &[string("lamb"), string("da")]
+        ))
+        .as_deref(),
+        Some("lambda")
+    );
+    assert_eq!(
+        call(
+            "unboundReference",
+            "(Ljava/lang/String;)I",
+            &[string("hello")]
+        ),
+        Ok(Ok(Some(Object::Int(5))))
+    );
+    assert_eq!(
+        call("constructorReference", "()I", &[]),
+        Ok(Ok(Some(Object::Int(42))))
+    );
+    assert_eq!(
+        call("primitives", "(II)I", &[Object::Int(-3), Object::Int(7)]),
+        Ok(Ok(Some(Object::Int(7))))
+    );
+    assert_eq!(
+        call("capturedThis", "()I", &[]),
+        Ok(Ok(Some(Object::Int(6))))
+    );
+    assert_eq!(
+        call("predicates", "(I)Z", &[Object::Int(7)]),
+        Ok(Ok(Some(Object::Int(1))))
+    );
+    assert_eq!(
+        call("predicates", "(I)Z", &[Object::Int(8)]),
+        Ok(Ok(Some(Object::Int(0))))
+    );
+    assert_eq!(
+        call("identity", "(Ljava/lang/String;)Z", &[string("same")]),
+        Ok(Ok(Some(Object::Int(1))))
+    );
+    assert_eq!(
+        returned_string(call(
+            "biFunction",
+            "(Ljava/lang/String;Ljava/lang/String;)Ljava/lang/String;",
+            &[string("bi"), string("function")]
+        ))
+        .as_deref(),
+        Some("bifunction!")
+    );
+    assert_eq!(call("consumers", "()I", &[]), Ok(Ok(Some(Object::Int(42)))));
+    assert_eq!(call("marker", "()Z", &[]), Ok(Ok(Some(Object::Int(1)))));
+    assert_eq!(
+        returned_string(call(
+            "bridge",
+            "(Ljava/lang/String;)Ljava/lang/String;",
+            &[string("bridged")]
+        ))
+        .as_deref(),
+        Some("bridged!")
+    );
+    assert_eq!(
+        call("distinctCaptures", "()I", &[]),
+        Ok(Ok(Some(Object::Int(30))))
+    );
+}
+
+#[test]
+fn test_string_concat() {
+    let jvm = sample_jvm("sample/concat");
+    let call = |name, descriptor, arguments: &[Object]| {
+        returned_string(call_static(&jvm, "Concat", name, descriptor, arguments))
+    };
+
+    assert_eq!(
+        call("literals", "(I)Ljava/lang/String;", &[Object::Int(-7)]).as_deref(),
+        Some("a-7b")
+    );
+    let primitives = [
+        Object::Int(-8),
+        Object::Int(300),
+        Object::Int('x' as i32),
+        Object::Int(42),
+        Object::Long(i64::MIN),
+        Object::Float(2.5),
+        Object::Double(-0.125),
+        Object::Int(1),
+    ];
+    assert_eq!(
+        call("primitives", "(BSCIJFDZ)Ljava/lang/String;", &primitives).as_deref(),
+        Some("-8,300,x,42,-9223372036854775808,2.5,-0.125,true")
+    );
+    assert_eq!(
+        call(
+            "chars",
+            "(CC)Ljava/lang/String;",
+            &[Object::Int('o' as i32), Object::Int('k' as i32)]
+        )
+        .as_deref(),
+        Some("ok")
+    );
+    assert_eq!(
+        call("floats", "()Ljava/lang/String;", &[]).as_deref(),
+        Some("1.0 1.0E10 0.001 NaN -Infinity")
+    );
+    assert_eq!(
+        call("nulls", "()Ljava/lang/String;", &[]).as_deref(),
+        Some("null/null")
+    );
+    assert_eq!(
+        call("objects", "()Ljava/lang/String;", &[]).as_deref(),
+        Some("first and second null")
+    );
+    assert_eq!(
+        call("tags", "(I)Ljava/lang/String;", &[Object::Int(5)]).as_deref(),
+        Some("\u{1}5\u{2}")
+    );
+    assert_eq!(
+        call("unicode", "(C)Ljava/lang/String;", &[Object::Int(0xfc)]).as_deref(),
+        Some("café ü 😀")
+    );
+    let lone = call_static(
+        &jvm,
+        "Concat",
+        "loneSurrogates",
+        "(I)Ljava/lang/String;",
+        &[Object::Int(5)],
+    );
+    let Ok(Ok(Some(Object::Reference(Some(lone))))) = lone else {
+        panic!("loneSurrogates should return a String");
+    };
+    assert_eq!(
+        read_utf16_string(&lone).unwrap(),
+        ['a' as u16, 0xD83D, '5' as u16, 0x1, 0xDE00]
+    );
+    assert_eq!(
+        call_static(&jvm, "Concat", "array", "()Z", &[]),
+        Ok(Ok(Some(Object::Int(1))))
+    );
+    assert_eq!(
+        call("loop", "(I)Ljava/lang/String;", &[Object::Int(12)]).as_deref(),
+        Some("01234567891011")
+    );
+    let arguments = [
+        string_argument(&jvm, "a"),
+        Object::Int(2),
+        Object::Int('c' as i32),
+    ];
+    assert_eq!(
+        call(
+            "withoutConstants",
+            "(Ljava/lang/String;IC)Ljava/lang/String;",
+            &arguments
+        )
+        .as_deref(),
+        Some("a-2-c-true")
+    );
+}
+
+// Condy.class is made with ASM, as javac doesn't emit dynamic constants, see Condy.javap.txt
+#[test]
+fn test_dynamic_constants() {
+    let jvm = sample_jvm("sample/condy");
+    let call = |name, descriptor| call_static(&jvm, "Condy", name, descriptor, &[]);
+    let calls = || call_static(&jvm, "Bootstraps", "calls", "()I", &[]);
+
+    // every constant is computed once
+    for _ in 0..2 {
+        assert_eq!(
+            returned_string(call("describe", "()Ljava/lang/String;")).as_deref(),
+            Some("hello greeting in Condy as java.lang.String")
+        );
+        assert_eq!(call("boxedLong", "()J"), Ok(Ok(Some(Object::Long(42)))));
+        assert_eq!(call("half", "()D"), Ok(Ok(Some(Object::Double(2.5)))));
+    }
+    assert_eq!(calls(), Ok(Ok(Some(Object::Int(3)))));
+
+    // the greeting given to the bootstrap method of nested is already resolved
+    assert_eq!(
+        returned_string(call("nested", "()Ljava/lang/String;")).as_deref(),
+        Some("hello greeting in Condy as java.lang.String")
+    );
+    assert_eq!(calls(), Ok(Ok(Some(Object::Int(4)))));
+
+    // a failed resolution throws the same error again, without running the bootstrap method
+    let Ok(Err(exception)) = call("failing", "()Ljava/lang/Object;") else {
+        panic!("the bootstrap method of failing should throw");
+    };
+    assert_eq!(
+        describe_exception(&exception).unwrap(),
+        "java.lang.BootstrapMethodError: java.lang.IllegalStateException: failing"
+    );
+    assert_eq!(call("failing", "()Ljava/lang/Object;"), Ok(Err(exception)));
+    assert_eq!(calls(), Ok(Ok(Some(Object::Int(5)))));
+
+    let Ok(Err(exception)) = call("mismatch", "()I") else {
+        panic!("a String can't be unboxed to an int");
+    };
+    assert_eq!(
+        describe_exception(&exception).unwrap(),
+        "java.lang.BootstrapMethodError: java.lang.ClassCastException: \
+         class java.lang.String cannot be cast to class java.lang.Integer"
+    );
+
+    // the trailing static arguments of the varargs bootstrap methods are given in an array
+    assert_eq!(
+        returned_string(call("joined", "()Ljava/lang/String;")).as_deref(),
+        Some("joined:1:two")
+    );
+    assert_eq!(call("sum", "()I"), Ok(Ok(Some(Object::Int(6)))));
+    assert_eq!(call("emptySum", "()I"), Ok(Ok(Some(Object::Int(0)))));
+}
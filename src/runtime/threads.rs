@@ -0,0 +1,332 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    thread::{self, ThreadId},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    parser::descriptor::FieldType,
+    rethrow_exception,
+    runtime_types::{
+        new_string, read_string, throw_exception, ExecResult, InternalError, MethodCallResult,
+        Object, Reference, Stack, OUT_OF_MEMORY_ERROR, STRING_CLASS, THREAD_STACK_SIZE,
+    },
+};
+
+use super::{jvm::Jvm, monitors::Monitor};
+
+pub const THREAD_CLASS: &str = "java/lang/Thread";
+pub const MAIN_THREAD_NAME: &str = "main";
+const NAME_FIELD: &str = "name";
+const DAEMON_FIELD: &str = "daemon";
+const THROWABLE_DESCRIPTOR: &str = "(Ljava/lang/Throwable;)V";
+
+#[derive(Debug, Default)]
+struct ThreadsState {
+    // the java/lang/Thread of every host thread running Java code
+    current: HashMap<ThreadId, Reference>,
+    // the threads that have started and not ended yet, by the address of their java/lang/Thread
+    alive: HashMap<usize, Reference>,
+    // the alive threads that have been interrupted, by address
+    interrupted: HashSet<usize>,
+    // the monitor each thread waits on in Object.wait, woken up when it is interrupted
+    waiting: HashMap<usize, Arc<Monitor>>,
+    // the alive threads the virtual machine waits for before exiting, main aside
+    non_daemon: usize,
+}
+
+/// The state of the java/lang/Thread objects, kept by the virtual machine
+///
+/// Every started thread is run by a host thread. The threads waiting in join or sleep
+/// are woken up when a thread ends or is interrupted.
+#[derive(Debug, Default)]
+pub struct Threads {
+    state: Mutex<ThreadsState>,
+    changed: Condvar,
+    // the number of the default thread names, like Thread-0
+    next_number: AtomicI32,
+}
+
+// the slot of a field declared by java/lang/Thread
+fn thread_field_slot(
+    thread: &Reference,
+    name: &str,
+    descriptor: &FieldType,
+) -> Result<usize, InternalError> {
+    thread
+        .get_class()
+        .resolve_field(name, descriptor)
+        .map(|(_, field)| field.get_slot())
+        .ok_or(InternalError::WrongType)
+}
+
+fn is_daemon(thread: &Reference) -> Result<bool, InternalError> {
+    let slot = thread_field_slot(thread, DAEMON_FIELD, &FieldType::Boolean)?;
+    match thread.get_field(slot)? {
+        Object::Int(daemon) => Ok(daemon != 0),
+        _ => Err(InternalError::WrongType),
+    }
+}
+
+/// The name of a java/lang/Thread, as set by the program
+pub fn get_thread_name(thread: &Reference) -> Result<String, InternalError> {
+    let descriptor = FieldType::Object(STRING_CLASS.to_string());
+    let slot = thread_field_slot(thread, NAME_FIELD, &descriptor)?;
+    match thread.get_field(slot)? {
+        Object::Reference(Some(name)) => read_string(&name),
+        _ => Err(InternalError::WrongType),
+    }
+}
+
+/// Run an instance method of the thread, selected by its class like invokevirtual
+fn call_thread_method(
+    jvm: &Jvm,
+    thread: &Reference,
+    name: &str,
+    descriptor: &str,
+    arguments: &[Object],
+) -> MethodCallResult {
+    let class = thread.get_class();
+    let (resolved_class, resolved) = class
+        .resolve_method(name, descriptor)
+        .ok_or(InternalError::WrongType)?;
+    let Some(Ok(Some(method))) = class.select_method(resolved_class, resolved) else {
+        return Err(InternalError::WrongType);
+    };
+    let mut stack = Stack::new(1 + arguments.len());
+    stack.push(Object::Reference(Some(thread.clone())));
+    for argument in arguments {
+        stack.push(argument.clone());
+    }
+    method.execute(jvm, &mut stack)
+}
+
+impl Threads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> Result<MutexGuard<'_, ThreadsState>, InternalError> {
+        Ok(self.state.lock()?)
+    }
+
+    pub fn next_thread_number(&self) -> i32 {
+        self.next_number.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// The java/lang/Thread running the code
+    ///
+    /// The first host thread running Java code without being started by the program,
+    /// like the one running main, gets a thread named main when it needs one.
+    pub fn current_thread(&self, jvm: &Jvm) -> ExecResult<Reference> {
+        let id = thread::current().id();
+        if let Some(thread) = self.state()?.current.get(&id) {
+            return Ok(Ok(thread.clone()));
+        }
+
+        let class = jvm
+            .class_loader()
+            .load_class(THREAD_CLASS)
+            .map_err(|_| InternalError::ClassLoadingFailed(THREAD_CLASS.to_string()))?;
+        rethrow_exception!(class.initialize(jvm)?);
+        let thread = rethrow_exception!(jvm.heap().new_object(jvm, class)?);
+        let name = rethrow_exception!(new_string(jvm, MAIN_THREAD_NAME)?);
+        let descriptor = FieldType::Object(STRING_CLASS.to_string());
+        let slot = thread_field_slot(&thread, NAME_FIELD, &descriptor)?;
+        thread.set_field(slot, Object::Reference(Some(name)))?;
+
+        let mut state = self.state()?;
+        state.alive.insert(thread.address(), thread.clone());
+        Ok(Ok(state.current.entry(id).or_insert(thread).clone()))
+    }
+
+    // the address of the current thread, None if it doesn't have a java/lang/Thread
+    fn current_address(&self, state: &ThreadsState) -> Option<usize> {
+        let id = thread::current().id();
+        state.current.get(&id).map(Reference::address)
+    }
+
+    /// Run the thread in a new host thread, Thread.start has checked it was not started yet
+    pub fn start(&self, jvm: &Jvm, thread: Reference) -> ExecResult<()> {
+        let shared = jvm.get_shared().ok_or(InternalError::JvmNotShared)?;
+        let daemon = is_daemon(&thread)?;
+        let name = get_thread_name(&thread)?;
+        {
+            let mut state = self.state()?;
+            state.alive.insert(thread.address(), thread.clone());
+            if !daemon {
+                state.non_daemon += 1;
+            }
+        }
+
+        let spawned_thread = thread.clone();
+        let spawned = thread::Builder::new()
+            .name(name)
+            .stack_size(THREAD_STACK_SIZE)
+            .spawn(move || run_thread(&shared, spawned_thread, daemon));
+        if spawned.is_ok() {
+            return Ok(Ok(()));
+        }
+        self.end(&thread, daemon)?;
+        let message = "unable to create native thread".to_string();
+        throw_exception(jvm, OUT_OF_MEMORY_ERROR, Some(message))
+    }
+
+    // the thread is not alive anymore, the threads joining it are woken up
+    fn end(&self, thread: &Reference, daemon: bool) -> Result<(), InternalError> {
+        let mut state = self.state()?;
+        let address = thread.address();
+        state.current.retain(|_, current| current != thread);
+        state.alive.remove(&address);
+        state.interrupted.remove(&address);
+        state.waiting.remove(&address);
+        if !daemon {
+            state.non_daemon -= 1;
+        }
+        self.changed.notify_all();
+        Ok(())
+    }
+
+    pub fn is_alive(&self, thread: &Reference) -> Result<bool, InternalError> {
+        Ok(self.state()?.alive.contains_key(&thread.address()))
+    }
+
+    /// Wake up the thread if it is waiting, or it will be when it waits next
+    pub fn interrupt(&self, thread: &Reference) -> Result<(), InternalError> {
+        let monitor = {
+            let mut state = self.state()?;
+            let address = thread.address();
+            if !state.alive.contains_key(&address) {
+                return Ok(());
+            }
+            state.interrupted.insert(address);
+            self.changed.notify_all();
+            state.waiting.get(&address).cloned()
+        };
+        // the monitor is locked after the threads, the waiting thread locks them the other way
+        match monitor {
+            Some(monitor) => monitor.wake_waiters(),
+            None => Ok(()),
+        }
+    }
+
+    /// Clear the interrupt status of the current thread, returning whether it was interrupted
+    pub fn take_interrupt(&self) -> Result<bool, InternalError> {
+        let mut state = self.state()?;
+        match self.current_address(&state) {
+            Some(current) => Ok(state.interrupted.remove(&current)),
+            None => Ok(false),
+        }
+    }
+
+    /// Set the monitor the current thread waits on, so interrupting the thread wakes it up
+    pub fn set_waiting_monitor(&self, monitor: Option<Arc<Monitor>>) -> Result<(), InternalError> {
+        let mut state = self.state()?;
+        let Some(current) = self.current_address(&state) else {
+            return Ok(());
+        };
+        match monitor {
+            Some(monitor) => state.waiting.insert(current, monitor),
+            None => state.waiting.remove(&current),
+        };
+        Ok(())
+    }
+
+    pub fn is_interrupted(&self, thread: &Reference, clear: bool) -> Result<bool, InternalError> {
+        let mut state = self.state()?;
+        let address = thread.address();
+        if clear {
+            Ok(state.interrupted.remove(&address))
+        } else {
+            Ok(state.interrupted.contains(&address))
+        }
+    }
+
+    // Wait until the condition holds or the deadline passes, or the current thread is interrupted.
+    // Returns false when interrupted, and clears the interrupt status.
+    fn wait_until<F>(&self, deadline: Option<Instant>, condition: F) -> Result<bool, InternalError>
+    where
+        F: Fn(&ThreadsState) -> bool,
+    {
+        let mut state = self.state()?;
+        loop {
+            if let Some(current) = self.current_address(&state) {
+                if state.interrupted.remove(&current) {
+                    return Ok(false);
+                }
+            }
+            if condition(&state) {
+                return Ok(true);
+            }
+            state = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Ok(true);
+                    }
+                    self.changed.wait_timeout(state, deadline - now)?.0
+                }
+                None => self.changed.wait(state)?,
+            };
+        }
+    }
+
+    /// Wait for the thread to end, without timeout when it is zero
+    ///
+    /// Returns false if the current thread was interrupted while waiting.
+    pub fn join(&self, thread: &Reference, timeout: Duration) -> Result<bool, InternalError> {
+        let deadline = (!timeout.is_zero()).then(|| Instant::now() + timeout);
+        let address = thread.address();
+        self.wait_until(deadline, |state| !state.alive.contains_key(&address))
+    }
+
+    /// Returns false if the current thread was interrupted while sleeping
+    pub fn sleep(&self, duration: Duration) -> Result<bool, InternalError> {
+        self.wait_until(Some(Instant::now() + duration), |_| false)
+    }
+
+    /// Wait for the threads that are not daemons to end, before the virtual machine exits
+    pub fn wait_for_non_daemon_threads(&self) -> Result<(), InternalError> {
+        let mut state = self.state()?;
+        while state.non_daemon > 0 {
+            state = self.changed.wait(state)?;
+        }
+        Ok(())
+    }
+}
+
+// the body of a host thread, an uncaught exception is given to Thread.dispatchUncaughtException
+fn run_thread(jvm: &Jvm, thread: Reference, daemon: bool) {
+    let threads = jvm.threads();
+    let registered = threads
+        .state()
+        .map(|mut state| state.current.insert(thread::current().id(), thread.clone()));
+    if registered.is_ok() {
+        let result = call_thread_method(jvm, &thread, "run", "()V", &[]);
+        match result {
+            Ok(Ok(_)) => {}
+            Ok(Err(exception)) => {
+                let exception = Object::Reference(Some(exception));
+                let dispatched = call_thread_method(
+                    jvm,
+                    &thread,
+                    "dispatchUncaughtException",
+                    THROWABLE_DESCRIPTOR,
+                    &[exception],
+                );
+                // like HotSpot, an exception thrown by the handler is ignored
+                if let Err(error) = dispatched {
+                    eprintln!("Internal error in the virtual machine: {:?}", error);
+                }
+            }
+            Err(error) => eprintln!("Internal error in the virtual machine: {:?}", error),
+        }
+    }
+    if let Err(error) = threads.end(&thread, daemon) {
+        eprintln!("Internal error in the virtual machine: {:?}", error);
+    }
+}
@@ -0,0 +1,1026 @@
+use crate::parser::{
+    classfile::{
+        attributes::CodeAttribute,
+        constant_pool::{ConstantInfo, ConstantPool},
+        opcode::{ArrayType, OpCode, Wide},
+    },
+    descriptor::{parse_field_descriptor, parse_method_descriptor, FieldType, MethodDescriptor},
+    utils::ParseError,
+};
+
+use super::{
+    types::{Frame, VerificationType},
+    ClassHierarchy, VerifyErrorKind,
+};
+
+const INT: VerificationType = VerificationType::Integer;
+const FLOAT: VerificationType = VerificationType::Float;
+const LONG: VerificationType = VerificationType::Long;
+const DOUBLE: VerificationType = VerificationType::Double;
+
+const CONSTRUCTOR_NAME: &str = "<init>";
+const THROWABLE_CLASS: &str = "java/lang/Throwable";
+
+/// What the verifier needs to know about the method being verified
+pub struct MethodContext<'a> {
+    pub constant_pool: &'a ConstantPool,
+    pub class_name: &'a str,
+    // None for java/lang/Object
+    pub super_class_name: Option<&'a str>,
+    pub method_name: &'a str,
+    pub descriptor: &'a MethodDescriptor,
+    pub code: &'a CodeAttribute,
+    pub hierarchy: &'a dyn ClassHierarchy,
+}
+
+impl MethodContext<'_> {
+    pub fn is_constructor(&self) -> bool {
+        self.method_name == CONSTRUCTOR_NAME
+    }
+
+    fn max_stack(&self) -> usize {
+        self.code.max_stack()
+    }
+
+    fn push(&self, frame: &mut Frame, pushed: VerificationType) -> Result<(), VerifyErrorKind> {
+        frame.push(pushed, self.max_stack())
+    }
+
+    fn pop(
+        &self,
+        frame: &mut Frame,
+        expected: &VerificationType,
+    ) -> Result<VerificationType, VerifyErrorKind> {
+        frame.pop(expected, self.hierarchy)
+    }
+
+    fn class_name(&self, index: usize) -> Result<&str, VerifyErrorKind> {
+        self.constant_pool
+            .get_class_name(index)
+            .map_err(VerifyErrorKind::InvalidConstant)
+    }
+
+    /// The type of a class constant, array classes are named by their descriptor
+    fn class_type(&self, index: usize) -> Result<VerificationType, VerifyErrorKind> {
+        let name = self.class_name(index)?;
+        Ok(VerificationType::Reference(name.to_string()))
+    }
+
+    /// return (class name, field type)
+    fn field_ref(&self, index: usize) -> Result<(&str, FieldType), VerifyErrorKind> {
+        let ConstantInfo::FieldRef {
+            class_index,
+            name_and_type_index,
+        } = self.constant_info(index)?
+        else {
+            return Err(unexpected_constant(index, "FieldRef"));
+        };
+        let class_name = self.class_name(*class_index)?;
+        let (_, descriptor) = self.name_and_type(*name_and_type_index)?;
+        let field_type =
+            parse_field_descriptor(descriptor).map_err(VerifyErrorKind::InvalidConstant)?;
+        Ok((class_name, field_type))
+    }
+
+    /// return (class name, method name, descriptor)
+    fn method_ref(
+        &self,
+        index: usize,
+        allow_method: bool,
+        allow_interface_method: bool,
+    ) -> Result<(&str, &str, MethodDescriptor), VerifyErrorKind> {
+        let (class_index, name_and_type_index) = match self.constant_info(index)? {
+            ConstantInfo::MethodRef {
+                class_index,
+                name_and_type_index,
+            } if allow_method => (class_index, name_and_type_index),
+            ConstantInfo::InterfaceMethodRef {
+                class_index,
+                name_and_type_index,
+            } if allow_interface_method => (class_index, name_and_type_index),
+            _ if allow_method => return Err(unexpected_constant(index, "MethodRef")),
+            _ => return Err(unexpected_constant(index, "InterfaceMethodRef")),
+        };
+        let class_name = self.class_name(*class_index)?;
+        let (name, descriptor) = self.name_and_type(*name_and_type_index)?;
+        let descriptor =
+            parse_method_descriptor(descriptor).map_err(VerifyErrorKind::InvalidConstant)?;
+        Ok((class_name, name, descriptor))
+    }
+
+    fn constant_info(&self, index: usize) -> Result<&ConstantInfo, VerifyErrorKind> {
+        self.constant_pool
+            .get_checked(index)
+            .map_err(VerifyErrorKind::InvalidConstant)
+    }
+
+    fn name_and_type(&self, index: usize) -> Result<(&str, &str), VerifyErrorKind> {
+        self.constant_pool
+            .get_name_and_type(index)
+            .map_err(VerifyErrorKind::InvalidConstant)
+    }
+
+    /// Type of the value pushed by ldc, ldc_w and ldc2_w
+    fn loadable_constant_type(&self, index: usize) -> Result<VerificationType, VerifyErrorKind> {
+        let verification_type = match self.constant_info(index)? {
+            ConstantInfo::Integer(_) => INT,
+            ConstantInfo::Float(_) => FLOAT,
+            ConstantInfo::Long(_) => LONG,
+            ConstantInfo::Double(_) => DOUBLE,
+            ConstantInfo::String { .. } => reference("java/lang/String"),
+            ConstantInfo::Class { .. } => reference("java/lang/Class"),
+            ConstantInfo::MethodType { .. } => reference("java/lang/invoke/MethodType"),
+            ConstantInfo::MethodHandle { .. } => reference("java/lang/invoke/MethodHandle"),
+            ConstantInfo::Dynamic {
+                name_and_type_index,
+                ..
+            } => {
+                let (_, descriptor) = self.name_and_type(*name_and_type_index)?;
+                let field_type =
+                    parse_field_descriptor(descriptor).map_err(VerifyErrorKind::InvalidConstant)?;
+                VerificationType::from_field_type(&field_type)
+            }
+            _ => return Err(unexpected_constant(index, "loadable constant")),
+        };
+        Ok(verification_type)
+    }
+
+    pub fn catch_type(&self, catch_type_index: usize) -> Result<VerificationType, VerifyErrorKind> {
+        // catch_type 0 catches everything
+        if catch_type_index == 0 {
+            return Ok(reference(THROWABLE_CLASS));
+        }
+        let catch_type = self.class_type(catch_type_index)?;
+        if !catch_type.is_assignable_to(&reference(THROWABLE_CLASS), self.hierarchy)? {
+            return Err(VerifyErrorKind::bad_operand(
+                &reference(THROWABLE_CLASS),
+                &catch_type,
+            ));
+        }
+        Ok(catch_type)
+    }
+}
+
+fn reference(class_name: &str) -> VerificationType {
+    VerificationType::Reference(class_name.to_string())
+}
+
+fn unexpected_constant(index: usize, expected: &'static str) -> VerifyErrorKind {
+    VerifyErrorKind::InvalidConstant(ParseError::UnexpectedConstant { index, expected })
+}
+
+/// Where the execution can go after an instruction
+#[derive(Debug, Default)]
+pub struct Successors {
+    // opcode indexes of the branch targets
+    pub targets: Vec<usize>,
+    pub falls_through: bool,
+}
+
+impl Successors {
+    fn next() -> Self {
+        Successors {
+            targets: Vec::new(),
+            falls_through: true,
+        }
+    }
+
+    fn branch(target: usize) -> Self {
+        Successors {
+            targets: vec![target],
+            falls_through: true,
+        }
+    }
+
+    fn jump(targets: Vec<usize>) -> Self {
+        Successors {
+            targets,
+            falls_through: false,
+        }
+    }
+}
+
+fn binary(
+    context: &MethodContext,
+    frame: &mut Frame,
+    operand: VerificationType,
+    result: VerificationType,
+) -> Result<Successors, VerifyErrorKind> {
+    context.pop(frame, &operand)?;
+    context.pop(frame, &operand)?;
+    context.push(frame, result)?;
+    Ok(Successors::next())
+}
+
+fn unary(
+    context: &MethodContext,
+    frame: &mut Frame,
+    operand: VerificationType,
+    result: VerificationType,
+) -> Result<Successors, VerifyErrorKind> {
+    context.pop(frame, &operand)?;
+    context.push(frame, result)?;
+    Ok(Successors::next())
+}
+
+fn shift(
+    context: &MethodContext,
+    frame: &mut Frame,
+    value: VerificationType,
+) -> Result<Successors, VerifyErrorKind> {
+    context.pop(frame, &INT)?;
+    context.pop(frame, &value)?;
+    context.push(frame, value)?;
+    Ok(Successors::next())
+}
+
+fn load(
+    context: &MethodContext,
+    frame: &mut Frame,
+    local_index: usize,
+    expected: VerificationType,
+) -> Result<Successors, VerifyErrorKind> {
+    let loaded = frame.load(local_index, &expected, context.hierarchy)?;
+    context.push(frame, loaded)?;
+    Ok(Successors::next())
+}
+
+fn load_reference(
+    context: &MethodContext,
+    frame: &mut Frame,
+    local_index: usize,
+) -> Result<Successors, VerifyErrorKind> {
+    let loaded = frame.load_reference(local_index)?;
+    context.push(frame, loaded)?;
+    Ok(Successors::next())
+}
+
+fn store(
+    context: &MethodContext,
+    frame: &mut Frame,
+    local_index: usize,
+    expected: VerificationType,
+) -> Result<Successors, VerifyErrorKind> {
+    let stored = context.pop(frame, &expected)?;
+    frame.store(local_index, stored)?;
+    Ok(Successors::next())
+}
+
+fn store_reference(frame: &mut Frame, local_index: usize) -> Result<Successors, VerifyErrorKind> {
+    frame.store_reference(local_index)?;
+    Ok(Successors::next())
+}
+
+/// Pop an array reference whose components are assignable to one of the expected types
+fn pop_array(
+    frame: &mut Frame,
+    expected: &[FieldType],
+    description: &str,
+) -> Result<Option<FieldType>, VerifyErrorKind> {
+    let array = frame.pop_slot()?;
+    if array == VerificationType::Null {
+        return Ok(None);
+    }
+    match array.array_component() {
+        Some(component) if expected.is_empty() || expected.contains(&component) => {
+            Ok(Some(component))
+        }
+        _ => Err(VerifyErrorKind::BadOperandType {
+            expected: description.to_string(),
+            found: array,
+        }),
+    }
+}
+
+fn array_load(
+    context: &MethodContext,
+    frame: &mut Frame,
+    component: FieldType,
+) -> Result<Successors, VerifyErrorKind> {
+    context.pop(frame, &INT)?;
+    let loaded = VerificationType::from_field_type(&component);
+    pop_array(frame, &[component], &format!("array of {}", loaded))?;
+    context.push(frame, loaded)?;
+    Ok(Successors::next())
+}
+
+fn array_store(
+    context: &MethodContext,
+    frame: &mut Frame,
+    components: &[FieldType],
+) -> Result<Successors, VerifyErrorKind> {
+    let stored = VerificationType::from_field_type(&components[0]);
+    context.pop(frame, &stored)?;
+    context.pop(frame, &INT)?;
+    pop_array(frame, components, &format!("array of {}", stored))?;
+    Ok(Successors::next())
+}
+
+fn pop_reference_array(frame: &mut Frame) -> Result<Option<FieldType>, VerifyErrorKind> {
+    let array = frame.pop_slot()?;
+    match array.array_component() {
+        _ if array == VerificationType::Null => Ok(None),
+        Some(component) if component.is_reference() => Ok(Some(component)),
+        _ => Err(VerifyErrorKind::BadOperandType {
+            expected: "array of reference".to_string(),
+            found: array,
+        }),
+    }
+}
+
+fn return_value(
+    context: &MethodContext,
+    frame: &mut Frame,
+    expected: VerificationType,
+) -> Result<Successors, VerifyErrorKind> {
+    let return_type = context
+        .descriptor
+        .return_type()
+        .map(VerificationType::from_field_type);
+    let is_valid = match &return_type {
+        Some(return_type) if expected.is_reference() => return_type.is_reference(),
+        Some(return_type) => *return_type == expected,
+        None => false,
+    };
+    let Some(return_type) = return_type.filter(|_| is_valid) else {
+        return Err(VerifyErrorKind::BadReturnType);
+    };
+    context.pop(frame, &return_type)?;
+    Ok(Successors::jump(Vec::new()))
+}
+
+fn branch_if(
+    context: &MethodContext,
+    frame: &mut Frame,
+    operand: VerificationType,
+    count: usize,
+    target: usize,
+) -> Result<Successors, VerifyErrorKind> {
+    for _ in 0..count {
+        context.pop(frame, &operand)?;
+    }
+    Ok(Successors::branch(target))
+}
+
+fn branch_if_reference(
+    frame: &mut Frame,
+    count: usize,
+    target: usize,
+) -> Result<Successors, VerifyErrorKind> {
+    for _ in 0..count {
+        frame.pop_reference()?;
+    }
+    Ok(Successors::branch(target))
+}
+
+fn pop_arguments(
+    context: &MethodContext,
+    frame: &mut Frame,
+    descriptor: &MethodDescriptor,
+) -> Result<(), VerifyErrorKind> {
+    for parameter in descriptor.parameters().iter().rev() {
+        context.pop(frame, &VerificationType::from_field_type(parameter))?;
+    }
+    Ok(())
+}
+
+fn push_return_value(
+    context: &MethodContext,
+    frame: &mut Frame,
+    descriptor: &MethodDescriptor,
+) -> Result<(), VerifyErrorKind> {
+    match descriptor.return_type() {
+        Some(return_type) => context.push(frame, VerificationType::from_field_type(return_type)),
+        None => Ok(()),
+    }
+}
+
+// only <init> can be called by invokespecial, and no other method starting with '<' can be called
+fn check_method_name(name: &str, is_invokespecial: bool) -> Result<(), VerifyErrorKind> {
+    let is_valid = if name.starts_with('<') {
+        is_invokespecial && name == CONSTRUCTOR_NAME
+    } else {
+        true
+    };
+    if !is_valid {
+        return Err(VerifyErrorKind::InvalidMethodCall(name.to_string()));
+    }
+    Ok(())
+}
+
+fn invoke(
+    context: &MethodContext,
+    frame: &mut Frame,
+    index: usize,
+    has_receiver: bool,
+    allow_interface_method: bool,
+) -> Result<Successors, VerifyErrorKind> {
+    let (class_name, name, descriptor) = context.method_ref(index, true, allow_interface_method)?;
+    check_method_name(name, false)?;
+    pop_arguments(context, frame, &descriptor)?;
+    if has_receiver {
+        let receiver = frame.pop_initialized_reference()?;
+        let class_type = reference(class_name);
+        if !receiver.is_assignable_to(&class_type, context.hierarchy)? {
+            return Err(VerifyErrorKind::bad_operand(&class_type, &receiver));
+        }
+    }
+    push_return_value(context, frame, &descriptor)?;
+    Ok(Successors::next())
+}
+
+fn invokespecial(
+    context: &MethodContext,
+    frame: &mut Frame,
+    index: usize,
+) -> Result<Successors, VerifyErrorKind> {
+    let (class_name, name, descriptor) = context.method_ref(index, true, true)?;
+    check_method_name(name, true)?;
+    pop_arguments(context, frame, &descriptor)?;
+
+    if name != CONSTRUCTOR_NAME {
+        // private methods and super calls, the receiver is the current class
+        let receiver = frame.pop_initialized_reference()?;
+        let this_type = reference(context.class_name);
+        if !receiver.is_assignable_to(&this_type, context.hierarchy)? {
+            return Err(VerifyErrorKind::bad_operand(&this_type, &receiver));
+        }
+        push_return_value(context, frame, &descriptor)?;
+        return Ok(Successors::next());
+    }
+
+    if descriptor.return_type().is_some() {
+        return Err(VerifyErrorKind::InvalidMethodCall(name.to_string()));
+    }
+    let receiver = frame.pop_reference()?;
+    let initialized = match &receiver {
+        // `this` is initialized by another constructor of the class or of its super class
+        VerificationType::UninitializedThis => {
+            let is_valid =
+                class_name == context.class_name || Some(class_name) == context.super_class_name;
+            if !is_valid {
+                return Err(VerifyErrorKind::InvalidConstructorCall(
+                    class_name.to_string(),
+                ));
+            }
+            reference(context.class_name)
+        }
+        VerificationType::Uninitialized(new_index) => {
+            let Some(OpCode::new(class_index)) = context.code.code().get(*new_index) else {
+                return Err(VerifyErrorKind::UninitializedObject(receiver));
+            };
+            let new_class = context.class_name(*class_index)?;
+            if new_class != class_name {
+                return Err(VerifyErrorKind::InvalidConstructorCall(
+                    class_name.to_string(),
+                ));
+            }
+            reference(new_class)
+        }
+        _ => {
+            return Err(VerifyErrorKind::BadOperandType {
+                expected: "uninitialized object".to_string(),
+                found: receiver,
+            })
+        }
+    };
+    frame.initialize(&receiver, initialized);
+    Ok(Successors::next())
+}
+
+fn getfield(
+    context: &MethodContext,
+    frame: &mut Frame,
+    index: usize,
+) -> Result<Successors, VerifyErrorKind> {
+    let (class_name, field_type) = context.field_ref(index)?;
+    let object = frame.pop_initialized_reference()?;
+    let class_type = reference(class_name);
+    if !object.is_assignable_to(&class_type, context.hierarchy)? {
+        return Err(VerifyErrorKind::bad_operand(&class_type, &object));
+    }
+    context.push(frame, VerificationType::from_field_type(&field_type))?;
+    Ok(Successors::next())
+}
+
+fn putfield(
+    context: &MethodContext,
+    frame: &mut Frame,
+    index: usize,
+) -> Result<Successors, VerifyErrorKind> {
+    let (class_name, field_type) = context.field_ref(index)?;
+    context.pop(frame, &VerificationType::from_field_type(&field_type))?;
+    let object = frame.pop_reference()?;
+    // a constructor can set the fields of its own class before calling the super constructor
+    if object == VerificationType::UninitializedThis && class_name == context.class_name {
+        return Ok(Successors::next());
+    }
+    if object.is_uninitialized() {
+        return Err(VerifyErrorKind::UninitializedObject(object));
+    }
+    let class_type = reference(class_name);
+    if !object.is_assignable_to(&class_type, context.hierarchy)? {
+        return Err(VerifyErrorKind::bad_operand(&class_type, &object));
+    }
+    Ok(Successors::next())
+}
+
+fn new_object(
+    context: &MethodContext,
+    frame: &mut Frame,
+    index: usize,
+    class_index: usize,
+) -> Result<Successors, VerifyErrorKind> {
+    let class_type = context.class_type(class_index)?;
+    if class_type.array_component().is_some() {
+        return Err(VerifyErrorKind::InvalidConstant(
+            ParseError::UnexpectedConstant {
+                index: class_index,
+                expected: "non array Class",
+            },
+        ));
+    }
+    let uninitialized = VerificationType::Uninitialized(index);
+    // an object created by the same `new` in a previous iteration can't be used anymore
+    if frame.stack.contains(&uninitialized) {
+        return Err(VerifyErrorKind::UninitializedObject(uninitialized));
+    }
+    for local in frame.locals.iter_mut() {
+        if *local == uninitialized {
+            *local = VerificationType::Top;
+        }
+    }
+    context.push(frame, uninitialized)?;
+    Ok(Successors::next())
+}
+
+fn array_type_descriptor(array_type: &ArrayType) -> Result<&'static str, VerifyErrorKind> {
+    let descriptor = match array_type {
+        ArrayType::Boolean => "[Z",
+        ArrayType::Char => "[C",
+        ArrayType::Float => "[F",
+        ArrayType::Double => "[D",
+        ArrayType::Byte => "[B",
+        ArrayType::Short => "[S",
+        ArrayType::Int => "[I",
+        ArrayType::Long => "[J",
+        ArrayType::Reference(_) => {
+            return Err(VerifyErrorKind::InvalidConstant(ParseError::InvalidOpCode))
+        }
+    };
+    Ok(descriptor)
+}
+
+fn multianewarray(
+    context: &MethodContext,
+    frame: &mut Frame,
+    class_index: usize,
+    dimensions: usize,
+) -> Result<Successors, VerifyErrorKind> {
+    let array_type = context.class_type(class_index)?;
+    let VerificationType::Reference(name) = &array_type else {
+        unreachable!("class constants are references");
+    };
+    let array_dimensions = name.chars().take_while(|c| *c == '[').count();
+    if dimensions == 0 || dimensions > array_dimensions {
+        return Err(VerifyErrorKind::InvalidConstant(
+            ParseError::UnexpectedConstant {
+                index: class_index,
+                expected: "array Class with enough dimensions",
+            },
+        ));
+    }
+    for _ in 0..dimensions {
+        context.pop(frame, &INT)?;
+    }
+    context.push(frame, array_type)?;
+    Ok(Successors::next())
+}
+
+fn execute_wide(
+    context: &MethodContext,
+    frame: &mut Frame,
+    wide: &Wide,
+) -> Result<Successors, VerifyErrorKind> {
+    match wide {
+        Wide::iload(local_index) => load(context, frame, *local_index, INT),
+        Wide::fload(local_index) => load(context, frame, *local_index, FLOAT),
+        Wide::lload(local_index) => load(context, frame, *local_index, LONG),
+        Wide::dload(local_index) => load(context, frame, *local_index, DOUBLE),
+        Wide::aload(local_index) => load_reference(context, frame, *local_index),
+        Wide::istore(local_index) => store(context, frame, *local_index, INT),
+        Wide::fstore(local_index) => store(context, frame, *local_index, FLOAT),
+        Wide::lstore(local_index) => store(context, frame, *local_index, LONG),
+        Wide::dstore(local_index) => store(context, frame, *local_index, DOUBLE),
+        Wide::astore(local_index) => store_reference(frame, *local_index),
+        Wide::iinc(local_index, _) => {
+            frame.load(*local_index, &INT, context.hierarchy)?;
+            Ok(Successors::next())
+        }
+        Wide::ret(_) => Err(VerifyErrorKind::UnsupportedInstruction("ret")),
+    }
+}
+
+/// Apply the effect of the instruction at `index` on the frame,
+/// the frame must be the state of the locals and the stack before the instruction.
+pub fn execute(
+    context: &MethodContext,
+    frame: &mut Frame,
+    index: usize,
+    opcode: &OpCode,
+) -> Result<Successors, VerifyErrorKind> {
+    use OpCode as P;
+    match opcode {
+        P::nop => Ok(Successors::next()),
+
+        // constants
+        P::aconst_null => {
+            context.push(frame, VerificationType::Null)?;
+            Ok(Successors::next())
+        }
+        P::iconst_m1
+        | P::iconst_0
+        | P::iconst_1
+        | P::iconst_2
+        | P::iconst_3
+        | P::iconst_4
+        | P::iconst_5
+        | P::bipush(_)
+        | P::sipush(_) => {
+            context.push(frame, INT)?;
+            Ok(Successors::next())
+        }
+        P::fconst_0 | P::fconst_1 | P::fconst_2 => {
+            context.push(frame, FLOAT)?;
+            Ok(Successors::next())
+        }
+        P::lconst_0 | P::lconst_1 => {
+            context.push(frame, LONG)?;
+            Ok(Successors::next())
+        }
+        P::dconst_0 | P::dconst_1 => {
+            context.push(frame, DOUBLE)?;
+            Ok(Successors::next())
+        }
+        P::ldc(constant_index) | P::ldc_w(constant_index) | P::ldc2_w(constant_index) => {
+            let constant_type = context.loadable_constant_type(*constant_index)?;
+            let is_wide = matches!(opcode, P::ldc2_w(_));
+            if (constant_type.size() == 2) != is_wide {
+                return Err(unexpected_constant(
+                    *constant_index,
+                    if is_wide {
+                        "Long or Double"
+                    } else {
+                        "category 1 constant"
+                    },
+                ));
+            }
+            context.push(frame, constant_type)?;
+            Ok(Successors::next())
+        }
+
+        // locals
+        P::iload(local_index) => load(context, frame, *local_index, INT),
+        P::iload_0 => load(context, frame, 0, INT),
+        P::iload_1 => load(context, frame, 1, INT),
+        P::iload_2 => load(context, frame, 2, INT),
+        P::iload_3 => load(context, frame, 3, INT),
+        P::fload(local_index) => load(context, frame, *local_index, FLOAT),
+        P::fload_0 => load(context, frame, 0, FLOAT),
+        P::fload_1 => load(context, frame, 1, FLOAT),
+        P::fload_2 => load(context, frame, 2, FLOAT),
+        P::fload_3 => load(context, frame, 3, FLOAT),
+        P::lload(local_index) => load(context, frame, *local_index, LONG),
+        P::lload_0 => load(context, frame, 0, LONG),
+        P::lload_1 => load(context, frame, 1, LONG),
+        P::lload_2 => load(context, frame, 2, LONG),
+        P::lload_3 => load(context, frame, 3, LONG),
+        P::dload(local_index) => load(context, frame, *local_index, DOUBLE),
+        P::dload_0 => load(context, frame, 0, DOUBLE),
+        P::dload_1 => load(context, frame, 1, DOUBLE),
+        P::dload_2 => load(context, frame, 2, DOUBLE),
+        P::dload_3 => load(context, frame, 3, DOUBLE),
+        P::aload(local_index) => load_reference(context, frame, *local_index),
+        P::aload_0 => load_reference(context, frame, 0),
+        P::aload_1 => load_reference(context, frame, 1),
+        P::aload_2 => load_reference(context, frame, 2),
+        P::aload_3 => load_reference(context, frame, 3),
+        P::istore(local_index) => store(context, frame, *local_index, INT),
+        P::istore_0 => store(context, frame, 0, INT),
+        P::istore_1 => store(context, frame, 1, INT),
+        P::istore_2 => store(context, frame, 2, INT),
+        P::istore_3 => store(context, frame, 3, INT),
+        P::fstore(local_index) => store(context, frame, *local_index, FLOAT),
+        P::fstore_0 => store(context, frame, 0, FLOAT),
+        P::fstore_1 => store(context, frame, 1, FLOAT),
+        P::fstore_2 => store(context, frame, 2, FLOAT),
+        P::fstore_3 => store(context, frame, 3, FLOAT),
+        P::lstore(local_index) => store(context, frame, *local_index, LONG),
+        P::lstore_0 => store(context, frame, 0, LONG),
+        P::lstore_1 => store(context, frame, 1, LONG),
+        P::lstore_2 => store(context, frame, 2, LONG),
+        P::lstore_3 => store(context, frame, 3, LONG),
+        P::dstore(local_index) => store(context, frame, *local_index, DOUBLE),
+        P::dstore_0 => store(context, frame, 0, DOUBLE),
+        P::dstore_1 => store(context, frame, 1, DOUBLE),
+        P::dstore_2 => store(context, frame, 2, DOUBLE),
+        P::dstore_3 => store(context, frame, 3, DOUBLE),
+        P::astore(local_index) => store_reference(frame, *local_index),
+        P::astore_0 => store_reference(frame, 0),
+        P::astore_1 => store_reference(frame, 1),
+        P::astore_2 => store_reference(frame, 2),
+        P::astore_3 => store_reference(frame, 3),
+        P::iinc(local_index, _) => {
+            frame.load(*local_index, &INT, context.hierarchy)?;
+            Ok(Successors::next())
+        }
+        P::wide(wide) => execute_wide(context, frame, wide),
+
+        // arrays
+        P::iaload => array_load(context, frame, FieldType::Int),
+        P::faload => array_load(context, frame, FieldType::Float),
+        P::laload => array_load(context, frame, FieldType::Long),
+        P::daload => array_load(context, frame, FieldType::Double),
+        P::caload => array_load(context, frame, FieldType::Char),
+        P::saload => array_load(context, frame, FieldType::Short),
+        P::baload => {
+            context.pop(frame, &INT)?;
+            pop_array(
+                frame,
+                &[FieldType::Byte, FieldType::Boolean],
+                "array of byte or boolean",
+            )?;
+            context.push(frame, INT)?;
+            Ok(Successors::next())
+        }
+        P::aaload => {
+            context.pop(frame, &INT)?;
+            let loaded = match pop_reference_array(frame)? {
+                Some(component) => VerificationType::from_field_type(&component),
+                None => VerificationType::Null,
+            };
+            context.push(frame, loaded)?;
+            Ok(Successors::next())
+        }
+        P::iastore => array_store(context, frame, &[FieldType::Int]),
+        P::fastore => array_store(context, frame, &[FieldType::Float]),
+        P::lastore => array_store(context, frame, &[FieldType::Long]),
+        P::dastore => array_store(context, frame, &[FieldType::Double]),
+        P::castore => array_store(context, frame, &[FieldType::Char]),
+        P::sastore => array_store(context, frame, &[FieldType::Short]),
+        P::bastore => array_store(context, frame, &[FieldType::Byte, FieldType::Boolean]),
+        P::aastore => {
+            // the type of the value is checked against the array at runtime
+            frame.pop_initialized_reference()?;
+            context.pop(frame, &INT)?;
+            pop_reference_array(frame)?;
+            Ok(Successors::next())
+        }
+        P::arraylength => {
+            pop_array(frame, &[], "array")?;
+            context.push(frame, INT)?;
+            Ok(Successors::next())
+        }
+        P::newarray(array_type) => {
+            context.pop(frame, &INT)?;
+            let descriptor = array_type_descriptor(array_type)?;
+            context.push(frame, reference(descriptor))?;
+            Ok(Successors::next())
+        }
+        P::anewarray(class_index) => {
+            context.pop(frame, &INT)?;
+            let component = context.class_name(*class_index)?;
+            let descriptor = if component.starts_with('[') {
+                format!("[{}", component)
+            } else {
+                format!("[L{};", component)
+            };
+            context.push(frame, VerificationType::Reference(descriptor))?;
+            Ok(Successors::next())
+        }
+        P::multinewarray(class_index, dimensions) => {
+            multianewarray(context, frame, *class_index, *dimensions)
+        }
+
+        // stack
+        P::pop => {
+            frame.pop_category1()?;
+            Ok(Successors::next())
+        }
+        P::pop2 => {
+            frame.pop_two_slots()?;
+            Ok(Successors::next())
+        }
+        P::dup => {
+            let value = frame.pop_category1()?;
+            frame.push_slots(&[value.clone(), value], context.max_stack())?;
+            Ok(Successors::next())
+        }
+        P::dup_x1 => {
+            let value1 = frame.pop_category1()?;
+            let value2 = frame.pop_category1()?;
+            frame.push_slots(&[value1.clone(), value2, value1], context.max_stack())?;
+            Ok(Successors::next())
+        }
+        P::dup_x2 => {
+            let value1 = frame.pop_category1()?;
+            let [value3, value2] = frame.pop_two_slots()?;
+            frame.push_slots(
+                &[value1.clone(), value3, value2, value1],
+                context.max_stack(),
+            )?;
+            Ok(Successors::next())
+        }
+        P::dup2 => {
+            let [value2, value1] = frame.pop_two_slots()?;
+            let slots = [value2.clone(), value1.clone(), value2, value1];
+            frame.push_slots(&slots, context.max_stack())?;
+            Ok(Successors::next())
+        }
+        P::dup2_x1 => {
+            let [value2, value1] = frame.pop_two_slots()?;
+            let value3 = frame.pop_category1()?;
+            let slots = [value2.clone(), value1.clone(), value3, value2, value1];
+            frame.push_slots(&slots, context.max_stack())?;
+            Ok(Successors::next())
+        }
+        P::dup2_x2 => {
+            let [value2, value1] = frame.pop_two_slots()?;
+            let [value4, value3] = frame.pop_two_slots()?;
+            let slots = [
+                value2.clone(),
+                value1.clone(),
+                value4,
+                value3,
+                value2,
+                value1,
+            ];
+            frame.push_slots(&slots, context.max_stack())?;
+            Ok(Successors::next())
+        }
+        P::swap => {
+            let value1 = frame.pop_category1()?;
+            let value2 = frame.pop_category1()?;
+            frame.push_slots(&[value1, value2], context.max_stack())?;
+            Ok(Successors::next())
+        }
+
+        // arithmetic
+        P::iadd | P::isub | P::imul | P::idiv | P::irem | P::iand | P::ior | P::ixor => {
+            binary(context, frame, INT, INT)
+        }
+        P::fadd | P::fsub | P::fmul | P::fdiv | P::frem => binary(context, frame, FLOAT, FLOAT),
+        P::ladd | P::lsub | P::lmul | P::ldiv | P::lrem | P::land | P::lor | P::lxor => {
+            binary(context, frame, LONG, LONG)
+        }
+        P::dadd | P::dsub | P::dmul | P::ddiv | P::drem => binary(context, frame, DOUBLE, DOUBLE),
+        P::ishl | P::ishr | P::iushr => shift(context, frame, INT),
+        P::lshl | P::lshr | P::lushr => shift(context, frame, LONG),
+        P::ineg => unary(context, frame, INT, INT),
+        P::fneg => unary(context, frame, FLOAT, FLOAT),
+        P::lneg => unary(context, frame, LONG, LONG),
+        P::dneg => unary(context, frame, DOUBLE, DOUBLE),
+        P::lcmp => binary(context, frame, LONG, INT),
+        P::fcmpl | P::fcmpg => binary(context, frame, FLOAT, INT),
+        P::dcmpl | P::dcmpg => binary(context, frame, DOUBLE, INT),
+
+        // conversions
+        P::i2l => unary(context, frame, INT, LONG),
+        P::i2f => unary(context, frame, INT, FLOAT),
+        P::i2d => unary(context, frame, INT, DOUBLE),
+        P::l2i => unary(context, frame, LONG, INT),
+        P::l2f => unary(context, frame, LONG, FLOAT),
+        P::l2d => unary(context, frame, LONG, DOUBLE),
+        P::f2i => unary(context, frame, FLOAT, INT),
+        P::f2l => unary(context, frame, FLOAT, LONG),
+        P::f2d => unary(context, frame, FLOAT, DOUBLE),
+        P::d2i => unary(context, frame, DOUBLE, INT),
+        P::d2l => unary(context, frame, DOUBLE, LONG),
+        P::d2f => unary(context, frame, DOUBLE, FLOAT),
+        P::i2b | P::i2c | P::i2s => unary(context, frame, INT, INT),
+
+        // branches
+        P::ifeq(target)
+        | P::ifne(target)
+        | P::iflt(target)
+        | P::ifge(target)
+        | P::ifgt(target)
+        | P::ifle(target) => branch_if(context, frame, INT, 1, *target),
+        P::if_icmpeq(target)
+        | P::if_icmpne(target)
+        | P::if_icmplt(target)
+        | P::if_icmpge(target)
+        | P::if_icmpgt(target)
+        | P::if_icmple(target) => branch_if(context, frame, INT, 2, *target),
+        P::if_acmpeq(target) | P::if_acmpne(target) => branch_if_reference(frame, 2, *target),
+        P::ifnull(target) | P::ifnonnull(target) => branch_if_reference(frame, 1, *target),
+        P::goto(target) | P::goto_w(target) => Ok(Successors::jump(vec![*target])),
+        P::tableswitch(table_switch) => {
+            context.pop(frame, &INT)?;
+            let mut targets = table_switch.jumps().to_vec();
+            targets.push(table_switch.default());
+            Ok(Successors::jump(targets))
+        }
+        P::lookupswitch(lookup_switch) => {
+            context.pop(frame, &INT)?;
+            let pairs = lookup_switch.pairs();
+            if pairs
+                .windows(2)
+                .any(|pair| pair[0].value() >= pair[1].value())
+            {
+                return Err(VerifyErrorKind::UnsortedLookupSwitch);
+            }
+            let mut targets: Vec<usize> = pairs.iter().map(|pair| pair.jump()).collect();
+            targets.push(lookup_switch.default());
+            Ok(Successors::jump(targets))
+        }
+        P::jsr(_) | P::jsr_w(_) => Err(VerifyErrorKind::UnsupportedInstruction("jsr")),
+        P::ret(_) => Err(VerifyErrorKind::UnsupportedInstruction("ret")),
+
+        // returns
+        P::ireturn => return_value(context, frame, INT),
+        P::freturn => return_value(context, frame, FLOAT),
+        P::lreturn => return_value(context, frame, LONG),
+        P::dreturn => return_value(context, frame, DOUBLE),
+        P::areturn => return_value(context, frame, VerificationType::object()),
+        P::retrn => {
+            if context.descriptor.return_type().is_some() {
+                return Err(VerifyErrorKind::BadReturnType);
+            }
+            if frame.this_uninitialized {
+                return Err(VerifyErrorKind::ConstructorNotCalled);
+            }
+            Ok(Successors::jump(Vec::new()))
+        }
+        P::athrow => {
+            context.pop(frame, &reference(THROWABLE_CLASS))?;
+            Ok(Successors::jump(Vec::new()))
+        }
+
+        // fields
+        P::getstatic(field_index) => {
+            let (_, field_type) = context.field_ref(*field_index)?;
+            context.push(frame, VerificationType::from_field_type(&field_type))?;
+            Ok(Successors::next())
+        }
+        P::putstatic(field_index) => {
+            let (_, field_type) = context.field_ref(*field_index)?;
+            context.pop(frame, &VerificationType::from_field_type(&field_type))?;
+            Ok(Successors::next())
+        }
+        P::getfield(field_index) => getfield(context, frame, *field_index),
+        P::putfield(field_index) => putfield(context, frame, *field_index),
+
+        // invocations
+        P::invokevirtual(method_index) => invoke(context, frame, *method_index, true, false),
+        P::invokestatic(method_index) => invoke(context, frame, *method_index, false, true),
+        P::invokespecial(method_index) => invokespecial(context, frame, *method_index),
+        P::invokeinterface(method_index, count) => {
+            let (_, name, descriptor) = context.method_ref(*method_index, false, true)?;
+            check_method_name(name, false)?;
+            if *count != descriptor.parameters_size() + 1 {
+                return Err(VerifyErrorKind::InvalidMethodCall(name.to_string()));
+            }
+            pop_arguments(context, frame, &descriptor)?;
+            // the receiver is only checked at runtime, interfaces are treated like Object
+            frame.pop_initialized_reference()?;
+            push_return_value(context, frame, &descriptor)?;
+            Ok(Successors::next())
+        }
+        P::invokedynamic(call_site_index) => {
+            let ConstantInfo::InvokeDynamic {
+                name_and_type_index,
+                ..
+            } = context.constant_info(*call_site_index)?
+            else {
+                return Err(unexpected_constant(*call_site_index, "InvokeDynamic"));
+            };
+            let (name, descriptor) = context.name_and_type(*name_and_type_index)?;
+            check_method_name(name, false)?;
+            let descriptor =
+                parse_method_descriptor(descriptor).map_err(VerifyErrorKind::InvalidConstant)?;
+            pop_arguments(context, frame, &descriptor)?;
+            push_return_value(context, frame, &descriptor)?;
+            Ok(Successors::next())
+        }
+
+        // objects
+        P::new(class_index) => new_object(context, frame, index, *class_index),
+        P::checkcast(class_index) => {
+            frame.pop_initialized_reference()?;
+            let class_type = context.class_type(*class_index)?;
+            context.push(frame, class_type)?;
+            Ok(Successors::next())
+        }
+        P::instanceof(class_index) => {
+            frame.pop_initialized_reference()?;
+            context.class_type(*class_index)?;
+            context.push(frame, INT)?;
+            Ok(Successors::next())
+        }
+        P::monitorenter | P::monitorexit => {
+            frame.pop_initialized_reference()?;
+            Ok(Successors::next())
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use std::fmt;
+
+use crate::parser::{
+    classfile::{
+        classfile::{AccesFlag, ClassFile},
+        methods::MethodInfo,
+    },
+    descriptor::parse_method_descriptor,
+    utils::ParseError,
+};
+
+use self::{
+    instructions::MethodContext, type_checker::type_check_method,
+    type_inference::infer_method_types, types::VerificationType,
+};
+
+use super::class_loader::ClassLoadingError;
+
+mod instructions;
+mod type_checker;
+mod type_inference;
+pub mod types;
+
+// Class files older than Java 6 have no StackMapTable
+const TYPE_CHECKING_MIN_MAJOR_VERSION: u16 = 50;
+// Java 6 class files failing type checking can still be verified by type inference
+const TYPE_INFERENCE_FAILOVER_MAJOR_VERSION: u16 = 50;
+
+/// Access to the super classes of the classes referenced by the verified code
+pub trait ClassHierarchy {
+    /// None for java/lang/Object
+    fn super_class(&self, class_name: &str) -> Result<Option<String>, VerifyErrorKind>;
+
+    fn is_interface(&self, class_name: &str) -> Result<bool, VerifyErrorKind>;
+}
+
+#[derive(Debug)]
+pub enum VerifyErrorKind {
+    StackUnderflow,
+    StackOverflow,
+    BadOperandType {
+        expected: String,
+        found: VerificationType,
+    },
+    BadLocalType {
+        index: usize,
+        expected: String,
+        found: VerificationType,
+    },
+    LocalIndexOutOfBounds(usize),
+    ArgumentsExceedMaxLocals,
+    // use of an object before its constructor is called
+    UninitializedObject(VerificationType),
+    // <init> called on the wrong class
+    InvalidConstructorCall(String),
+    // a constructor returning before calling the super constructor
+    ConstructorNotCalled,
+    InvalidMethodCall(String),
+    BadReturnType,
+    // opcode index of the target
+    InvalidBranchTarget(usize),
+    MissingStackMapFrame(usize),
+    IncompatibleStackMapFrame(usize),
+    InvalidStackMapFrame,
+    // two paths reach the same instruction with different stacks
+    InconsistentStackHeight,
+    MismatchedStackTypes {
+        first: VerificationType,
+        second: VerificationType,
+    },
+    RecursiveSubroutine(usize),
+    InvalidExceptionHandler,
+    UnsortedLookupSwitch,
+    FallingOffCode,
+    MissingCode,
+    UnsupportedInstruction(&'static str),
+    InvalidConstant(ParseError),
+    // a class needed to check assignability could not be loaded
+    ClassLoading(Box<ClassLoadingError>),
+}
+
+impl VerifyErrorKind {
+    pub fn bad_operand(expected: &VerificationType, found: &VerificationType) -> Self {
+        VerifyErrorKind::BadOperandType {
+            expected: expected.to_string(),
+            found: found.clone(),
+        }
+    }
+}
+
+impl fmt::Display for VerifyErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyErrorKind::StackUnderflow => write!(f, "Operand stack underflow"),
+            VerifyErrorKind::StackOverflow => write!(f, "Operand stack overflow"),
+            VerifyErrorKind::BadOperandType { expected, found } => write!(
+                f,
+                "Bad type on operand stack (expected {}, found {})",
+                expected, found
+            ),
+            VerifyErrorKind::BadLocalType {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Bad local variable type (local {}, expected {}, found {})",
+                index, expected, found
+            ),
+            VerifyErrorKind::LocalIndexOutOfBounds(index) => {
+                write!(f, "Local variable index {} out of bounds", index)
+            }
+            VerifyErrorKind::ArgumentsExceedMaxLocals => {
+                write!(f, "Arguments can't fit into locals")
+            }
+            VerifyErrorKind::UninitializedObject(found) => {
+                write!(f, "Use of uninitialized object {}", found)
+            }
+            VerifyErrorKind::InvalidConstructorCall(class_name) => {
+                write!(f, "Bad <init> method call to {}", class_name)
+            }
+            VerifyErrorKind::ConstructorNotCalled => {
+                write!(f, "Constructor must call super() or this() before return")
+            }
+            VerifyErrorKind::InvalidMethodCall(name) => {
+                write!(f, "Illegal call to method {}", name)
+            }
+            VerifyErrorKind::BadReturnType => write!(f, "Method returns the wrong type"),
+            VerifyErrorKind::InvalidBranchTarget(target) => {
+                write!(f, "Illegal target of jump or branch {}", target)
+            }
+            VerifyErrorKind::MissingStackMapFrame(target) => {
+                write!(f, "Expecting a stackmap frame at instruction {}", target)
+            }
+            VerifyErrorKind::IncompatibleStackMapFrame(target) => write!(
+                f,
+                "Current frame is not assignable to stack map frame at instruction {}",
+                target
+            ),
+            VerifyErrorKind::InvalidStackMapFrame => write!(f, "Invalid stack map frame"),
+            VerifyErrorKind::InconsistentStackHeight => {
+                write!(f, "Inconsistent stack height")
+            }
+            VerifyErrorKind::MismatchedStackTypes { first, second } => {
+                write!(f, "Mismatched stack types ({} and {})", first, second)
+            }
+            VerifyErrorKind::RecursiveSubroutine(start) => {
+                write!(f, "Recursive call to subroutine at instruction {}", start)
+            }
+            VerifyErrorKind::InvalidExceptionHandler => {
+                write!(f, "Illegal exception table range")
+            }
+            VerifyErrorKind::UnsortedLookupSwitch => {
+                write!(f, "Bad lookupswitch instruction, keys are not sorted")
+            }
+            VerifyErrorKind::FallingOffCode => write!(f, "Falling off the end of the code"),
+            VerifyErrorKind::MissingCode => write!(f, "Missing Code attribute"),
+            VerifyErrorKind::UnsupportedInstruction(name) => write!(
+                f,
+                "{} is not allowed in class files with a StackMapTable",
+                name
+            ),
+            VerifyErrorKind::InvalidConstant(error) => write!(f, "Invalid constant: {:?}", error),
+            VerifyErrorKind::ClassLoading(error) => {
+                write!(f, "Could not load class: {:?}", error)
+            }
+        }
+    }
+}
+
+// error of a method, without the method location
+struct MethodVerifyError {
+    // None when the error is not caused by a specific instruction
+    index: Option<usize>,
+    kind: VerifyErrorKind,
+}
+
+#[derive(Debug)]
+pub struct VerifyError {
+    pub class_name: String,
+    pub method_name: String,
+    pub method_descriptor: String,
+    // opcode index of the faulty instruction
+    pub index: Option<usize>,
+    pub kind: VerifyErrorKind,
+}
+
+/// Display the error like the message of java.lang.VerifyError
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in method {}.{}{}",
+            self.kind,
+            self.class_name.replace('/', "."),
+            self.method_name,
+            self.method_descriptor
+        )?;
+        if let Some(index) = self.index {
+            write!(f, " at instruction {}", index)?;
+        }
+        Ok(())
+    }
+}
+
+fn verify_method(
+    class_file: &ClassFile,
+    class_name: &str,
+    super_class_name: Option<&str>,
+    method: &MethodInfo,
+    hierarchy: &dyn ClassHierarchy,
+) -> Result<(), Box<VerifyError>> {
+    let constant_pool = class_file.constant_pool();
+    let method_name = constant_pool.get_utf8(method.name_index()).unwrap_or("");
+    let method_descriptor = constant_pool
+        .get_utf8(method.descriptor_index())
+        .unwrap_or("");
+    let verify_error = |error: MethodVerifyError| {
+        Box::new(VerifyError {
+            class_name: class_name.to_string(),
+            method_name: method_name.to_string(),
+            method_descriptor: method_descriptor.to_string(),
+            index: error.index,
+            kind: error.kind,
+        })
+    };
+    let at_method = |kind| verify_error(MethodVerifyError { index: None, kind });
+
+    let flags = method.access_flags();
+    let has_no_code = flags.contains(AccesFlag::ABSTRACT) || flags.contains(AccesFlag::NATIVE);
+    let code = match (method.get_code(), has_no_code) {
+        (None, true) => return Ok(()),
+        (Some(code), false) => code,
+        _ => return Err(at_method(VerifyErrorKind::MissingCode)),
+    };
+
+    let descriptor = parse_method_descriptor(method_descriptor)
+        .map_err(|error| at_method(VerifyErrorKind::InvalidConstant(error)))?;
+
+    let context = MethodContext {
+        constant_pool,
+        class_name,
+        super_class_name,
+        method_name,
+        descriptor: &descriptor,
+        code,
+        hierarchy,
+    };
+    let is_static = flags.contains(AccesFlag::STATIC);
+    let major_version = class_file.major_version();
+    if major_version < TYPE_CHECKING_MIN_MAJOR_VERSION {
+        return infer_method_types(&context, is_static).map_err(verify_error);
+    }
+    match type_check_method(&context, is_static) {
+        Err(_) if major_version == TYPE_INFERENCE_FAILOVER_MAJOR_VERSION => {
+            infer_method_types(&context, is_static).map_err(verify_error)
+        }
+        result => result.map_err(verify_error),
+    }
+}
+
+/// Verify the code of every method of the class before it can be executed (JVMS §4.10)
+///
+/// Methods are verified by type checking against their StackMapTable,
+/// or by type inference in class files older than the StackMapTable attribute.
+pub fn verify_class(
+    class_file: &ClassFile,
+    hierarchy: &dyn ClassHierarchy,
+) -> Result<(), Box<VerifyError>> {
+    // the names were already checked when the class was loaded
+    let constant_pool = class_file.constant_pool();
+    let class_name = constant_pool
+        .get_class_name(class_file.this_class())
+        .unwrap_or("");
+    let super_class_name = match class_file.super_class() {
+        0 => None,
+        index => constant_pool.get_class_name(index).ok(),
+    };
+
+    for method in class_file.methods().iter() {
+        verify_method(class_file, class_name, super_class_name, method, hierarchy)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use crate::parser::classfile::{
+    attributes::{Attribute, CodeAttribute},
+    opcode::OpCode,
+    stack_map_table::{StackMapFrameType, StackMapTableAttribute, VerificationTypeInfo},
+};
+
+use super::{
+    instructions::{execute, MethodContext},
+    types::{Frame, VerificationType},
+    MethodVerifyError, VerifyErrorKind,
+};
+
+// Verification by type checking (JVMS §4.10.1):
+// the StackMapTable gives the expected frame at every branch target and exception handler,
+// so each instruction is checked once, in order, against the frame computed by the previous one.
+
+/// The frame at the start of the method, built from its descriptor
+pub fn initial_frame(context: &MethodContext, is_static: bool) -> Result<Frame, VerifyErrorKind> {
+    let mut locals = Vec::new();
+    let mut this_uninitialized = false;
+    if !is_static {
+        // only java/lang/Object has no super constructor to call
+        if context.is_constructor() && context.super_class_name.is_some() {
+            locals.push(VerificationType::UninitializedThis);
+            this_uninitialized = true;
+        } else {
+            locals.push(VerificationType::Reference(context.class_name.to_string()));
+        }
+    }
+    for parameter in context.descriptor.parameters() {
+        push_local(&mut locals, VerificationType::from_field_type(parameter));
+    }
+    let max_locals = context.code.max_locals();
+    if locals.len() > max_locals {
+        return Err(VerifyErrorKind::ArgumentsExceedMaxLocals);
+    }
+    locals.resize(max_locals, VerificationType::Top);
+    Ok(Frame {
+        locals,
+        stack: Vec::new(),
+        this_uninitialized,
+    })
+}
+
+fn push_local(locals: &mut Vec<VerificationType>, verification_type: VerificationType) {
+    let is_category2 = verification_type.size() == 2;
+    locals.push(verification_type);
+    if is_category2 {
+        locals.push(VerificationType::Top);
+    }
+}
+
+fn convert_type_info(
+    context: &MethodContext,
+    type_info: &VerificationTypeInfo,
+) -> Result<VerificationType, VerifyErrorKind> {
+    let verification_type = match type_info {
+        VerificationTypeInfo::Top => VerificationType::Top,
+        VerificationTypeInfo::Integer => VerificationType::Integer,
+        VerificationTypeInfo::Float => VerificationType::Float,
+        VerificationTypeInfo::Long => VerificationType::Long,
+        VerificationTypeInfo::Double => VerificationType::Double,
+        VerificationTypeInfo::Null => VerificationType::Null,
+        VerificationTypeInfo::UninitializedThis => VerificationType::UninitializedThis,
+        VerificationTypeInfo::Object(class_index) => {
+            let class_name = context
+                .constant_pool
+                .get_class_name(*class_index)
+                .map_err(VerifyErrorKind::InvalidConstant)?;
+            VerificationType::Reference(class_name.to_string())
+        }
+        VerificationTypeInfo::Uninitialized(index) => {
+            let is_new = matches!(context.code.code().get(*index), Some(OpCode::new(_)));
+            if !is_new {
+                return Err(VerifyErrorKind::InvalidStackMapFrame);
+            }
+            VerificationType::Uninitialized(*index)
+        }
+    };
+    Ok(verification_type)
+}
+
+fn expand_types(
+    context: &MethodContext,
+    type_infos: &[VerificationTypeInfo],
+) -> Result<Vec<VerificationType>, VerifyErrorKind> {
+    let mut types = Vec::new();
+    for type_info in type_infos {
+        push_local(&mut types, convert_type_info(context, type_info)?);
+    }
+    Ok(types)
+}
+
+/// Expand the stack map frames to full frames, indexed by opcode index
+///
+/// The frames are stored as deltas on the locals of the previous frame,
+/// where longs and doubles count as a single local.
+fn expand_stack_map_frames(
+    context: &MethodContext,
+    initial_frame: &Frame,
+    stack_map_table: Option<&StackMapTableAttribute>,
+) -> Result<HashMap<usize, Frame>, VerifyErrorKind> {
+    let mut frames = HashMap::new();
+    let Some(stack_map_table) = stack_map_table else {
+        return Ok(frames);
+    };
+
+    // the initial locals without the trailing Tops and the second halves of longs and doubles
+    let mut locals: Vec<VerificationType> = Vec::new();
+    let mut slots = initial_frame.locals.iter();
+    while let Some(local) = slots.next() {
+        if *local == VerificationType::Top {
+            break;
+        }
+        if local.size() == 2 {
+            slots.next();
+        }
+        locals.push(local.clone());
+    }
+
+    for frame in stack_map_table.frames() {
+        let stack = match frame.frame_type() {
+            StackMapFrameType::Same | StackMapFrameType::SameExtended => Vec::new(),
+            StackMapFrameType::SameLocals1StackItem(item)
+            | StackMapFrameType::SameLocals1StackItemExtended(item) => {
+                expand_types(context, std::slice::from_ref(item))?
+            }
+            StackMapFrameType::Chop(count) => {
+                let remaining = locals
+                    .len()
+                    .checked_sub(*count)
+                    .ok_or(VerifyErrorKind::InvalidStackMapFrame)?;
+                locals.truncate(remaining);
+                Vec::new()
+            }
+            StackMapFrameType::Append(appended) => {
+                for type_info in appended {
+                    locals.push(convert_type_info(context, type_info)?);
+                }
+                Vec::new()
+            }
+            StackMapFrameType::Full {
+                locals: full_locals,
+                stack,
+            } => {
+                locals = full_locals
+                    .iter()
+                    .map(|type_info| convert_type_info(context, type_info))
+                    .collect::<Result<_, _>>()?;
+                expand_types(context, stack)?
+            }
+        };
+
+        let mut expanded_locals = Vec::new();
+        for local in &locals {
+            push_local(&mut expanded_locals, local.clone());
+        }
+        let max_locals = context.code.max_locals();
+        if expanded_locals.len() > max_locals || stack.len() > context.code.max_stack() {
+            return Err(VerifyErrorKind::InvalidStackMapFrame);
+        }
+        expanded_locals.resize(max_locals, VerificationType::Top);
+
+        let this_uninitialized = expanded_locals.contains(&VerificationType::UninitializedThis);
+        let expanded = Frame {
+            locals: expanded_locals,
+            stack,
+            this_uninitialized,
+        };
+        frames.insert(frame.index(), expanded);
+    }
+
+    Ok(frames)
+}
+
+fn stack_map_table(code: &CodeAttribute) -> Option<&StackMapTableAttribute> {
+    code.attributes()
+        .iter()
+        .find_map(|attribute_info| match attribute_info.attribute() {
+            Attribute::StackMapTable(stack_map_table) => Some(stack_map_table),
+            _ => None,
+        })
+}
+
+/// Check that the frame is compatible with the stack map frame of the target
+fn check_target(
+    context: &MethodContext,
+    frames: &HashMap<usize, Frame>,
+    frame: &Frame,
+    target: usize,
+) -> Result<(), VerifyErrorKind> {
+    if target >= context.code.code().len() {
+        return Err(VerifyErrorKind::InvalidBranchTarget(target));
+    }
+    let target_frame = frames
+        .get(&target)
+        .ok_or(VerifyErrorKind::MissingStackMapFrame(target))?;
+    if !frame.is_assignable_to(target_frame, context.hierarchy)? {
+        return Err(VerifyErrorKind::IncompatibleStackMapFrame(target));
+    }
+    Ok(())
+}
+
+fn check_exception_handlers(
+    context: &MethodContext,
+    frames: &HashMap<usize, Frame>,
+    frame: &Frame,
+    index: usize,
+) -> Result<(), VerifyErrorKind> {
+    for handler in context.code.exception_table() {
+        if !handler.code_range().contains(&index) {
+            continue;
+        }
+        let catch_type = context.catch_type(handler.catch_type())?;
+        let handler_frame = Frame {
+            locals: frame.locals.clone(),
+            stack: vec![catch_type],
+            this_uninitialized: frame.this_uninitialized,
+        };
+        check_target(context, frames, &handler_frame, handler.handler_pc())?;
+    }
+    Ok(())
+}
+
+pub fn check_exception_table(context: &MethodContext) -> Result<(), VerifyErrorKind> {
+    let code_length = context.code.code().len();
+    for handler in context.code.exception_table() {
+        let range = handler.code_range();
+        if range.start >= range.end || range.end > code_length {
+            return Err(VerifyErrorKind::InvalidExceptionHandler);
+        }
+    }
+    Ok(())
+}
+
+pub fn type_check_method(
+    context: &MethodContext,
+    is_static: bool,
+) -> Result<(), MethodVerifyError> {
+    let at_method = |kind| MethodVerifyError { index: None, kind };
+    let initial_frame = initial_frame(context, is_static).map_err(at_method)?;
+    check_exception_table(context).map_err(at_method)?;
+    let frames = expand_stack_map_frames(context, &initial_frame, stack_map_table(context.code))
+        .map_err(at_method)?;
+
+    // None when the previous instruction doesn't fall through
+    let mut current = Some(initial_frame);
+    for (index, opcode) in context.code.code().iter().enumerate() {
+        let at_instruction = |kind| MethodVerifyError {
+            index: Some(index),
+            kind,
+        };
+
+        if let Some(stack_map_frame) = frames.get(&index) {
+            if let Some(frame) = &current {
+                let is_assignable = frame
+                    .is_assignable_to(stack_map_frame, context.hierarchy)
+                    .map_err(at_instruction)?;
+                if !is_assignable {
+                    return Err(at_instruction(VerifyErrorKind::IncompatibleStackMapFrame(
+                        index,
+                    )));
+                }
+            }
+            current = Some(stack_map_frame.clone());
+        }
+        let frame = current
+            .take()
+            .ok_or(VerifyErrorKind::MissingStackMapFrame(index))
+            .map_err(at_instruction)?;
+
+        check_exception_handlers(context, &frames, &frame, index).map_err(at_instruction)?;
+
+        let mut next = frame;
+        let successors = execute(context, &mut next, index, opcode).map_err(at_instruction)?;
+        for target in successors.targets {
+            check_target(context, &frames, &next, target).map_err(at_instruction)?;
+        }
+        if successors.falls_through {
+            current = Some(next);
+        }
+    }
+
+    if current.is_some() {
+        let last_index = context.code.code().len().checked_sub(1);
+        return Err(MethodVerifyError {
+            index: last_index,
+            kind: VerifyErrorKind::FallingOffCode,
+        });
+    }
+
+    Ok(())
+}
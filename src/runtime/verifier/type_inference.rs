@@ -0,0 +1,375 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::parser::classfile::opcode::{OpCode, Wide};
+
+use super::{
+    instructions::{execute, MethodContext},
+    type_checker::{check_exception_table, initial_frame},
+    types::{Frame, VerificationType},
+    MethodVerifyError, VerifyErrorKind,
+};
+
+// Verification by type inference (JVMS §4.10.2), for the class files without StackMapTable:
+// the frame before each instruction is the merge of the frames of all the paths reaching it,
+// computed by running the instructions until no frame changes anymore.
+//
+// Subroutines (jsr/ret) are called with different frames by each caller,
+// so the locals the subroutine doesn't touch are restored from the caller when it returns.
+
+/// Local slots read or written by the instruction, (index, size)
+fn local_access(opcode: &OpCode) -> Option<(usize, usize)> {
+    use OpCode as P;
+    let access = match opcode {
+        P::iload(index)
+        | P::fload(index)
+        | P::aload(index)
+        | P::istore(index)
+        | P::fstore(index)
+        | P::astore(index)
+        | P::iinc(index, _)
+        | P::ret(index) => (*index, 1),
+        P::lload(index) | P::dload(index) | P::lstore(index) | P::dstore(index) => (*index, 2),
+        P::iload_0 | P::fload_0 | P::aload_0 | P::istore_0 | P::fstore_0 | P::astore_0 => (0, 1),
+        P::iload_1 | P::fload_1 | P::aload_1 | P::istore_1 | P::fstore_1 | P::astore_1 => (1, 1),
+        P::iload_2 | P::fload_2 | P::aload_2 | P::istore_2 | P::fstore_2 | P::astore_2 => (2, 1),
+        P::iload_3 | P::fload_3 | P::aload_3 | P::istore_3 | P::fstore_3 | P::astore_3 => (3, 1),
+        P::lload_0 | P::dload_0 | P::lstore_0 | P::dstore_0 => (0, 2),
+        P::lload_1 | P::dload_1 | P::lstore_1 | P::dstore_1 => (1, 2),
+        P::lload_2 | P::dload_2 | P::lstore_2 | P::dstore_2 => (2, 2),
+        P::lload_3 | P::dload_3 | P::lstore_3 | P::dstore_3 => (3, 2),
+        P::wide(wide) => match wide {
+            Wide::iload(index)
+            | Wide::fload(index)
+            | Wide::aload(index)
+            | Wide::istore(index)
+            | Wide::fstore(index)
+            | Wide::astore(index)
+            | Wide::iinc(index, _)
+            | Wide::ret(index) => (*index, 1),
+            Wide::lload(index) | Wide::dload(index) | Wide::lstore(index) | Wide::dstore(index) => {
+                (*index, 2)
+            }
+        },
+        _ => return None,
+    };
+    Some(access)
+}
+
+/// Static successors of an instruction, without following jsr into the subroutine
+fn static_successors(opcode: &OpCode, index: usize) -> Vec<usize> {
+    use OpCode as P;
+    match opcode {
+        P::ifeq(target)
+        | P::ifne(target)
+        | P::iflt(target)
+        | P::ifge(target)
+        | P::ifgt(target)
+        | P::ifle(target)
+        | P::if_icmpeq(target)
+        | P::if_icmpne(target)
+        | P::if_icmplt(target)
+        | P::if_icmpge(target)
+        | P::if_icmpgt(target)
+        | P::if_icmple(target)
+        | P::if_acmpeq(target)
+        | P::if_acmpne(target)
+        | P::ifnull(target)
+        | P::ifnonnull(target) => vec![index + 1, *target],
+        P::goto(target) | P::goto_w(target) => vec![*target],
+        P::tableswitch(table_switch) => {
+            let mut targets = table_switch.jumps().to_vec();
+            targets.push(table_switch.default());
+            targets
+        }
+        P::lookupswitch(lookup_switch) => {
+            let mut targets: Vec<usize> = lookup_switch
+                .pairs()
+                .iter()
+                .map(|pair| pair.jump())
+                .collect();
+            targets.push(lookup_switch.default());
+            targets
+        }
+        P::ireturn
+        | P::freturn
+        | P::lreturn
+        | P::dreturn
+        | P::areturn
+        | P::retrn
+        | P::athrow
+        | P::ret(_)
+        | P::wide(Wide::ret(_)) => Vec::new(),
+        _ => vec![index + 1],
+    }
+}
+
+fn jsr_target(opcode: &OpCode) -> Option<usize> {
+    match opcode {
+        OpCode::jsr(target) | OpCode::jsr_w(target) => Some(*target),
+        _ => None,
+    }
+}
+
+fn ret_local(opcode: &OpCode) -> Option<usize> {
+    match opcode {
+        OpCode::ret(index) | OpCode::wide(Wide::ret(index)) => Some(*index),
+        _ => None,
+    }
+}
+
+/// The locals used by each subroutine, indexed by the opcode index of its first instruction
+///
+/// Each instruction belongs to the first subroutine reaching it, the main code being first,
+/// so the handlers of the method are not counted as part of the subroutines they cover.
+fn find_subroutine_locals(context: &MethodContext) -> HashMap<usize, HashSet<usize>> {
+    let code = context.code.code();
+    let mut claimed = vec![false; code.len()];
+    // start of the subroutine, locals used directly, subroutines called
+    let mut subroutines: Vec<(usize, HashSet<usize>, Vec<usize>)> = Vec::new();
+
+    let mut starts = vec![0];
+    let mut next_start = 0;
+    while let Some(&start) = starts.get(next_start) {
+        next_start += 1;
+        let mut locals = HashSet::new();
+        let mut called = Vec::new();
+        let mut worklist = vec![start];
+        while let Some(index) = worklist.pop() {
+            if index >= code.len() || claimed[index] {
+                continue;
+            }
+            claimed[index] = true;
+            let opcode = &code[index];
+            if let Some((local_index, size)) = local_access(opcode) {
+                locals.extend(local_index..local_index + size);
+            }
+            if let Some(target) = jsr_target(opcode) {
+                if !starts.contains(&target) {
+                    starts.push(target);
+                }
+                called.push(target);
+                worklist.push(index + 1);
+            } else {
+                worklist.extend(static_successors(opcode, index));
+            }
+            for handler in context.code.exception_table() {
+                if handler.code_range().contains(&index) {
+                    worklist.push(handler.handler_pc());
+                }
+            }
+        }
+        subroutines.push((start, locals, called));
+    }
+
+    // the locals used by the subroutines called from a subroutine are used by it too
+    let direct: HashMap<usize, (HashSet<usize>, Vec<usize>)> = subroutines
+        .into_iter()
+        .skip(1)
+        .map(|(start, locals, called)| (start, (locals, called)))
+        .collect();
+    let mut subroutine_locals = HashMap::new();
+    for &start in direct.keys() {
+        let mut locals = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut to_visit = vec![start];
+        while let Some(subroutine) = to_visit.pop() {
+            if !visited.insert(subroutine) {
+                continue;
+            }
+            if let Some((direct_locals, called)) = direct.get(&subroutine) {
+                locals.extend(direct_locals);
+                to_visit.extend(called);
+            }
+        }
+        subroutine_locals.insert(start, locals);
+    }
+    subroutine_locals
+}
+
+struct TypeInference<'a, 'b> {
+    context: &'a MethodContext<'b>,
+    // frame before each instruction, None if not reached yet
+    frames: Vec<Option<Frame>>,
+    worklist: BTreeSet<usize>,
+    subroutine_locals: HashMap<usize, HashSet<usize>>,
+    // opcode indexes of the jsr calling each subroutine
+    callers: HashMap<usize, BTreeSet<usize>>,
+    // opcode indexes of the ret seen returning from each subroutine
+    returns: HashMap<usize, BTreeSet<usize>>,
+}
+
+impl TypeInference<'_, '_> {
+    /// Merge the frame into the frame of the target, and schedule the target if it changed
+    fn merge_into(&mut self, target: usize, frame: Frame) -> Result<(), VerifyErrorKind> {
+        let Some(slot) = self.frames.get_mut(target) else {
+            return Err(VerifyErrorKind::InvalidBranchTarget(target));
+        };
+        let merged = match slot {
+            Some(current) => {
+                let merged = current.merge(&frame, self.context.hierarchy)?;
+                if merged == *current {
+                    return Ok(());
+                }
+                merged
+            }
+            None => frame,
+        };
+        *slot = Some(merged);
+        self.worklist.insert(target);
+        Ok(())
+    }
+
+    fn merge_into_handlers(&mut self, frame: &Frame, index: usize) -> Result<(), VerifyErrorKind> {
+        for handler in self.context.code.exception_table() {
+            if !handler.code_range().contains(&index) {
+                continue;
+            }
+            let catch_type = self.context.catch_type(handler.catch_type())?;
+            let handler_frame = Frame {
+                locals: frame.locals.clone(),
+                stack: vec![catch_type],
+                this_uninitialized: frame.this_uninitialized,
+            };
+            self.merge_into(handler.handler_pc(), handler_frame)?;
+        }
+        Ok(())
+    }
+
+    fn jsr(&mut self, frame: &Frame, index: usize, target: usize) -> Result<(), VerifyErrorKind> {
+        let return_address = VerificationType::ReturnAddress(target);
+        if frame.locals.contains(&return_address) || frame.stack.contains(&return_address) {
+            return Err(VerifyErrorKind::RecursiveSubroutine(target));
+        }
+        let mut called = frame.clone();
+        called.push(return_address, self.context.code.max_stack())?;
+        self.merge_into(target, called)?;
+
+        // the frame of the caller may have changed, the returns must be merged again
+        self.callers.entry(target).or_default().insert(index);
+        if let Some(returns) = self.returns.get(&target) {
+            self.worklist.extend(returns);
+        }
+        Ok(())
+    }
+
+    fn ret(
+        &mut self,
+        frame: &Frame,
+        index: usize,
+        local_index: usize,
+    ) -> Result<(), VerifyErrorKind> {
+        let Some(VerificationType::ReturnAddress(subroutine)) = frame.locals.get(local_index)
+        else {
+            return Err(match frame.locals.get(local_index) {
+                Some(found) => VerifyErrorKind::BadLocalType {
+                    index: local_index,
+                    expected: "returnAddress".to_string(),
+                    found: found.clone(),
+                },
+                None => VerifyErrorKind::LocalIndexOutOfBounds(local_index),
+            });
+        };
+        let subroutine = *subroutine;
+        self.returns.entry(subroutine).or_default().insert(index);
+
+        let used_locals = self
+            .subroutine_locals
+            .get(&subroutine)
+            .cloned()
+            .unwrap_or_default();
+        let callers: Vec<usize> = self
+            .callers
+            .get(&subroutine)
+            .map(|callers| callers.iter().copied().collect())
+            .unwrap_or_default();
+        for caller in callers {
+            let Some(caller_frame) = &self.frames[caller] else {
+                continue;
+            };
+            // the locals untouched by the subroutine keep the type they had in the caller
+            let locals = frame
+                .locals
+                .iter()
+                .zip(&caller_frame.locals)
+                .enumerate()
+                .map(|(local_index, (returned, kept))| {
+                    if used_locals.contains(&local_index) {
+                        returned.clone()
+                    } else {
+                        kept.clone()
+                    }
+                })
+                .collect();
+            let returned = Frame {
+                locals,
+                stack: frame.stack.clone(),
+                this_uninitialized: frame.this_uninitialized,
+            };
+            self.merge_into(caller + 1, returned)?;
+        }
+        Ok(())
+    }
+
+    fn step(&mut self, index: usize) -> Result<(), VerifyErrorKind> {
+        let Some(frame) = self.frames[index].clone() else {
+            return Ok(());
+        };
+        let opcode = &self.context.code.code()[index];
+
+        self.merge_into_handlers(&frame, index)?;
+
+        if let Some(target) = jsr_target(opcode) {
+            return self.jsr(&frame, index, target);
+        }
+        if let Some(local_index) = ret_local(opcode) {
+            return self.ret(&frame, index, local_index);
+        }
+
+        let mut next = frame;
+        let successors = execute(self.context, &mut next, index, opcode)?;
+        for target in successors.targets {
+            self.merge_into(target, next.clone())?;
+        }
+        if successors.falls_through {
+            if index + 1 >= self.context.code.code().len() {
+                return Err(VerifyErrorKind::FallingOffCode);
+            }
+            self.merge_into(index + 1, next)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn infer_method_types(
+    context: &MethodContext,
+    is_static: bool,
+) -> Result<(), MethodVerifyError> {
+    let at_method = |kind| MethodVerifyError { index: None, kind };
+    let initial_frame = initial_frame(context, is_static).map_err(at_method)?;
+    check_exception_table(context).map_err(at_method)?;
+
+    let code_length = context.code.code().len();
+    if code_length == 0 {
+        return Err(at_method(VerifyErrorKind::FallingOffCode));
+    }
+
+    let mut inference = TypeInference {
+        context,
+        frames: vec![None; code_length],
+        worklist: BTreeSet::new(),
+        subroutine_locals: find_subroutine_locals(context),
+        callers: HashMap::new(),
+        returns: HashMap::new(),
+    };
+    inference.frames[0] = Some(initial_frame);
+    inference.worklist.insert(0);
+
+    while let Some(index) = inference.worklist.pop_first() {
+        inference.step(index).map_err(|kind| MethodVerifyError {
+            index: Some(index),
+            kind,
+        })?;
+    }
+
+    Ok(())
+}
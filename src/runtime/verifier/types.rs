@@ -0,0 +1,492 @@
+use std::fmt;
+
+use crate::parser::descriptor::{parse_field_descriptor, FieldType};
+
+use super::{ClassHierarchy, VerifyErrorKind};
+
+const OBJECT_CLASS: &str = "java/lang/Object";
+const CLONEABLE_CLASS: &str = "java/lang/Cloneable";
+const SERIALIZABLE_CLASS: &str = "java/io/Serializable";
+
+// The types of the verification type system (JVMS §4.10.1.2).
+// Long and Double take 2 slots, in the locals and on the stack, the second one being Top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationType {
+    Top,
+    Integer,
+    Float,
+    Long,
+    Double,
+    Null,
+    UninitializedThis,
+    // opcode index of the `new` creating the object
+    Uninitialized(usize),
+    // class name, or descriptor for arrays ("[I", "[Ljava/lang/String;")
+    Reference(String),
+    // pushed by jsr, opcode index of the called subroutine
+    ReturnAddress(usize),
+}
+
+impl VerificationType {
+    /// The type of a value of the given field type once loaded on the stack,
+    /// boolean, byte, char and short are all ints
+    pub fn from_field_type(field_type: &FieldType) -> Self {
+        match field_type {
+            FieldType::Boolean
+            | FieldType::Byte
+            | FieldType::Char
+            | FieldType::Short
+            | FieldType::Int => VerificationType::Integer,
+            FieldType::Float => VerificationType::Float,
+            FieldType::Long => VerificationType::Long,
+            FieldType::Double => VerificationType::Double,
+            FieldType::Object(class_name) => VerificationType::Reference(class_name.clone()),
+            FieldType::Array(_) => VerificationType::Reference(field_type.descriptor()),
+        }
+    }
+
+    pub fn object() -> Self {
+        VerificationType::Reference(OBJECT_CLASS.to_string())
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            VerificationType::Long | VerificationType::Double => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn is_reference(&self) -> bool {
+        matches!(
+            self,
+            VerificationType::Null
+                | VerificationType::UninitializedThis
+                | VerificationType::Uninitialized(_)
+                | VerificationType::Reference(_)
+        )
+    }
+
+    pub fn is_uninitialized(&self) -> bool {
+        matches!(
+            self,
+            VerificationType::UninitializedThis | VerificationType::Uninitialized(_)
+        )
+    }
+
+    /// Component type of an array type, None if this is not an array
+    pub fn array_component(&self) -> Option<FieldType> {
+        match self {
+            VerificationType::Reference(name) if name.starts_with('[') => {
+                parse_field_descriptor(&name[1..]).ok()
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_assignable_to(
+        &self,
+        target: &VerificationType,
+        hierarchy: &dyn ClassHierarchy,
+    ) -> Result<bool, VerifyErrorKind> {
+        match (self, target) {
+            (from, to) if from == to => Ok(true),
+            (_, VerificationType::Top) => Ok(true),
+            (VerificationType::Null, VerificationType::Reference(_)) => Ok(true),
+            (VerificationType::Reference(from), VerificationType::Reference(to)) => {
+                is_class_assignable(from, to, hierarchy)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// The most precise type both types are assignable to, Top if they have nothing in common
+    pub fn merge(
+        &self,
+        other: &VerificationType,
+        hierarchy: &dyn ClassHierarchy,
+    ) -> Result<VerificationType, VerifyErrorKind> {
+        let merged = match (self, other) {
+            (first, second) if first == second => first.clone(),
+            (VerificationType::Null, VerificationType::Reference(_)) => other.clone(),
+            (VerificationType::Reference(_), VerificationType::Null) => self.clone(),
+            (VerificationType::Reference(first), VerificationType::Reference(second)) => {
+                VerificationType::Reference(common_super_class(first, second, hierarchy)?)
+            }
+            _ => VerificationType::Top,
+        };
+        Ok(merged)
+    }
+}
+
+/// Display the type like in the VerifyErrors of HotSpot
+impl fmt::Display for VerificationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationType::Top => write!(f, "top"),
+            VerificationType::Integer => write!(f, "integer"),
+            VerificationType::Float => write!(f, "float"),
+            VerificationType::Long => write!(f, "long"),
+            VerificationType::Double => write!(f, "double"),
+            VerificationType::Null => write!(f, "null"),
+            VerificationType::UninitializedThis => write!(f, "uninitializedThis"),
+            VerificationType::Uninitialized(index) => write!(f, "uninitialized({})", index),
+            VerificationType::Reference(name) => write!(f, "'{}'", name),
+            VerificationType::ReturnAddress(_) => write!(f, "returnAddress"),
+        }
+    }
+}
+
+// Interfaces are treated like java/lang/Object, as the type checker does,
+// the real check is done at runtime by invokeinterface.
+fn is_class_assignable(
+    from: &str,
+    to: &str,
+    hierarchy: &dyn ClassHierarchy,
+) -> Result<bool, VerifyErrorKind> {
+    if from == to || to == OBJECT_CLASS {
+        return Ok(true);
+    }
+
+    match (from.strip_prefix('['), to.strip_prefix('[')) {
+        (Some(from_component), Some(to_component)) => {
+            match (
+                array_component_class(from_component),
+                array_component_class(to_component),
+            ) {
+                (Some(from_class), Some(to_class)) => {
+                    is_class_assignable(from_class, to_class, hierarchy)
+                }
+                // arrays of primitives are only assignable to the same array type
+                _ => Ok(false),
+            }
+        }
+        (Some(_), None) => Ok(to == CLONEABLE_CLASS || to == SERIALIZABLE_CLASS),
+        (None, Some(_)) => Ok(false),
+        (None, None) => {
+            if hierarchy.is_interface(to)? {
+                return Ok(true);
+            }
+            let mut current = hierarchy.super_class(from)?;
+            while let Some(class_name) = current {
+                if class_name == to {
+                    return Ok(true);
+                }
+                current = hierarchy.super_class(&class_name)?;
+            }
+            Ok(false)
+        }
+    }
+}
+
+// Interfaces are treated like java/lang/Object here too.
+fn common_super_class(
+    first: &str,
+    second: &str,
+    hierarchy: &dyn ClassHierarchy,
+) -> Result<String, VerifyErrorKind> {
+    if first == second {
+        return Ok(first.to_string());
+    }
+
+    match (first.strip_prefix('['), second.strip_prefix('[')) {
+        (Some(first_component), Some(second_component)) => {
+            let components = (
+                array_component_class(first_component),
+                array_component_class(second_component),
+            );
+            let (Some(first_class), Some(second_class)) = components else {
+                return Ok(OBJECT_CLASS.to_string());
+            };
+            let component = common_super_class(first_class, second_class, hierarchy)?;
+            if component.starts_with('[') {
+                Ok(format!("[{}", component))
+            } else {
+                Ok(format!("[L{};", component))
+            }
+        }
+        (None, None) => {
+            if hierarchy.is_interface(first)? || hierarchy.is_interface(second)? {
+                return Ok(OBJECT_CLASS.to_string());
+            }
+            let mut first_super_classes = vec![first.to_string()];
+            while let Some(super_class) =
+                hierarchy.super_class(first_super_classes.last().unwrap())?
+            {
+                first_super_classes.push(super_class);
+            }
+            let mut current = Some(second.to_string());
+            while let Some(class_name) = current {
+                if first_super_classes.contains(&class_name) {
+                    return Ok(class_name);
+                }
+                current = hierarchy.super_class(&class_name)?;
+            }
+            Ok(OBJECT_CLASS.to_string())
+        }
+        _ => Ok(OBJECT_CLASS.to_string()),
+    }
+}
+
+// "Ljava/lang/String;" -> "java/lang/String", "[I" -> "[I", "I" -> None
+fn array_component_class(component: &str) -> Option<&str> {
+    if component.starts_with('[') {
+        Some(component)
+    } else {
+        component.strip_prefix('L')?.strip_suffix(';')
+    }
+}
+
+/// The state of the locals and the operand stack before an instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    // always max_locals long
+    pub locals: Vec<VerificationType>,
+    pub stack: Vec<VerificationType>,
+    // set in constructors while `this` is not initialized yet
+    pub this_uninitialized: bool,
+}
+
+impl Frame {
+    pub fn is_assignable_to(
+        &self,
+        target: &Frame,
+        hierarchy: &dyn ClassHierarchy,
+    ) -> Result<bool, VerifyErrorKind> {
+        if self.locals.len() != target.locals.len()
+            || self.stack.len() != target.stack.len()
+            || (self.this_uninitialized && !target.this_uninitialized)
+        {
+            return Ok(false);
+        }
+        let pairs = self
+            .locals
+            .iter()
+            .zip(&target.locals)
+            .chain(self.stack.iter().zip(&target.stack));
+        for (from, to) in pairs {
+            if !from.is_assignable_to(to, hierarchy)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Merge the frame of another path to the same instruction
+    ///
+    /// Locals that differ become unusable, but the stacks must be compatible.
+    pub fn merge(
+        &self,
+        other: &Frame,
+        hierarchy: &dyn ClassHierarchy,
+    ) -> Result<Frame, VerifyErrorKind> {
+        if self.stack.len() != other.stack.len() {
+            return Err(VerifyErrorKind::InconsistentStackHeight);
+        }
+        let mut locals = Vec::with_capacity(self.locals.len());
+        for (first, second) in self.locals.iter().zip(&other.locals) {
+            locals.push(first.merge(second, hierarchy)?);
+        }
+        let mut stack = Vec::with_capacity(self.stack.len());
+        for (first, second) in self.stack.iter().zip(&other.stack) {
+            let merged = first.merge(second, hierarchy)?;
+            if merged == VerificationType::Top && *first != VerificationType::Top {
+                return Err(VerifyErrorKind::MismatchedStackTypes {
+                    first: first.clone(),
+                    second: second.clone(),
+                });
+            }
+            stack.push(merged);
+        }
+        Ok(Frame {
+            locals,
+            stack,
+            this_uninitialized: self.this_uninitialized || other.this_uninitialized,
+        })
+    }
+
+    pub fn push(
+        &mut self,
+        verification_type: VerificationType,
+        max_stack: usize,
+    ) -> Result<(), VerifyErrorKind> {
+        if self.stack.len() + verification_type.size() > max_stack {
+            return Err(VerifyErrorKind::StackOverflow);
+        }
+        let is_category2 = verification_type.size() == 2;
+        self.stack.push(verification_type);
+        if is_category2 {
+            self.stack.push(VerificationType::Top);
+        }
+        Ok(())
+    }
+
+    pub fn pop_slot(&mut self) -> Result<VerificationType, VerifyErrorKind> {
+        self.stack.pop().ok_or(VerifyErrorKind::StackUnderflow)
+    }
+
+    /// Pop a value that must be assignable to the expected type, return the actual type
+    pub fn pop(
+        &mut self,
+        expected: &VerificationType,
+        hierarchy: &dyn ClassHierarchy,
+    ) -> Result<VerificationType, VerifyErrorKind> {
+        let found = if expected.size() == 2 {
+            let top = self.pop_slot()?;
+            let value = self.pop_slot()?;
+            if top != VerificationType::Top {
+                return Err(VerifyErrorKind::bad_operand(expected, &top));
+            }
+            value
+        } else {
+            self.pop_slot()?
+        };
+        if !found.is_assignable_to(expected, hierarchy)? {
+            return Err(VerifyErrorKind::bad_operand(expected, &found));
+        }
+        Ok(found)
+    }
+
+    /// Pop any reference, initialized or not
+    pub fn pop_reference(&mut self) -> Result<VerificationType, VerifyErrorKind> {
+        let found = self.pop_slot()?;
+        if !found.is_reference() {
+            return Err(VerifyErrorKind::BadOperandType {
+                expected: "reference".to_string(),
+                found,
+            });
+        }
+        Ok(found)
+    }
+
+    /// Pop an initialized reference, or null
+    pub fn pop_initialized_reference(&mut self) -> Result<VerificationType, VerifyErrorKind> {
+        let found = self.pop_reference()?;
+        if found.is_uninitialized() {
+            return Err(VerifyErrorKind::UninitializedObject(found));
+        }
+        Ok(found)
+    }
+
+    pub fn pop_category1(&mut self) -> Result<VerificationType, VerifyErrorKind> {
+        let found = self.pop_slot()?;
+        if found == VerificationType::Top || found.size() != 1 {
+            return Err(VerifyErrorKind::BadOperandType {
+                expected: "category 1 value".to_string(),
+                found,
+            });
+        }
+        Ok(found)
+    }
+
+    /// Pop two slots without splitting a long or a double, return them bottom first
+    pub fn pop_two_slots(&mut self) -> Result<[VerificationType; 2], VerifyErrorKind> {
+        let top = self.pop_slot()?;
+        let bottom = self.pop_slot()?;
+        let is_valid = match &top {
+            VerificationType::Top => bottom.size() == 2,
+            _ => bottom != VerificationType::Top && bottom.size() == 1,
+        };
+        if !is_valid {
+            return Err(VerifyErrorKind::BadOperandType {
+                expected: "two category 1 values or a category 2 value".to_string(),
+                found: bottom,
+            });
+        }
+        Ok([bottom, top])
+    }
+
+    pub fn push_slots(
+        &mut self,
+        slots: &[VerificationType],
+        max_stack: usize,
+    ) -> Result<(), VerifyErrorKind> {
+        if self.stack.len() + slots.len() > max_stack {
+            return Err(VerifyErrorKind::StackOverflow);
+        }
+        self.stack.extend_from_slice(slots);
+        Ok(())
+    }
+
+    fn check_local_index(&self, index: usize, size: usize) -> Result<(), VerifyErrorKind> {
+        if index + size > self.locals.len() {
+            return Err(VerifyErrorKind::LocalIndexOutOfBounds(index));
+        }
+        Ok(())
+    }
+
+    /// Load a local that must be assignable to the expected type, return the actual type
+    pub fn load(
+        &self,
+        index: usize,
+        expected: &VerificationType,
+        hierarchy: &dyn ClassHierarchy,
+    ) -> Result<VerificationType, VerifyErrorKind> {
+        self.check_local_index(index, expected.size())?;
+        let found = &self.locals[index];
+        let is_valid = found.is_assignable_to(expected, hierarchy)?
+            && (expected.size() == 1 || self.locals[index + 1] == VerificationType::Top);
+        if !is_valid {
+            return Err(VerifyErrorKind::BadLocalType {
+                index,
+                expected: expected.to_string(),
+                found: found.clone(),
+            });
+        }
+        Ok(found.clone())
+    }
+
+    pub fn load_reference(&self, index: usize) -> Result<VerificationType, VerifyErrorKind> {
+        self.check_local_index(index, 1)?;
+        let found = &self.locals[index];
+        if !found.is_reference() {
+            return Err(VerifyErrorKind::BadLocalType {
+                index,
+                expected: "reference".to_string(),
+                found: found.clone(),
+            });
+        }
+        Ok(found.clone())
+    }
+
+    /// Store the type on top of the stack, a reference or a return address
+    pub fn store_reference(&mut self, index: usize) -> Result<(), VerifyErrorKind> {
+        let stored = self.pop_slot()?;
+        if !stored.is_reference() && !matches!(stored, VerificationType::ReturnAddress(_)) {
+            return Err(VerifyErrorKind::BadOperandType {
+                expected: "reference or returnAddress".to_string(),
+                found: stored,
+            });
+        }
+        self.store(index, stored)
+    }
+
+    pub fn store(
+        &mut self,
+        index: usize,
+        verification_type: VerificationType,
+    ) -> Result<(), VerifyErrorKind> {
+        let size = verification_type.size();
+        self.check_local_index(index, size)?;
+        // overwriting the second half of a long or a double invalidates the first half
+        if index > 0 && self.locals[index - 1].size() == 2 {
+            self.locals[index - 1] = VerificationType::Top;
+        }
+        self.locals[index] = verification_type;
+        if size == 2 {
+            self.locals[index + 1] = VerificationType::Top;
+        }
+        Ok(())
+    }
+
+    /// Replace every occurence of an uninitialized type once its constructor is called
+    pub fn initialize(&mut self, uninitialized: &VerificationType, initialized: VerificationType) {
+        for slot in self.locals.iter_mut().chain(self.stack.iter_mut()) {
+            if slot == uninitialized {
+                *slot = initialized.clone();
+            }
+        }
+        if *uninitialized == VerificationType::UninitializedThis {
+            self.this_uninitialized = false;
+        }
+    }
+}
@@ -3,17 +3,20 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use crate::{parser::classfile::opcode::ArrayType, rethrow_exception};
+use crate::{parser::classfile::opcode::ArrayType, rethrow_exception, runtime::jvm::Jvm};
 
-use super::{Class, Exception, ExecResult, InternalError, Object, Reference, ResultValue};
+use super::{
+    identity_hash_code, throw_exception, Class, ExecResult, InternalError, Object, ResultValue,
+    ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION, ARRAY_STORE_EXCEPTION,
+};
 
 #[derive(Debug, Clone)]
 pub enum Array {
     Boolean(Arc<Mutex<Box<[bool]>>>),
-    Char(Arc<Mutex<Box<[u8]>>>),
+    Char(Arc<Mutex<Box<[u16]>>>),
     Float(Arc<Mutex<Box<[f32]>>>),
     Double(Arc<Mutex<Box<[f64]>>>),
-    Byte(Arc<Mutex<Box<[u8]>>>),
+    Byte(Arc<Mutex<Box<[i8]>>>),
     Short(Arc<Mutex<Box<[i16]>>>),
     Int(Arc<Mutex<Box<[i32]>>>),
     Long(Arc<Mutex<Box<[i64]>>>),
@@ -41,12 +44,14 @@ impl Eq for Array {}
 
 #[derive(Debug)]
 pub struct ReferenceArray {
-    array: Mutex<Box<[Option<Reference>]>>,
+    // Object::Reference items, or Object::Array when the items are arrays themselves
+    array: Mutex<Box<[Object]>>,
+    // the class of the items, an array class for the arrays of arrays
     class: Arc<Class>,
 }
 
 impl Deref for ReferenceArray {
-    type Target = Mutex<Box<[Option<Reference>]>>;
+    type Target = Mutex<Box<[Object]>>;
 
     fn deref(&self) -> &Self::Target {
         &self.array
@@ -54,12 +59,26 @@ impl Deref for ReferenceArray {
 }
 
 impl ReferenceArray {
-    pub fn get_class(&self) -> &Arc<Class> {
+    pub fn get_component_class(&self) -> &Arc<Class> {
         &self.class
     }
 
-    pub fn can_accept(&self, reference: &Reference) -> bool {
-        reference.is_subclass(&self.class)
+    /// Whether the value can be stored in the array, null can be stored in any of them
+    pub fn can_accept(&self, jvm: &Jvm, value: &Object) -> Result<bool, InternalError> {
+        match value {
+            Object::Reference(Some(reference)) => Ok(reference.is_subclass(&self.class)),
+            Object::Array(Some(array)) => Ok(array.get_class(jvm)?.is_subclass(&self.class)),
+            Object::Reference(None) | Object::Array(None) => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    /// The value as stored in the array, where null is an array for the arrays of arrays
+    pub fn as_item(&self, value: Object) -> Object {
+        match value {
+            Object::Reference(None) | Object::Array(None) => null_item(&self.class),
+            value => value,
+        }
     }
 
     pub fn size(&self) -> Result<i32, InternalError> {
@@ -67,6 +86,15 @@ impl ReferenceArray {
     }
 }
 
+// the null of the items of a reference array, like the default value of a field of that type
+fn null_item(class: &Class) -> Object {
+    if class.get_name().starts_with('[') {
+        Object::Array(None)
+    } else {
+        Object::Reference(None)
+    }
+}
+
 fn new_boxed_array<T: Default>(size: usize) -> Box<[T]> {
     let mut v = Vec::with_capacity(size);
     let default_iter = std::iter::repeat_with(|| T::default()).take(size);
@@ -80,55 +108,46 @@ fn get_size<T>(array: &Mutex<Box<[T]>>) -> Result<i32, InternalError> {
     Ok(size)
 }
 
-fn get_index_raw<T: Clone>(
-    array: &Mutex<Box<[T]>>,
-    index: usize,
-) -> Result<Option<T>, InternalError> {
-    let array = array.lock()?;
-    Ok(array.get(index).cloned())
-}
-
-fn store_index_raw<T>(
-    array: &Mutex<Box<[T]>>,
-    index: usize,
-    value: T,
-) -> Result<Result<(), ()>, InternalError> {
-    let mut array = array.lock()?;
-    if let Some(place_to_store) = array.get_mut(index) {
-        *place_to_store = value;
-        Ok(Ok(()))
-    } else {
-        Ok(Err(()))
-    }
+fn index_out_of_bounds<T>(jvm: &Jvm, index: i32, length: usize) -> ExecResult<T> {
+    let message = format!("Index {} out of bounds for length {}", index, length);
+    throw_exception(jvm, ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION, Some(message))
 }
 
-fn store_index<T>(array: &Mutex<Box<[T]>>, index: i32, value: T) -> ExecResult {
-    if index >= 0 {
-        if store_index_raw(array, index as usize, value)?.is_ok() {
+fn store_index<T>(jvm: &Jvm, array: &Mutex<Box<[T]>>, index: i32, value: T) -> ExecResult {
+    // the lock is released before loading the exception class
+    let length = {
+        let mut array = array.lock()?;
+        let place_to_store = usize::try_from(index)
+            .ok()
+            .and_then(|index| array.get_mut(index));
+        if let Some(place_to_store) = place_to_store {
+            *place_to_store = value;
             return Ok(Ok(ResultValue::None));
         }
-    }
-    // TODO: Throw ArrayIndexOutOfBoundsException
-    todo!()
+        array.len()
+    };
+    index_out_of_bounds(jvm, index, length)
 }
 
-fn get_index<T: Clone>(
-    array: &Mutex<Box<[T]>>,
-    index: i32,
-) -> Result<Result<T, Exception>, InternalError> {
-    if index >= 0 {
-        if let Some(elem) = get_index_raw(array, index as usize)? {
-            return Ok(Ok(elem));
+fn get_index<T: Clone>(jvm: &Jvm, array: &Mutex<Box<[T]>>, index: i32) -> ExecResult<T> {
+    let length = {
+        let array = array.lock()?;
+        let elem = usize::try_from(index)
+            .ok()
+            .and_then(|index| array.get(index));
+        if let Some(elem) = elem {
+            return Ok(Ok(elem.clone()));
         }
-    }
-    // TODO: Throw ArrayIndexOutOfBoundsException
-    todo!()
+        array.len()
+    };
+    index_out_of_bounds(jvm, index, length)
 }
 
 impl Array {
     pub fn new_reference(class: Arc<Class>, size: usize) -> Self {
+        let array = vec![null_item(&class); size].into_boxed_slice();
         Array::Reference(Arc::new(ReferenceArray {
-            array: Mutex::new(new_boxed_array(size)),
+            array: Mutex::new(array),
             class,
         }))
     }
@@ -147,6 +166,79 @@ impl Array {
         }
     }
 
+    /// Create the array of multinewarray, with the given size for each of its first dimensions
+    ///
+    /// The arrays of the last dimension given are filled with null
+    /// when the class has more dimensions than that.
+    pub fn new_multi_dimensional(jvm: &Jvm, class: &Class, sizes: &[usize]) -> ExecResult<Self> {
+        let (&size, inner_sizes) = sizes.split_first().ok_or(InternalError::WrongType)?;
+        let component = class
+            .get_name()
+            .strip_prefix('[')
+            .ok_or(InternalError::WrongType)?;
+        let primitive_type = match component {
+            "Z" => Some(ArrayType::Boolean),
+            "C" => Some(ArrayType::Char),
+            "F" => Some(ArrayType::Float),
+            "D" => Some(ArrayType::Double),
+            "B" => Some(ArrayType::Byte),
+            "S" => Some(ArrayType::Short),
+            "I" => Some(ArrayType::Int),
+            "J" => Some(ArrayType::Long),
+            _ => None,
+        };
+        if let Some(primitive_type) = primitive_type {
+            return jvm.heap().new_array(jvm, &primitive_type, size);
+        }
+
+        // the component class is loaded with the array class
+        let component_name = component
+            .strip_prefix('L')
+            .and_then(|name| name.strip_suffix(';'))
+            .unwrap_or(component);
+        let component_class = jvm
+            .class_loader()
+            .load_class(component_name)
+            .map_err(|_| InternalError::ClassLoadingFailed(component_name.to_string()))?;
+        // the inner arrays are created first, the collector can't run while an array is locked
+        let mut inner_arrays = Vec::new();
+        if !inner_sizes.is_empty() {
+            for _ in 0..size {
+                let inner = rethrow_exception!(Self::new_multi_dimensional(
+                    jvm,
+                    &component_class,
+                    inner_sizes
+                )?);
+                inner_arrays.push(Object::Array(Some(inner)));
+            }
+        }
+        let array_type = ArrayType::Reference(component_class);
+        let array = rethrow_exception!(jvm.heap().new_array(jvm, &array_type, size)?);
+        if let (false, Array::Reference(items)) = (inner_arrays.is_empty(), &array) {
+            items.lock()?.clone_from_slice(&inner_arrays);
+        }
+        Ok(Ok(array))
+    }
+
+    /// A shallow copy with the same class and items, like clone, still to be allocated
+    pub fn copy(&self) -> Result<Self, InternalError> {
+        let copy = match self {
+            Array::Boolean(array) => Array::Boolean(Arc::new(Mutex::new(array.lock()?.clone()))),
+            Array::Char(array) => Array::Char(Arc::new(Mutex::new(array.lock()?.clone()))),
+            Array::Float(array) => Array::Float(Arc::new(Mutex::new(array.lock()?.clone()))),
+            Array::Double(array) => Array::Double(Arc::new(Mutex::new(array.lock()?.clone()))),
+            Array::Byte(array) => Array::Byte(Arc::new(Mutex::new(array.lock()?.clone()))),
+            Array::Short(array) => Array::Short(Arc::new(Mutex::new(array.lock()?.clone()))),
+            Array::Int(array) => Array::Int(Arc::new(Mutex::new(array.lock()?.clone()))),
+            Array::Long(array) => Array::Long(Arc::new(Mutex::new(array.lock()?.clone()))),
+            Array::Reference(array) => Array::Reference(Arc::new(ReferenceArray {
+                array: Mutex::new(array.lock()?.clone()),
+                class: array.class.clone(),
+            })),
+        };
+        Ok(copy)
+    }
+
     pub fn size(&self) -> Result<i32, InternalError> {
         match self {
             Array::Boolean(array) => get_size(&array),
@@ -161,69 +253,128 @@ impl Array {
         }
     }
 
-    pub fn get_index(&self, index: i32) -> ExecResult {
+    /// The binary name of the class of the array, like `[I` or `[Ljava/lang/String;`
+    pub fn get_class_name(&self) -> String {
+        match self {
+            Array::Boolean(_) => "[Z".to_string(),
+            Array::Char(_) => "[C".to_string(),
+            Array::Float(_) => "[F".to_string(),
+            Array::Double(_) => "[D".to_string(),
+            Array::Byte(_) => "[B".to_string(),
+            Array::Short(_) => "[S".to_string(),
+            Array::Int(_) => "[I".to_string(),
+            Array::Long(_) => "[J".to_string(),
+            Array::Reference(array) => match array.get_component_class().get_name() {
+                component if component.starts_with('[') => format!("[{}", component),
+                component => format!("[L{};", component),
+            },
+        }
+    }
+
+    /// The class of the array, defined by the class loader the first time it is needed
+    pub fn get_class(&self, jvm: &Jvm) -> Result<Arc<Class>, InternalError> {
+        let class_name = self.get_class_name();
+        jvm.class_loader()
+            .load_class(&class_name)
+            .map_err(|_| InternalError::ClassLoadingFailed(class_name))
+    }
+
+    /// The address of the array, which identifies it while it is alive
+    pub fn address(&self) -> usize {
+        match self {
+            Array::Boolean(array) => Arc::as_ptr(array) as usize,
+            Array::Char(array) => Arc::as_ptr(array) as usize,
+            Array::Float(array) => Arc::as_ptr(array) as usize,
+            Array::Double(array) => Arc::as_ptr(array) as usize,
+            Array::Byte(array) => Arc::as_ptr(array) as usize,
+            Array::Short(array) => Arc::as_ptr(array) as usize,
+            Array::Int(array) => Arc::as_ptr(array) as usize,
+            Array::Long(array) => Arc::as_ptr(array) as usize,
+            Array::Reference(array) => Arc::as_ptr(array) as usize,
+        }
+    }
+
+    /// The hash code of Object.hashCode, derived from the identity of the array
+    pub fn identity_hash_code(&self) -> i32 {
+        match self {
+            Array::Boolean(array) => identity_hash_code(Arc::as_ptr(array)),
+            Array::Char(array) => identity_hash_code(Arc::as_ptr(array)),
+            Array::Float(array) => identity_hash_code(Arc::as_ptr(array)),
+            Array::Double(array) => identity_hash_code(Arc::as_ptr(array)),
+            Array::Byte(array) => identity_hash_code(Arc::as_ptr(array)),
+            Array::Short(array) => identity_hash_code(Arc::as_ptr(array)),
+            Array::Int(array) => identity_hash_code(Arc::as_ptr(array)),
+            Array::Long(array) => identity_hash_code(Arc::as_ptr(array)),
+            Array::Reference(array) => identity_hash_code(Arc::as_ptr(array)),
+        }
+    }
+
+    pub fn get_index(&self, jvm: &Jvm, index: i32) -> ExecResult {
         let value = match self {
             Array::Boolean(array) => {
-                let value = rethrow_exception!(get_index(array, index)?);
+                let value = rethrow_exception!(get_index(jvm, array, index)?);
                 Object::Int(value as i32)
             }
             Array::Char(array) => {
-                let value = rethrow_exception!(get_index(array, index)?);
+                let value = rethrow_exception!(get_index(jvm, array, index)?);
                 Object::Int(value as i32)
             }
             Array::Float(array) => {
-                let value = rethrow_exception!(get_index(array, index)?);
+                let value = rethrow_exception!(get_index(jvm, array, index)?);
                 Object::Float(value)
             }
             Array::Double(array) => {
-                let value = rethrow_exception!(get_index(array, index)?);
+                let value = rethrow_exception!(get_index(jvm, array, index)?);
                 Object::Double(value)
             }
             Array::Byte(array) => {
-                let value = rethrow_exception!(get_index(array, index)?);
+                let value = rethrow_exception!(get_index(jvm, array, index)?);
                 Object::Int(value as i32)
             }
             Array::Short(array) => {
-                let value = rethrow_exception!(get_index(array, index)?);
+                let value = rethrow_exception!(get_index(jvm, array, index)?);
                 Object::Int(value as i32)
             }
             Array::Int(array) => {
-                let value = rethrow_exception!(get_index(array, index)?);
+                let value = rethrow_exception!(get_index(jvm, array, index)?);
                 Object::Int(value)
             }
             Array::Long(array) => {
-                let value = rethrow_exception!(get_index(array, index)?);
+                let value = rethrow_exception!(get_index(jvm, array, index)?);
                 Object::Long(value)
             }
-            Array::Reference(array) => {
-                let value = rethrow_exception!(get_index(array, index)?);
-                Object::Reference(value)
-            }
+            Array::Reference(array) => rethrow_exception!(get_index(jvm, array, index)?),
         };
         Ok(Ok(ResultValue::Object(value)))
     }
 
-    pub fn store_index(&self, index: i32, value: Object) -> ExecResult {
+    pub fn store_index(&self, jvm: &Jvm, index: i32, value: Object) -> ExecResult {
         match (self, value) {
             (Array::Boolean(array), Object::Int(value)) => {
-                let value = if value % 2 == 0 { false } else { true };
-                store_index(array, index, value)
+                store_index(jvm, array, index, value & 1 == 1)
+            }
+            (Array::Char(array), Object::Int(value)) => {
+                store_index(jvm, array, index, value as u16)
+            }
+            (Array::Float(array), Object::Float(value)) => store_index(jvm, array, index, value),
+            (Array::Double(array), Object::Double(value)) => store_index(jvm, array, index, value),
+            (Array::Byte(array), Object::Int(value)) => store_index(jvm, array, index, value as i8),
+            (Array::Short(array), Object::Int(value)) => {
+                store_index(jvm, array, index, value as i16)
             }
-            (Array::Char(array), Object::Int(value)) => store_index(array, index, value as u8),
-            (Array::Float(array), Object::Float(value)) => store_index(array, index, value),
-            (Array::Double(array), Object::Double(value)) => store_index(array, index, value),
-            (Array::Byte(array), Object::Int(value)) => store_index(array, index, value as u8),
-            (Array::Short(array), Object::Int(value)) => store_index(array, index, value as i16),
-            (Array::Int(array), Object::Int(value)) => store_index(array, index, value),
-            (Array::Long(array), Object::Long(value)) => store_index(array, index, value),
-            (Array::Reference(array), Object::Reference(reference)) => {
-                if let Some(reference) = &reference {
-                    if !array.can_accept(reference) {
-                        // TODO: throw ArrayStoreException
-                        todo!()
-                    }
+            (Array::Int(array), Object::Int(value)) => store_index(jvm, array, index, value),
+            (Array::Long(array), Object::Long(value)) => store_index(jvm, array, index, value),
+            (Array::Reference(array), value @ (Object::Reference(_) | Object::Array(_))) => {
+                if !array.can_accept(jvm, &value)? {
+                    let class_name = match &value {
+                        Object::Reference(Some(reference)) => reference.get_class().get_name(),
+                        Object::Array(Some(array)) => &array.get_class_name(),
+                        _ => return Err(InternalError::WrongType),
+                    };
+                    let message = class_name.replace('/', ".");
+                    return throw_exception(jvm, ARRAY_STORE_EXCEPTION, Some(message));
                 }
-                store_index(array, index, reference)
+                store_index(jvm, array, index, array.as_item(value))
             }
             _ => Err(InternalError::WrongType),
         }
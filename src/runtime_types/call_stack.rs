@@ -0,0 +1,75 @@
+use std::{
+    cell::{Cell, RefCell},
+    sync::{Arc, Weak},
+};
+
+use super::Class;
+
+/// The most frames a thread can have, calling a method past it throws a StackOverflowError
+pub const MAX_FRAMES: usize = 2048;
+
+/// The stack size of the host threads running Java code
+///
+/// A frame of the interpreter takes about 6 KiB of host stack in a debug build and less than
+/// 1 KiB in a release build, this leaves room for the natives and the class initializers.
+pub const THREAD_STACK_SIZE: usize = 32 * 1024 * 1024;
+
+// a method being run, kept to be named in the stack traces
+struct Frame {
+    class: Weak<Class>,
+    method: Arc<str>,
+}
+
+thread_local! {
+    // the methods run by the current thread, the innermost last
+    static FRAMES: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+    // set while the StackOverflowError of a full stack is created
+    static BEYOND_LIMIT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The frame of a method on the call stack of the current thread, popped when dropped
+pub struct FrameGuard(());
+
+impl Drop for FrameGuard {
+    fn drop(&mut self) {
+        FRAMES.with(|frames| frames.borrow_mut().pop());
+    }
+}
+
+/// Push the frame of a method run by the current thread, None if it already has MAX_FRAMES
+pub fn enter_frame(class: Weak<Class>, method: Arc<str>) -> Option<FrameGuard> {
+    FRAMES.with(|frames| {
+        let mut frames = frames.borrow_mut();
+        if frames.len() >= MAX_FRAMES && !BEYOND_LIMIT.get() {
+            return None;
+        }
+        frames.push(Frame { class, method });
+        Some(FrameGuard(()))
+    })
+}
+
+/// Run `f` without the frame limit, to run the constructors of the StackOverflowError thrown
+/// when a thread reached it
+pub fn beyond_frame_limit<T>(f: impl FnOnce() -> T) -> T {
+    let previous = BEYOND_LIMIT.replace(true);
+    let result = f();
+    BEYOND_LIMIT.set(previous);
+    result
+}
+
+/// The frames of the current thread like a stack trace, the innermost first
+///
+/// The frames are named by the class and the method, the line numbers are not kept.
+/// The innermost frames for which `skip` returns true are left out.
+pub fn current_frames(skip: impl Fn(&Arc<Class>, &str) -> bool) -> Vec<String> {
+    FRAMES.with(|frames| {
+        frames
+            .borrow()
+            .iter()
+            .rev()
+            .filter_map(|frame| Some((frame.class.upgrade()?, &frame.method)))
+            .skip_while(|(class, method)| skip(class, method))
+            .map(|(class, method)| format!("{}.{}", class.get_name().replace('/', "."), method))
+            .collect()
+    })
+}
@@ -1,28 +1,606 @@
-use std::sync::Arc;
+use std::{
+    collections::HashSet,
+    sync::{Arc, Condvar, Mutex, OnceLock, Weak},
+    thread::{self, ThreadId},
+};
 
-use super::Method;
+use crate::{
+    parser::{
+        classfile::{classfile::AccesFlag, opcode::ArrayType},
+        descriptor::FieldType,
+    },
+    rethrow_exception,
+    runtime::jvm::Jvm,
+};
 
-#[derive(Debug, Clone)]
+use super::{
+    build_itable, build_vtable, collect_interfaces, default_value, maximally_specific_methods,
+    number_interface_methods, overrides, select_default_method, throw_exception, wrap_in_error,
+    ExecResult, Field, ITableEntry, InternalError, Method, Object, Reference, RuntimeConstant,
+    SelectedMethod, Stack, EXCEPTION_IN_INITIALIZER_ERROR, NO_CLASS_DEF_FOUND_ERROR,
+};
+
+pub const CLASS_CLASS: &str = "java/lang/Class";
+pub const CONSTRUCTOR_NAME: &str = "<init>";
+pub const CLASS_INITIALIZER_NAME: &str = "<clinit>";
+const CLASS_INITIALIZER_DESCRIPTOR: &str = "()V";
+const OBJECT_CLASS: &str = "java/lang/Object";
+
+/// Initialization state of a class (JVMS §5.5)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Initialization {
+    NotStarted,
+    // the class looks already initialized to the thread running its initialization
+    InProgress(ThreadId),
+    Done,
+    // the initialization threw an exception, the class can't be used anymore
+    Failed,
+}
+
+#[derive(Debug)]
 pub struct Class {
     name: String,
+    access_flags: AccesFlag,
     super_class: Option<Arc<Self>>,
-    methods: Vec<Method>,
+    interfaces: Vec<Arc<Self>>,
+    // fields declared by this class only
+    fields: Vec<Field>,
+    // initial value of every slot of the instances, inherited fields first
+    instance_slots: Box<[Object]>,
+    // values of the static fields declared by this class
+    static_slots: Mutex<Box<[Object]>>,
+    initialization: Mutex<Initialization>,
+    // notified when the initialization ends, for the threads waiting on another one
+    initialization_ended: Condvar,
+    methods: Vec<Arc<Method>>,
+    // methods run by invokevirtual, indexed by the dispatch index of the resolved method,
+    // interfaces don't have one as they are never the class of a receiver
+    vtable: Box<[Arc<Method>]>,
+    // one entry for every interface implemented by a class, empty for interfaces
+    itable: Box<[ITableEntry]>,
+    // the java/lang/Class representing the class, created the first time it is used
+    mirror: OnceLock<Reference>,
+    // the type of the items, for the array classes only
+    component_type: Option<ArrayType>,
+    // the identity of every super class and super interface, direct or not,
+    // so subtypes are checked in constant time
+    super_types: HashSet<usize>,
 }
 
 impl Class {
+    /// The methods need a reference to their class, so they are built once the class is allocated
+    ///
+    /// The dispatch tables are built with the methods, from the ones of the super types.
+    pub fn new<F>(
+        name: String,
+        access_flags: AccesFlag,
+        super_class: Option<Arc<Self>>,
+        interfaces: Vec<Arc<Self>>,
+        fields: Vec<Field>,
+        build_methods: F,
+    ) -> Arc<Self>
+    where
+        F: FnOnce(&Weak<Self>) -> Vec<Method>,
+    {
+        Self::define(
+            name,
+            access_flags,
+            super_class,
+            interfaces,
+            fields,
+            None,
+            build_methods,
+        )
+    }
+
+    /// An array class has no members of its own, it inherits those of its super class Object
+    /// and implements the interfaces given, Cloneable and Serializable (JLS §10.8)
+    pub fn new_array(
+        name: String,
+        access_flags: AccesFlag,
+        super_class: Arc<Self>,
+        interfaces: Vec<Arc<Self>>,
+        component_type: ArrayType,
+    ) -> Arc<Self> {
+        Self::define(
+            name,
+            access_flags,
+            Some(super_class),
+            interfaces,
+            Vec::new(),
+            Some(component_type),
+            |_| Vec::new(),
+        )
+    }
+
+    /// The class of a primitive type, like int.class, which has no super class and no members
+    pub fn new_primitive(name: String) -> Arc<Self> {
+        let access_flags = AccesFlag::PUBLIC | AccesFlag::FINAL | AccesFlag::ABSTRACT;
+        Self::define(
+            name,
+            AccesFlag::new(access_flags),
+            None,
+            Vec::new(),
+            Vec::new(),
+            None,
+            |_| Vec::new(),
+        )
+    }
+
+    fn define<F>(
+        name: String,
+        access_flags: AccesFlag,
+        super_class: Option<Arc<Self>>,
+        interfaces: Vec<Arc<Self>>,
+        fields: Vec<Field>,
+        component_type: Option<ArrayType>,
+        build_methods: F,
+    ) -> Arc<Self>
+    where
+        F: FnOnce(&Weak<Self>) -> Vec<Method>,
+    {
+        let mut instance_slots = super_class
+            .as_ref()
+            .map(|super_class| super_class.instance_slots.to_vec())
+            .unwrap_or_default();
+        for field in fields.iter().filter(|field| !field.is_static()) {
+            let slot = field.get_slot();
+            let size = field.get_descriptor().slot_size();
+            if instance_slots.len() < slot + size {
+                instance_slots.resize(slot + size, Object::Padding);
+            }
+            instance_slots[slot] = default_value(field.get_descriptor());
+        }
+
+        let mut static_slots = Vec::new();
+        for field in fields.iter().filter(|field| field.is_static()) {
+            let slot = field.get_slot();
+            let size = field.get_descriptor().slot_size();
+            if static_slots.len() < slot + size {
+                static_slots.resize(slot + size, Object::Padding);
+            }
+            static_slots[slot] = default_value(field.get_descriptor());
+        }
+
+        let mut super_types = HashSet::new();
+        for super_type in super_class.iter().chain(&interfaces) {
+            super_types.insert(class_id(super_type));
+            super_types.extend(&super_type.super_types);
+        }
+
+        Arc::new_cyclic(|class| {
+            let methods = build_methods(class);
+            let (methods, vtable, itable) = if access_flags.contains(AccesFlag::INTERFACE) {
+                let methods = number_interface_methods(methods);
+                (methods, Box::default(), Box::default())
+            } else {
+                let super_vtable = super_class
+                    .as_ref()
+                    .map_or(&[][..], |super_class| super_class.get_vtable());
+                let (methods, vtable) = build_vtable(&name, super_vtable, methods);
+                let all_interfaces = collect_interfaces(super_class.as_ref(), &interfaces);
+                let itable = build_itable(&methods, super_class.as_ref(), &all_interfaces);
+                (methods, vtable, itable)
+            };
+            Class {
+                name,
+                access_flags,
+                super_class,
+                interfaces,
+                fields,
+                instance_slots: instance_slots.into_boxed_slice(),
+                static_slots: Mutex::new(static_slots.into_boxed_slice()),
+                initialization: Mutex::new(Initialization::NotStarted),
+                initialization_ended: Condvar::new(),
+                methods,
+                vtable,
+                itable,
+                mirror: OnceLock::new(),
+                component_type,
+                super_types,
+            }
+        })
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_access_flags(&self) -> AccesFlag {
+        self.access_flags
+    }
+
+    pub fn is_interface(&self) -> bool {
+        self.access_flags.contains(AccesFlag::INTERFACE)
+    }
+
+    /// Only Object and the primitive types have no super class
+    pub fn is_primitive(&self) -> bool {
+        self.super_class.is_none() && self.name != OBJECT_CLASS
+    }
+
     pub fn get_superclass(&self) -> Option<&Arc<Self>> {
         self.super_class.as_ref()
     }
 
-    pub fn is_subclass(self: &Arc<Self>, super_class: &Arc<Self>) -> bool {
+    pub fn get_interfaces(&self) -> &[Arc<Self>] {
+        &self.interfaces
+    }
+
+    pub fn is_array(&self) -> bool {
+        self.component_type.is_some()
+    }
+
+    /// The type of the items of an array class, None for the other classes
+    pub fn get_component_type(&self) -> Option<&ArrayType> {
+        self.component_type.as_ref()
+    }
+
+    /// The java/lang/Class representing the class, always the same object (JLS §15.8.2)
+    pub fn get_mirror(self: &Arc<Self>, jvm: &Jvm) -> ExecResult<Reference> {
+        if let Some(mirror) = self.mirror.get() {
+            return Ok(Ok(mirror.clone()));
+        }
+        let class_class = jvm
+            .class_loader()
+            .load_class(CLASS_CLASS)
+            .map_err(|_| InternalError::ClassLoadingFailed(CLASS_CLASS.to_string()))?;
+        rethrow_exception!(class_class.initialize(jvm)?);
+        let mirror = Reference::new_mirror(class_class, self);
+        jvm.heap().track_object(&mirror)?;
+        Ok(Ok(self.mirror.get_or_init(|| mirror).clone()))
+    }
+
+    pub fn get_fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Number of slots of the instances, including the inherited fields
+    pub fn instance_size(&self) -> usize {
+        self.instance_slots.len()
+    }
+
+    /// The slots of a new instance, with every field set to its default value
+    pub fn new_instance_slots(&self) -> Box<[Object]> {
+        self.instance_slots.clone()
+    }
+
+    pub fn get_static(&self, slot: usize) -> Result<Object, InternalError> {
+        let static_slots = self.static_slots.lock()?;
+        static_slots
+            .get(slot)
+            .cloned()
+            .ok_or(InternalError::FieldSlotOutOfBounds)
+    }
+
+    /// Longs and doubles keep the padding of their second slot
+    pub fn set_static(&self, slot: usize, value: Object) -> Result<(), InternalError> {
+        let mut static_slots = self.static_slots.lock()?;
+        let static_slot = static_slots
+            .get_mut(slot)
+            .ok_or(InternalError::FieldSlotOutOfBounds)?;
+        *static_slot = value;
+        Ok(())
+    }
+
+    pub fn get_methods(&self) -> &[Arc<Method>] {
+        &self.methods
+    }
+
+    /// Only look at the methods declared by this class
+    pub fn find_method(&self, name: &str, descriptor: &str) -> Option<&Arc<Method>> {
+        self.methods
+            .iter()
+            .find(|method| method.get_name() == name && method.get_descriptor() == descriptor)
+    }
+
+    /// Only look at the fields declared by this class
+    pub fn find_field(&self, name: &str, descriptor: &FieldType) -> Option<&Field> {
+        self.fields
+            .iter()
+            .find(|field| field.get_name() == name && field.get_descriptor() == descriptor)
+    }
+
+    /// Field resolution (JVMS §5.4.3.2): look in the class, then its interfaces,
+    /// then its super class, and return the field with the class declaring it
+    pub fn resolve_field(
+        self: &Arc<Self>,
+        name: &str,
+        descriptor: &FieldType,
+    ) -> Option<(Arc<Self>, Field)> {
+        if let Some(field) = self.find_field(name, descriptor) {
+            return Some((self.clone(), field.clone()));
+        }
+        self.interfaces
+            .iter()
+            .chain(self.super_class.iter())
+            .find_map(|class| class.resolve_field(name, descriptor))
+    }
+
+    /// Method resolution (JVMS §5.4.3.3): look in the class and its super classes,
+    /// then in the super interfaces, and return the method with the class declaring it
+    pub fn resolve_method(
+        self: &Arc<Self>,
+        name: &str,
+        descriptor: &str,
+    ) -> Option<(&Arc<Self>, &Arc<Method>)> {
         let mut current = Some(self);
         while let Some(class) = current {
-            if Arc::ptr_eq(&class, super_class) {
-                return true;
-            } else {
+            if let Some(method) = class.find_method(name, descriptor) {
+                return Some((class, method));
+            }
+            current = class.get_superclass();
+        }
+        self.find_interface_method(name, descriptor)
+    }
+
+    /// Interface method resolution (JVMS §5.4.3.4): look in the interface,
+    /// then in the public methods of Object, then in the super interfaces
+    pub fn resolve_interface_method(
+        self: &Arc<Self>,
+        name: &str,
+        descriptor: &str,
+    ) -> Option<(&Arc<Self>, &Arc<Method>)> {
+        if let Some(method) = self.find_method(name, descriptor) {
+            return Some((self, method));
+        }
+        self.find_object_method(name, descriptor)
+            .or_else(|| self.find_interface_method(name, descriptor))
+    }
+
+    /// The public instance methods of Object, that are also members of the interfaces
+    fn find_object_method(
+        &self,
+        name: &str,
+        descriptor: &str,
+    ) -> Option<(&Arc<Self>, &Arc<Method>)> {
+        // the super class of an interface is always Object
+        let object = self.super_class.as_ref()?;
+        object
+            .find_method(name, descriptor)
+            .filter(|method| method.is_public() && !method.is_static())
+            .map(|method| (object, method))
+    }
+
+    /// Resolution picks the only non abstract maximally-specific method, or any of them
+    fn find_interface_method(
+        &self,
+        name: &str,
+        descriptor: &str,
+    ) -> Option<(&Arc<Self>, &Arc<Method>)> {
+        let methods = maximally_specific_methods(&self.get_all_interfaces(), name, descriptor);
+        let mut non_abstract = methods.iter().filter(|(_, method)| !method.is_abstract());
+        match (non_abstract.next(), non_abstract.next()) {
+            (Some(method), None) => Some(*method),
+            _ => methods.first().copied(),
+        }
+    }
+
+    pub fn get_vtable(&self) -> &[Arc<Method>] {
+        &self.vtable
+    }
+
+    pub fn get_itable(&self) -> &[ITableEntry] {
+        &self.itable
+    }
+
+    /// Method selection (JVMS §5.4.6) of invokevirtual and invokeinterface,
+    /// when the receiver is an instance of this class
+    ///
+    /// The slot of the resolved method is looked up in the vtable, or in the itable
+    /// for interface methods. None if the receiver is not an instance of the resolved class.
+    pub fn select_method(
+        &self,
+        resolved_class: &Arc<Self>,
+        resolved: &Arc<Method>,
+    ) -> Option<SelectedMethod> {
+        if resolved.is_private() {
+            return Some(Ok(Some(resolved.clone())));
+        }
+        let dispatch_index = resolved.get_dispatch_index()?;
+        if resolved_class.is_interface() {
+            let entry = self
+                .itable
+                .iter()
+                .find(|entry| Arc::ptr_eq(entry.get_interface(), resolved_class))?;
+            entry.get_method(dispatch_index).cloned()
+        } else {
+            let method = self.vtable.get(dispatch_index)?;
+            Some(Ok(
+                Some(method.clone()).filter(|method| !method.is_abstract())
+            ))
+        }
+    }
+
+    /// Method selection of invokespecial (JVMS §6.5), starting the lookup in this class
+    pub fn select_special_method(self: &Arc<Self>, name: &str, descriptor: &str) -> SelectedMethod {
+        let declared = if self.is_interface() {
+            self.find_instance_method(name, descriptor).or_else(|| {
+                self.find_object_method(name, descriptor)
+                    .map(|(_, method)| method)
+            })
+        } else {
+            let mut current = Some(self);
+            let mut declared = None;
+            while let (Some(class), None) = (current, declared) {
+                declared = class.find_instance_method(name, descriptor);
                 current = class.get_superclass();
             }
+            declared
+        };
+        match declared {
+            Some(method) => Ok(Some(method.clone()).filter(|method| !method.is_abstract())),
+            None => select_default_method(&self.get_all_interfaces(), name, descriptor),
+        }
+    }
+
+    fn find_instance_method(&self, name: &str, descriptor: &str) -> Option<&Arc<Method>> {
+        self.find_method(name, descriptor)
+            .filter(|method| !method.is_static())
+    }
+
+    /// A method of this class overriding a final method of a super class, with the final method
+    pub fn find_overridden_final_method(&self) -> Option<(&Arc<Method>, &Arc<Method>)> {
+        let super_vtable = self.super_class.as_ref()?.get_vtable();
+        self.methods.iter().find_map(|method| {
+            super_vtable
+                .iter()
+                .find(|inherited| {
+                    inherited.get_access_flags().contains(AccesFlag::FINAL)
+                        && overrides(&self.name, method, inherited)
+                })
+                .map(|inherited| (method, inherited))
+        })
+    }
+
+    /// The binary name of the package of the class, empty for the unnamed package
+    pub fn get_package(&self) -> &str {
+        get_package_name(&self.name)
+    }
+
+    /// Every interface implemented by the class, directly or through its super types
+    pub fn get_all_interfaces(&self) -> Vec<&Arc<Self>> {
+        collect_interfaces(self.super_class.as_ref(), &self.interfaces)
+    }
+
+    /// Whether the class implements the interface, or the interface extends it,
+    /// directly or through its super types
+    pub fn implements(&self, interface: &Arc<Self>) -> bool {
+        self.super_types.contains(&class_id(interface))
+    }
+
+    /// Whether a value of this class can be assigned to the type, like checkcast (JVMS §6.5)
+    ///
+    /// A class is a subclass of its super classes and of the interfaces it implements,
+    /// and an interface of Object. An array is a subclass of Object, Cloneable and Serializable,
+    /// and of the arrays of a super type of its items, if they are references.
+    pub fn is_subclass(self: &Arc<Self>, super_class: &Arc<Self>) -> bool {
+        if Arc::ptr_eq(self, super_class) {
+            return true;
+        }
+        match (&self.component_type, &super_class.component_type) {
+            (
+                Some(ArrayType::Reference(component)),
+                Some(ArrayType::Reference(super_component)),
+            ) => component.is_subclass(super_component),
+            // the arrays of primitives are only subclasses of themselves
+            (_, Some(_)) => false,
+            _ => self.super_types.contains(&class_id(super_class)),
+        }
+    }
+
+    /// Initialize the class before its first active use (JVMS §5.5)
+    ///
+    /// The super class is initialized first, then the constant values are set and <clinit> runs.
+    /// If an exception is thrown the class can't be initialized anymore,
+    /// and the next uses throw a NoClassDefFoundError.
+    pub fn initialize(self: &Arc<Self>, jvm: &Jvm) -> ExecResult<()> {
+        let current_thread = thread::current().id();
+        let has_failed = {
+            let mut initialization = self.initialization.lock()?;
+            loop {
+                match *initialization {
+                    Initialization::Done => return Ok(Ok(())),
+                    // recursive request, while running <clinit> or initializing a subclass
+                    Initialization::InProgress(thread) if thread == current_thread => {
+                        return Ok(Ok(()))
+                    }
+                    Initialization::InProgress(_) => {
+                        initialization = self.initialization_ended.wait(initialization)?;
+                    }
+                    Initialization::Failed => break true,
+                    Initialization::NotStarted => {
+                        *initialization = Initialization::InProgress(current_thread);
+                        break false;
+                    }
+                }
+            }
+        };
+        if has_failed {
+            let message = format!("Could not initialize class {}", self.name.replace('/', "."));
+            return throw_exception(jvm, NO_CLASS_DEF_FOUND_ERROR, Some(message));
+        }
+
+        let result = self.run_initialization(jvm);
+        let state = match result {
+            Ok(Ok(())) => Initialization::Done,
+            _ => Initialization::Failed,
+        };
+        *self.initialization.lock()? = state;
+        self.initialization_ended.notify_all();
+        result
+    }
+
+    fn run_initialization(self: &Arc<Self>, jvm: &Jvm) -> ExecResult<()> {
+        // the exceptions of the super types are thrown as is
+        if !self.is_interface() {
+            if let Some(super_class) = &self.super_class {
+                rethrow_exception!(super_class.initialize(jvm)?);
+            }
+            for interface in self.interfaces_with_default_methods() {
+                rethrow_exception!(interface.initialize(jvm)?);
+            }
+        }
+
+        rethrow_exception!(self.set_constant_values(jvm)?);
+
+        let Some(class_initializer) =
+            self.find_method(CLASS_INITIALIZER_NAME, CLASS_INITIALIZER_DESCRIPTOR)
+        else {
+            return Ok(Ok(()));
+        };
+        // errors thrown by <clinit> are thrown as is, other exceptions are wrapped
+        match class_initializer.execute(jvm, &mut Stack::new(0))? {
+            Ok(_) => Ok(Ok(())),
+            Err(exception) => {
+                wrap_in_error(jvm, exception, EXCEPTION_IN_INITIALIZER_ERROR).map(Err)
+            }
         }
-        false
     }
+
+    /// The String constants are interned, like the string literals
+    fn set_constant_values(&self, jvm: &Jvm) -> ExecResult<()> {
+        for field in self.fields.iter().filter(|field| field.is_static()) {
+            let value = match field.get_constant_value() {
+                Some(RuntimeConstant::Integer(value)) => Object::Int(*value),
+                Some(RuntimeConstant::Float(value)) => Object::Float(*value),
+                Some(RuntimeConstant::Long(value)) => Object::Long(*value),
+                Some(RuntimeConstant::Double(value)) => Object::Double(*value),
+                Some(RuntimeConstant::String(string)) => {
+                    let string = rethrow_exception!(string.resolve(jvm)?);
+                    Object::Reference(Some(string.clone()))
+                }
+                _ => continue,
+            };
+            self.set_static(field.get_slot(), value)?;
+        }
+        Ok(Ok(()))
+    }
+
+    /// The super interfaces declaring a default method, that are initialized with the class
+    fn interfaces_with_default_methods(&self) -> Vec<&Arc<Self>> {
+        let mut interfaces = Vec::new();
+        for interface in &self.interfaces {
+            interfaces.extend(interface.interfaces_with_default_methods());
+            let has_default_method = interface.methods.iter().any(|method| {
+                !method.is_static() && !method.get_access_flags().contains(AccesFlag::ABSTRACT)
+            });
+            if has_default_method {
+                interfaces.push(interface);
+            }
+        }
+        interfaces
+    }
+}
+
+// only one class is loaded for a name, so a class is identified by its address
+fn class_id(class: &Arc<Class>) -> usize {
+    Arc::as_ptr(class) as usize
+}
+
+/// The binary name of the package of a class, empty for the unnamed package
+pub fn get_package_name(class_name: &str) -> &str {
+    class_name
+        .rsplit_once('/')
+        .map_or("", |(package, _)| package)
 }
@@ -1,6 +1,8 @@
-use std::{ops::RangeInclusive, sync::Arc};
+use std::{ops::Range, sync::Arc};
 
-use super::{Class, InternalError, Object, OpCode, Reference};
+use crate::{rethrow_exception, runtime::jvm::Jvm};
+
+use super::{throw_loading_error, Class, ClassRef, InternalError, Object, OpCode, Reference};
 
 pub enum ResultValue {
     None,
@@ -11,22 +13,54 @@ pub enum ResultValue {
 }
 
 pub type Exception = Reference;
-pub type OpResult = Result<ResultValue, Exception>;
-pub type ExecResult = Result<OpResult, InternalError>;
+// T is the value produced by the operation when no exception is thrown
+pub type ExecResult<T = ResultValue> = Result<Result<T, Exception>, InternalError>;
 
 #[derive(Debug, Clone)]
 pub struct ExceptionTableInfo {
-    code_range: RangeInclusive<usize>,
+    code_range: Range<usize>,
     handler_pc: usize,
-    catch_type: Arc<Class>,
+    // None catch any exception
+    catch_type: Option<Arc<ClassRef>>,
 }
 
 impl ExceptionTableInfo {
-    fn does_handle(&self, current_pc: usize, exception_class: &Arc<Class>) -> Option<usize> {
-        if self.code_range.contains(&current_pc) && exception_class.is_subclass(&self.catch_type) {
-            Some(self.handler_pc)
+    pub fn new(
+        code_range: Range<usize>,
+        handler_pc: usize,
+        catch_type: Option<Arc<ClassRef>>,
+    ) -> Self {
+        ExceptionTableInfo {
+            code_range,
+            handler_pc,
+            catch_type,
+        }
+    }
+
+    fn does_catch(&self, jvm: &Jvm, exception_class: &Arc<Class>) -> ExecResult<bool> {
+        let Some(catch_type) = &self.catch_type else {
+            return Ok(Ok(true));
+        };
+        let catch_type = match catch_type.resolve(jvm.class_loader()) {
+            Ok(catch_type) => catch_type,
+            Err(error) => return throw_loading_error(jvm, error),
+        };
+        Ok(Ok(exception_class.is_subclass(catch_type)))
+    }
+
+    fn does_handle(
+        &self,
+        jvm: &Jvm,
+        current_pc: usize,
+        exception_class: &Arc<Class>,
+    ) -> ExecResult<Option<usize>> {
+        // only resolve the catch type if the pc is in range
+        if self.code_range.contains(&current_pc)
+            && rethrow_exception!(self.does_catch(jvm, exception_class)?)
+        {
+            Ok(Ok(Some(self.handler_pc)))
         } else {
-            None
+            Ok(Ok(None))
         }
     }
 }
@@ -41,11 +75,25 @@ impl ExceptionTable {
         ExceptionTable { infos }
     }
 
-    fn get_jump(&self, current_pc: usize, exception_class: &Arc<Class>) -> Option<usize> {
-        self.infos
-            .as_ref()?
-            .iter()
-            .find_map(|info| info.does_handle(current_pc, exception_class))
+    /// The handler of the exception thrown at the pc, if there is one
+    ///
+    /// A catch type that can't be resolved throws its LinkageError instead.
+    fn get_jump(
+        &self,
+        jvm: &Jvm,
+        current_pc: usize,
+        exception_class: &Arc<Class>,
+    ) -> ExecResult<Option<usize>> {
+        let Some(infos) = &self.infos else {
+            return Ok(Ok(None));
+        };
+        for info in infos {
+            let jump = rethrow_exception!(info.does_handle(jvm, current_pc, exception_class)?);
+            if jump.is_some() {
+                return Ok(Ok(jump));
+            }
+        }
+        Ok(Ok(None))
     }
 }
 
@@ -81,7 +129,7 @@ impl Code {
         Locals::from_stack(self.max_locals, self.args_count, stack)
     }
 
-    pub fn execute(&self, caller_stack: &mut Stack) -> MethodCallResult {
+    pub fn execute(&self, jvm: &Jvm, caller_stack: &mut Stack) -> MethodCallResult {
         let mut locals = self.create_locals(caller_stack)?;
         let mut stack = Stack::new(self.max_stack);
         let mut programm_counter = 0;
@@ -89,7 +137,7 @@ impl Code {
             let Some(opcode) = self.opcodes.get(programm_counter) else {
                 return Err(InternalError::InvalidProgrammCounter);
             };
-            let result = opcode.execute(&mut locals, &mut stack)?;
+            let result = opcode.execute(jvm, &mut locals, &mut stack)?;
             match result {
                 Ok(ResultValue::None) => {
                     programm_counter += 1;
@@ -109,11 +157,18 @@ impl Code {
                 }
                 Err(exception) => {
                     let exception_class = exception.get_class();
-                    let Some(handle_pc) = self.exception_table.get_jump(programm_counter, exception_class) else {
+                    // the error of a catch type that can't be resolved leaves the method
+                    let Some(handle_pc) = rethrow_exception!(self.exception_table.get_jump(
+                        jvm,
+                        programm_counter,
+                        exception_class,
+                    )?) else {
                         return Ok(Err(exception));
                     };
+                    // the handler starts with only the exception on the stack
                     programm_counter = handle_pc;
                     stack.clear();
+                    stack.push(Object::Reference(Some(exception)));
                 }
             }
         }
@@ -139,6 +194,16 @@ impl Stack {
         self.stack.last().ok_or(InternalError::EmptyStack)
     }
 
+    /// The value `depth` slots under the top of the stack, like the receiver under the arguments
+    pub fn peek_at(&self, depth: usize) -> Result<&Object, InternalError> {
+        let index = self
+            .stack
+            .len()
+            .checked_sub(depth + 1)
+            .ok_or(InternalError::EmptyStack)?;
+        Ok(&self.stack[index])
+    }
+
     pub fn pop(&mut self) -> Result<Object, InternalError> {
         let value = self.pop_single()?;
         if value.is_wide() {
@@ -202,7 +267,8 @@ impl Stack {
             self.push(top);
         } else {
             let second = self.pop_single()?;
-            self.stack.push(second);
+            self.stack
+                .extend([second.clone(), top.clone(), second, top]);
         }
         Ok(())
     }
@@ -279,12 +345,12 @@ impl Stack {
         Ok(())
     }
 
+    /// Wide values are pushed above their padding, so pop finds the value first
     pub fn push(&mut self, value: Object) {
-        let is_wide = value.is_wide();
-        self.stack.push(value);
-        if is_wide {
+        if value.is_wide() {
             self.stack.push(Object::Padding);
         }
+        self.stack.push(value);
     }
 
     pub fn clear(&mut self) {
@@ -310,12 +376,15 @@ impl Locals {
         stack: &mut Stack,
     ) -> Result<Self, InternalError> {
         let mut locals = Self::new(max_size);
-        for i in 0..arg_count {
-            let value = stack.pop_single()?;
-            let Some(place_to_store) = locals.locals.get_mut(i) else {
-                return Err(InternalError::LocalsOutOfBounds);
-            };
-            *place_to_store = Some(value);
+        // the last argument is on top of the stack, longs and doubles take two slots
+        let mut remaining_slots = arg_count;
+        while remaining_slots > 0 {
+            let value = stack.pop()?;
+            let size = if value.is_wide() { 2 } else { 1 };
+            remaining_slots = remaining_slots
+                .checked_sub(size)
+                .ok_or(InternalError::InvalidWideLoad)?;
+            locals.store(remaining_slots, value)?;
         }
         Ok(locals)
     }
@@ -0,0 +1,499 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex, OnceLock},
+    thread::{self, ThreadId},
+};
+
+use crate::{
+    parser::{
+        classfile::constant_pool::MethodHandleKind,
+        descriptor::{FieldType, MethodDescriptor},
+        utils::ParseError,
+    },
+    rethrow_exception,
+    runtime::{
+        class_loader::{ClassLoader, ClassLoadingError},
+        invoke::{link_call_site, resolve_dynamic_constant, CallSite},
+        jvm::Jvm,
+    },
+};
+
+use super::{
+    intern_utf16_string, throw_exception, Class, Exception, ExecResult, Field, InternalError,
+    Method, Object, Reference, STACK_OVERFLOW_ERROR,
+};
+
+/// Symbolic reference to a class, resolved lazily the first time it is used
+#[derive(Debug)]
+pub struct ClassRef {
+    name: String,
+    resolved: OnceLock<Arc<Class>>,
+}
+
+impl ClassRef {
+    pub fn new(name: String) -> Self {
+        ClassRef {
+            name,
+            resolved: OnceLock::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get(&self) -> Option<&Arc<Class>> {
+        self.resolved.get()
+    }
+
+    pub fn resolve(&self, class_loader: &ClassLoader) -> Result<&Arc<Class>, ClassLoadingError> {
+        if let Some(class) = self.resolved.get() {
+            return Ok(class);
+        }
+        let class = class_loader.load_class(&self.name)?;
+        // another thread may have resolved it in the meantime,
+        // but the loader registry guarantee it is the same class
+        Ok(self.resolved.get_or_init(|| class))
+    }
+}
+
+/// Symbolic reference to a field, resolved lazily the first time it is used
+#[derive(Debug)]
+pub struct FieldRef {
+    class: Arc<ClassRef>,
+    name: String,
+    descriptor: FieldType,
+    // the field with the class declaring it, which can be a super class of the referenced one
+    resolved: OnceLock<(Arc<Class>, Field)>,
+}
+
+impl FieldRef {
+    pub fn new(class: Arc<ClassRef>, name: String, descriptor: FieldType) -> Self {
+        FieldRef {
+            class,
+            name,
+            descriptor,
+            resolved: OnceLock::new(),
+        }
+    }
+
+    /// None if the field doesn't exist, which is a NoSuchFieldError
+    pub fn resolve(
+        &self,
+        class_loader: &ClassLoader,
+    ) -> Result<Option<&(Arc<Class>, Field)>, ClassLoadingError> {
+        if let Some(resolved) = self.resolved.get() {
+            return Ok(Some(resolved));
+        }
+        let class = self.class.resolve(class_loader)?;
+        let Some(resolved) = class.resolve_field(&self.name, &self.descriptor) else {
+            return Ok(None);
+        };
+        Ok(Some(self.resolved.get_or_init(|| resolved)))
+    }
+
+    pub fn class(&self) -> &Arc<ClassRef> {
+        &self.class
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn descriptor(&self) -> &FieldType {
+        &self.descriptor
+    }
+}
+
+/// A string literal, resolved lazily to the interned string the first time it is used
+///
+/// It is kept as UTF-16 code units, which can hold lone surrogates like the string.
+#[derive(Debug)]
+pub struct StringRef {
+    units: Box<[u16]>,
+    resolved: OnceLock<Reference>,
+}
+
+impl StringRef {
+    pub fn new(units: Box<[u16]>) -> Self {
+        StringRef {
+            units,
+            resolved: OnceLock::new(),
+        }
+    }
+
+    pub fn units(&self) -> &[u16] {
+        &self.units
+    }
+
+    /// Every literal with the same text resolves to the same string, in every class
+    pub fn resolve(&self, jvm: &Jvm) -> ExecResult<&Reference> {
+        if let Some(string) = self.resolved.get() {
+            return Ok(Ok(string));
+        }
+        let string = rethrow_exception!(intern_utf16_string(jvm, self.units.clone())?);
+        Ok(Ok(self.resolved.get_or_init(|| string)))
+    }
+}
+
+/// A resolved method with the class declaring it
+pub type ResolvedMethod = (Arc<Class>, Arc<Method>);
+
+/// Symbolic reference to a method, used for both class and interface methods
+#[derive(Debug)]
+pub struct MethodRef {
+    class: Arc<ClassRef>,
+    name: String,
+    descriptor: String,
+    parsed_descriptor: MethodDescriptor,
+    // the method with the class declaring it, which can be a super type of the referenced one
+    resolved: OnceLock<ResolvedMethod>,
+}
+
+impl MethodRef {
+    pub fn new(
+        class: Arc<ClassRef>,
+        name: String,
+        descriptor: String,
+        parsed_descriptor: MethodDescriptor,
+    ) -> Self {
+        MethodRef {
+            class,
+            name,
+            descriptor,
+            parsed_descriptor,
+            resolved: OnceLock::new(),
+        }
+    }
+
+    /// Resolve the method, as an interface method if the referenced class is an interface
+    ///
+    /// None if the method doesn't exist, which is a NoSuchMethodError
+    pub fn resolve(
+        &self,
+        class_loader: &ClassLoader,
+    ) -> Result<Option<&ResolvedMethod>, ClassLoadingError> {
+        if let Some(resolved) = self.resolved.get() {
+            return Ok(Some(resolved));
+        }
+        let class = self.class.resolve(class_loader)?;
+        let resolved = if class.is_interface() {
+            class.resolve_interface_method(&self.name, &self.descriptor)
+        } else {
+            class.resolve_method(&self.name, &self.descriptor)
+        };
+        let Some((declaring_class, method)) = resolved else {
+            return Ok(None);
+        };
+        let resolved = (declaring_class.clone(), method.clone());
+        Ok(Some(self.resolved.get_or_init(|| resolved)))
+    }
+
+    pub fn class(&self) -> &Arc<ClassRef> {
+        &self.class
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn descriptor(&self) -> &str {
+        &self.descriptor
+    }
+
+    pub fn parsed_descriptor(&self) -> &MethodDescriptor {
+        &self.parsed_descriptor
+    }
+}
+
+/// The field or the method a method handle refers to, depending on its kind
+#[derive(Debug, Clone)]
+pub enum MethodHandleTarget {
+    Field(Arc<FieldRef>),
+    Method(Arc<MethodRef>),
+}
+
+/// A MethodHandle constant, used to refer to the bootstrap methods and in their arguments
+#[derive(Debug)]
+pub struct MethodHandleRef {
+    kind: MethodHandleKind,
+    target: MethodHandleTarget,
+}
+
+impl MethodHandleRef {
+    pub fn new(kind: MethodHandleKind, target: MethodHandleTarget) -> Self {
+        MethodHandleRef { kind, target }
+    }
+
+    pub fn kind(&self) -> MethodHandleKind {
+        self.kind
+    }
+
+    pub fn target(&self) -> &MethodHandleTarget {
+        &self.target
+    }
+
+    /// None for the handles of the field accessors
+    pub fn method(&self) -> Option<&Arc<MethodRef>> {
+        match &self.target {
+            MethodHandleTarget::Method(method) => Some(method),
+            MethodHandleTarget::Field(_) => None,
+        }
+    }
+}
+
+/// A MethodType constant
+#[derive(Debug)]
+pub struct MethodTypeRef {
+    descriptor: String,
+    parsed_descriptor: MethodDescriptor,
+}
+
+impl MethodTypeRef {
+    pub fn new(descriptor: String, parsed_descriptor: MethodDescriptor) -> Self {
+        MethodTypeRef {
+            descriptor,
+            parsed_descriptor,
+        }
+    }
+
+    pub fn descriptor(&self) -> &str {
+        &self.descriptor
+    }
+
+    pub fn parsed_descriptor(&self) -> &MethodDescriptor {
+        &self.parsed_descriptor
+    }
+}
+
+/// An entry of the BootstrapMethods attribute, shared by the constants using it
+#[derive(Debug)]
+pub struct BootstrapMethod {
+    method_handle: Arc<MethodHandleRef>,
+    // the static arguments given after the lookup, the name and the type
+    arguments: Vec<RuntimeConstant>,
+}
+
+impl BootstrapMethod {
+    pub fn new(method_handle: Arc<MethodHandleRef>, arguments: Vec<RuntimeConstant>) -> Self {
+        BootstrapMethod {
+            method_handle,
+            arguments,
+        }
+    }
+
+    pub fn method_handle(&self) -> &Arc<MethodHandleRef> {
+        &self.method_handle
+    }
+
+    pub fn arguments(&self) -> &[RuntimeConstant] {
+        &self.arguments
+    }
+}
+
+/// The call site of an invokedynamic, linked by its bootstrap method the first time it is run
+#[derive(Debug)]
+pub struct CallSiteRef {
+    name: String,
+    descriptor: String,
+    parsed_descriptor: MethodDescriptor,
+    // set once all the constants are lowered, as the bootstrap arguments can be any of them
+    bootstrap_method: OnceLock<Arc<BootstrapMethod>>,
+    // the error thrown by the bootstrap method is kept too, every later run throws it again
+    linked: OnceLock<Result<CallSite, Exception>>,
+}
+
+impl CallSiteRef {
+    pub fn new(name: String, descriptor: String, parsed_descriptor: MethodDescriptor) -> Self {
+        CallSiteRef {
+            name,
+            descriptor,
+            parsed_descriptor,
+            bootstrap_method: OnceLock::new(),
+            linked: OnceLock::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn descriptor(&self) -> &str {
+        &self.descriptor
+    }
+
+    pub fn parsed_descriptor(&self) -> &MethodDescriptor {
+        &self.parsed_descriptor
+    }
+
+    pub fn set_bootstrap_method(&self, bootstrap_method: Arc<BootstrapMethod>) {
+        let _ = self.bootstrap_method.set(bootstrap_method);
+    }
+
+    pub fn bootstrap_method(&self) -> Result<&Arc<BootstrapMethod>, InternalError> {
+        self.bootstrap_method
+            .get()
+            .ok_or(InternalError::MissingBootstrapMethod)
+    }
+
+    /// `caller` is the class running the invokedynamic
+    ///
+    /// A failed linkage is not retried, the same error is thrown again (JVMS §6.5).
+    /// Threads linking the call site at the same time can both run the bootstrap method,
+    /// only the first result is kept.
+    pub fn link(&self, jvm: &Jvm, caller: &Arc<ClassRef>) -> ExecResult<&CallSite> {
+        let linked = match self.linked.get() {
+            Some(linked) => linked,
+            None => {
+                let linked = link_call_site(jvm, self, caller)?;
+                self.linked.get_or_init(|| linked)
+            }
+        };
+        Ok(linked.as_ref().map_err(Clone::clone))
+    }
+}
+
+/// A dynamically-computed constant, computed by its bootstrap method the first time it is loaded
+#[derive(Debug)]
+pub struct DynamicRef {
+    name: String,
+    descriptor: FieldType,
+    // set once all the constants are lowered, like the bootstrap methods of the call sites
+    bootstrap_method: OnceLock<Arc<BootstrapMethod>>,
+    // the error thrown by the bootstrap method is kept too, every later load throws it again
+    resolved: OnceLock<Result<Object, Exception>>,
+    // the threads running the bootstrap method, to catch a constant needing its own value
+    resolving: Mutex<HashSet<ThreadId>>,
+}
+
+impl DynamicRef {
+    pub fn new(name: String, descriptor: FieldType) -> Self {
+        DynamicRef {
+            name,
+            descriptor,
+            bootstrap_method: OnceLock::new(),
+            resolved: OnceLock::new(),
+            resolving: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn descriptor(&self) -> &FieldType {
+        &self.descriptor
+    }
+
+    pub fn set_bootstrap_method(&self, bootstrap_method: Arc<BootstrapMethod>) {
+        let _ = self.bootstrap_method.set(bootstrap_method);
+    }
+
+    pub fn bootstrap_method(&self) -> Result<&Arc<BootstrapMethod>, InternalError> {
+        self.bootstrap_method
+            .get()
+            .ok_or(InternalError::MissingBootstrapMethod)
+    }
+
+    /// `caller` is the class whose constant pool holds the constant
+    ///
+    /// A failed resolution is not retried, the same error is thrown again (JVMS §5.4.3).
+    /// Threads resolving the constant at the same time can both run the bootstrap method,
+    /// only the first result is kept. A constant given to its own bootstrap method, directly
+    /// or not, would be resolved forever, a StackOverflowError is thrown instead.
+    pub fn resolve(&self, jvm: &Jvm, caller: &Arc<ClassRef>) -> ExecResult<Object> {
+        if let Some(resolved) = self.resolved.get() {
+            return Ok(resolved.clone());
+        }
+        let current = thread::current().id();
+        if !self.resolving.lock()?.insert(current) {
+            return throw_exception(jvm, STACK_OVERFLOW_ERROR, None);
+        }
+        let resolved = resolve_dynamic_constant(jvm, self, caller);
+        self.resolving.lock()?.remove(&current);
+        let resolved = resolved?;
+        Ok(self.resolved.get_or_init(|| resolved).clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RuntimeConstant {
+    Class(Arc<ClassRef>),
+    Field(Arc<FieldRef>),
+    Method(Arc<MethodRef>),
+    InterfaceMethod(Arc<MethodRef>),
+    String(Arc<StringRef>),
+    MethodHandle(Arc<MethodHandleRef>),
+    MethodType(Arc<MethodTypeRef>),
+    InvokeDynamic(Arc<CallSiteRef>),
+    Dynamic(Arc<DynamicRef>),
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    // entries only used by other entries (Utf8, NameAndType, ...), padding, or not supported yet
+    Unusable,
+}
+
+/// Constant pool of a class where all the symbolic references have been built
+///
+/// The references are shared between all the opcodes of the class using them,
+/// so they only need to be resolved once.
+#[derive(Debug, Clone)]
+pub struct RuntimeConstantPool {
+    constants: Vec<RuntimeConstant>,
+}
+
+macro_rules! impl_get_constant {
+    ($(($fn_name:ident, $pattern:path, $ret:ty, $expected:literal)), +) => {
+        $(pub fn $fn_name(&self, index: usize) -> Result<&$ret, ParseError> {
+            match self.get_checked(index)? {
+                $pattern(value) => Ok(value),
+                _ => Err(ParseError::UnexpectedConstant {
+                    index,
+                    expected: $expected,
+                }),
+            }
+        })+
+    };
+}
+
+impl RuntimeConstantPool {
+    pub fn new(constants: Vec<RuntimeConstant>) -> Self {
+        RuntimeConstantPool { constants }
+    }
+
+    /// indexes start at 1, like in the class file
+    pub fn get(&self, index: usize) -> Option<&RuntimeConstant> {
+        self.constants.get(index.checked_sub(1)?)
+    }
+
+    pub fn size(&self) -> usize {
+        self.constants.len() + 1
+    }
+
+    pub fn get_checked(&self, index: usize) -> Result<&RuntimeConstant, ParseError> {
+        self.get(index).ok_or(ParseError::BadConstPoolIndex {
+            target_index: index,
+            pool_size: self.size(),
+        })
+    }
+
+    impl_get_constant!(
+        (get_class, RuntimeConstant::Class, Arc<ClassRef>, "Class"),
+        (get_field, RuntimeConstant::Field, Arc<FieldRef>, "Fieldref"),
+        (get_method, RuntimeConstant::Method, Arc<MethodRef>, "Methodref"),
+        (
+            get_interface_method,
+            RuntimeConstant::InterfaceMethod,
+            Arc<MethodRef>,
+            "InterfaceMethodref"
+        ),
+        (
+            get_invoke_dynamic,
+            RuntimeConstant::InvokeDynamic,
+            Arc<CallSiteRef>,
+            "InvokeDynamic"
+        )
+    );
+}
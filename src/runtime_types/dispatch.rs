@@ -0,0 +1,238 @@
+use std::sync::Arc;
+
+use crate::parser::classfile::classfile::AccesFlag;
+
+use super::{get_package_name, Class, Method, CONSTRUCTOR_NAME};
+
+/// The default methods of several unrelated interfaces could be selected,
+/// with the names of the interfaces declaring them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MethodSelectionError {
+    ConflictingDefaultMethods(Vec<String>),
+}
+
+/// The method run for a resolved method, None if it is not implemented (AbstractMethodError)
+pub type SelectedMethod = Result<Option<Arc<Method>>, MethodSelectionError>;
+
+/// The methods run by invokeinterface for the methods of an interface implemented by a class
+#[derive(Debug)]
+pub struct ITableEntry {
+    interface: Arc<Class>,
+    // indexed by the dispatch index of the interface methods
+    methods: Box<[SelectedMethod]>,
+}
+
+impl ITableEntry {
+    pub fn get_interface(&self) -> &Arc<Class> {
+        &self.interface
+    }
+
+    pub fn get_method(&self, dispatch_index: usize) -> Option<&SelectedMethod> {
+        self.methods.get(dispatch_index)
+    }
+}
+
+/// Static methods, private methods and constructors are always called directly
+fn is_dispatched(method: &Method) -> bool {
+    !method.is_static() && !method.is_private() && method.get_name() != CONSTRUCTOR_NAME
+}
+
+/// A method declared in a class overrides an inherited one if it has the same name and descriptor,
+/// and the inherited one is accessible from the class (JVMS §5.4.5)
+pub fn overrides(class_name: &str, method: &Method, inherited: &Method) -> bool {
+    if !is_dispatched(method)
+        || method.get_name() != inherited.get_name()
+        || method.get_descriptor() != inherited.get_descriptor()
+    {
+        return false;
+    }
+    let flags = inherited.get_access_flags();
+    flags.contains(AccesFlag::PUBLIC)
+        || flags.contains(AccesFlag::PROTECTED)
+        || inherited.get_class().is_some_and(|inherited_class| {
+            inherited_class.get_package() == get_package_name(class_name)
+        })
+}
+
+/// Build the vtable of a class from the vtable of its super class
+///
+/// A method takes the slots of the inherited methods it overrides,
+/// several of them when a package private method is overridden from its package,
+/// other methods get a new slot at the end.
+pub fn build_vtable(
+    class_name: &str,
+    super_vtable: &[Arc<Method>],
+    mut methods: Vec<Method>,
+) -> (Vec<Arc<Method>>, Box<[Arc<Method>]>) {
+    let mut vtable_size = super_vtable.len();
+    // the slots of each method, in the order they are declared
+    let mut method_slots = Vec::with_capacity(methods.len());
+    for method in methods.iter_mut() {
+        if !is_dispatched(method) {
+            method_slots.push(Vec::new());
+            continue;
+        }
+        let mut slots: Vec<usize> = super_vtable
+            .iter()
+            .enumerate()
+            .filter(|(_, inherited)| overrides(class_name, method, inherited))
+            .map(|(slot, _)| slot)
+            .collect();
+        if slots.is_empty() {
+            slots.push(vtable_size);
+            vtable_size += 1;
+        }
+        method.set_dispatch_index(slots[0]);
+        method_slots.push(slots);
+    }
+
+    let methods: Vec<Arc<Method>> = methods.into_iter().map(Arc::new).collect();
+    let mut vtable: Vec<Option<Arc<Method>>> = super_vtable.iter().cloned().map(Some).collect();
+    vtable.resize(vtable_size, None);
+    for (method, slots) in methods.iter().zip(method_slots) {
+        for slot in slots {
+            vtable[slot] = Some(method.clone());
+        }
+    }
+    // every new slot was given to a method
+    (methods, vtable.into_iter().flatten().collect())
+}
+
+/// The methods of an interface are dispatched through the itables of the classes,
+/// where they take a slot in the order they are declared
+pub fn number_interface_methods(methods: Vec<Method>) -> Vec<Arc<Method>> {
+    let mut dispatch_index = 0;
+    methods
+        .into_iter()
+        .map(|mut method| {
+            if is_dispatched(&method) {
+                method.set_dispatch_index(dispatch_index);
+                dispatch_index += 1;
+            }
+            Arc::new(method)
+        })
+        .collect()
+}
+
+/// Every interface implemented by a class, directly or through its super types
+pub fn collect_interfaces<'a>(
+    super_class: Option<&'a Arc<Class>>,
+    interfaces: &'a [Arc<Class>],
+) -> Vec<&'a Arc<Class>> {
+    let direct = interfaces
+        .iter()
+        .flat_map(|interface| std::iter::once(interface).chain(interface.get_all_interfaces()));
+    let inherited = super_class
+        .into_iter()
+        .flat_map(|super_class| super_class.get_all_interfaces());
+    let mut all_interfaces: Vec<&Arc<Class>> = Vec::new();
+    for interface in direct.chain(inherited) {
+        if !all_interfaces
+            .iter()
+            .any(|known| Arc::ptr_eq(known, interface))
+        {
+            all_interfaces.push(interface);
+        }
+    }
+    all_interfaces
+}
+
+/// The maximally-specific superinterface methods (JVMS §5.4.3.3): the instance methods
+/// of the interfaces that are not overridden by a more specific interface
+pub fn maximally_specific_methods<'a>(
+    interfaces: &[&'a Arc<Class>],
+    name: &str,
+    descriptor: &str,
+) -> Vec<(&'a Arc<Class>, &'a Arc<Method>)> {
+    let candidates: Vec<(&Arc<Class>, &Arc<Method>)> = interfaces
+        .iter()
+        .filter_map(|interface| {
+            interface
+                .find_method(name, descriptor)
+                .filter(|method| !method.is_private() && !method.is_static())
+                .map(|method| (*interface, method))
+        })
+        .collect();
+    candidates
+        .iter()
+        .filter(|(interface, _)| {
+            !candidates
+                .iter()
+                .any(|(other, _)| !Arc::ptr_eq(other, interface) && other.implements(interface))
+        })
+        .copied()
+        .collect()
+}
+
+/// Selection needs exactly one non abstract maximally-specific method
+pub fn select_default_method(
+    interfaces: &[&Arc<Class>],
+    name: &str,
+    descriptor: &str,
+) -> SelectedMethod {
+    let non_abstract: Vec<_> = maximally_specific_methods(interfaces, name, descriptor)
+        .into_iter()
+        .filter(|(_, method)| !method.is_abstract())
+        .collect();
+    match non_abstract.as_slice() {
+        [] => Ok(None),
+        [(_, method)] => Ok(Some((*method).clone())),
+        conflicting => Err(MethodSelectionError::ConflictingDefaultMethods(
+            conflicting
+                .iter()
+                .map(|(interface, _)| interface.get_name().to_string())
+                .collect(),
+        )),
+    }
+}
+
+/// Build the itable of a class, selecting the method run for every method
+/// of every implemented interface (JVMS §5.4.6)
+///
+/// The methods declared by the class and its super classes come first, then the default methods.
+pub fn build_itable(
+    methods: &[Arc<Method>],
+    super_class: Option<&Arc<Class>>,
+    interfaces: &[&Arc<Class>],
+) -> Box<[ITableEntry]> {
+    let find_declared = |name: &str, descriptor: &str| {
+        let declared = methods.iter().find(|method| {
+            method.get_name() == name
+                && method.get_descriptor() == descriptor
+                && is_dispatched(method)
+        });
+        let mut current = super_class;
+        let mut declared = declared.cloned();
+        while let (None, Some(class)) = (&declared, current) {
+            declared = class
+                .find_method(name, descriptor)
+                .filter(|method| is_dispatched(method))
+                .cloned();
+            current = class.get_superclass();
+        }
+        declared
+    };
+
+    interfaces
+        .iter()
+        .map(|interface| {
+            let methods = interface
+                .get_methods()
+                .iter()
+                .filter(|method| method.get_dispatch_index().is_some())
+                .map(|method| {
+                    let name = method.get_name();
+                    let descriptor = method.get_descriptor();
+                    match find_declared(name, descriptor) {
+                        Some(declared) => Ok(Some(declared).filter(|method| !method.is_abstract())),
+                        None => select_default_method(interfaces, name, descriptor),
+                    }
+                })
+                .collect();
+            ITableEntry {
+                interface: (*interface).clone(),
+                methods,
+            }
+        })
+        .collect()
+}
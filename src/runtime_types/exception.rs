@@ -0,0 +1,253 @@
+use std::sync::Arc;
+
+use crate::{
+    parser::{classfile::opcode::ArrayType, descriptor::FieldType},
+    rethrow_exception,
+    runtime::{class_loader::ClassLoadingError, jvm::Jvm},
+};
+
+use super::{
+    current_frames, new_string, new_tracked_string, read_string, Array, Class, Exception,
+    ExecResult, InternalError, Object, Reference, STRING_CLASS,
+};
+
+pub const NULL_POINTER_EXCEPTION: &str = "java/lang/NullPointerException";
+pub const ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION: &str = "java/lang/ArrayIndexOutOfBoundsException";
+pub const NEGATIVE_ARRAY_SIZE_EXCEPTION: &str = "java/lang/NegativeArraySizeException";
+pub const ARRAY_STORE_EXCEPTION: &str = "java/lang/ArrayStoreException";
+pub const CLASS_CAST_EXCEPTION: &str = "java/lang/ClassCastException";
+pub const ARITHMETIC_EXCEPTION: &str = "java/lang/ArithmeticException";
+pub const ERROR: &str = "java/lang/Error";
+pub const EXCEPTION_IN_INITIALIZER_ERROR: &str = "java/lang/ExceptionInInitializerError";
+pub const NO_CLASS_DEF_FOUND_ERROR: &str = "java/lang/NoClassDefFoundError";
+pub const CLASS_FORMAT_ERROR: &str = "java/lang/ClassFormatError";
+pub const CLASS_CIRCULARITY_ERROR: &str = "java/lang/ClassCircularityError";
+pub const VERIFY_ERROR: &str = "java/lang/VerifyError";
+pub const INSTANTIATION_ERROR: &str = "java/lang/InstantiationError";
+pub const NO_SUCH_FIELD_ERROR: &str = "java/lang/NoSuchFieldError";
+pub const NO_SUCH_METHOD_ERROR: &str = "java/lang/NoSuchMethodError";
+pub const ABSTRACT_METHOD_ERROR: &str = "java/lang/AbstractMethodError";
+pub const INCOMPATIBLE_CLASS_CHANGE_ERROR: &str = "java/lang/IncompatibleClassChangeError";
+pub const UNSATISFIED_LINK_ERROR: &str = "java/lang/UnsatisfiedLinkError";
+pub const NUMBER_FORMAT_EXCEPTION: &str = "java/lang/NumberFormatException";
+pub const ILLEGAL_ARGUMENT_EXCEPTION: &str = "java/lang/IllegalArgumentException";
+pub const OUT_OF_MEMORY_ERROR: &str = "java/lang/OutOfMemoryError";
+pub const ILLEGAL_MONITOR_STATE_EXCEPTION: &str = "java/lang/IllegalMonitorStateException";
+pub const INTERRUPTED_EXCEPTION: &str = "java/lang/InterruptedException";
+pub const BOOTSTRAP_METHOD_ERROR: &str = "java/lang/BootstrapMethodError";
+pub const STACK_OVERFLOW_ERROR: &str = "java/lang/StackOverflowError";
+pub const CLONE_NOT_SUPPORTED_EXCEPTION: &str = "java/lang/CloneNotSupportedException";
+
+const DETAIL_MESSAGE_FIELD: &str = "detailMessage";
+const CAUSE_FIELD: &str = "cause";
+const STACK_TRACE_FIELD: &str = "stackTrace";
+const FILL_IN_STACK_TRACE_METHOD: &str = "fillInStackTrace";
+const CONSTRUCTOR_NAME: &str = "<init>";
+const THROWABLE_CLASS: &str = "java/lang/Throwable";
+
+// the slot of a field declared by Throwable, inherited by every exception
+fn throwable_field_slot(
+    exception: &Exception,
+    name: &str,
+    descriptor: FieldType,
+) -> Result<usize, InternalError> {
+    exception
+        .get_class()
+        .resolve_field(name, &descriptor)
+        .map(|(_, field)| field.get_slot())
+        .ok_or(InternalError::WrongType)
+}
+
+fn string_type() -> FieldType {
+    FieldType::Object(STRING_CLASS.to_string())
+}
+
+fn throwable_type() -> FieldType {
+    FieldType::Object(THROWABLE_CLASS.to_string())
+}
+
+fn stack_trace_type() -> FieldType {
+    FieldType::Array(Box::new(string_type()))
+}
+
+/// Allocate an exception thrown by the virtual machine itself, like a null array access
+///
+/// The constructor of the exception is not run, only its class, detail message and stack
+/// trace are set. If the message can't be created, the exception thrown while creating it
+/// is returned instead.
+///
+/// An OutOfMemoryError is created even when the heap is full, like the errors HotSpot
+/// allocates in advance: its message can go past the maximum size of the heap, and it has no
+/// stack trace since there may be no room for it.
+pub fn new_exception(
+    jvm: &Jvm,
+    class_name: &str,
+    message: Option<String>,
+) -> Result<Exception, InternalError> {
+    let class = jvm
+        .class_loader()
+        .load_class(class_name)
+        .map_err(|_| InternalError::ClassLoadingFailed(class_name.to_string()))?;
+    let exception = Reference::new(class);
+    jvm.heap().track_object(&exception)?;
+    let out_of_memory = class_name == OUT_OF_MEMORY_ERROR;
+    if let Some(message) = message {
+        let message = if out_of_memory {
+            new_tracked_string(jvm, &message)?
+        } else {
+            new_string(jvm, &message)?
+        };
+        let message = match message {
+            Ok(message) => message,
+            Err(thrown) => return Ok(thrown),
+        };
+        let slot = throwable_field_slot(&exception, DETAIL_MESSAGE_FIELD, string_type())?;
+        exception.set_field(slot, Object::Reference(Some(message)))?;
+    }
+    // the exception thrown when the trace can't be allocated is dropped
+    if !out_of_memory {
+        let _ = fill_in_stack_trace(jvm, &exception)?;
+    }
+    Ok(exception)
+}
+
+/// Record the frames of the current thread in the stack trace of an exception,
+/// like Throwable.fillInStackTrace
+///
+/// The frames creating the exception, its constructors and fillInStackTrace, are left out.
+pub fn fill_in_stack_trace(jvm: &Jvm, exception: &Exception) -> ExecResult<()> {
+    let throwable = load_throwable_class(jvm)?;
+    let frames = current_frames(|class: &Arc<Class>, method| {
+        method == FILL_IN_STACK_TRACE_METHOD
+            || (method == CONSTRUCTOR_NAME && class.is_subclass(&throwable))
+    });
+    let string_class = jvm
+        .class_loader()
+        .load_class(STRING_CLASS)
+        .map_err(|_| InternalError::ClassLoadingFailed(STRING_CLASS.to_string()))?;
+    let array_type = ArrayType::Reference(string_class);
+    let stack_trace = rethrow_exception!(jvm.heap().new_array(jvm, &array_type, frames.len())?);
+    for (index, frame) in frames.iter().enumerate() {
+        let frame = rethrow_exception!(new_string(jvm, frame)?);
+        let stored = stack_trace.store_index(jvm, index as i32, Object::Reference(Some(frame)))?;
+        stored.map_err(|_| InternalError::WrongType)?;
+    }
+    let slot = throwable_field_slot(exception, STACK_TRACE_FIELD, stack_trace_type())?;
+    exception.set_field(slot, Object::Array(Some(stack_trace)))?;
+    Ok(Ok(()))
+}
+
+/// The frames recorded by fill_in_stack_trace, the innermost first
+pub fn get_stack_trace(exception: &Exception) -> Result<Vec<String>, InternalError> {
+    let slot = throwable_field_slot(exception, STACK_TRACE_FIELD, stack_trace_type())?;
+    let stack_trace = match exception.get_field(slot)? {
+        Object::Array(Some(Array::Reference(stack_trace))) => stack_trace,
+        Object::Array(None) => return Ok(Vec::new()),
+        _ => return Err(InternalError::WrongType),
+    };
+    let frames = stack_trace.lock()?.to_vec();
+    frames
+        .iter()
+        .filter_map(|frame| match frame {
+            Object::Reference(Some(frame)) => Some(read_string(frame)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn load_throwable_class(jvm: &Jvm) -> Result<Arc<Class>, InternalError> {
+    jvm.class_loader()
+        .load_class(THROWABLE_CLASS)
+        .map_err(|_| InternalError::ClassLoadingFailed(THROWABLE_CLASS.to_string()))
+}
+
+/// Set the cause of an exception, like Throwable.initCause
+pub fn set_exception_cause(exception: &Exception, cause: Exception) -> Result<(), InternalError> {
+    let slot = throwable_field_slot(exception, CAUSE_FIELD, throwable_type())?;
+    exception.set_field(slot, Object::Reference(Some(cause)))
+}
+
+/// The cause of an exception, the field read by Throwable.getCause
+pub fn get_exception_cause(exception: &Exception) -> Result<Option<Exception>, InternalError> {
+    let slot = throwable_field_slot(exception, CAUSE_FIELD, throwable_type())?;
+    match exception.get_field(slot)? {
+        Object::Reference(cause) => Ok(cause),
+        _ => Err(InternalError::WrongType),
+    }
+}
+
+/// The detail message of an exception, the field read by Throwable.getMessage
+pub fn get_exception_message(exception: &Exception) -> Result<Option<String>, InternalError> {
+    let slot = throwable_field_slot(exception, DETAIL_MESSAGE_FIELD, string_type())?;
+    match exception.get_field(slot)? {
+        Object::Reference(Some(message)) => read_string(&message).map(Some),
+        Object::Reference(None) => Ok(None),
+        _ => Err(InternalError::WrongType),
+    }
+}
+
+/// Same as new_exception, already wrapped as the thrown result of an operation
+pub fn throw_exception<T>(jvm: &Jvm, class_name: &str, message: Option<String>) -> ExecResult<T> {
+    new_exception(jvm, class_name, message).map(Err)
+}
+
+/// Throw the LinkageError matching a class that could not be loaded, when resolving a reference
+pub fn throw_loading_error<T>(jvm: &Jvm, error: ClassLoadingError) -> ExecResult<T> {
+    let java_name = |name: &str| name.replace('/', ".");
+    let (class_name, message) = match error {
+        ClassLoadingError::NotFound(name) => (NO_CLASS_DEF_FOUND_ERROR, java_name(&name)),
+        ClassLoadingError::WrongClassName { expected, found } => (
+            NO_CLASS_DEF_FOUND_ERROR,
+            format!(
+                "{} (wrong name: {})",
+                java_name(&expected),
+                java_name(&found)
+            ),
+        ),
+        ClassLoadingError::InvalidClassFile { class_name, error } => (
+            CLASS_FORMAT_ERROR,
+            format!("{}: {:?}", java_name(&class_name), error),
+        ),
+        ClassLoadingError::CircularInheritance(name) => (CLASS_CIRCULARITY_ERROR, java_name(&name)),
+        ClassLoadingError::VerifyError(error) => (VERIFY_ERROR, error.to_string()),
+        ClassLoadingError::OverridesFinalMethod { class_name, method } => (
+            INCOMPATIBLE_CLASS_CHANGE_ERROR,
+            format!(
+                "class {} overrides final method {}",
+                java_name(&class_name),
+                method
+            ),
+        ),
+    };
+    throw_exception(jvm, class_name, Some(message))
+}
+
+/// Describe the exception like Throwable.toString, the class name followed by the message
+pub fn describe_exception(exception: &Exception) -> Result<String, InternalError> {
+    let class_name = exception.get_class().get_name().replace('/', ".");
+    Ok(match get_exception_message(exception)? {
+        Some(message) => format!("{}: {}", class_name, message),
+        None => class_name,
+    })
+}
+
+/// Errors are returned as is, other exceptions are wrapped in a new error of the given class
+///
+/// Like Throwable(Throwable cause), the message of the error describes its cause.
+pub fn wrap_in_error(
+    jvm: &Jvm,
+    exception: Exception,
+    error_class: &str,
+) -> Result<Exception, InternalError> {
+    let error = jvm
+        .class_loader()
+        .load_class(ERROR)
+        .map_err(|_| InternalError::ClassLoadingFailed(ERROR.to_string()))?;
+    if exception.is_subclass(&error) {
+        return Ok(exception);
+    }
+    let message = describe_exception(&exception)?;
+    let wrapper = new_exception(jvm, error_class, Some(message))?;
+    set_exception_cause(&wrapper, exception)?;
+    Ok(wrapper)
+}
@@ -1,4 +1,74 @@
+use crate::parser::{classfile::classfile::AccesFlag, descriptor::FieldType};
+
+use super::{Object, RuntimeConstant};
+
 #[derive(Debug, Clone)]
 pub struct Field {
     name: String,
+    descriptor: FieldType,
+    access_flags: AccesFlag,
+    // index of the first slot of the field in the instances of the class,
+    // or in the static storage of the class for static fields,
+    // longs and doubles take two slots like in the locals
+    slot: usize,
+    // ConstantValue attribute of a static field, set when the class is initialized
+    constant_value: Option<RuntimeConstant>,
+}
+
+impl Field {
+    pub fn new(
+        name: String,
+        descriptor: FieldType,
+        access_flags: AccesFlag,
+        slot: usize,
+        constant_value: Option<RuntimeConstant>,
+    ) -> Self {
+        Field {
+            name,
+            descriptor,
+            access_flags,
+            slot,
+            constant_value,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_descriptor(&self) -> &FieldType {
+        &self.descriptor
+    }
+
+    pub fn get_access_flags(&self) -> AccesFlag {
+        self.access_flags
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.access_flags.contains(AccesFlag::STATIC)
+    }
+
+    pub fn get_slot(&self) -> usize {
+        self.slot
+    }
+
+    pub fn get_constant_value(&self) -> Option<&RuntimeConstant> {
+        self.constant_value.as_ref()
+    }
+}
+
+/// The initial value of a field of the given type (JVMS §2.3, §2.4)
+pub fn default_value(field_type: &FieldType) -> Object {
+    match field_type {
+        FieldType::Byte
+        | FieldType::Char
+        | FieldType::Int
+        | FieldType::Short
+        | FieldType::Boolean => Object::Int(0),
+        FieldType::Float => Object::Float(0.0),
+        FieldType::Long => Object::Long(0),
+        FieldType::Double => Object::Double(0.0),
+        FieldType::Object(_) => Object::Reference(None),
+        FieldType::Array(_) => Object::Array(None),
+    }
 }
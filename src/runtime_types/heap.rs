@@ -0,0 +1,421 @@
+use std::{
+    collections::HashMap,
+    mem::{self, size_of},
+    sync::{Arc, Mutex, MutexGuard, PoisonError, Weak},
+    time::{Duration, Instant},
+};
+
+use crate::{parser::classfile::opcode::ArrayType, runtime::jvm::Jvm};
+
+use super::{
+    throw_exception, Array, Class, ExecResult, InternalError, Object, RefInner, Reference,
+    ReferenceArray, OUT_OF_MEMORY_ERROR,
+};
+
+/// The maximum size of the heap when -Xmx is not given
+pub const DEFAULT_MAX_HEAP_SIZE: usize = 256 * 1024 * 1024;
+// the heap is not collected before it reaches this size, even with a larger maximum
+const MIN_COLLECTION_THRESHOLD: usize = 4 * 1024 * 1024;
+const OUT_OF_MEMORY_MESSAGE: &str = "Java heap space";
+
+/// Why the heap is collected, shown by -verbose:gc
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GcCause {
+    // the heap has grown past the collection threshold
+    AllocationFailure,
+    // System.gc
+    SystemGc,
+}
+
+impl GcCause {
+    fn description(&self) -> &'static str {
+        match self {
+            GcCause::AllocationFailure => "Allocation Failure",
+            GcCause::SystemGc => "System.gc()",
+        }
+    }
+}
+
+/// What the collections have done since the start of the virtual machine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcStatistics {
+    pub collections: usize,
+    // the objects and arrays reclaimed by clearing their references
+    pub freed_objects: usize,
+    // the size reclaimed, the objects freed by their reference count between collections included
+    pub freed_bytes: usize,
+    pub total_time: Duration,
+}
+
+// An object or an array allocated in the heap, which doesn't keep it alive.
+// The arrays of primitives don't reference anything, they are only counted.
+#[derive(Debug)]
+enum Allocation {
+    Object(Weak<RefInner>),
+    References(Weak<ReferenceArray>),
+    Primitives(Weak<dyn Send + Sync>),
+}
+
+impl Allocation {
+    fn new_object(reference: &Reference) -> Self {
+        Allocation::Object(reference.downgrade())
+    }
+
+    fn new_array(array: &Array) -> Self {
+        match array {
+            Array::Boolean(array) => Allocation::Primitives(Arc::downgrade(array) as _),
+            Array::Char(array) => Allocation::Primitives(Arc::downgrade(array) as _),
+            Array::Float(array) => Allocation::Primitives(Arc::downgrade(array) as _),
+            Array::Double(array) => Allocation::Primitives(Arc::downgrade(array) as _),
+            Array::Byte(array) => Allocation::Primitives(Arc::downgrade(array) as _),
+            Array::Short(array) => Allocation::Primitives(Arc::downgrade(array) as _),
+            Array::Int(array) => Allocation::Primitives(Arc::downgrade(array) as _),
+            Array::Long(array) => Allocation::Primitives(Arc::downgrade(array) as _),
+            Array::Reference(array) => Allocation::References(Arc::downgrade(array)),
+        }
+    }
+
+    fn is_alive(&self) -> bool {
+        match self {
+            Allocation::Object(object) => object.strong_count() > 0,
+            Allocation::References(array) => array.strong_count() > 0,
+            Allocation::Primitives(array) => array.strong_count() > 0,
+        }
+    }
+
+    // the objects and arrays that can reference other ones, the nodes of the object graph
+    fn upgrade(&self) -> Option<Node> {
+        match self {
+            Allocation::Object(object) => Reference::upgrade(object).map(Node::Object),
+            Allocation::References(array) => array.upgrade().map(Node::References),
+            Allocation::Primitives(_) => None,
+        }
+    }
+}
+
+enum Node {
+    Object(Reference),
+    References(Arc<ReferenceArray>),
+}
+
+impl Node {
+    fn address(&self) -> usize {
+        match self {
+            Node::Object(reference) => reference.address(),
+            Node::References(array) => Arc::as_ptr(array) as usize,
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Node::Object(reference) => reference.strong_count(),
+            Node::References(array) => Arc::strong_count(array),
+        }
+    }
+
+    // None when the object is locked by the running code, which is still using it
+    fn try_lock(&self) -> Option<MutexGuard<'_, Box<[Object]>>> {
+        match self {
+            Node::Object(reference) => reference.get_fields().try_lock().ok(),
+            Node::References(array) => array.try_lock().ok(),
+        }
+    }
+}
+
+// the address of the object or the array of references referenced by a field or an item
+fn referenced_address(object: &Object) -> Option<usize> {
+    match object {
+        Object::Reference(Some(reference)) => Some(reference.address()),
+        Object::Array(Some(Array::Reference(array))) => Some(Arc::as_ptr(array) as usize),
+        _ => None,
+    }
+}
+
+// the size of an object with the given number of field slots, as laid out by the host
+fn object_size(slots: usize) -> usize {
+    size_of::<RefInner>() + slots * size_of::<Object>()
+}
+
+fn array_size(array_type: &ArrayType, length: usize) -> usize {
+    let item_size = match array_type {
+        ArrayType::Boolean => size_of::<bool>(),
+        ArrayType::Char => size_of::<u16>(),
+        ArrayType::Float => size_of::<f32>(),
+        ArrayType::Double => size_of::<f64>(),
+        ArrayType::Byte => size_of::<i8>(),
+        ArrayType::Short => size_of::<i16>(),
+        ArrayType::Int => size_of::<i32>(),
+        ArrayType::Long => size_of::<i64>(),
+        ArrayType::Reference(_) => size_of::<Object>(),
+    };
+    size_of::<ReferenceArray>() + length * item_size
+}
+
+// the type of the items of an array, to compute its size
+fn array_type(array: &Array) -> ArrayType {
+    match array {
+        Array::Boolean(_) => ArrayType::Boolean,
+        Array::Char(_) => ArrayType::Char,
+        Array::Float(_) => ArrayType::Float,
+        Array::Double(_) => ArrayType::Double,
+        Array::Byte(_) => ArrayType::Byte,
+        Array::Short(_) => ArrayType::Short,
+        Array::Int(_) => ArrayType::Int,
+        Array::Long(_) => ArrayType::Long,
+        Array::Reference(array) => ArrayType::Reference(array.get_component_class().clone()),
+    }
+}
+
+#[derive(Debug)]
+struct HeapState {
+    allocations: Vec<(Allocation, usize)>,
+    // the size of the allocations, with the ones freed since the last collection
+    size: usize,
+    // the heap is collected when its size would go past it
+    threshold: usize,
+    statistics: GcStatistics,
+}
+
+/// The objects and arrays of the program, and the collector reclaiming them
+///
+/// The objects are freed by their reference count as soon as nothing uses them,
+/// the collector reclaims the cycles of objects that only reference each other.
+///
+/// It is not a tracing collector, because the roots can't be walked: the stacks and locals
+/// of the frames are Rust values on the stacks of the host threads, like the values held by
+/// the native methods, and the threads keep running while the heap is collected.
+/// Like the cycle collector of CPython, it finds the roots by trial deletion instead: every
+/// reference that is not held by another object of the heap comes from a root (a frame,
+/// a static field, the intern table, a native method...) and keeps its object alive.
+/// The objects that are not reachable from those are garbage, their references are cleared
+/// which frees them.
+///
+/// Where a tracing collector would reclaim them, it keeps:
+/// - the cycles with an object locked by a running thread during the collection,
+///   until the next collection
+/// - the interned strings, which the intern table references until the virtual machine ends
+/// - the objects reachable from the static fields and the mirrors of the classes, which are
+///   never unloaded
+///
+/// The sizes are the ones of the objects in memory, not the ones they would have in HotSpot.
+#[derive(Debug)]
+pub struct Heap {
+    state: Mutex<HeapState>,
+    max_size: usize,
+    // log every collection, like -verbose:gc
+    verbose: bool,
+}
+
+impl Heap {
+    pub fn new(max_size: usize) -> Self {
+        Heap {
+            state: Mutex::new(HeapState {
+                allocations: Vec::new(),
+                size: 0,
+                threshold: MIN_COLLECTION_THRESHOLD.min(max_size),
+                statistics: GcStatistics::default(),
+            }),
+            max_size,
+            verbose: false,
+        }
+    }
+
+    pub fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+        let state = self.state.get_mut().unwrap_or_else(PoisonError::into_inner);
+        state.threshold = MIN_COLLECTION_THRESHOLD.min(max_size);
+    }
+
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    pub fn get_max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// The size of the allocated objects, some may have been freed since the last collection
+    pub fn get_size(&self) -> Result<usize, InternalError> {
+        Ok(self.state.lock()?.size)
+    }
+
+    pub fn get_statistics(&self) -> Result<GcStatistics, InternalError> {
+        Ok(self.state.lock()?.statistics)
+    }
+
+    /// Allocate an instance of the class for new, with its fields set to their default value
+    ///
+    /// An OutOfMemoryError is thrown if the heap is still full after a collection.
+    pub fn new_object(&self, jvm: &Jvm, class: Arc<Class>) -> ExecResult<Reference> {
+        let size = object_size(class.instance_size());
+        self.allocate(jvm, size, || Reference::new(class), Allocation::new_object)
+    }
+
+    /// Allocate an array for newarray and anewarray, its items set to their default value
+    pub fn new_array(&self, jvm: &Jvm, array_type: &ArrayType, length: usize) -> ExecResult<Array> {
+        let size = array_size(array_type, length);
+        let create = || Array::new(array_type, length);
+        self.allocate(jvm, size, create, Allocation::new_array)
+    }
+
+    /// Allocate an array created with its items, like the value of a string
+    pub fn allocate_array(&self, jvm: &Jvm, array: Array) -> ExecResult<Array> {
+        let size = array_size(&array_type(&array), array.size()? as usize);
+        self.allocate(jvm, size, || array, Allocation::new_array)
+    }
+
+    fn allocate<T, F>(
+        &self,
+        jvm: &Jvm,
+        size: usize,
+        create: F,
+        allocation: fn(&T) -> Allocation,
+    ) -> ExecResult<T>
+    where
+        F: FnOnce() -> T,
+    {
+        {
+            let mut state = self.state.lock()?;
+            if state.size + size > state.threshold {
+                self.collect(&mut state, GcCause::AllocationFailure);
+            }
+            if state.size + size <= self.max_size {
+                let object = create();
+                state.allocations.push((allocation(&object), size));
+                state.size += size;
+                return Ok(Ok(object));
+            }
+        }
+        // the heap is not locked anymore, the error is allocated in it past its maximum size
+        let message = OUT_OF_MEMORY_MESSAGE.to_string();
+        throw_exception(jvm, OUT_OF_MEMORY_ERROR, Some(message))
+    }
+
+    /// Add an object created by the virtual machine itself, like the string of a literal
+    ///
+    /// It is counted in the size of the heap, but can go past its maximum size,
+    /// like the errors that HotSpot allocates in advance.
+    pub fn track_object(&self, reference: &Reference) -> Result<(), InternalError> {
+        let size = object_size(reference.get_fields().lock()?.len());
+        self.track(Allocation::new_object(reference), size)
+    }
+
+    /// Same as track_object, for an array
+    pub fn track_array(&self, array: &Array) -> Result<(), InternalError> {
+        let size = array_size(&array_type(array), array.size()? as usize);
+        self.track(Allocation::new_array(array), size)
+    }
+
+    fn track(&self, allocation: Allocation, size: usize) -> Result<(), InternalError> {
+        let mut state = self.state.lock()?;
+        if state.size + size > state.threshold {
+            self.collect(&mut state, GcCause::AllocationFailure);
+        }
+        state.allocations.push((allocation, size));
+        state.size += size;
+        Ok(())
+    }
+
+    /// Reclaim the cycles of objects that are not reachable anymore
+    pub fn collect_garbage(&self, cause: GcCause) -> Result<(), InternalError> {
+        let mut state = self.state.lock()?;
+        self.collect(&mut state, cause);
+        Ok(())
+    }
+
+    fn collect(&self, state: &mut HeapState, cause: GcCause) {
+        let start = Instant::now();
+        let size_before = state.size;
+
+        state
+            .allocations
+            .retain(|(allocation, _)| allocation.is_alive());
+        let nodes: Vec<Node> = state
+            .allocations
+            .iter()
+            .filter_map(|(allocation, _)| allocation.upgrade())
+            .collect();
+        let indexes: HashMap<usize, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.address(), index))
+            .collect();
+        let mut items: Vec<Option<MutexGuard<'_, Box<[Object]>>>> =
+            nodes.iter().map(Node::try_lock).collect();
+        let references = |items: &Option<MutexGuard<'_, Box<[Object]>>>| -> Vec<usize> {
+            items
+                .iter()
+                .flat_map(|items| items.iter())
+                .filter_map(referenced_address)
+                .filter_map(|address| indexes.get(&address).copied())
+                .collect()
+        };
+
+        // the references that are not held by the nodes come from the roots,
+        // the one added by upgrading the node aside
+        let mut outside_references: Vec<usize> =
+            nodes.iter().map(|node| node.strong_count() - 1).collect();
+        for node_items in &items {
+            for index in references(node_items) {
+                outside_references[index] = outside_references[index].saturating_sub(1);
+            }
+        }
+
+        // a locked object is still used, like the ones referenced from outside of the heap
+        let mut reachable: Vec<bool> = outside_references
+            .iter()
+            .zip(&items)
+            .map(|(&count, items)| count > 0 || items.is_none())
+            .collect();
+        let mut pending: Vec<usize> = (0..nodes.len()).filter(|&index| reachable[index]).collect();
+        while let Some(index) = pending.pop() {
+            for referenced in references(&items[index]) {
+                if !reachable[referenced] {
+                    reachable[referenced] = true;
+                    pending.push(referenced);
+                }
+            }
+        }
+
+        // the garbage is freed when the references between the unreachable objects are dropped
+        let mut garbage = Vec::new();
+        for (node_items, _) in items
+            .iter_mut()
+            .zip(&reachable)
+            .filter(|(_, &reachable)| !reachable)
+        {
+            if let Some(node_items) = node_items {
+                garbage.push(mem::take(&mut **node_items));
+            }
+        }
+        let freed_objects = garbage.len();
+        drop(items);
+        drop(garbage);
+        drop(nodes);
+
+        state
+            .allocations
+            .retain(|(allocation, _)| allocation.is_alive());
+        state.size = state.allocations.iter().map(|(_, size)| size).sum();
+        state.threshold = (state.size * 2)
+            .max(MIN_COLLECTION_THRESHOLD)
+            .min(self.max_size);
+
+        let time = start.elapsed();
+        let statistics = &mut state.statistics;
+        statistics.collections += 1;
+        statistics.freed_objects += freed_objects;
+        statistics.freed_bytes += size_before.saturating_sub(state.size);
+        statistics.total_time += time;
+        if self.verbose {
+            println!(
+                "[GC ({}) {}K->{}K({}K), {:.7} secs]",
+                cause.description(),
+                size_before / 1024,
+                state.size / 1024,
+                self.max_size / 1024,
+                time.as_secs_f64()
+            );
+        }
+    }
+}
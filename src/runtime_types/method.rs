@@ -1,17 +1,177 @@
-use std::sync;
+use std::sync::{self, Arc, OnceLock};
 
-use super::{Class, Code, MethodCallResult, Stack};
+use crate::{
+    parser::classfile::classfile::AccesFlag,
+    rethrow_exception,
+    runtime::{jvm::Jvm, natives::NativeMethod},
+};
+
+use super::{
+    beyond_frame_limit, enter_frame, throw_exception, Class, Code, InternalError, MethodCallResult,
+    Object, Stack, STACK_OVERFLOW_ERROR, UNSATISFIED_LINK_ERROR,
+};
 
 #[derive(Debug, Clone)]
 pub struct Method {
-    name: String,
+    // shared with the frames of the call stack
+    name: Arc<str>,
     descriptor: String,
+    access_flags: AccesFlag,
     class: sync::Weak<Class>,
-    code: Code,
+    // slots taken by the arguments, with the receiver of instance methods
+    args_count: usize,
+    // abstract and native methods don't have code
+    code: Option<Code>,
+    // slot of the method in the vtable of the classes, or in the itable entry of its interface,
+    // None for the methods that are never dispatched: static, private and constructors
+    dispatch_index: Option<usize>,
+    // the implementation of a native method, found in the registry of the jvm on the first call
+    native: OnceLock<NativeMethod>,
 }
 
 impl Method {
-    pub fn execute(&self, caller_stack: &mut Stack) -> MethodCallResult {
-        self.code.execute(caller_stack)
+    pub fn new(
+        name: String,
+        descriptor: String,
+        access_flags: AccesFlag,
+        class: sync::Weak<Class>,
+        args_count: usize,
+        code: Option<Code>,
+    ) -> Self {
+        Method {
+            name: name.into(),
+            descriptor,
+            access_flags,
+            class,
+            args_count,
+            code,
+            dispatch_index: None,
+            native: OnceLock::new(),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_descriptor(&self) -> &str {
+        &self.descriptor
+    }
+
+    pub fn get_access_flags(&self) -> AccesFlag {
+        self.access_flags
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.access_flags.contains(AccesFlag::PUBLIC)
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.access_flags.contains(AccesFlag::STATIC)
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.access_flags.contains(AccesFlag::PRIVATE)
+    }
+
+    pub fn is_abstract(&self) -> bool {
+        self.access_flags.contains(AccesFlag::ABSTRACT)
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.access_flags.contains(AccesFlag::NATIVE)
+    }
+
+    pub fn is_synchronized(&self) -> bool {
+        self.access_flags.contains(AccesFlag::SYNCHRONIZED)
+    }
+
+    pub fn get_class(&self) -> Option<sync::Arc<Class>> {
+        self.class.upgrade()
+    }
+
+    pub fn get_dispatch_index(&self) -> Option<usize> {
+        self.dispatch_index
+    }
+
+    /// Set by the class when it builds its dispatch tables
+    pub fn set_dispatch_index(&mut self, dispatch_index: usize) {
+        self.dispatch_index = Some(dispatch_index);
+    }
+
+    /// A synchronized method holds the monitor of its receiver, or of its class when it is
+    /// static, until it returns or throws
+    ///
+    /// The call throws a StackOverflowError when the thread already has MAX_FRAMES frames.
+    pub fn execute(&self, jvm: &Jvm, caller_stack: &mut Stack) -> MethodCallResult {
+        let Some(_frame) = enter_frame(self.class.clone(), self.name.clone()) else {
+            return beyond_frame_limit(|| throw_exception(jvm, STACK_OVERFLOW_ERROR, None));
+        };
+        if !self.is_synchronized() {
+            return self.invoke(jvm, caller_stack);
+        }
+        let object = if self.is_static() {
+            let class = self.get_class().ok_or(InternalError::MethodWithoutCode)?;
+            Object::Reference(Some(rethrow_exception!(class.get_mirror(jvm)?)))
+        } else {
+            let depth = self
+                .args_count
+                .checked_sub(1)
+                .ok_or(InternalError::EmptyStack)?;
+            caller_stack.peek_at(depth)?.clone()
+        };
+        rethrow_exception!(jvm.monitors().enter(jvm, &object)?);
+        let result = self.invoke(jvm, caller_stack)?;
+        let exited = jvm.monitors().exit(jvm, &object)?;
+        match (result, exited) {
+            (Ok(value), Ok(())) => Ok(Ok(value)),
+            // the exception of the method is kept if it released the monitor itself
+            (Err(exception), _) | (Ok(_), Err(exception)) => Ok(Err(exception)),
+        }
+    }
+
+    fn invoke(&self, jvm: &Jvm, caller_stack: &mut Stack) -> MethodCallResult {
+        if let Some(code) = &self.code {
+            return code.execute(jvm, caller_stack);
+        }
+        if !self.is_native() {
+            return Err(InternalError::MethodWithoutCode);
+        }
+        let args = pop_arguments(caller_stack, self.args_count)?;
+        let class = self.get_class().ok_or(InternalError::MethodWithoutCode)?;
+        let native = match self.native.get() {
+            Some(native) => native,
+            None => {
+                let Some(native) = jvm.find_native(class.get_name(), &self.name, &self.descriptor)
+                else {
+                    let message = format!(
+                        "{}.{}{}",
+                        class.get_name().replace('/', "."),
+                        self.name,
+                        self.descriptor
+                    );
+                    return throw_exception(jvm, UNSATISFIED_LINK_ERROR, Some(message));
+                };
+                self.native.get_or_init(|| native)
+            }
+        };
+        native(jvm, &args)
+    }
+}
+
+/// Pop the arguments of a native method, the receiver first and without the padding of the
+/// longs and doubles
+fn pop_arguments(stack: &mut Stack, args_count: usize) -> Result<Vec<Object>, InternalError> {
+    let mut args = Vec::with_capacity(args_count);
+    let mut remaining_slots = args_count;
+    while remaining_slots > 0 {
+        let value = stack.pop()?;
+        let size = if value.is_wide() { 2 } else { 1 };
+        remaining_slots = remaining_slots
+            .checked_sub(size)
+            .ok_or(InternalError::InvalidWideLoad)?;
+        args.push(value);
     }
+    args.reverse();
+    Ok(args)
 }
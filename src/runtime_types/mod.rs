@@ -1,12 +1,17 @@
 mod array;
+mod call_stack;
 mod class;
 mod code;
+mod constant_pool;
+mod dispatch;
+mod exception;
 mod field;
-mod interface_method;
+mod heap;
 mod method;
 mod object;
 mod opcode;
 mod reference;
+mod string;
 
 #[cfg(test)]
 mod test;
@@ -14,14 +19,19 @@ mod test;
 use std::sync::PoisonError;
 
 pub use array::*;
+pub use call_stack::*;
 pub use class::*;
 pub use code::*;
+pub use constant_pool::*;
+pub use dispatch::*;
+pub use exception::*;
 pub use field::*;
-pub use interface_method::*;
+pub use heap::*;
 pub use method::*;
 pub use object::*;
 pub use opcode::*;
 pub use reference::*;
+pub use string::*;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum InternalError {
@@ -32,6 +42,13 @@ pub enum InternalError {
     LocalsOutOfBounds,
     InvalidWideLoad,
     InvalidProgrammCounter,
+    ClassLoadingFailed(String),
+    MethodWithoutCode,
+    FieldSlotOutOfBounds,
+    // the dynamic constants get their bootstrap method when the constant pool is lowered
+    MissingBootstrapMethod,
+    // threads can only be started by a virtual machine made shared with Jvm::into_shared
+    JvmNotShared,
 }
 
 impl<Guard> From<PoisonError<Guard>> for InternalError {
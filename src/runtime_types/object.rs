@@ -1,4 +1,8 @@
-use super::{Array, Reference};
+use std::sync::Arc;
+
+use crate::runtime::jvm::Jvm;
+
+use super::{Array, Class, InternalError, Reference};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Object {
@@ -19,4 +23,24 @@ impl Object {
             _ => false,
         }
     }
+
+    /// The class of a reference or of an array, None for null
+    pub fn get_class(&self, jvm: &Jvm) -> Result<Option<Arc<Class>>, InternalError> {
+        match self {
+            Object::Reference(Some(reference)) => Ok(Some(reference.get_class().clone())),
+            Object::Array(Some(array)) => array.get_class(jvm).map(Some),
+            Object::Reference(None) | Object::Array(None) => Ok(None),
+            _ => Err(InternalError::WrongType),
+        }
+    }
+
+    /// The address of a reference or of an array, which identifies it, None for null
+    pub fn address(&self) -> Result<Option<usize>, InternalError> {
+        match self {
+            Object::Reference(Some(reference)) => Ok(Some(reference.address())),
+            Object::Array(Some(array)) => Ok(Some(array.address())),
+            Object::Reference(None) | Object::Array(None) => Ok(None),
+            _ => Err(InternalError::WrongType),
+        }
+    }
 }
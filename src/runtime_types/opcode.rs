@@ -1,9 +1,19 @@
-use crate::parser::classfile::opcode::{ArrayType, LookupSwitch, TableSwitch};
+use crate::{
+    parser::classfile::{
+        classfile::AccesFlag,
+        opcode::{ArrayType, LookupSwitch, TableSwitch},
+    },
+    runtime::jvm::Jvm,
+};
 use std::sync::Arc;
 
 use super::{
-    Array, Class, Exception, ExecResult, Field, InterfaceMethod, InternalError, Locals, Method,
-    Object, OpResult, ResultValue, Stack,
+    throw_exception, throw_loading_error, Array, CallSiteRef, Class, ClassRef, DynamicRef,
+    ExecResult, Field, FieldRef, InternalError, Locals, Method, MethodRef, MethodSelectionError,
+    Object, ResolvedMethod, ResultValue, SelectedMethod, Stack, StringRef, ABSTRACT_METHOD_ERROR,
+    ARITHMETIC_EXCEPTION, CLASS_CAST_EXCEPTION, CONSTRUCTOR_NAME, INCOMPATIBLE_CLASS_CHANGE_ERROR,
+    INSTANTIATION_ERROR, NEGATIVE_ARRAY_SIZE_EXCEPTION, NO_SUCH_FIELD_ERROR, NO_SUCH_METHOD_ERROR,
+    NULL_POINTER_EXCEPTION,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -12,12 +22,28 @@ pub enum ConstantNumerical {
     Long(i64),
 }
 
+#[derive(Debug, Clone)]
+pub enum LoadableConstant {
+    Int(i32),
+    Float(f32),
+    String(Arc<StringRef>),
+    Class(Arc<ClassRef>),
+    Dynamic {
+        constant: Arc<DynamicRef>,
+        // the class of the method running the opcode, given to the bootstrap method
+        caller: Arc<ClassRef>,
+    },
+}
+
 #[derive(Debug, Clone)]
 #[allow(non_camel_case_types)]
 pub enum OpCode {
     aload,
     astore,
     aconst_null,
+    anewarray {
+        class: Arc<ClassRef>,
+    },
     load_i {
         local_index: usize,
     },
@@ -37,7 +63,7 @@ pub enum OpCode {
     athrow,
     bipush(i32),
     checkcast {
-        class: Arc<Class>,
+        class: Arc<ClassRef>,
     },
     d2f,
     d2i,
@@ -67,10 +93,10 @@ pub enum OpCode {
     fconst_1,
     fconst_2,
     getfield {
-        field_name: String,
+        field: Arc<FieldRef>,
     },
     getstatic {
-        field: Arc<Field>,
+        field: Arc<FieldRef>,
     },
     goto(usize),
     goto_w(usize),
@@ -108,22 +134,27 @@ pub enum OpCode {
         delta: i32,
     },
     instanceof {
-        class: Arc<Class>,
+        class: Arc<ClassRef>,
+    },
+    invokedynamic {
+        call_site: Arc<CallSiteRef>,
+        // the class of the method running the opcode, given to the bootstrap method
+        caller: Arc<ClassRef>,
     },
-    // TODO
-    invokedynamic(usize),
     invokeinterface {
-        interface_method: Arc<InterfaceMethod>,
+        method: Arc<MethodRef>,
         count: usize,
     },
     invokespecial {
-        method_signature: Arc<String>,
+        method: Arc<MethodRef>,
+        // the class of the method running the opcode, for calls to the super class
+        caller: Arc<ClassRef>,
     },
     invokestatic {
-        method: Arc<Method>,
+        method: Arc<MethodRef>,
     },
     invokevirtual {
-        method_signature: Arc<String>,
+        method: Arc<MethodRef>,
     },
     and,
     or,
@@ -139,30 +170,29 @@ pub enum OpCode {
     lcmp,
     lconst_0,
     lconst_1,
-    // TODO:
-    ldc(usize),
-    // TODO:
-    ldc_w(usize),
+    // ldc and ldc_w
+    ldc(LoadableConstant),
     ldc2_w(ConstantNumerical),
     lookupswitch(LookupSwitch),
     monitorenter,
     monitorexit,
     multinewarray {
-        item_class: Arc<Class>,
+        // the class of the created array, not of its items
+        class: Arc<ClassRef>,
         dimensions: usize,
     },
     new {
-        class: Arc<Class>,
+        class: Arc<ClassRef>,
     },
     newarray(ArrayType),
     nop,
     pop,
     pop2,
     putfield {
-        field_name: String,
+        field: Arc<FieldRef>,
     },
     putstatic {
-        field: Arc<Field>, // Arc ?
+        field: Arc<FieldRef>,
     },
     ret {
         local_index: usize,
@@ -171,31 +201,31 @@ pub enum OpCode {
     sipush(i32),
     swap,
     tableswitch(TableSwitch),
-    // TODO
-    // wide(Wide),
+    // wide is lowered to load_i, store_i, iinc and ret
 }
 
 impl OpCode {
-    pub fn execute(&self, locals: &mut Locals, stack: &mut Stack) -> ExecResult {
+    pub fn execute(&self, jvm: &Jvm, locals: &mut Locals, stack: &mut Stack) -> ExecResult {
         use OpCode::*;
         match self {
-            aload => exec_aload(stack),
-            astore => exec_astore(stack),
+            aload => exec_aload(jvm, stack),
+            astore => exec_astore(jvm, stack),
             aconst_null => Ok(Ok(ResultValue::Object(Object::Reference(None)))), // yep that's a long wrapping for null
+            anewarray { class } => exec_anewarray(jvm, stack, class),
             load_i { local_index } => exec_load_local(locals, *local_index),
             load_0 => exec_load_local(locals, 0),
             load_1 => exec_load_local(locals, 1),
             load_2 => exec_load_local(locals, 2),
             load_3 => exec_load_local(locals, 3),
-            arraylength => exec_arraylength(stack),
+            arraylength => exec_arraylength(jvm, stack),
             store_i { local_index } => exec_store_local(locals, stack, *local_index),
             store_0 => exec_store_local(locals, stack, 0),
             store_1 => exec_store_local(locals, stack, 1),
             store_2 => exec_store_local(locals, stack, 2),
             store_3 => exec_store_local(locals, stack, 3),
-            athrow => exec_athrow(stack),
+            athrow => exec_athrow(jvm, stack),
             bipush(value) => Ok(Ok(ResultValue::Object(Object::Int(*value)))),
-            checkcast { class } => exec_checkcast(stack, class),
+            checkcast { class } => exec_checkcast(jvm, stack, class),
             d2f => exec_d2f(stack),
             d2i => exec_d2i(stack),
             d2l => exec_d2l(stack),
@@ -204,9 +234,9 @@ impl OpCode {
             dcmpl => exec_dcmpl(stack),
             dconst_0 => Ok(Ok(ResultValue::Object(Object::Double(0.0)))),
             dconst_1 => Ok(Ok(ResultValue::Object(Object::Double(1.0)))),
-            div => exec_div(stack),
+            div => exec_div(jvm, stack),
             mul => exec_mul(stack),
-            rem => todo!(),
+            rem => exec_rem(jvm, stack),
             sub => exec_sub(stack),
             dup => exec_stack_op(stack, Stack::dup),
             dup_x1 => exec_stack_op(stack, Stack::dup_x1),
@@ -222,8 +252,8 @@ impl OpCode {
             fconst_0 => Ok(Ok(ResultValue::Object(Object::Float(0.0)))),
             fconst_1 => Ok(Ok(ResultValue::Object(Object::Float(1.0)))),
             fconst_2 => Ok(Ok(ResultValue::Object(Object::Float(2.0)))),
-            getfield { field_name } => todo!(),
-            getstatic { field } => todo!(),
+            getfield { field } => exec_getfield(jvm, stack, field),
+            getstatic { field } => exec_getstatic(jvm, field),
             goto(jump) => Ok(Ok(ResultValue::Jump(*jump))),
             goto_w(jump) => Ok(Ok(ResultValue::Jump(*jump))),
             i2b => exec_i2b(stack),
@@ -256,22 +286,21 @@ impl OpCode {
             ifnonnull(jump) => exec_ifnonnull(stack, *jump),
             ifnull(jump) => exec_ifnull(stack, *jump),
             iinc { local_index, delta } => exec_iinc(locals, *local_index, *delta),
-            instanceof { class } => exec_instanceof(stack, class),
-            invokedynamic(_) => todo!(),
-            invokeinterface {
-                interface_method,
-                count,
-            } => todo!(),
-            invokespecial { method_signature } => todo!(),
-            invokestatic { method } => todo!(),
-            invokevirtual { method_signature } => todo!(),
+            instanceof { class } => exec_instanceof(jvm, stack, class),
+            invokedynamic { call_site, caller } => {
+                exec_invokedynamic(jvm, stack, call_site, caller)
+            }
+            invokeinterface { method, .. } => exec_invokeinterface(jvm, stack, method),
+            invokespecial { method, caller } => exec_invokespecial(jvm, stack, method, caller),
+            invokestatic { method } => exec_invokestatic(jvm, stack, method),
+            invokevirtual { method } => exec_invokevirtual(jvm, stack, method),
             neg => exec_numerical_neg(stack),
             and => exec_and(stack),
             or => exec_or(stack),
-            shl => exec_shl(stack),
-            shr => exec_shr(stack),
-            iushr => todo!(),
-            lushr => todo!(),
+            shl => exec_shift(stack, i32::wrapping_shl, i64::wrapping_shl),
+            shr => exec_shift(stack, i32::wrapping_shr, i64::wrapping_shr),
+            iushr => exec_iushr(stack),
+            lushr => exec_lushr(stack),
             xor => exec_xor(stack),
             jsr(jump) => Ok(Ok(ResultValue::Object(Object::ReturnAdress(*jump)))),
             l2d => exec_l2d(stack),
@@ -280,23 +309,21 @@ impl OpCode {
             lcmp => exec_lcmp(stack),
             lconst_0 => Ok(Ok(ResultValue::Object(Object::Long(0)))),
             lconst_1 => Ok(Ok(ResultValue::Object(Object::Long(1)))),
-            ldc(_) => todo!(),
-            ldc_w(_) => todo!(),
-            ldc2_w(_) => todo!(),
+            ldc(constant) => exec_ldc(jvm, constant),
+            ldc2_w(constant) => exec_ldc2_w(*constant),
             lookupswitch(lookup_switch) => exec_lookupswitch(stack, lookup_switch),
-            monitorenter => todo!(),
-            monitorexit => todo!(),
-            multinewarray {
-                item_class,
-                dimensions,
-            } => todo!(),
-            new { class } => todo!(),
-            newarray(array_type) => exec_newarray(stack, array_type),
+            monitorenter => exec_monitorenter(jvm, stack),
+            monitorexit => exec_monitorexit(jvm, stack),
+            multinewarray { class, dimensions } => {
+                exec_multinewarray(jvm, stack, class, *dimensions)
+            }
+            new { class } => exec_new(jvm, class),
+            newarray(array_type) => exec_newarray(jvm, stack, array_type),
             nop => Ok(Ok(ResultValue::None)), // easiest opcode lol
             pop => exec_pop(stack, false),
             pop2 => exec_pop(stack, true),
-            putfield { field_name } => todo!(),
-            putstatic { field } => todo!(),
+            putfield { field } => exec_putfield(jvm, stack, field),
+            putstatic { field } => exec_putstatic(jvm, stack, field),
             ret { local_index } => exec_ret(locals, *local_index),
             retrn => Ok(Ok(ResultValue::Return)),
             sipush(value) => Ok(Ok(ResultValue::Object(Object::Int(*value)))),
@@ -314,32 +341,19 @@ where
     stack_fn(stack).map(|_| ResultValue::None).map(Ok)
 }
 
-fn check_negative_array_size(size: i32) -> Result<usize, Exception> {
+fn check_negative_array_size(jvm: &Jvm, size: i32) -> ExecResult<usize> {
     if size < 0 {
-        // TODO: Throw NegativeArraySizeException
-        todo!()
+        throw_exception(jvm, NEGATIVE_ARRAY_SIZE_EXCEPTION, Some(size.to_string()))
     } else {
-        Ok(size as usize)
+        Ok(Ok(size as usize))
     }
 }
 
-fn store_array<T>(array: &mut [T], index: i32, value: T) -> OpResult {
-    if index >= 0 {
-        if let Some(elem) = array.get_mut(index as usize) {
-            *elem = value;
-            return Ok(ResultValue::None);
-        }
-    }
-    // TODO: Throw ArrayIndexOutOfBoundsException
-    todo!()
-}
-
-fn check_null<T>(nullable: Option<T>) -> Result<T, Exception> {
+fn check_null<T>(jvm: &Jvm, nullable: Option<T>) -> ExecResult<T> {
     if let Some(nullable) = nullable {
-        Ok(nullable)
+        Ok(Ok(nullable))
     } else {
-        // TODO: Throw NullPointerException
-        todo!()
+        throw_exception(jvm, NULL_POINTER_EXCEPTION, None)
     }
 }
 
@@ -376,11 +390,29 @@ macro_rules! rethrow_exception {
 }
 
 macro_rules! type_check_nullable {
-    ($pattern:path, $expression:expr) => {
-        check_type!($pattern, rethrow_exception!(check_null($expression)))
+    ($jvm:ident, $pattern:path, $expression:expr) => {
+        check_type!($pattern, rethrow_exception!(check_null($jvm, $expression)?))
     };
 }
 
+/// Pop an object or an array, which share the same null for the comparisons
+fn pop_nullable_object(stack: &mut Stack) -> Result<Option<Object>, InternalError> {
+    match stack.pop()? {
+        Object::Reference(None) | Object::Array(None) => Ok(None),
+        object @ (Object::Reference(Some(_)) | Object::Array(Some(_))) => Ok(Some(object)),
+        _ => Err(InternalError::WrongType),
+    }
+}
+
+/// aconst_null pushes a null reference, that can be used as a null array too
+fn pop_nullable_array(stack: &mut Stack) -> Result<Option<Array>, InternalError> {
+    match stack.pop()? {
+        Object::Array(array) => Ok(array),
+        Object::Reference(None) => Ok(None),
+        _ => Err(InternalError::WrongType),
+    }
+}
+
 // Some comments will talk about the specs saying something *must* be of this type or whatever
 // The requirements should be met by the compiler, but in the case the requirement are not met
 // the behavior is undefined by the specs and can be implemented as we like.
@@ -391,19 +423,19 @@ macro_rules! type_check_nullable {
 // like loading a local of a wrong type, then trying to store it in array.
 // I will try to put a comment every time I encounter a possible UB describing the implemented behavior
 
-fn exec_aload(stack: &mut Stack) -> ExecResult {
+fn exec_aload(jvm: &Jvm, stack: &mut Stack) -> ExecResult {
     let index = pop_stack_typechecked!(Object::Int, stack);
-    let array = pop_stack_typechecked!(Object::Array, stack);
-    let array = rethrow_exception!(check_null(array));
-    array.get_index(index)
+    let array = pop_nullable_array(stack)?;
+    let array = rethrow_exception!(check_null(jvm, array)?);
+    array.get_index(jvm, index)
 }
 
-fn exec_astore(stack: &mut Stack) -> ExecResult {
+fn exec_astore(jvm: &Jvm, stack: &mut Stack) -> ExecResult {
     let value = stack.pop()?;
     let index = pop_stack_typechecked!(Object::Int, stack);
-    let array = pop_stack_typechecked!(Object::Array, stack);
-    let array = rethrow_exception!(check_null(array));
-    array.store_index(index, value)
+    let array = pop_nullable_array(stack)?;
+    let array = rethrow_exception!(check_null(jvm, array)?);
+    array.store_index(jvm, index, value)
 }
 
 /// Specs for all load opcodes says it *has* to be a certain type,
@@ -416,10 +448,40 @@ fn exec_load_local(locals: &Locals, index: usize) -> ExecResult {
         .map(Ok)
 }
 
-fn exec_newarray(stack: &mut Stack, array_type: &ArrayType) -> ExecResult {
+fn exec_newarray(jvm: &Jvm, stack: &mut Stack, array_type: &ArrayType) -> ExecResult {
+    let size = pop_stack_typechecked!(Object::Int, stack);
+    let size = rethrow_exception!(check_negative_array_size(jvm, size)?);
+    let array = rethrow_exception!(jvm.heap().new_array(jvm, array_type, size)?);
+    Ok(Ok(ResultValue::Object(Object::Array(Some(array)))))
+}
+
+fn exec_anewarray(jvm: &Jvm, stack: &mut Stack, class: &ClassRef) -> ExecResult {
     let size = pop_stack_typechecked!(Object::Int, stack);
-    let size = rethrow_exception!(check_negative_array_size(size));
-    let array = Array::new(array_type, size);
+    let size = rethrow_exception!(check_negative_array_size(jvm, size)?);
+    let array_type = ArrayType::Reference(rethrow_exception!(resolve_class(jvm, class)?).clone());
+    let array = rethrow_exception!(jvm.heap().new_array(jvm, &array_type, size)?);
+    Ok(Ok(ResultValue::Object(Object::Array(Some(array)))))
+}
+
+/// The size of the first dimension is the deepest on the stack
+///
+/// Nothing is allocated if one of the sizes is negative.
+fn exec_multinewarray(
+    jvm: &Jvm,
+    stack: &mut Stack,
+    class: &ClassRef,
+    dimensions: usize,
+) -> ExecResult {
+    let class = rethrow_exception!(resolve_class(jvm, class)?);
+    let mut counts = vec![0; dimensions];
+    for count in counts.iter_mut().rev() {
+        *count = pop_stack_typechecked!(Object::Int, stack);
+    }
+    let mut sizes = Vec::with_capacity(dimensions);
+    for count in counts {
+        sizes.push(rethrow_exception!(check_negative_array_size(jvm, count)?));
+    }
+    let array = rethrow_exception!(Array::new_multi_dimensional(jvm, class, &sizes)?);
     Ok(Ok(ResultValue::Object(Object::Array(Some(array)))))
 }
 
@@ -431,9 +493,9 @@ fn exec_return_with_value(stack: &mut Stack) -> ExecResult {
     stack.pop().map(ResultValue::ReturnObject).map(Ok)
 }
 
-fn exec_arraylength(stack: &mut Stack) -> ExecResult {
-    let array = pop_stack_typechecked!(Object::Array, stack);
-    let array = rethrow_exception!(check_null(array));
+fn exec_arraylength(jvm: &Jvm, stack: &mut Stack) -> ExecResult {
+    let array = pop_nullable_array(stack)?;
+    let array = rethrow_exception!(check_null(jvm, array)?);
     array
         .size()
         .map(Object::Int)
@@ -450,24 +512,311 @@ fn exec_store_local(locals: &mut Locals, stack: &mut Stack, index: usize) -> Exe
     Ok(Ok(ResultValue::None))
 }
 
-fn exec_athrow(stack: &mut Stack) -> ExecResult {
+fn exec_athrow(jvm: &Jvm, stack: &mut Stack) -> ExecResult {
     let exception = pop_stack_typechecked!(Object::Reference, stack);
-    let exception = rethrow_exception!(check_null(exception));
+    let exception = rethrow_exception!(check_null(jvm, exception)?);
     Ok(Err(exception))
 }
 
-fn exec_checkcast(stack: &mut Stack, super_class: &Arc<Class>) -> ExecResult {
+/// A class that can't be loaded is thrown as a LinkageError, like NoClassDefFoundError
+fn resolve_class<'a>(jvm: &Jvm, class: &'a ClassRef) -> ExecResult<&'a Arc<Class>> {
+    match class.resolve(jvm.class_loader()) {
+        Ok(class) => Ok(Ok(class)),
+        Err(error) => throw_loading_error(jvm, error),
+    }
+}
+
+fn exec_monitorenter(jvm: &Jvm, stack: &mut Stack) -> ExecResult {
+    let object = stack.pop()?;
+    rethrow_exception!(jvm.monitors().enter(jvm, &object)?);
+    Ok(Ok(ResultValue::None))
+}
+
+fn exec_monitorexit(jvm: &Jvm, stack: &mut Stack) -> ExecResult {
+    let object = stack.pop()?;
+    rethrow_exception!(jvm.monitors().exit(jvm, &object)?);
+    Ok(Ok(ResultValue::None))
+}
+
+fn exec_checkcast(jvm: &Jvm, stack: &mut Stack, super_class: &ClassRef) -> ExecResult {
+    let object = stack.pop()?;
+    if let Some(class) = object.get_class(jvm)? {
+        let super_class = rethrow_exception!(resolve_class(jvm, super_class)?);
+        if !class.is_subclass(super_class) {
+            let message = format!(
+                "class {} cannot be cast to class {}",
+                class.get_name().replace('/', "."),
+                super_class.get_name().replace('/', ".")
+            );
+            return throw_exception(jvm, CLASS_CAST_EXCEPTION, Some(message));
+        }
+    }
+    Ok(Ok(ResultValue::Object(object)))
+}
+
+fn exec_new(jvm: &Jvm, class: &ClassRef) -> ExecResult {
+    let class = rethrow_exception!(resolve_class(jvm, class)?);
+    if class.is_interface() || class.get_access_flags().contains(AccesFlag::ABSTRACT) {
+        let class_name = class.get_name().replace('/', ".");
+        return throw_exception(jvm, INSTANTIATION_ERROR, Some(class_name));
+    }
+    rethrow_exception!(class.initialize(jvm)?);
+    let reference = rethrow_exception!(jvm.heap().new_object(jvm, class.clone())?);
+    Ok(Ok(ResultValue::Object(Object::Reference(Some(reference)))))
+}
+
+/// Resolve the field, and check it is static for getstatic/putstatic or not for getfield/putfield
+///
+/// The class declaring the field is returned with it
+fn resolve_field<'a>(
+    jvm: &Jvm,
+    field: &'a FieldRef,
+    is_static: bool,
+) -> ExecResult<&'a (Arc<Class>, Field)> {
+    let resolved = match field.resolve(jvm.class_loader()) {
+        Ok(resolved) => resolved,
+        Err(error) => return throw_loading_error(jvm, error),
+    };
+    let field_name = format!(
+        "{}.{}",
+        field.class().name().replace('/', "."),
+        field.name()
+    );
+    let Some(resolved) = resolved else {
+        return throw_exception(jvm, NO_SUCH_FIELD_ERROR, Some(field_name));
+    };
+    if resolved.1.is_static() != is_static {
+        let expected = if is_static { "static" } else { "non-static" };
+        let message = format!("Expected {} field {}", expected, field_name);
+        return throw_exception(jvm, INCOMPATIBLE_CLASS_CHANGE_ERROR, Some(message));
+    }
+    Ok(Ok(resolved))
+}
+
+fn exec_getfield(jvm: &Jvm, stack: &mut Stack, field: &FieldRef) -> ExecResult {
+    let (_, field) = rethrow_exception!(resolve_field(jvm, field, false)?);
+    let reference = pop_stack_typechecked!(Object::Reference, stack);
+    let reference = rethrow_exception!(check_null(jvm, reference)?);
+    reference
+        .get_field(field.get_slot())
+        .map(ResultValue::Object)
+        .map(Ok)
+}
+
+/// The value is stored as is, the verifier checks its type matches the field
+fn exec_putfield(jvm: &Jvm, stack: &mut Stack, field: &FieldRef) -> ExecResult {
+    let (_, field) = rethrow_exception!(resolve_field(jvm, field, false)?);
+    let value = stack.pop()?;
     let reference = pop_stack_typechecked!(Object::Reference, stack);
-    if let Some(reference) = reference {
-        if reference.is_subclass(super_class) {
-            Ok(Ok(ResultValue::Object(Object::Reference(Some(reference)))))
+    let reference = rethrow_exception!(check_null(jvm, reference)?);
+    reference.set_field(field.get_slot(), value)?;
+    Ok(Ok(ResultValue::None))
+}
+
+/// The class declaring the field is initialized first, it may not be the referenced one
+fn exec_getstatic(jvm: &Jvm, field: &FieldRef) -> ExecResult {
+    let (class, field) = rethrow_exception!(resolve_field(jvm, field, true)?);
+    rethrow_exception!(class.initialize(jvm)?);
+    class
+        .get_static(field.get_slot())
+        .map(ResultValue::Object)
+        .map(Ok)
+}
+
+fn exec_putstatic(jvm: &Jvm, stack: &mut Stack, field: &FieldRef) -> ExecResult {
+    let (class, field) = rethrow_exception!(resolve_field(jvm, field, true)?);
+    rethrow_exception!(class.initialize(jvm)?);
+    let value = stack.pop()?;
+    class.set_static(field.get_slot(), value)?;
+    Ok(Ok(ResultValue::None))
+}
+
+fn describe_method(class_name: &str, method: &MethodRef) -> String {
+    format!(
+        "{}.{}{}",
+        class_name.replace('/', "."),
+        method.name(),
+        method.descriptor()
+    )
+}
+
+/// Resolve the method, and check it is static for invokestatic or not for the other invokes
+///
+/// The class declaring the method is returned with it
+pub fn resolve_method<'a>(
+    jvm: &Jvm,
+    method: &'a MethodRef,
+    is_static: bool,
+) -> ExecResult<&'a ResolvedMethod> {
+    let resolved = match method.resolve(jvm.class_loader()) {
+        Ok(resolved) => resolved,
+        Err(error) => return throw_loading_error(jvm, error),
+    };
+    let method_name = describe_method(method.class().name(), method);
+    let Some(resolved) = resolved else {
+        return throw_exception(jvm, NO_SUCH_METHOD_ERROR, Some(method_name));
+    };
+    if resolved.1.is_static() != is_static {
+        let message = if is_static {
+            format!("Expected static method {}", method_name)
         } else {
-            // TODO: Throw ClassCastException
-            todo!()
-        }
+            format!("Expecting non-static method {}", method_name)
+        };
+        return throw_exception(jvm, INCOMPATIBLE_CLASS_CHANGE_ERROR, Some(message));
+    }
+    Ok(Ok(resolved))
+}
+
+/// Check invokevirtual references a class and invokeinterface an interface
+fn check_referenced_class<'a>(
+    jvm: &Jvm,
+    method: &'a MethodRef,
+    is_interface: bool,
+) -> ExecResult<&'a Arc<Class>> {
+    let class = rethrow_exception!(resolve_class(jvm, method.class())?);
+    if class.is_interface() == is_interface {
+        return Ok(Ok(class));
+    }
+    let (found, expected) = if is_interface {
+        ("class", "interface")
     } else {
-        Ok(Ok(ResultValue::Object(Object::Reference(None))))
+        ("interface", "class")
+    };
+    let message = format!(
+        "Found {} {}, but {} was expected",
+        found,
+        class.get_name().replace('/', "."),
+        expected
+    );
+    throw_exception(jvm, INCOMPATIBLE_CLASS_CHANGE_ERROR, Some(message))
+}
+
+/// The class of the receiver, which is under the arguments on the stack
+fn peek_receiver_class(jvm: &Jvm, stack: &Stack, method: &MethodRef) -> ExecResult<Arc<Class>> {
+    let arguments_size = method.parsed_descriptor().parameters_size();
+    match stack.peek_at(arguments_size)? {
+        Object::Reference(Some(reference)) => Ok(Ok(reference.get_class().clone())),
+        // arrays only have the methods of Object
+        Object::Array(Some(array)) => array.get_class(jvm).map(Ok),
+        Object::Reference(None) | Object::Array(None) => {
+            throw_exception(jvm, NULL_POINTER_EXCEPTION, None)
+        }
+        _ => Err(InternalError::WrongType),
+    }
+}
+
+/// Turn a failed method selection into an AbstractMethodError or an IncompatibleClassChangeError
+fn check_selected(
+    jvm: &Jvm,
+    class: &Class,
+    method: &MethodRef,
+    selected: SelectedMethod,
+) -> ExecResult<Arc<Method>> {
+    match selected {
+        Ok(Some(selected)) => Ok(Ok(selected)),
+        Ok(None) => {
+            let message = describe_method(class.get_name(), method);
+            throw_exception(jvm, ABSTRACT_METHOD_ERROR, Some(message))
+        }
+        Err(MethodSelectionError::ConflictingDefaultMethods(interfaces)) => {
+            let methods: Vec<String> = interfaces
+                .iter()
+                .map(|interface| format!("{}.{}", interface.replace('/', "."), method.name()))
+                .collect();
+            let message = format!("Conflicting default methods: {}", methods.join(" "));
+            throw_exception(jvm, INCOMPATIBLE_CLASS_CHANGE_ERROR, Some(message))
+        }
+    }
+}
+
+/// Run the method with the arguments and the receiver on the stack,
+/// the returned value is pushed on the stack and exceptions are thrown back to the caller
+fn invoke(jvm: &Jvm, stack: &mut Stack, method: &Method) -> ExecResult {
+    let returned = rethrow_exception!(method.execute(jvm, stack)?);
+    Ok(Ok(returned.map_or(ResultValue::None, ResultValue::Object)))
+}
+
+/// The class declaring the method is initialized first
+fn exec_invokestatic(jvm: &Jvm, stack: &mut Stack, method: &MethodRef) -> ExecResult {
+    let (class, resolved) = rethrow_exception!(resolve_method(jvm, method, true)?);
+    rethrow_exception!(class.initialize(jvm)?);
+    invoke(jvm, stack, resolved)
+}
+
+/// The method is selected with the dispatch tables of the class of the receiver,
+/// the verifier guarantees the receiver is an instance of the referenced class
+fn exec_invokevirtual(jvm: &Jvm, stack: &mut Stack, method: &MethodRef) -> ExecResult {
+    rethrow_exception!(check_referenced_class(jvm, method, false)?);
+    let (class, resolved) = rethrow_exception!(resolve_method(jvm, method, false)?);
+    let receiver_class = rethrow_exception!(peek_receiver_class(jvm, stack, method)?);
+    let selected = receiver_class
+        .select_method(class, resolved)
+        .ok_or(InternalError::WrongType)?;
+    let selected = rethrow_exception!(check_selected(jvm, &receiver_class, method, selected)?);
+    invoke(jvm, stack, &selected)
+}
+
+/// Interfaces are verified like Object, so the receiver is checked when the method is called
+fn exec_invokeinterface(jvm: &Jvm, stack: &mut Stack, method: &MethodRef) -> ExecResult {
+    let interface = rethrow_exception!(check_referenced_class(jvm, method, true)?);
+    let (class, resolved) = rethrow_exception!(resolve_method(jvm, method, false)?);
+    let receiver_class = rethrow_exception!(peek_receiver_class(jvm, stack, method)?);
+    let selected = receiver_class
+        .select_method(class, resolved)
+        .filter(|_| receiver_class.implements(interface));
+    let Some(selected) = selected else {
+        let message = format!(
+            "Class {} does not implement the requested interface {}",
+            receiver_class.get_name().replace('/', "."),
+            interface.get_name().replace('/', ".")
+        );
+        return throw_exception(jvm, INCOMPATIBLE_CLASS_CHANGE_ERROR, Some(message));
+    };
+    let selected = rethrow_exception!(check_selected(jvm, &receiver_class, method, selected)?);
+    invoke(jvm, stack, &selected)
+}
+
+/// The call site is linked by its bootstrap method the first time it runs
+fn exec_invokedynamic(
+    jvm: &Jvm,
+    stack: &mut Stack,
+    call_site: &CallSiteRef,
+    caller: &Arc<ClassRef>,
+) -> ExecResult {
+    let call_site = rethrow_exception!(call_site.link(jvm, caller)?);
+    call_site.invoke(jvm, stack)
+}
+
+/// Call a constructor, a private method, or a method of a super class without virtual dispatch
+///
+/// `super.method()` calls start the lookup in the super class of the caller,
+/// in case the referenced method is overridden by a class in between.
+fn exec_invokespecial(
+    jvm: &Jvm,
+    stack: &mut Stack,
+    method: &MethodRef,
+    caller: &ClassRef,
+) -> ExecResult {
+    let referenced = rethrow_exception!(resolve_class(jvm, method.class())?);
+    let (class, _) = rethrow_exception!(resolve_method(jvm, method, false)?);
+    let is_constructor = method.name() == CONSTRUCTOR_NAME;
+    if is_constructor && !Arc::ptr_eq(class, referenced) {
+        let message = describe_method(referenced.get_name(), method);
+        return throw_exception(jvm, NO_SUCH_METHOD_ERROR, Some(message));
     }
+    let caller = rethrow_exception!(resolve_class(jvm, caller)?);
+    let is_super_call = !is_constructor
+        && !referenced.is_interface()
+        && !Arc::ptr_eq(caller, referenced)
+        && caller.is_subclass(referenced);
+    let lookup_class = match caller.get_superclass() {
+        Some(super_class) if is_super_call => super_class,
+        _ => referenced,
+    };
+    rethrow_exception!(peek_receiver_class(jvm, stack, method)?);
+    let selected = lookup_class.select_special_method(method.name(), method.descriptor());
+    let selected = rethrow_exception!(check_selected(jvm, lookup_class, method, selected)?);
+    invoke(jvm, stack, &selected)
 }
 
 macro_rules! impl_numerical_cast {
@@ -486,8 +835,8 @@ impl_numerical_cast!(
     (exec_f2d, Object::Float, Object::Double, f64),
     (exec_f2i, Object::Float, Object::Int, i32),
     (exec_f2l, Object::Float, Object::Long, i64),
-    (exec_i2b, Object::Int, Object::Int, u8 as i32),
-    (exec_i2c, Object::Int, Object::Int, u8 as i32),
+    (exec_i2b, Object::Int, Object::Int, i8 as i32),
+    (exec_i2c, Object::Int, Object::Int, u16 as i32),
     (exec_i2d, Object::Int, Object::Double, f64),
     (exec_i2f, Object::Int, Object::Float, f32),
     (exec_i2l, Object::Int, Object::Long, i64),
@@ -497,16 +846,17 @@ impl_numerical_cast!(
     (exec_l2i, Object::Long, Object::Int, i32)
 );
 
+// int and long arithmetic wraps around on overflow
 macro_rules! impl_numeric_operation {
-    ($(($fn_name:ident, $operation:tt)),+) => {
+    ($(($fn_name:ident, $operation:tt, $integer_operation:ident)),+) => {
         $(fn $fn_name(stack: &mut Stack) -> ExecResult {
-            let op_1 = stack.pop()?;
             let op_2 = stack.pop()?;
+            let op_1 = stack.pop()?;
             let value = match (op_1, op_2) {
                 (Object::Double(op_1), Object::Double(op_2)) => Object::Double(op_1 $operation op_2),
                 (Object::Float(op_1), Object::Float(op_2)) => Object::Float(op_1 $operation op_2),
-                (Object::Int(op_1), Object::Int(op_2)) => Object::Int(op_1 $operation op_2),
-                (Object::Long(op_1), Object::Long(op_2)) => Object::Long(op_1 $operation op_2),
+                (Object::Int(op_1), Object::Int(op_2)) => Object::Int(op_1.$integer_operation(op_2)),
+                (Object::Long(op_1), Object::Long(op_2)) => Object::Long(op_1.$integer_operation(op_2)),
                 _ => return Err(InternalError::WrongType)
             };
             Ok(Ok(ResultValue::Object(value)))
@@ -530,25 +880,82 @@ macro_rules! impl_decimal_operation {
 }
 
 impl_numeric_operation!(
-    (exec_add, +),
-    (exec_div, /),
-    (exec_mul, *),
-    (exec_sub, -)
+    (exec_add, +, wrapping_add),
+    (exec_mul, *, wrapping_mul),
+    (exec_sub, -, wrapping_sub)
+);
+
+// integer division by zero throws instead of giving infinity or NaN
+macro_rules! impl_division {
+    ($(($fn_name:ident, $operation:tt, $integer_operation:ident)),+) => {
+        $(fn $fn_name(jvm: &Jvm, stack: &mut Stack) -> ExecResult {
+            let op_2 = stack.pop()?;
+            let op_1 = stack.pop()?;
+            let value = match (op_1, op_2) {
+                (Object::Double(op_1), Object::Double(op_2)) => Object::Double(op_1 $operation op_2),
+                (Object::Float(op_1), Object::Float(op_2)) => Object::Float(op_1 $operation op_2),
+                (Object::Int(_), Object::Int(0)) | (Object::Long(_), Object::Long(0)) => {
+                    return throw_exception(jvm, ARITHMETIC_EXCEPTION, Some("/ by zero".to_string()));
+                }
+                // MIN / -1 overflows back to MIN
+                (Object::Int(op_1), Object::Int(op_2)) => Object::Int(op_1.$integer_operation(op_2)),
+                (Object::Long(op_1), Object::Long(op_2)) => Object::Long(op_1.$integer_operation(op_2)),
+                _ => return Err(InternalError::WrongType)
+            };
+            Ok(Ok(ResultValue::Object(value)))
+        })+
+    };
+}
+
+impl_division!(
+    (exec_div, /, wrapping_div),
+    (exec_rem, %, wrapping_rem)
 );
 
 impl_decimal_operation!(
     (exec_or, |),
     (exec_and, &),
-    (exec_shl, <<),
-    (exec_shr, >>),
     (exec_xor, ^)
 );
 
+/// The distance is an int for both int and long values, only its low 5 bits are used for an int
+/// and its low 6 bits for a long, which is how the wrapping shifts mask it
+fn exec_shift(
+    stack: &mut Stack,
+    int_shift: fn(i32, u32) -> i32,
+    long_shift: fn(i64, u32) -> i64,
+) -> ExecResult {
+    let distance = pop_stack_typechecked!(Object::Int, stack) as u32;
+    let value = match stack.pop()? {
+        Object::Int(value) => Object::Int(int_shift(value, distance)),
+        Object::Long(value) => Object::Long(long_shift(value, distance)),
+        _ => return Err(InternalError::WrongType),
+    };
+    Ok(Ok(ResultValue::Object(value)))
+}
+
+// the logical shifts fill the high bits with zeros, like a shift of the unsigned value
+fn exec_iushr(stack: &mut Stack) -> ExecResult {
+    let distance = pop_stack_typechecked!(Object::Int, stack) as u32;
+    let value = pop_stack_typechecked!(Object::Int, stack) as u32;
+    Ok(Ok(ResultValue::Object(Object::Int(
+        value.wrapping_shr(distance) as i32,
+    ))))
+}
+
+fn exec_lushr(stack: &mut Stack) -> ExecResult {
+    let distance = pop_stack_typechecked!(Object::Int, stack) as u32;
+    let value = pop_stack_typechecked!(Object::Long, stack) as u64;
+    Ok(Ok(ResultValue::Object(Object::Long(
+        value.wrapping_shr(distance) as i64,
+    ))))
+}
+
 macro_rules! impl_cmp {
     ($(($fn_name:ident, $num_type:path, $default:literal)), +) => {
         $(fn $fn_name(stack: &mut Stack) -> ExecResult {
-            let op_1 = pop_stack_typechecked!($num_type, stack);
             let op_2 = pop_stack_typechecked!($num_type, stack);
+            let op_1 = pop_stack_typechecked!($num_type, stack);
             let value = match op_1.partial_cmp(&op_2) {
                 Some(std::cmp::Ordering::Greater) => 1,
                 Some(std::cmp::Ordering::Equal) => 0,
@@ -568,8 +975,8 @@ impl_cmp!(
 );
 
 pub fn exec_lcmp(stack: &mut Stack) -> ExecResult {
-    let l_1 = pop_stack_typechecked!(Object::Long, stack);
     let l_2 = pop_stack_typechecked!(Object::Long, stack);
+    let l_1 = pop_stack_typechecked!(Object::Long, stack);
     let value = match l_1.cmp(&l_2) {
         std::cmp::Ordering::Less => -1,
         std::cmp::Ordering::Equal => 0,
@@ -581,9 +988,10 @@ pub fn exec_lcmp(stack: &mut Stack) -> ExecResult {
 fn exec_numerical_neg(stack: &mut Stack) -> ExecResult {
     let num = stack.pop()?;
     let num = match num {
-        Object::Int(num) => Object::Int(-num),
+        // the negation of MIN overflows back to MIN
+        Object::Int(num) => Object::Int(num.wrapping_neg()),
         Object::Float(num) => Object::Float(-num),
-        Object::Long(num) => Object::Long(-num),
+        Object::Long(num) => Object::Long(num.wrapping_neg()),
         Object::Double(num) => Object::Double(-num),
         _ => return Err(InternalError::WrongType),
     };
@@ -591,8 +999,8 @@ fn exec_numerical_neg(stack: &mut Stack) -> ExecResult {
 }
 
 fn exec_if_acmpeq(stack: &mut Stack, jump: usize) -> ExecResult {
-    let ref1 = pop_stack_typechecked!(Object::Reference, stack);
-    let ref2 = pop_stack_typechecked!(Object::Reference, stack);
+    let ref1 = pop_nullable_object(stack)?;
+    let ref2 = pop_nullable_object(stack)?;
     if ref1 == ref2 {
         Ok(Ok(ResultValue::Jump(jump)))
     } else {
@@ -601,8 +1009,8 @@ fn exec_if_acmpeq(stack: &mut Stack, jump: usize) -> ExecResult {
 }
 
 fn exec_if_acmpne(stack: &mut Stack, jump: usize) -> ExecResult {
-    let ref_1 = pop_stack_typechecked!(Object::Reference, stack);
-    let ref_2 = pop_stack_typechecked!(Object::Reference, stack);
+    let ref_1 = pop_nullable_object(stack)?;
+    let ref_2 = pop_nullable_object(stack)?;
     if ref_1 != ref_2 {
         Ok(Ok(ResultValue::Jump(jump)))
     } else {
@@ -656,7 +1064,7 @@ impl_icmp_zero!(
 );
 
 fn exec_ifnonnull(stack: &mut Stack, jump: usize) -> ExecResult {
-    let reference = pop_stack_typechecked!(Object::Reference, stack);
+    let reference = pop_nullable_object(stack)?;
     if reference.is_some() {
         Ok(Ok(ResultValue::Jump(jump)))
     } else {
@@ -665,7 +1073,7 @@ fn exec_ifnonnull(stack: &mut Stack, jump: usize) -> ExecResult {
 }
 
 fn exec_ifnull(stack: &mut Stack, jump: usize) -> ExecResult {
-    let reference = pop_stack_typechecked!(Object::Reference, stack);
+    let reference = pop_nullable_object(stack)?;
     if reference.is_none() {
         Ok(Ok(ResultValue::Jump(jump)))
     } else {
@@ -676,18 +1084,15 @@ fn exec_ifnull(stack: &mut Stack, jump: usize) -> ExecResult {
 fn exec_iinc(locals: &mut Locals, index: usize, delta: i32) -> ExecResult {
     let i = locals.get_non_empty_mut(index)?;
     let i = check_type!(Object::Int, i);
-    *i += delta;
+    *i = i.wrapping_add(delta);
     Ok(Ok(ResultValue::None))
 }
 
-fn exec_instanceof(stack: &mut Stack, class: &Arc<Class>) -> ExecResult {
-    let reference = pop_stack_typechecked!(Object::Reference, stack);
-    let value = if let Some(reference) = reference {
-        if reference.is_subclass(class) {
-            1
-        } else {
-            0
-        }
+fn exec_instanceof(jvm: &Jvm, stack: &mut Stack, class: &ClassRef) -> ExecResult {
+    let object = stack.pop()?;
+    let value = if let Some(object_class) = object.get_class(jvm)? {
+        let class = rethrow_exception!(resolve_class(jvm, class)?);
+        object_class.is_subclass(class) as i32
     } else {
         0
     };
@@ -719,3 +1124,31 @@ fn exec_tableswitc(stack: &mut Stack, table_switch: &TableSwitch) -> ExecResult
     let jump_adress = table_switch.find_jump(index);
     Ok(Ok(ResultValue::Jump(jump_adress)))
 }
+
+fn exec_ldc(jvm: &Jvm, constant: &LoadableConstant) -> ExecResult {
+    let value = match constant {
+        LoadableConstant::Int(value) => Object::Int(*value),
+        LoadableConstant::Float(value) => Object::Float(*value),
+        LoadableConstant::String(string) => {
+            let string = rethrow_exception!(string.resolve(jvm)?);
+            Object::Reference(Some(string.clone()))
+        }
+        LoadableConstant::Class(class) => {
+            let class = rethrow_exception!(resolve_class(jvm, class)?);
+            let mirror = rethrow_exception!(class.get_mirror(jvm)?);
+            Object::Reference(Some(mirror))
+        }
+        LoadableConstant::Dynamic { constant, caller } => {
+            rethrow_exception!(constant.resolve(jvm, caller)?)
+        }
+    };
+    Ok(Ok(ResultValue::Object(value)))
+}
+
+fn exec_ldc2_w(constant: ConstantNumerical) -> ExecResult {
+    let value = match constant {
+        ConstantNumerical::Double(value) => Object::Double(value),
+        ConstantNumerical::Long(value) => Object::Long(value),
+    };
+    Ok(Ok(ResultValue::Object(value)))
+}
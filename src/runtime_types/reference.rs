@@ -1,7 +1,6 @@
-use super::Class;
-use std::sync::Arc;
-// use std::sync::Mutex;
+use super::{Class, InternalError, Object};
 use std::ops::Deref;
+use std::sync::{Arc, Mutex, Weak};
 
 #[derive(Debug, Clone)]
 pub struct Reference(Arc<RefInner>);
@@ -9,6 +8,10 @@ pub struct Reference(Arc<RefInner>);
 #[derive(Debug)]
 pub struct RefInner {
     class: Arc<Class>,
+    // instance fields, laid out by the class
+    fields: Mutex<Box<[Object]>>,
+    // the class represented by an instance of java/lang/Class, like the hidden field of HotSpot
+    mirrored_class: Option<Weak<Class>>,
 }
 
 impl Deref for Reference {
@@ -23,14 +26,92 @@ impl RefInner {
     pub fn get_class(&self) -> &Arc<Class> {
         &self.class
     }
+
+    /// The class represented by a java/lang/Class, None for the other objects
+    pub fn get_mirrored_class(&self) -> Option<Arc<Class>> {
+        self.mirrored_class.as_ref().and_then(Weak::upgrade)
+    }
+
+    /// The slots of the fields, locked by the collector to follow the references
+    pub fn get_fields(&self) -> &Mutex<Box<[Object]>> {
+        &self.fields
+    }
+
+    pub fn get_field(&self, slot: usize) -> Result<Object, InternalError> {
+        let fields = self.fields.lock()?;
+        fields
+            .get(slot)
+            .cloned()
+            .ok_or(InternalError::FieldSlotOutOfBounds)
+    }
+
+    /// Longs and doubles keep the padding of their second slot
+    pub fn set_field(&self, slot: usize, value: Object) -> Result<(), InternalError> {
+        let mut fields = self.fields.lock()?;
+        let field = fields
+            .get_mut(slot)
+            .ok_or(InternalError::FieldSlotOutOfBounds)?;
+        *field = value;
+        Ok(())
+    }
 }
 
 impl Reference {
+    /// Allocate a new instance of the class, with its fields set to their default value
+    pub fn new(class: Arc<Class>) -> Self {
+        let fields = Mutex::new(class.new_instance_slots());
+        Reference(Arc::new(RefInner {
+            class,
+            fields,
+            mirrored_class: None,
+        }))
+    }
+
+    /// Allocate the java/lang/Class representing a class, which only keeps a weak reference
+    /// to it as the class keeps its mirror
+    pub fn new_mirror(class_class: Arc<Class>, mirrored_class: &Arc<Class>) -> Self {
+        let fields = Mutex::new(class_class.new_instance_slots());
+        Reference(Arc::new(RefInner {
+            class: class_class,
+            fields,
+            mirrored_class: Some(Arc::downgrade(mirrored_class)),
+        }))
+    }
+
+    /// A reference that doesn't keep the object alive, kept by the heap
+    pub fn downgrade(&self) -> Weak<RefInner> {
+        Arc::downgrade(&self.0)
+    }
+
+    pub fn upgrade(object: &Weak<RefInner>) -> Option<Self> {
+        object.upgrade().map(Reference)
+    }
+
+    /// The number of references to the object, this one included
+    pub fn strong_count(&self) -> usize {
+        Arc::strong_count(&self.0)
+    }
+
+    /// The address of the object, which identifies it while it is alive
+    pub fn address(&self) -> usize {
+        Arc::as_ptr(&self.0) as usize
+    }
+
+    /// The hash code of Object.hashCode, derived from the identity of the object
+    pub fn identity_hash_code(&self) -> i32 {
+        identity_hash_code(Arc::as_ptr(&self.0))
+    }
+
     pub fn is_subclass(&self, super_class: &Arc<Class>) -> bool {
         self.get_class().is_subclass(super_class)
     }
 }
 
+/// The address of an object, without the bits that are the same for every allocation
+pub fn identity_hash_code<T>(pointer: *const T) -> i32 {
+    (pointer as usize >> 3) as i32
+}
+
 impl PartialEq for Reference {
     fn eq(&self, rhs: &Self) -> bool {
         Arc::ptr_eq(&self.0, &rhs.0)
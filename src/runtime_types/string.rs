@@ -0,0 +1,149 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, LowerExp},
+    sync::{Arc, Mutex},
+};
+
+use crate::{parser::descriptor::FieldType, rethrow_exception, runtime::jvm::Jvm};
+
+use super::{Array, Class, ExecResult, InternalError, Object, Reference};
+
+pub const STRING_CLASS: &str = "java/lang/String";
+const VALUE_FIELD: &str = "value";
+
+// the slot of the char[] holding the UTF-16 code units of a string
+fn value_slot(string_class: &Class) -> Result<usize, InternalError> {
+    string_class
+        .find_field(VALUE_FIELD, &FieldType::Array(Box::new(FieldType::Char)))
+        .map(|field| field.get_slot())
+        .ok_or(InternalError::WrongType)
+}
+
+/// Create a java/lang/String with the UTF-16 code units, without running a constructor
+///
+/// The class is initialized first, like new would. An OutOfMemoryError is thrown if the
+/// string doesn't fit in the heap.
+pub fn new_utf16_string(jvm: &Jvm, units: Box<[u16]>) -> ExecResult<Reference> {
+    create_string(jvm, units, true)
+}
+
+/// Same as new_string, for the strings the virtual machine needs when the heap is full,
+/// like the message of an OutOfMemoryError
+///
+/// They are counted in the heap, but can go past its maximum size.
+pub fn new_tracked_string(jvm: &Jvm, text: &str) -> ExecResult<Reference> {
+    create_string(jvm, text.encode_utf16().collect(), false)
+}
+
+fn create_string(jvm: &Jvm, units: Box<[u16]>, bounded: bool) -> ExecResult<Reference> {
+    let class = jvm
+        .class_loader()
+        .load_class(STRING_CLASS)
+        .map_err(|_| InternalError::ClassLoadingFailed(STRING_CLASS.to_string()))?;
+    rethrow_exception!(class.initialize(jvm)?);
+    let slot = value_slot(&class)?;
+    let value = Array::Char(Arc::new(Mutex::new(units)));
+    let (string, value) = if bounded {
+        let value = rethrow_exception!(jvm.heap().allocate_array(jvm, value)?);
+        let string = rethrow_exception!(jvm.heap().new_object(jvm, class)?);
+        (string, value)
+    } else {
+        let string = Reference::new(class);
+        jvm.heap().track_object(&string)?;
+        jvm.heap().track_array(&value)?;
+        (string, value)
+    };
+    string.set_field(slot, Object::Array(Some(value)))?;
+    Ok(Ok(string))
+}
+
+/// Same as new_utf16_string, with the text encoded in UTF-16
+pub fn new_string(jvm: &Jvm, text: &str) -> ExecResult<Reference> {
+    new_utf16_string(jvm, text.encode_utf16().collect())
+}
+
+/// The UTF-16 code units of a java/lang/String
+pub fn read_utf16_string(string: &Reference) -> Result<Vec<u16>, InternalError> {
+    let slot = value_slot(string.get_class())?;
+    let Object::Array(Some(Array::Char(units))) = string.get_field(slot)? else {
+        return Err(InternalError::WrongType);
+    };
+    let units = units.lock()?;
+    Ok(units.to_vec())
+}
+
+/// The text of a java/lang/String, the lone surrogates are replaced by U+FFFD
+pub fn read_string(string: &Reference) -> Result<String, InternalError> {
+    read_utf16_string(string).map(|units| String::from_utf16_lossy(&units))
+}
+
+/// The strings returned by String.intern, shared by every class
+///
+/// Two string literals with the same characters are the same object (JLS §3.10.5).
+/// The strings are kept by their UTF-16 code units, so they can hold lone surrogates.
+#[derive(Debug, Default)]
+pub struct InternTable {
+    strings: Mutex<HashMap<Box<[u16]>, Reference>>,
+}
+
+impl InternTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, units: &[u16]) -> Result<Option<Reference>, InternalError> {
+        let strings = self.strings.lock()?;
+        Ok(strings.get(units).cloned())
+    }
+
+    /// Add the string if there isn't one with the same characters already,
+    /// and return the one in the table
+    pub fn insert(&self, units: Box<[u16]>, string: Reference) -> Result<Reference, InternalError> {
+        let mut strings = self.strings.lock()?;
+        Ok(strings.entry(units).or_insert(string).clone())
+    }
+}
+
+/// The interned string with the UTF-16 code units, created if there isn't one yet
+pub fn intern_utf16_string(jvm: &Jvm, units: Box<[u16]>) -> ExecResult<Reference> {
+    if let Some(string) = jvm.intern_table().get(&units)? {
+        return Ok(Ok(string));
+    }
+    // the table is not locked while the string class is initialized, it can intern strings too
+    let string = rethrow_exception!(new_utf16_string(jvm, units.clone())?);
+    jvm.intern_table().insert(units, string).map(Ok)
+}
+
+/// The interned string with the text, like a string literal
+pub fn intern_string(jvm: &Jvm, text: &str) -> ExecResult<Reference> {
+    intern_utf16_string(jvm, text.encode_utf16().collect())
+}
+
+/// Format a float or a double like Double.toString: plain between 10^-3 and 10^7,
+/// in scientific notation otherwise, always with a fractional part
+pub fn format_floating<T: Display + LowerExp + Into<f64> + Copy>(value: T) -> String {
+    let double: f64 = value.into();
+    if double.is_nan() {
+        return "NaN".to_string();
+    }
+    if double.is_infinite() {
+        let sign = if double < 0.0 { "-" } else { "" };
+        return format!("{}Infinity", sign);
+    }
+    let magnitude = double.abs();
+    if magnitude == 0.0 || (1e-3..1e7).contains(&magnitude) {
+        let plain = value.to_string();
+        return if plain.contains('.') {
+            plain
+        } else {
+            format!("{}.0", plain)
+        };
+    }
+    let scientific = format!("{:e}", value);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    if mantissa.contains('.') {
+        format!("{}E{}", mantissa, exponent)
+    } else {
+        format!("{}.0E{}", mantissa, exponent)
+    }
+}
@@ -1,4 +1,7 @@
-use crate::runtime_types::{Code, ExceptionTable, OpCode};
+use crate::{
+    parser::classfile::opcode::ArrayType,
+    runtime_types::{Code, ExceptionTable, OpCode},
+};
 
 pub fn basic_add_function() -> Code {
     let max_stack = 2;
@@ -73,3 +76,81 @@ pub fn fibonacci_calculator() -> Code {
 
     Code::new(max_stack, max_locals, opcodes, args_count, exception_table)
 }
+
+/*
+    stores its argument in a new array of one item, and loads it back
+    0: iconst_1
+    1: newarray      <array_type>
+    3: dup
+    4: iconst_0
+    5: xload_0
+    6: xastore
+    7: iconst_0
+    8: xaload
+    9: xreturn
+*/
+
+pub fn array_round_trip(array_type: ArrayType, args_count: usize) -> Code {
+    let max_stack = 3 + args_count;
+    let max_locals = args_count;
+    let exception_table = ExceptionTable::new(None);
+
+    use OpCode::*;
+    let opcodes = vec![
+        iconst_1,
+        newarray(array_type),
+        dup,
+        iconst_0,
+        load_0,
+        astore,
+        iconst_0,
+        aload,
+        return_v,
+    ];
+
+    Code::new(max_stack, max_locals, opcodes, args_count, exception_table)
+}
+
+/*
+    0: iload_0
+    1: <conversion>
+    2: ireturn
+*/
+
+pub fn int_conversion(conversion: OpCode) -> Code {
+    let exception_table = ExceptionTable::new(None);
+
+    use OpCode::*;
+    let opcodes = vec![load_0, conversion, return_v];
+
+    Code::new(1, 1, opcodes, 1, exception_table)
+}
+
+/*
+    0: iload_0 or lload_0
+    1: iload_1 or iload_2, after the long
+    2: <shift>
+    3: ireturn or lreturn
+*/
+
+pub fn shift(shift: OpCode, value_size: usize) -> Code {
+    let exception_table = ExceptionTable::new(None);
+
+    use OpCode::*;
+    let opcodes = vec![
+        load_0,
+        load_i {
+            local_index: value_size,
+        },
+        shift,
+        return_v,
+    ];
+
+    Code::new(
+        value_size + 1,
+        value_size + 1,
+        opcodes,
+        value_size + 1,
+        exception_table,
+    )
+}
@@ -1,9 +1,20 @@
-use super::{Object, Stack};
+use std::collections::HashMap;
+
+use crate::{
+    parser::classfile::opcode::ArrayType,
+    runtime::{class_loader::ClassLoader, jvm::Jvm},
+};
+
+use super::{Object, OpCode, Reference, Stack};
 
 mod code_creation;
 
 use code_creation::*;
 
+fn empty_jvm() -> Jvm {
+    Jvm::new(ClassLoader::new(vec![]), HashMap::new())
+}
+
 #[test]
 fn test_basic_add() {
     let code = basic_add_function();
@@ -13,11 +24,30 @@ fn test_basic_add() {
 
     stack.push(Object::Int(a));
     stack.push(Object::Int(b));
-    let result = code.execute(&mut stack);
+    let result = code.execute(&empty_jvm(), &mut stack);
 
     assert_eq!(result, Ok(Ok(Some(Object::Int(a + b)))));
 }
 
+#[test]
+fn test_dup2() {
+    // two single values, like an array and an index before a[i]++
+    let mut stack = Stack::new(4);
+    stack.push(Object::Int(1));
+    stack.push(Object::Int(2));
+    stack.dup2().unwrap();
+    let popped: Vec<Object> = (0..4).map(|_| stack.pop().unwrap()).collect();
+    let expected = [2, 1, 2, 1].map(Object::Int);
+    assert_eq!(popped, expected);
+
+    let mut stack = Stack::new(4);
+    stack.push(Object::Long(3));
+    stack.dup2().unwrap();
+    assert_eq!(stack.pop(), Ok(Object::Long(3)));
+    assert_eq!(stack.pop(), Ok(Object::Long(3)));
+    assert!(stack.pop().is_err());
+}
+
 #[test]
 fn test_fibonacci() {
     fn fib(n: i32) -> i32 {
@@ -35,8 +65,169 @@ fn test_fibonacci() {
     let mut stack = Stack::new(1);
     let n = 30;
     stack.push(Object::Int(n));
-    let result = code.execute(&mut stack);
+    let result = code.execute(&empty_jvm(), &mut stack);
     let should_be = fib(n);
 
     assert_eq!(result, Ok(Ok(Some(Object::Int(should_be)))))
 }
+
+/// The value read back after storing it in an array of the type
+fn store_and_load(jvm: &Jvm, array_type: ArrayType, value: Object) -> Object {
+    let args_count = if value.is_wide() { 2 } else { 1 };
+    let code = array_round_trip(array_type, args_count);
+    let mut stack = Stack::new(args_count);
+    stack.push(value);
+    match code.execute(jvm, &mut stack) {
+        Ok(Ok(Some(loaded))) => loaded,
+        result => panic!("the round trip should return a value: {:?}", result),
+    }
+}
+
+// the int arrays truncate the int stored, and the loads extend it back to an int
+#[test]
+fn test_int_array_conformance() {
+    let jvm = empty_jvm();
+    let cases = [
+        (ArrayType::Boolean, 1, 1),
+        (ArrayType::Boolean, 2, 0),
+        (ArrayType::Boolean, 3, 1),
+        (ArrayType::Boolean, -1, 1),
+        (ArrayType::Boolean, -2, 0),
+        (ArrayType::Byte, 127, 127),
+        (ArrayType::Byte, 200, -56),
+        (ArrayType::Byte, -129, 127),
+        (ArrayType::Byte, 0x1FF, -1),
+        (ArrayType::Char, 'A' as i32, 'A' as i32),
+        (ArrayType::Char, 0x1_0041, 0x41),
+        (ArrayType::Char, 0xFFFF, 0xFFFF),
+        (ArrayType::Char, -1, 0xFFFF),
+        (ArrayType::Short, 40000, -25536),
+        (ArrayType::Short, 0x1_8000, -32768),
+        (ArrayType::Short, -1, -1),
+        (ArrayType::Int, i32::MIN, i32::MIN),
+        (ArrayType::Int, -1, -1),
+    ];
+    for (array_type, stored, loaded) in cases {
+        let description = format!("{:?} {}", array_type, stored);
+        assert_eq!(
+            store_and_load(&jvm, array_type, Object::Int(stored)),
+            Object::Int(loaded),
+            "{}",
+            description
+        );
+    }
+}
+
+#[test]
+fn test_wide_and_floating_array_conformance() {
+    let jvm = empty_jvm();
+    let values = [
+        (ArrayType::Long, Object::Long(i64::MIN)),
+        (ArrayType::Long, Object::Long(-1)),
+        (ArrayType::Float, Object::Float(-0.0)),
+        (ArrayType::Float, Object::Float(f32::MIN_POSITIVE)),
+        (ArrayType::Double, Object::Double(f64::MAX)),
+        (ArrayType::Double, Object::Double(-1.5)),
+    ];
+    for (array_type, value) in values {
+        assert_eq!(store_and_load(&jvm, array_type, value.clone()), value);
+    }
+    let Object::Float(nan) = store_and_load(&jvm, ArrayType::Float, Object::Float(f32::NAN)) else {
+        panic!("faload should load a float");
+    };
+    assert!(nan.is_nan());
+}
+
+#[test]
+fn test_reference_array_conformance() {
+    let jvm = empty_jvm();
+    let object_class = jvm.class_loader().load_class("java/lang/Object").unwrap();
+    let object = Object::Reference(Some(Reference::new(object_class.clone())));
+    let array_type = || ArrayType::Reference(object_class.clone());
+    assert_eq!(store_and_load(&jvm, array_type(), object.clone()), object);
+    assert_eq!(
+        store_and_load(&jvm, array_type(), Object::Reference(None)),
+        Object::Reference(None)
+    );
+
+    // the arrays are objects too
+    let stored_array = Object::Array(Some(super::Array::new(&ArrayType::Int, 2)));
+    assert_eq!(
+        store_and_load(&jvm, array_type(), stored_array.clone()),
+        stored_array
+    );
+}
+
+#[test]
+fn test_int_conversions() {
+    let jvm = empty_jvm();
+    let cases = [
+        (OpCode::i2b, 200, -56),
+        (OpCode::i2b, -129, 127),
+        (OpCode::i2c, -1, 0xFFFF),
+        (OpCode::i2c, 0x1_0041, 0x41),
+        (OpCode::i2s, 40000, -25536),
+    ];
+    for (conversion, value, converted) in cases {
+        let description = format!("{:?} {}", conversion, value);
+        let code = int_conversion(conversion);
+        let mut stack = Stack::new(1);
+        stack.push(Object::Int(value));
+        assert_eq!(
+            code.execute(&jvm, &mut stack),
+            Ok(Ok(Some(Object::Int(converted)))),
+            "{}",
+            description
+        );
+    }
+}
+
+#[test]
+fn test_iushr() {
+    let jvm = empty_jvm();
+    let code = shift(OpCode::iushr, 1);
+    // the distance is masked with 31
+    let cases = [
+        (-1, 28, 0xF),
+        (-16, 2, 0x3FFF_FFFC),
+        (-1, 32, -1),
+        (i32::MIN, 63, 1),
+    ];
+    for (value, distance, shifted) in cases {
+        let mut stack = Stack::new(2);
+        stack.push(Object::Int(value));
+        stack.push(Object::Int(distance));
+        assert_eq!(
+            code.execute(&jvm, &mut stack),
+            Ok(Ok(Some(Object::Int(shifted)))),
+            "{} >>> {}",
+            value,
+            distance
+        );
+    }
+}
+
+#[test]
+fn test_lushr() {
+    let jvm = empty_jvm();
+    let code = shift(OpCode::lushr, 2);
+    // the distance is masked with 63
+    let cases = [
+        (-1, 60, 0xF),
+        (-16, 2, 0x3FFF_FFFF_FFFF_FFFC),
+        (-1, 64, -1),
+        (i64::MIN, -1, 1),
+    ];
+    for (value, distance, shifted) in cases {
+        let mut stack = Stack::new(3);
+        stack.push(Object::Long(value));
+        stack.push(Object::Int(distance));
+        assert_eq!(
+            code.execute(&jvm, &mut stack),
+            Ok(Ok(Some(Object::Long(shifted)))),
+            "{} >>> {}",
+            value,
+            distance
+        );
+    }
+}